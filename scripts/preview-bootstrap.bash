@@ -16,8 +16,10 @@
 #
 # For the full copyright and license information, please view the LICENSE file
 # that was distributed with this source code.
-
-set -euf -o pipefail
+# POSIX sh compatible, as httm falls back to 'sh' when 'bash' is unavailable
+set -euf
+# pipefail is not available in every sh
+(set -o pipefail 2>/dev/null) && set -o pipefail
 #set -x
 
 print_err_exit() {
@@ -44,34 +46,78 @@ graceful_shutdown() {
 }
 
 prep_exec() {
-	[[ -n "$(
+	[ -n "$(
 		command -v cut
 		exit 0
-	)" ]] || print_err_exit "'cut' is required to execute 'httm --preview'.  Please check that 'cut' is in your path."
+	)" ] || print_err_exit "'cut' is required to execute 'httm --preview'.  Please check that 'cut' is in your path."
+}
+
+kill_tree() {
+	# the command's process group, where job control gave the command a group of its own
+	! kill -TERM -- "-$1" 2>/dev/null || return 0
+
+	# otherwise, as when sh refuses job control without a tty, each of its descendants in turn
+	for child_pid in $(ps -A -o pid= -o ppid= 2>/dev/null | awk -v ppid="$1" '$2 == ppid { print $1 }'); do
+		kill_tree "$child_pid"
+	done
+
+	kill -TERM "$1" 2>/dev/null
+}
+
+exec_with_timeout() {
+	# run the preview command in the background, and kill it, and all its children, should it
+	# outlive its timeout, so a runaway preview command never hangs the selection view
+	set -m 2>/dev/null || true
+
+	(
+		{command}
+	) 2>&1 &
+	command_pid=$!
+
+	# redirect the watchdog's output, so its sleep never holds open the preview's output
+	(
+		sleep {timeout}
+		kill_tree "$command_pid"
+	) >/dev/null 2>&1 &
+	watchdog_pid=$!
+
+	set +m 2>/dev/null || true
+
+	status=0
+	wait "$command_pid" || status=$?
+
+	kill_tree "$watchdog_pid" || true
+
+	# 143 is 128 + SIGTERM
+	[ "$status" -ne 143 ] || print_warn "Preview command timed out after {timeout} seconds."
+
+	return "$status"
 }
 
 bootstrap_preview() {
 	prep_exec
 
-	local raw_input=""
-	local snap_file=""
+	raw_input=""
+	snap_file=""
 
 	raw_input={}
 
-	[[ -n $raw_input ]] || print_err_exit "Selection is empty."
+	[ -n "$raw_input" ] || print_err_exit "Selection is empty."
 
-	[[ $raw_input != ─*─ ]] || graceful_shutdown
+	case "$raw_input" in
+	─*─) graceful_shutdown ;;
+	esac
 
 	# remove first and last chars in string in case they are also quotes 
 	# possible we drop good chars, but these chars are unnecessary for parsing
 	snap_file="$(echo ${raw_input} | cut -d'"' -f2)"
 
-	[[ -n "$snap_file" ]] || print_err_exit "Snap file path is empty."
+	[ -n "$snap_file" ] || print_err_exit "Snap file path is empty."
 
-	[[ -f "$snap_file" ]] || [[ -d "$snap_file" ]] || [[ -L "$snap_file" ]] || print_warn_exit "Selection does not refer to a valid file, link or directory."
+	[ -f "$snap_file" ] || [ -d "$snap_file" ] || [ -L "$snap_file" ] || print_warn_exit "Selection does not refer to a valid file, link or directory."
 
 	exec 0<&-
-	{command} 2>&1
+	exec_with_timeout
 }
 
 bootstrap_preview
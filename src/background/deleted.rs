@@ -15,7 +15,7 @@
 // For the full copyright and license information, please view the LICENSE file
 // that was distributed with this source code.
 
-use crate::background::recursive::{Entries, PathProvenance};
+use crate::background::recursive::{Entries, PathProvenance, SkimBatch};
 use crate::config::generate::DeletedMode;
 use crate::data::paths::BasicDirEntryInfo;
 use crate::library::results::HttmResult;
@@ -24,10 +24,14 @@ use rayon::Scope;
 use skim::prelude::*;
 use std::path::Path;
 use std::sync::atomic::AtomicBool;
+use std::time::Instant;
+
+// set when any deleted search stops short, see DELETED_DEPTH and DELETED_TIMEOUT
+static IS_TRUNCATED: AtomicBool = AtomicBool::new(false);
 
 pub struct DeletedSearch {
     requested_dir: BasicDirEntryInfo,
-    skim_tx: SkimItemSender,
+    skim_batch: SkimBatch,
     hangup: Arc<AtomicBool>,
 }
 
@@ -36,28 +40,33 @@ impl DeletedSearch {
     pub fn spawn(
         requested_dir: &Path,
         deleted_scope: &Scope,
-        skim_tx: &SkimItemSender,
+        skim_batch: &SkimBatch,
         hangup: &Arc<AtomicBool>,
     ) {
-        let new = Self::new(requested_dir, skim_tx.clone(), hangup.clone());
+        let new = Self::new(requested_dir, skim_batch.clone(), hangup.clone());
 
         deleted_scope.spawn(move |_| {
             let _ = new.run_loop();
         })
     }
 
-    fn new(requested_dir: &Path, skim_tx: SkimItemSender, hangup: Arc<AtomicBool>) -> Self {
+    fn new(requested_dir: &Path, skim_batch: SkimBatch, hangup: Arc<AtomicBool>) -> Self {
         Self {
             requested_dir: BasicDirEntryInfo::new(requested_dir.to_path_buf(), None),
-            skim_tx,
+            skim_batch,
             hangup,
         }
     }
 
     fn run_loop(&self) -> HttmResult<()> {
-        let mut queue = vec![self.requested_dir.clone()];
+        let opt_deadline = GLOBAL_CONFIG
+            .opt_deleted_timeout
+            .map(|timeout| Instant::now() + timeout);
+
+        // depth is the number of deleted directories behind which a directory lies
+        let mut queue = vec![(self.requested_dir.clone(), 0usize)];
 
-        while let Some(deleted_dir) = queue.pop() {
+        while let Some((deleted_dir, depth)) = queue.pop() {
             // check -- should deleted threads keep working?
             // exit/error on disconnected channel, which closes
             // at end of browse scope
@@ -65,14 +74,38 @@ impl DeletedSearch {
                 break;
             }
 
-            if let Ok(mut res) = self.enter_directory(&deleted_dir.path()) {
-                queue.append(&mut res);
+            if opt_deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                IS_TRUNCATED.store(true, Ordering::Relaxed);
+                break;
+            }
+
+            if let Ok(res) = self.enter_directory(&deleted_dir.path()) {
+                if !res.is_empty()
+                    && GLOBAL_CONFIG
+                        .opt_deleted_depth
+                        .is_some_and(|max_depth| depth >= max_depth)
+                {
+                    IS_TRUNCATED.store(true, Ordering::Relaxed);
+                    continue;
+                }
+
+                queue.extend(res.into_iter().map(|dir| (dir, depth + 1)));
             }
         }
 
         Ok(())
     }
 
+    // warnings printed during the search would garble the browse view, so we warn only once it is over
+    pub fn warn_if_truncated() {
+        if IS_TRUNCATED.swap(false, Ordering::Relaxed) {
+            eprintln!(
+                "WARN: httm stopped searching behind some deleted directories, as the DELETED_DEPTH \
+                or DELETED_TIMEOUT limit was reached.  Results may be incomplete."
+            );
+        }
+    }
+
     // deleted file search for all modes
     fn enter_directory(&self, requested_dir: &Path) -> HttmResult<Vec<BasicDirEntryInfo>> {
         // check -- should deleted threads keep working?
@@ -83,7 +116,7 @@ impl DeletedSearch {
         }
 
         // create entries struct here
-        let entries = Entries::new(requested_dir, &PathProvenance::IsPhantom, &self.skim_tx)?;
+        let entries = Entries::new(requested_dir, &PathProvenance::IsPhantom, &self.skim_batch)?;
 
         // combined entries will be sent or printed, but we need the vec_dirs to recurse
         let vec_dirs = entries.combine_and_send()?;
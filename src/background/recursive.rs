@@ -16,19 +16,33 @@
 // that was distributed with this source code.
 
 use crate::background::deleted::DeletedSearch;
-use crate::config::generate::{DeletedMode, ExecMode};
+use crate::config::generate::{BrowseSort, DeletedMode, ExecMode};
 use crate::data::paths::{BasicDirEntryInfo, PathData};
+use crate::display::deleted_report::DeletedReport;
+use crate::display::deleted_summary::DeletedSummary;
 use crate::display::wrapper::DisplayWrapper;
+use crate::interactive::browse::AvailabilityFilter;
+use crate::library::cleanup::CleanupGuard;
 use crate::library::results::{HttmError, HttmResult};
 use crate::library::utility::print_output_buf;
 use crate::lookup::deleted::DeletedFiles;
 use crate::{VersionsMap, GLOBAL_CONFIG};
 use rayon::{Scope, ThreadPool};
 use skim::prelude::*;
+use std::cmp::Reverse;
 use std::fs::read_dir;
 use std::path::Path;
-use std::sync::atomic::AtomicBool;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::{Arc, Mutex, MutexGuard, Once};
+use std::time::{Duration, Instant, SystemTime};
+
+// items are sent to the interactive view in whole batches, so that each search thread contends with
+// the UI thread for the channel in bursts, not per item.  A batch begins small, so the first items
+// are displayed at once, and grows while items arrive faster than they are flushed
+const SKIM_MIN_BATCH_SIZE: usize = 8;
+const SKIM_MAX_BATCH_SIZE: usize = 1_024;
+// when items trickle in, or a search stalls, a partial batch is sent once this interval has elapsed
+const SKIM_FLUSH_INTERVAL: Duration = Duration::from_millis(16);
 
 #[derive(Clone, Copy)]
 pub enum PathProvenance {
@@ -36,9 +50,164 @@ pub enum PathProvenance {
     IsPhantom,
 }
 
+// shared by the live search and every deleted search beneath it, see DeletedSearch
+#[derive(Clone)]
+pub struct SkimBatch {
+    inner: Arc<SkimBatchInner>,
+}
+
+struct SkimBatchInner {
+    skim_tx: SkimItemSender,
+    buffer: Mutex<SkimBatchBuffer>,
+    batch_size: AtomicUsize,
+    is_disconnected: AtomicBool,
+    timer: Once,
+}
+
+struct SkimBatchBuffer {
+    items: Vec<Arc<dyn SkimItem>>,
+    last_flush: Instant,
+}
+
+impl SkimBatch {
+    pub fn new(skim_tx: SkimItemSender) -> Self {
+        let inner = SkimBatchInner {
+            skim_tx,
+            buffer: Mutex::new(SkimBatchBuffer {
+                items: Vec::with_capacity(SKIM_MIN_BATCH_SIZE),
+                last_flush: Instant::now(),
+            }),
+            batch_size: AtomicUsize::new(SKIM_MIN_BATCH_SIZE),
+            is_disconnected: AtomicBool::new(false),
+            timer: Once::new(),
+        };
+
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    pub fn push(&self, item: Arc<dyn SkimItem>) -> HttmResult<()> {
+        // a disconnected channel is an error here, just as it was when we sent each item,
+        // so a search still stops once the user has made a selection
+        if self.inner.is_disconnected.load(Ordering::Relaxed) {
+            return Err(HttmError::new("httm's interactive view has hung up.").into());
+        }
+
+        self.inner
+            .timer
+            .call_once(|| Self::spawn_timer(&self.inner));
+
+        let opt_full_batch = {
+            let mut buffer = self.inner.lock_buffer();
+
+            buffer.items.push(item);
+
+            if buffer.items.len() < self.inner.batch_size.load(Ordering::Relaxed) {
+                None
+            } else {
+                // a batch which fills before the interval elapses implies we should batch more
+                if buffer.last_flush.elapsed() < SKIM_FLUSH_INTERVAL {
+                    self.inner.resize(|size| size * 2);
+                }
+
+                Some(buffer.take())
+            }
+        };
+
+        match opt_full_batch {
+            Some(batch) => self.inner.send(batch),
+            None => Ok(()),
+        }
+    }
+
+    pub fn flush(&self) -> HttmResult<()> {
+        let batch = self.inner.lock_buffer().take();
+
+        self.inner.send(batch)
+    }
+
+    // the timer only holds a weak reference, and exits once every search has dropped its SkimBatch
+    fn spawn_timer(inner: &Arc<SkimBatchInner>) {
+        let weak = Arc::downgrade(inner);
+
+        std::thread::spawn(move || loop {
+            std::thread::sleep(SKIM_FLUSH_INTERVAL);
+
+            let Some(inner) = weak.upgrade() else {
+                return;
+            };
+
+            let opt_stale_batch = {
+                let mut buffer = inner.lock_buffer();
+
+                if buffer.items.is_empty() || buffer.last_flush.elapsed() < SKIM_FLUSH_INTERVAL {
+                    None
+                } else {
+                    Some(buffer.take())
+                }
+            };
+
+            if let Some(batch) = opt_stale_batch {
+                // a batch which the timer must flush implies we should batch less
+                inner.resize(|size| size / 2);
+
+                if inner.send(batch).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+}
+
+impl SkimBatchInner {
+    fn lock_buffer(&self) -> MutexGuard<SkimBatchBuffer> {
+        // a poisoned buffer is still just a buffer
+        self.buffer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn resize(&self, f: impl Fn(usize) -> usize) {
+        let _ = self
+            .batch_size
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |size| {
+                Some(f(size).clamp(SKIM_MIN_BATCH_SIZE, SKIM_MAX_BATCH_SIZE))
+            });
+    }
+
+    // items are sent outside the buffer's lock, so other searches may continue to fill the next batch
+    fn send(&self, batch: Vec<Arc<dyn SkimItem>>) -> HttmResult<()> {
+        batch
+            .into_iter()
+            .try_for_each(|item| self.skim_tx.try_send(item))
+            .map_err(|err| {
+                self.is_disconnected.store(true, Ordering::Relaxed);
+                err.into()
+            })
+    }
+}
+
+impl SkimBatchBuffer {
+    fn take(&mut self) -> Vec<Arc<dyn SkimItem>> {
+        self.last_flush = Instant::now();
+
+        std::mem::take(&mut self.items)
+    }
+}
+
+impl Drop for SkimBatchInner {
+    fn drop(&mut self) {
+        let batch = self.lock_buffer().take();
+
+        // the receiver may have already hung up, nothing more to do
+        let _ = self.send(batch);
+    }
+}
+
 pub struct RecursiveSearch<'a> {
     requested_dir: &'a Path,
-    skim_tx: SkimItemSender,
+    skim_batch: SkimBatch,
     hangup: Arc<AtomicBool>,
     started: Arc<AtomicBool>,
 }
@@ -52,7 +221,7 @@ impl<'a> RecursiveSearch<'a> {
     ) -> Self {
         Self {
             requested_dir,
-            skim_tx,
+            skim_batch: SkimBatch::new(skim_tx),
             hangup,
             started,
         }
@@ -81,7 +250,7 @@ impl<'a> RecursiveSearch<'a> {
         // we are in our own detached system thread, so print error and exit if error trickles up
         self.loop_body(opt_deleted_scope).unwrap_or_else(|error| {
             eprintln!("ERROR: {error}");
-            std::process::exit(1)
+            CleanupGuard::exit(1)
         });
     }
 
@@ -105,7 +274,7 @@ impl<'a> RecursiveSearch<'a> {
         let initial_entries = Entries {
             requested_dir: self.requested_dir,
             is_phantom: &PathProvenance::FromLiveDataset,
-            skim_tx: &self.skim_tx,
+            skim_batch: &self.skim_batch,
             vec_dirs: initial_vec_dirs,
             vec_files: Vec::new(),
         };
@@ -118,10 +287,13 @@ impl<'a> RecursiveSearch<'a> {
         let mut queue: Vec<BasicDirEntryInfo> = Self::enter_directory(
             self.requested_dir,
             opt_deleted_scope,
-            &self.skim_tx,
+            &self.skim_batch,
             &self.hangup,
         )?;
 
+        // the first directory should be displayed immediately, whatever its size
+        self.skim_batch.flush()?;
+
         self.started.store(true, Ordering::SeqCst);
 
         if GLOBAL_CONFIG.opt_recursive {
@@ -140,7 +312,7 @@ impl<'a> RecursiveSearch<'a> {
                 if let Ok(mut items) = Self::enter_directory(
                     &item.path(),
                     opt_deleted_scope,
-                    &self.skim_tx,
+                    &self.skim_batch,
                     &self.hangup,
                 ) {
                     queue.append(&mut items)
@@ -154,14 +326,14 @@ impl<'a> RecursiveSearch<'a> {
     fn enter_directory(
         requested_dir: &Path,
         opt_deleted_scope: Option<&Scope>,
-        skim_tx: &SkimItemSender,
+        skim_batch: &SkimBatch,
         hangup: &Arc<AtomicBool>,
     ) -> HttmResult<Vec<BasicDirEntryInfo>> {
         // combined entries will be sent or printed, but we need the vec_dirs to recurse
-        let entries = Entries::new(requested_dir, &PathProvenance::FromLiveDataset, skim_tx)?;
+        let entries = Entries::new(requested_dir, &PathProvenance::FromLiveDataset, skim_batch)?;
 
         if let Some(deleted_scope) = opt_deleted_scope {
-            DeletedSearch::spawn(requested_dir, deleted_scope, skim_tx, hangup);
+            DeletedSearch::spawn(requested_dir, deleted_scope, skim_batch, hangup);
         }
 
         // entries struct is consumed, but we return vec_dirs here to continue to feed the queue
@@ -172,7 +344,7 @@ impl<'a> RecursiveSearch<'a> {
 pub struct Entries<'a> {
     pub requested_dir: &'a Path,
     pub is_phantom: &'a PathProvenance,
-    pub skim_tx: &'a SkimItemSender,
+    pub skim_batch: &'a SkimBatch,
     pub vec_dirs: Vec<BasicDirEntryInfo>,
     pub vec_files: Vec<BasicDirEntryInfo>,
 }
@@ -181,7 +353,7 @@ impl<'a> Entries<'a> {
     pub fn new(
         requested_dir: &'a Path,
         is_phantom: &'a PathProvenance,
-        skim_tx: &'a SkimItemSender,
+        skim_batch: &'a SkimBatch,
     ) -> HttmResult<Self> {
         // separates entries into dirs and files
         let (vec_dirs, vec_files) = match is_phantom {
@@ -207,15 +379,37 @@ impl<'a> Entries<'a> {
         Ok(Self {
             requested_dir,
             is_phantom,
-            skim_tx,
+            skim_batch,
             vec_dirs,
             vec_files,
         })
     }
 
     pub fn combine_and_send(self) -> HttmResult<Vec<BasicDirEntryInfo>> {
-        let mut combined = self.vec_files;
-        combined.extend_from_slice(&self.vec_dirs);
+        let combined = match GLOBAL_CONFIG.browse_sort {
+            BrowseSort::Disable => {
+                let mut combined = self.vec_files;
+                combined.extend_from_slice(&self.vec_dirs);
+                combined
+            }
+            _ => {
+                // ordering is only a hint within this batch, as each directory's entries
+                // are sent as soon as they are ready, dirs are placed first
+                let mut combined = self.vec_dirs.clone();
+                let mut vec_files = self.vec_files;
+
+                // deleted entries arrive already in the order the user requested, see DELETED_ORDER
+                if !matches!(self.is_phantom, PathProvenance::IsPhantom)
+                    || GLOBAL_CONFIG.opt_deleted_order.is_none()
+                {
+                    Self::sort_group(&mut combined);
+                    Self::sort_group(&mut vec_files);
+                }
+
+                combined.append(&mut vec_files);
+                combined
+            }
+        };
 
         let entries_ready_to_send = match self.is_phantom {
             PathProvenance::FromLiveDataset => {
@@ -243,31 +437,57 @@ impl<'a> Entries<'a> {
             }
         };
 
-        DisplayOrTransmit::new(entries_ready_to_send, self.is_phantom, self.skim_tx).exec()?;
+        DisplayOrTransmit::new(entries_ready_to_send, self.is_phantom, self.skim_batch).exec()?;
 
         // here we consume the struct after sending the entries,
         // however we still need the dirs to populate the loop's queue
         // so we return the vec of dirs here
         Ok(self.vec_dirs)
     }
+
+    fn sort_group(group: &mut [BasicDirEntryInfo]) {
+        // symlink_metadata so we never traverse links, and an entry we cannot
+        // stat is simply sorted last
+        match GLOBAL_CONFIG.browse_sort {
+            BrowseSort::Newest => group.sort_by_cached_key(|entry| {
+                Reverse(
+                    entry
+                        .path()
+                        .symlink_metadata()
+                        .and_then(|md| md.modified())
+                        .unwrap_or(SystemTime::UNIX_EPOCH),
+                )
+            }),
+            BrowseSort::Largest => group.sort_by_cached_key(|entry| {
+                Reverse(
+                    entry
+                        .path()
+                        .symlink_metadata()
+                        .map(|md| md.len())
+                        .unwrap_or_default(),
+                )
+            }),
+            BrowseSort::DirsFirst | BrowseSort::Disable => {}
+        }
+    }
 }
 
 struct DisplayOrTransmit<'a> {
     entries: Vec<BasicDirEntryInfo>,
     is_phantom: &'a PathProvenance,
-    skim_tx: &'a SkimItemSender,
+    skim_batch: &'a SkimBatch,
 }
 
 impl<'a> DisplayOrTransmit<'a> {
     fn new(
         entries: Vec<BasicDirEntryInfo>,
         is_phantom: &'a PathProvenance,
-        skim_tx: &'a SkimItemSender,
+        skim_batch: &'a SkimBatch,
     ) -> Self {
         Self {
             entries,
             is_phantom,
-            skim_tx,
+            skim_batch,
         }
     }
 
@@ -285,6 +505,16 @@ impl<'a> DisplayOrTransmit<'a> {
                             Perhaps try specifying a deleted mode in combination with \"--recursive\"."
                         )
                     }
+                } else if GLOBAL_CONFIG.opt_summary {
+                    self.summarize()?;
+
+                    if GLOBAL_CONFIG.opt_recursive {
+                        progress_bar.tick();
+                    }
+                } else if GLOBAL_CONFIG.opt_deleted_report {
+                    self.report()?;
+
+                    progress_bar.tick();
                 } else {
                     self.display()?;
 
@@ -301,25 +531,74 @@ impl<'a> DisplayOrTransmit<'a> {
     }
 
     fn transmit(self) -> HttmResult<()> {
+        let availability = AvailabilityFilter::current();
+
         // don't want a par_iter here because it will block and wait for all
         // results, instead of printing and recursing into the subsequent dirs
         self.entries
             .into_iter()
+            .filter(|basic_info| basic_info.entry_filters())
+            .filter(|basic_info| availability.admits(basic_info))
             .try_for_each(|basic_info| {
-                self.skim_tx
-                    .try_send(Arc::new(basic_info.into_selection(&self.is_phantom)))
+                let selection = basic_info.into_selection(&self.is_phantom);
+
+                // the walk continues while the badge is looked up, see BADGES
+                if GLOBAL_CONFIG.opt_badges {
+                    let skim_batch = self.skim_batch.clone();
+
+                    selection.with_badge(move |selection| {
+                        // the receiver may have already hung up, and the walk will see as much
+                        let _ = skim_batch.push(Arc::new(selection));
+                    });
+
+                    return Ok(());
+                }
+
+                self.skim_batch.push(Arc::new(selection))
             })
-            .map_err(std::convert::Into::into)
     }
 
     fn display(self) -> HttmResult<()> {
         let pseudo_live_set: Vec<PathData> = self.entries.into_iter().map(PathData::from).collect();
 
-        let versions_map = VersionsMap::new(&GLOBAL_CONFIG, &pseudo_live_set)?;
+        let mut versions_map = VersionsMap::new(&GLOBAL_CONFIG, &pseudo_live_set)?;
+
+        // a versions map is ordered by path, so, to keep the order the user requested, we look up
+        // all deleted entries at once, but display each in the order it arrived, see DELETED_ORDER
+        if matches!(self.is_phantom, PathProvenance::IsPhantom)
+            && GLOBAL_CONFIG.opt_deleted_order.is_some()
+        {
+            let output_buf: String = pseudo_live_set
+                .iter()
+                .filter_map(|pathdata| versions_map.remove_entry(pathdata))
+                .map(|entry| {
+                    DisplayWrapper::from(&GLOBAL_CONFIG, VersionsMap::from([entry])).to_string()
+                })
+                .collect();
+
+            return print_output_buf(&output_buf);
+        }
+
         let output_buf = DisplayWrapper::from(&GLOBAL_CONFIG, versions_map).to_string();
 
         print_output_buf(&output_buf)
     }
+
+    fn summarize(self) -> HttmResult<()> {
+        let pseudo_live_set: Vec<PathData> = self.entries.into_iter().map(PathData::from).collect();
+
+        let versions_map = VersionsMap::new(&GLOBAL_CONFIG, &pseudo_live_set)?;
+
+        DeletedSummary::record(&versions_map)
+    }
+
+    fn report(self) -> HttmResult<()> {
+        let pseudo_live_set: Vec<PathData> = self.entries.into_iter().map(PathData::from).collect();
+
+        let versions_map = VersionsMap::new(&GLOBAL_CONFIG, &pseudo_live_set)?;
+
+        DeletedReport::record(&versions_map)
+    }
 }
 
 // this is wrapper for non-interactive searches, which will be executed through the SharedRecursive fns
@@ -337,6 +616,29 @@ impl NonInteractiveRecursiveWrapper {
         match &GLOBAL_CONFIG.opt_requested_dir {
             Some(requested_dir) => {
                 RecursiveSearch::new(requested_dir, dummy_skim_tx, hangup, started).exec();
+
+                DeletedSearch::warn_if_truncated();
+
+                // all deleted threads have completed once exec() returns
+                if GLOBAL_CONFIG.opt_summary {
+                    if let ExecMode::NonInteractiveRecursive(progress_bar) =
+                        &GLOBAL_CONFIG.exec_mode
+                    {
+                        progress_bar.finish_and_clear();
+                    }
+
+                    DeletedSummary::print()?;
+                }
+
+                if GLOBAL_CONFIG.opt_deleted_report {
+                    if let ExecMode::NonInteractiveRecursive(progress_bar) =
+                        &GLOBAL_CONFIG.exec_mode
+                    {
+                        progress_bar.finish_and_clear();
+                    }
+
+                    DeletedReport::print()?;
+                }
             }
             None => {
                 return Err(HttmError::new(
@@ -0,0 +1,123 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::build_cli;
+use crate::data::filesystem_info::FilesystemInfo;
+use crate::display::snap_info::SnapInfo;
+use crate::library::cleanup::CleanupGuard;
+use crate::library::results::HttmResult;
+use clap_complete::Shell;
+use std::collections::BTreeSet;
+use std::io::Write;
+
+// static completions, generated from our clap definitions, cannot know the names of the snapshots
+// on this system, so, for the shells which allow, each script asks httm for those names, as the
+// user completes a value for LIST_SNAPS (and therefore for PRUNE, which is filtered by such values)
+const BASH_SNAP_NAMES: &str = r#"
+_httm_snap_names() {
+    local cur prev
+    cur="${COMP_WORDS[COMP_CWORD]}"
+    prev="${COMP_WORDS[COMP_CWORD-1]}"
+
+    # bash splits "--list-snaps=NAME" at the '=', so a snapshot name follows a lone '='
+    if [[ "${cur}" == "=" ]]; then
+        cur=""
+    elif [[ "${prev}" == "=" && ${COMP_CWORD} -ge 2 ]]; then
+        prev="${COMP_WORDS[COMP_CWORD-2]}"
+    else
+        prev=""
+    fi
+
+    case "${prev}" in
+        --list-snaps|--snap-names|--snaps-for-file|--ls-snaps|--list-snapshots)
+            COMPREPLY=( $(compgen -W "$(httm --complete-snap-names 2>/dev/null)" -- "${cur}") )
+            return 0
+            ;;
+    esac
+
+    _httm "$@"
+}
+
+complete -F _httm_snap_names -o bashdefault -o default httm
+"#;
+
+const ZSH_SNAP_NAMES: &str = r#"
+_httm_snap_names() {
+    if [[ "${words[CURRENT]}" == --(list-snaps|snap-names|snaps-for-file|ls-snaps|list-snapshots)=* ]]; then
+        local -a snap_names
+        snap_names=(${(f)"$(httm --complete-snap-names 2>/dev/null)"})
+        compset -P '*='
+        compadd -a snap_names
+        return
+    fi
+
+    _httm "$@"
+}
+
+compdef _httm_snap_names httm
+"#;
+
+const FISH_SNAP_NAMES: &str = r#"
+complete -c httm -l list-snaps -l snap-names -l snaps-for-file -l ls-snaps -l list-snapshots -f -r -a '(httm --complete-snap-names 2>/dev/null)'
+"#;
+
+pub struct Completions;
+
+impl Completions {
+    pub fn print(shell: Shell) -> HttmResult<()> {
+        let mut out_locked = std::io::stdout().lock();
+
+        clap_complete::generate(shell, &mut build_cli(), "httm", &mut out_locked);
+
+        let opt_snap_names = match shell {
+            Shell::Bash => Some(BASH_SNAP_NAMES),
+            Shell::Zsh => Some(ZSH_SNAP_NAMES),
+            Shell::Fish => Some(FISH_SNAP_NAMES),
+            _ => None,
+        };
+
+        if let Some(snap_names) = opt_snap_names {
+            write!(out_locked, "{}", snap_names)?;
+        }
+
+        out_locked.flush()?;
+
+        // nothing else should run once the script is printed, as we are called while parsing our config
+        CleanupGuard::exit(0)
+    }
+
+    // the name of every snapshot of every dataset we know of, one per line, for the scripts above
+    pub fn print_snap_names(dataset_collection: &FilesystemInfo) -> HttmResult<()> {
+        let snap_names: BTreeSet<String> = dataset_collection
+            .map_of_snaps
+            .values()
+            .flatten()
+            .filter_map(|snap_mount| SnapInfo::snap_mount_name(snap_mount))
+            .collect();
+
+        let mut out_locked = std::io::stdout().lock();
+
+        snap_names
+            .iter()
+            .try_for_each(|snap_name| writeln!(out_locked, "{}", snap_name))?;
+
+        out_locked.flush()?;
+
+        // the dataset collection may have mounted local Time Machine snapshots
+        CleanupGuard::exit(0)
+    }
+}
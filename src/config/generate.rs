@@ -15,20 +15,27 @@
 // For the full copyright and license information, please view the LICENSE file
 // that was distributed with this source code.
 
+use crate::config::completions::Completions;
 use crate::config::install_hot_keys::install_hot_keys;
 use crate::data::filesystem_info::FilesystemInfo;
 use crate::data::paths::{PathData, PathDeconstruction, ZfsSnapPathGuard};
-use crate::filesystem::mounts::{FilesystemType, ROOT_PATH};
+use crate::display::format_template::FormatTemplate;
+use crate::filesystem::mounts::{FilesystemType, MapOfDatasets, ROOT_PATH};
 use crate::library::results::{HttmError, HttmResult};
-use crate::library::utility::{pwd, HttmIsDir};
+use crate::library::terminal::Terminal;
+use crate::library::utility::{parse_age, parse_timestamp, pwd, HttmIsDir};
 use crate::lookup::file_mounts::MountDisplay;
 use clap::parser::ValuesRef;
 use clap::{crate_name, crate_version, Arg, ArgAction, ArgMatches};
+use clap_complete::Shell;
 use indicatif::ProgressBar;
 use rayon::iter::{ParallelBridge, ParallelIterator};
+use regex::bytes::Regex;
+use std::ffi::OsString;
 use std::io::Read;
 use std::ops::Index;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 use time::UtcOffset;
 
 #[derive(Debug, Clone)]
@@ -37,11 +44,64 @@ pub enum ExecMode {
     NonInteractiveRecursive(indicatif::ProgressBar),
     BasicDisplay,
     SnapFileMount(String),
+    SnapSchedule(ScheduleSpec),
     Prune(Option<ListSnapsFilters>),
     MountsForFiles(MountDisplay),
     SnapsForFiles(Option<ListSnapsFilters>),
     NumVersions(NumVersionsMode),
     RollForward(String),
+    CompareTwin(TwinDirs),
+    SnapshotDiffReport(SnapDiffPair),
+    Tui,
+    RestoreTree(SystemTime),
+    Diff(Option<DiffPair>),
+    PreviewRender(String),
+    PreviewExec(String, String, String),
+    PreviewVariables(PathBuf),
+    NonInteractiveRestore(RestoreMode),
+    LatestPerDir,
+    SnapsForVersion(VersionSpec),
+    Export(PathBuf),
+    VerifyExport(PathBuf),
+    Stats,
+    SoftDelete,
+    Grep(GrepSpec),
+    Bisect(Option<String>),
+    LinkFarm(PathBuf),
+    UndoRestore,
+}
+
+// see EVERY, KEEP, and SYSTEMD
+#[derive(Debug, Clone)]
+pub struct ScheduleSpec {
+    pub snapshot_suffix: String,
+    pub opt_every: Option<Duration>,
+    pub opt_keep: Option<usize>,
+    pub opt_systemd_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffPair {
+    pub snap_file: PathBuf,
+    pub live_file: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct SnapDiffPair {
+    pub first: String,
+    pub second: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct GrepSpec {
+    pub regex: Regex,
+    pub opt_appearance: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct TwinDirs {
+    pub local_dir: PathBuf,
+    pub twin_dir: PathBuf,
 }
 
 #[derive(Debug, Clone)]
@@ -67,7 +127,10 @@ pub enum RestoreSnapGuard {
 pub enum SelectMode {
     Path,
     Contents,
+    RawContents,
     Preview,
+    Pairs,
+    Edit,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -75,6 +138,7 @@ pub enum RestoreMode {
     CopyOnly,
     CopyAndPreserve,
     Overwrite(RestoreSnapGuard),
+    Merge(RestoreSnapGuard),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -103,11 +167,83 @@ pub enum DeletedMode {
     Only,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeletedOrder {
+    Deleted,
+    Mtime,
+    Name,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OnConflict {
+    Skip,
+    Overwrite,
+    RenameNumbered,
+    Ask,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub opt_since: Option<SystemTime>,
+    pub opt_until: Option<SystemTime>,
+}
+
+impl TimeWindow {
+    #[inline(always)]
+    pub fn contains(&self, time: &SystemTime) -> bool {
+        self.opt_since.map_or(true, |since| time >= &since)
+            && self.opt_until.map_or(true, |until| time <= &until)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BrowseSort {
+    Disable,
+    DirsFirst,
+    Newest,
+    Largest,
+}
+
+#[derive(Debug, Clone)]
+pub enum RelativeTo {
+    Dir(Box<Path>),
+    DatasetMount,
+}
+
 #[derive(Debug, Clone)]
 pub enum DedupBy {
     Disable,
     Metadata,
     Contents,
+    ContentsByBlocks,
+}
+
+#[derive(Debug, Clone)]
+pub enum ChangeDetection {
+    Mtime,
+    Ctime,
+    MtimeAndSize,
+    Contents,
+}
+
+#[derive(Debug, Clone)]
+pub struct VersionSpec {
+    pub reference: PathBuf,
+    pub by_contents: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryType {
+    File,
+    Dir,
+    Symlink,
+}
+
+#[derive(Debug, Clone)]
+pub struct EntryFilters {
+    pub opt_min_size: Option<u64>,
+    pub opt_max_size: Option<u64>,
+    pub opt_entry_type: Option<EntryType>,
 }
 
 #[derive(Debug, Clone)]
@@ -115,6 +251,7 @@ pub struct ListSnapsFilters {
     pub select_mode: bool,
     pub omit_num_snaps: usize,
     pub name_filters: Option<Vec<String>>,
+    pub opt_older_than: Option<Duration>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -136,6 +273,8 @@ pub enum NumVersionsMode {
     Multiple,
 }
 
+pub const DEFAULT_PREVIEW_TIMEOUT: Duration = Duration::from_secs(10);
+
 const NATIVE_SNAP_SUFFIXES: [&str; 4] = [
     "ounceSnapFileMount",
     "httmSnapFileMount",
@@ -143,7 +282,41 @@ const NATIVE_SNAP_SUFFIXES: [&str; 4] = [
     "httmSnapRestore",
 ];
 
+// accepts a number of bytes, or a number followed by a suffix K, M, G, or T, as powers of 1024
+fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((idx, suffix)) if suffix.is_ascii_alphabetic() => {
+            let multiplier: u64 = match suffix.to_ascii_uppercase() {
+                'K' => 1 << 10,
+                'M' => 1 << 20,
+                'G' => 1 << 30,
+                'T' => 1 << 40,
+                _ => return Err(format!("Unknown size suffix: {suffix:?}")),
+            };
+
+            (&value[..idx], multiplier)
+        }
+        _ => (value, 1),
+    };
+
+    number
+        .trim()
+        .parse::<u64>()
+        .map_err(|err| err.to_string())
+        .and_then(|number| {
+            number
+                .checked_mul(multiplier)
+                .ok_or_else(|| "Size specified is too large.".to_owned())
+        })
+}
+
 fn parse_args() -> ArgMatches {
+    build_cli().get_matches_from(multi_call_args())
+}
+
+pub fn build_cli() -> clap::Command {
     clap::command!(crate_name!())
         .about("httm prints the size, date and corresponding locations of available unique versions of files residing on snapshots. \
         May also be used interactively to select and restore from such versions, and even to snapshot datasets which contain certain files.")
@@ -173,14 +346,20 @@ fn parse_args() -> ArgMatches {
             Arg::new("SELECT")
                 .short('s')
                 .long("select")
-                .value_parser(["path", "contents", "preview"])
+                .value_parser(["path", "contents", "raw-contents", "preview", "pairs", "edit"])
                 .num_args(0..=1)
                 .default_missing_value("path")
                 .require_equals(true)
                 .help("interactive browse and search a specified directory to display unique file versions. \
                 Continue to another dialog to select a snapshot version to dump to stdout. This argument optionally takes a value. \
-                Default behavior/value is to simply print the path name, but, if the path is a file, the user can print the file's contents by giving the value \"contents\", \
-                or print the PREVIEW output by giving the value \"preview\".")
+                Default behavior/value is to simply print the path name, but, if the path is a file, the user can print the file's contents by giving the value \"contents\". \
+                If the file's contents are not valid UTF-8, \"contents\" will print a summary and a bounded hex dump, instead of binary data. \
+                The value \"raw-contents\" always prints the file's raw bytes, for instance, when redirecting binary data to a file. \
+                The user may also print the PREVIEW output by giving the value \"preview\". \
+                The value \"pairs\" prints each selected snapshot version followed by its corresponding live path, \
+                which, when combined with ZEROS, can be piped into a tool like \"xargs -0 -n2\" to diff or merge each pair. \
+                The value \"edit\" opens a read-only temporary copy of each selected snapshot version in the user's $VISUAL or $EDITOR (or \"vi\", if neither is set). \
+                In any select mode, the user may also press \"ctrl+o\" in the select view to open the selection in an editor, instead of the requested behavior.")
                 .conflicts_with("RESTORE")
                 .display_order(3)
                 .action(ArgAction::Append)
@@ -189,7 +368,7 @@ fn parse_args() -> ArgMatches {
             Arg::new("RESTORE")
                 .short('r')
                 .long("restore")
-                .value_parser(["copy", "copy-and-preserve", "overwrite", "yolo", "guard"])
+                .value_parser(["copy", "copy-and-preserve", "overwrite", "yolo", "guard", "merge", "merge-guard", "last-snap", "last-snap-overwrite", "last-snap-guard"])
                 .num_args(0..=1)
                 .default_missing_value("copy")
                 .require_equals(true)
@@ -199,7 +378,17 @@ fn parse_args() -> ArgMatches {
                 Overwrite mode will attempt to preserve attributes, like the permissions/mode, timestamps, xattrs and ownership of the selected snapshot file version (this is and will likely remain a UNIX only feature). \
                 In order to preserve such attributes in \"copy\" mode, specify the \"copy-and-preserve\" value. User may also specify \"guard\". \
                 Guard mode has the same semantics as \"overwrite\" but will attempt to take a precautionary snapshot before any overwrite action occurs. \
-                Note: Guard mode is a ZFS only option. User may also set via the HTTM_RESTORE_MODE environment variable.")
+                Note: Guard mode is a ZFS and btrfs only option.  On btrfs, httm takes a read-only snapshot of the subvolume which contains the file, \
+                beneath a \".httm_snapshots\" directory at the root of that subvolume, which httm excludes from its searches, and in which httm keeps only the 8 most recent such snapshots. \
+                For text files, the user may instead specify \"merge\" to merge a snapshot version into the live file, one changed hunk at a time, \
+                choosing, for each hunk, whether to keep the live version or the snapshot version, before consenting to write the merged result to the live file. \
+                If the MERGE_TOOL environment variable is set, to a tool like \"vimdiff\", httm will instead launch that tool upon a working copy of the live file, \
+                followed by a read-only copy of the snapshot version, and any changes made to the working copy are the merged result. \
+                The merged result is written to the live file in place, so its ownership, xattrs, and hard links are kept, and \"merge-guard\" will first attempt to take a precautionary snapshot, as in guard mode. \
+                User may also set via the HTTM_RESTORE_MODE environment variable. \
+                Finally, the user may specify \"last-snap\", \"last-snap-overwrite\", or \"last-snap-guard\" to restore, without any interactive view or confirmation, \
+                the last snapshot version of each path given as an argument, or on stdin, with the semantics of \"copy\", \"overwrite\", or \"guard\" respectively. \
+                These modes are intended for scripts and cron jobs, where no TTY may be available.")
                 .conflicts_with("SELECT")
                 .display_order(4)
                 .action(ArgAction::Append)
@@ -246,8 +435,14 @@ fn parse_args() -> ArgMatches {
                 .long("preview")
                 .help("user may specify a command to preview snapshots while in a snapshot selection view. This argument optionally takes a value specifying the command to be executed. \
                 The default value/command, if no command value specified, is a 'bowie' formatted 'diff'. \
-                User defined commands must specify the snapshot file name \"{snap_file}\" and the live file name \"{live_file}\" within their shell command. \
-                NOTE: 'bash' is required to bootstrap any preview script, even if user defined preview commands or script is written in a different language.")
+                User defined commands may refer to the snapshot file name as \"$snap_file\", and to the live file name as \"$live_file\", within their shell command, \
+                as well as to the name of the snapshot as \"$snap_name\", the creation time of the snapshot as \"$snap_time\", and the dataset which contains the snapshot as \"$dataset\", \
+                for instance: \"diff -u $snap_file $live_file\".  httm quotes any such variable the user has not quoted, so paths which contain whitespace, or other special characters, are always safe. \
+                The older \"{snap_file}\" and \"{live_file}\" forms are also accepted. If a user defined command does not refer to the snapshot file, the snapshot file name is appended to the command. \
+                A preview command is executed directly, without any shell, unless PREVIEW_SHELL is specified. \
+                A preview command which runs longer than PREVIEW_TIMEOUT is killed. \
+                The user may instead specify the value \"builtin\", which requires no preview script nor any other executable, \
+                and displays the first and last lines of a text file, a hex dump of a binary file, the dimensions of a PNG, JPEG, GIF, or BMP image, or a listing of a directory.")
                 .value_parser(clap::value_parser!(String))
                 .num_args(0..=1)
                 .require_equals(true)
@@ -258,7 +453,7 @@ fn parse_args() -> ArgMatches {
         .arg(
             Arg::new("DEDUP_BY")
                 .long("dedup-by")
-                .value_parser(["disable", "all", "no-filter", "metadata", "contents"])
+                .value_parser(["disable", "all", "no-filter", "metadata", "contents", "content-by-blocks"])
                 .num_args(0..=1)
                 .visible_aliases(&["unique", "uniqueness"])
                 .default_missing_value("contents")
@@ -267,6 +462,7 @@ fn parse_args() -> ArgMatches {
                 in the sense that, modify time is not a precise measure of whether a file has actually changed. A program might overwrite a file with the same contents, \
                 or a user can simply update the modify time via 'touch'. If only this flag is specified, the \"contents\" option compares the actual file contents of file versions, if their sizes match, \
                 and overrides the default \"metadata\" behavior. The \"contents\" option can be expensive, as the file versions need to be read back and compared, and should probably only be used for smaller files. \
+                For larger files, the \"content-by-blocks\" option compares file contents in fixed size blocks, hashed in parallel, and stops reading at the first block which differs. \
                 Given how expensive this operation can be, for larger files or files with many versions, \"contents\" option is not shown in Interactive browse mode, \
                 but after a selection is made, can be utilized, when enabled, in Select or Restore modes. The \"disable\" \"all\" or \"no-filter\" option dumps all snapshot versions, and no attempt is made to determine if the file versions are distinct.")
                 .display_order(9)
@@ -342,7 +538,9 @@ fn parse_args() -> ArgMatches {
                 \"zfs destroy\" is a DESTRUCTIVE operation which *does not* only apply to the file in question, but the entire snapshot upon which it resides. \
                 Careless use may cause you to lose snapshot data you care about. \
                 This argument requires and will be filtered according to any values specified at LIST_SNAPS. \
-                User may also enable SELECT mode to make a granular selection of specific snapshots to prune. \
+                User may also enable SELECT mode to make a granular selection of specific snapshots to prune, and may limit those snapshots pruned via KEEP_LAST and OLDER_THAN. \
+                Before any snapshot is destroyed, httm displays those versions of the input file/s which exist only upon the snapshots to be destroyed, and so would disappear, \
+                and requires the user to type the number of snapshots to be destroyed.  Specify DRY_RUN to display what would be destroyed, without destroying anything. \
                 Note: This is a ZFS only option.")
                 .conflicts_with_all(&["BROWSE", "RESTORE", "ALT_REPLICATED", "REMOTE_DIR", "LOCAL_DIR"])                
                 .display_order(13)
@@ -402,15 +600,20 @@ fn parse_args() -> ArgMatches {
                 .short('0')
                 .long("zero")
                 .visible_alias("null")
-                .help("display the snapshot locations only, without extraneous information, delimited by a NULL character.")
-                .conflicts_with_all(&["RAW", "CSV", "NOT_SO_PRETTY"])
+                .help("display the snapshot locations only, without extraneous information, delimited by a NULL character. \
+                When combined with NOT_SO_PRETTY, display the ordinary tab delimited output, but with each line terminated by a NULL character, instead of a NEWLINE, \
+                so that even paths which contain a NEWLINE may be parsed. Any output which is terminated by a NEWLINE, including JSON, FORMAT, and the output of other modes, \
+                is terminated by a NULL character when this flag is specified.")
+                .conflicts_with_all(&["RAW", "CSV"])
                 .display_order(17)
                 .action(ArgAction::SetTrue)
         )
         .arg(
             Arg::new("CSV")
                 .long("csv")
-                .help("display all information, delimited by a comma.")
+                .help("display all information, delimited by a comma, as RFC 4180 style CSV. \
+                Each path is quoted, and any quotation mark within a path is doubled, so paths which contain commas, quotes, or newlines are preserved. \
+                The ordinary display of versions, as well as DELETED_REPORT, FILE_MOUNT and LIST_SNAPS output, begins with a header row naming each column.")
                 .conflicts_with_all(&["RAW", "ZEROS", "NOT_SO_PRETTY", "JSON"])
                 .display_order(18)
                 .action(ArgAction::SetTrue)
@@ -420,14 +623,14 @@ fn parse_args() -> ArgMatches {
                 .long("not-so-pretty")
                 .visible_aliases(&["tabs", "plain-jane", "not-pretty"])
                 .help("display the ordinary output, but tab delimited, without any pretty border lines.")
-                .conflicts_with_all(&["RAW", "ZEROS", "CSV"])
+                .conflicts_with_all(&["RAW", "CSV"])
                 .display_order(19)
                 .action(ArgAction::SetTrue)
         )
         .arg(
             Arg::new("JSON")
                 .long("json")
-                .help("display the ordinary output, but as formatted JSON.")
+                .help("display the ordinary output, but as formatted JSON. Each version includes its path, size, and modify time, as well as the dataset which contains it, and, for ZFS snapshot versions, the snapshot name.")
                 .conflicts_with_all(&["SELECT", "RESTORE"])
                 .display_order(20)
                 .conflicts_with_all(&["CSV"])
@@ -460,7 +663,7 @@ fn parse_args() -> ArgMatches {
         .arg(
             Arg::new("ONE_FILESYSTEM")
                 .long("one-filesystem")
-                .aliases(&["same-filesystem", "single-filesystem", "one-fs", "onefs"])
+                .aliases(&["one-file-system", "xdev", "same-filesystem", "single-filesystem", "one-fs", "onefs"])
                 .requires("RECURSIVE")
                 .help("limit recursive search to file and directories on the same filesystem/device as the target directory.")
                 .display_order(24)
@@ -516,6 +719,8 @@ fn parse_args() -> ArgMatches {
                 This option is useful if you wish to view snapshot versions from within the local directory you back up to a remote network share. \
                 This option requires a value. Such a value is delimited by a colon, ':', and is specified in the form <LOCAL_DIR>:<REMOTE_DIR> \
                 (eg. --map-aliases /Users/<User Name>:/Volumes/Home). Multiple maps may be specified delimited by a comma, ','. \
+                A REMOTE_DIR may also be the mount point of a dataset on another machine, reachable via SSH, specified in the form \"ssh://[user@]host[:port]/<REMOTE_DIR>\" \
+                (eg. --map-aliases /home:ssh://backup/tank/home), in which case httm mounts that remote dir, via \"sshfs\", for the duration of the program, and unmounts it upon exit. \
                 You may also set via the environment variable HTTM_MAP_ALIASES.")
                 .use_value_delimiter(true)
                 .value_parser(clap::builder::ValueParser::os_string())
@@ -575,196 +780,1508 @@ fn parse_args() -> ArgMatches {
         .arg(
             Arg::new("NO_CLONES")
                 .long("no-clones")
-                .help("by default, when copying files from snapshots, httm will first attempt a zero copy \"reflink\" clone on systems that support it. \
+                .visible_aliases(&["no-reflink"])
+                .help("by default, when copying files from snapshots, httm will first attempt a zero copy \"reflink\" clone on systems that support it, by way of FICLONE, and then copy_file_range, \
+                which, on ZFS, with block cloning enabled, and on btrfs and XFS, shares the source's data with the restored file, rather than copying it. \
                 Here, you may disable that behavior, and force httm to use the fall back diff copy behavior as the default. \
                 You may also set an environment variable to any value, \"HTTM_NO_CLONE\" to disable.")
                 .display_order(34)
                 .action(ArgAction::SetTrue)
         )
         .arg(
-            Arg::new("DEBUG")
-                .long("debug")
-                .help("print configuration and debugging info")
+            Arg::new("DIFF_SUMMARY")
+                .long("diff-summary")
+                .help("in the formatted display modes, display a terse line-level delta (like \"+12/-3\") between each file version and its predecessor. \
+                Only text files smaller than 1MiB are compared.  Note: moved lines are not counted as changes.")
+                .conflicts_with_all(&["RAW", "ZEROS", "CSV", "JSON"])
                 .display_order(35)
                 .action(ArgAction::SetTrue)
         )
         .arg(
-            Arg::new("ZSH_HOT_KEYS")
-                .long("install-zsh-hot-keys")
-                .help("install zsh hot keys to the users home directory, and then exit")
-                .exclusive(true)
+            Arg::new("RESTORE_JOBS")
+                .long("restore-jobs")
+                .help("when more than one snapshot version is selected for restore, httm will queue each restore, and perform restores in parallel. \
+                This argument specifies the maximum number of restores to perform at once.  The default is the number of available CPUs. \
+                httm asks for consent to the whole batch at once, unless the user chooses \"EACH\", to consent to each restore in turn. \
+                A restore which fails for a transient reason, like an interrupted system call, or a busy file, will be retried, before httm gives up, and reports a summary of failures.")
+                .requires("RESTORE")
+                .value_parser(clap::value_parser!(usize))
                 .display_order(36)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("RESTORE_JOURNAL")
+                .long("restore-journal")
+                .help("record each completed restore to the specified journal file.  If the journal file already exists, \
+                restores recorded in that journal are skipped, so an interrupted batch restore may be resumed by specifying the same journal again. \
+                Specifying a journal always queues restores, even if only one snapshot version is selected.")
+                .requires("RESTORE")
+                .value_parser(clap::value_parser!(PathBuf))
+                .display_order(37)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("SNAP_MOUNT")
+                .long("snap-mount")
+                .help("manually specify a mounted snapshot directory (eg. \"/mnt/snap-20230601\") as a source of file versions. \
+                Such snapshot mounts are the only version source for lookups under their local directory, and no alias or dataset mapping is required. \
+                This option requires a value, specified in the form <SNAP_MOUNT> or <LOCAL_DIR>:<SNAP_MOUNT> (eg. --snap-mount /home:/mnt/snap-20230601/home). \
+                If no local directory is specified, httm defaults to your current working directory.  This option may be specified multiple times.")
+                .value_parser(clap::builder::ValueParser::os_string())
+                .display_order(38)
+                .action(ArgAction::Append)
+        )
+        .arg(
+            Arg::new("LOOKUP_TIMEOUT")
+                .long("lookup-timeout")
+                .help("bound how long, in milliseconds, httm may spend looking up file versions in the non-interactive display modes. \
+                Once the timeout has elapsed, httm displays only those results already available, and warns that the results have been truncated. \
+                Useful for prompt and editor integrations, which should never block indefinitely on slow datasets.")
+                .value_parser(clap::value_parser!(u64))
+                .display_order(39)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("BROWSE_SORT")
+                .long("browse-sort")
+                .value_parser(["disable", "none", "dirs-first", "newest", "mtime", "largest", "size"])
+                .num_args(0..=1)
+                .default_missing_value("dirs-first")
+                .require_equals(true)
+                .help("order each batch of entries sent to the interactive browse view, before those entries are sent. \
+                The \"dirs-first\" option (the default, if only this flag is specified) places directories before files. \
+                The \"newest\" (or \"mtime\") option places directories first, and then orders each group by modify time, newest first. \
+                The \"largest\" (or \"size\") option places directories first, and then orders each group by size, largest first. \
+                Ordering only applies within each directory's batch, as entries are streamed as they are discovered. \
+                The \"newest\" and \"largest\" options require a metadata lookup for each entry, and may slow browsing of very large directories. \
+                The \"disable\" or \"none\" option (the default, if this flag is not specified) sends entries as they are found, for maximum streaming speed.")
+                .display_order(40)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("COMPARE_TWIN")
+                .long("compare-twin")
+                .visible_aliases(&["twin"])
+                .value_parser(clap::value_parser!(String))
+                .num_args(1)
+                .require_equals(true)
+                .help("compare each input file against its \"twin\" on a replica, such as a received replication target mounted locally, to verify replication health at the file level. \
+                Such a value is delimited by a colon, ':', and is specified in the form <LOCAL_DIR>:<TWIN_DIR> (eg. --compare-twin=/srv/data:/backup/srv/data), in the same manner as MAP_ALIASES. \
+                For each input file, httm compares the live version and the snapshot versions of the local file against those of the file at the same relative path within the twin directory, \
+                and reports whether the local file is \"in-sync\" with, \"ahead\" of, or \"behind\" its twin, or whether they \"conflict\", as well as the number of snapshot versions which exist only on each side. \
+                As elsewhere, versions are compared on the basis of size and modify time.")
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "PRUNE", "ROLL_FORWARD", "NUM_VERSIONS", "FILE_MOUNT", "LIST_SNAPS", "SNAPSHOT", "DELETED", "RECURSIVE"])
+                .display_order(41)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("EXEC")
+                .long("exec")
+                .value_parser(clap::value_parser!(String))
+                .num_args(1)
+                .require_equals(true)
+                .help("run a command once for each snapshot version path, instead of displaying or printing those paths, similar to 'find -exec'. \
+                In SELECT mode, the command is run on each selected snapshot version.  In the default display mode, the command is run on each snapshot version found, \
+                as limited by any other filters, such as LAST_SNAP or OMIT_DITTO. \
+                Within the command, \"{}\" is replaced with the version path, or, if \"{}\" is not present, the version path is appended as the last argument (eg. --exec=\"clamscan --no-summary {}\"). \
+                The command is run by the user's $SHELL, where that shell is a POSIX sh, like bash, zsh, or dash, or is fish, and otherwise by 'sh'.  httm reports a summary of any commands which fail, and exits with an error, if any did fail.")
+                .conflicts_with_all(&["RESTORE", "PRUNE", "ROLL_FORWARD", "NUM_VERSIONS", "FILE_MOUNT", "LIST_SNAPS", "SNAPSHOT", "COMPARE_TWIN"])
+                .display_order(42)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("EXEC_JOBS")
+                .long("exec-jobs")
+                .help("the maximum number of EXEC commands to run in parallel.  By default, httm uses the number of available CPUs.")
+                .value_parser(clap::value_parser!(usize))
+                .requires("EXEC")
+                .display_order(43)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("DIFF")
+                .long("diff")
+                .help("display a unified diff between a snapshot version and the live version of each input file. \
+                By default, httm diffs the most recent snapshot version which differs from the live version. \
+                When combined with SELECT, the diff between the highlighted snapshot version and the live version is displayed in the preview pane. \
+                Binary files are reported as differing, but are not diffed.")
+                .conflicts_with_all(&["RESTORE", "PRUNE", "ROLL_FORWARD", "NUM_VERSIONS", "FILE_MOUNT", "LIST_SNAPS", "SNAPSHOT", "COMPARE_TWIN", "EXEC", "PREVIEW", "DELETED", "LAST_SNAP"])
+                .display_order(44)
                 .action(ArgAction::SetTrue)
         )
-        .get_matches()
-}
-
-#[derive(Debug, Clone)]
-pub struct Config {
-    pub paths: Vec<PathData>,
-    pub opt_recursive: bool,
-    pub opt_exact: bool,
-    pub opt_no_filter: bool,
-    pub opt_debug: bool,
-    pub opt_no_traverse: bool,
-    pub opt_omit_ditto: bool,
-    pub opt_no_hidden: bool,
-    pub opt_json: bool,
-    pub opt_one_filesystem: bool,
-    pub opt_no_clones: bool,
-    pub dedup_by: DedupBy,
-    pub opt_bulk_exclusion: Option<BulkExclusion>,
-    pub opt_last_snap: Option<LastSnapMode>,
-    pub opt_preview: Option<String>,
-    pub opt_deleted_mode: Option<DeletedMode>,
-    pub opt_requested_dir: Option<PathBuf>,
-    pub requested_utc_offset: UtcOffset,
-    pub exec_mode: ExecMode,
-    pub print_mode: PrintMode,
-    pub dataset_collection: FilesystemInfo,
-    pub pwd: PathBuf,
-}
-
-impl Config {
-    pub fn new() -> HttmResult<Self> {
-        let arg_matches = parse_args();
-        let config = Config::from_matches(&arg_matches)?;
-        if config.opt_debug {
-            eprintln!("{config:#?}");
-        }
-        Ok(config)
-    }
-
-    fn from_matches(matches: &ArgMatches) -> HttmResult<Self> {
-        if matches.get_flag("ZSH_HOT_KEYS") {
-            install_hot_keys()?
-        }
-
-        let requested_utc_offset = if matches.get_flag("UTC") {
-            UtcOffset::UTC
-        } else {
-            // this fn is surprisingly finicky. it needs to be done
-            // when program is not multithreaded, etc., so we don't even print an
-            // error and we just default to UTC if something fails
-            UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC)
-        };
-
-        let opt_debug = matches.get_flag("DEBUG");
-
-        // current working directory will be helpful in a number of places
-        let pwd = pwd()?;
-
-        // obtain a map of datasets, a map of snapshot directories, and possibly a map of
-        // alternate filesystems and map of aliases if the user requests
-        let mut opt_map_aliases: Option<Vec<String>> =
-            matches.get_raw("MAP_ALIASES").map(|aliases| {
-                aliases
-                    .map(|os_str| os_str.to_string_lossy().to_string())
-                    .collect()
-            });
-
-        let opt_alt_store: Option<FilesystemType> = match matches
-            .get_one::<String>("ALT_STORE")
-            .map(|inner| inner.as_str())
-        {
-            Some("timemachine") => Some(FilesystemType::Apfs),
-            Some("restic") => Some(FilesystemType::Restic(None)),
-            _ => None,
-        };
-
-        if opt_alt_store.is_some() && opt_map_aliases.is_some() {
-            eprintln!(
-                "WARN: httm has disabled any MAP_ALIASES in preference to an ALT_STORE specified."
-            );
-            opt_map_aliases = None;
-        }
-
-        let opt_alt_replicated = matches.get_flag("ALT_REPLICATED");
-        let opt_remote_dir = matches.get_one::<String>("REMOTE_DIR");
-        let opt_local_dir = matches.get_one::<String>("LOCAL_DIR");
-
-        let dataset_collection = FilesystemInfo::new(
-            opt_alt_replicated,
-            opt_debug,
-            opt_remote_dir,
-            opt_local_dir,
-            opt_map_aliases,
-            opt_alt_store,
-            pwd.clone(),
-        )?;
-
-        let opt_json = matches.get_flag("JSON");
-
-        let mut print_mode = if matches.get_flag("CSV") {
-            PrintMode::Raw(RawMode::Csv)
-        } else if matches.get_flag("ZEROS") {
-            PrintMode::Raw(RawMode::Zero)
-        } else if matches.get_flag("RAW") {
-            PrintMode::Raw(RawMode::Newline)
-        } else if matches.get_flag("NOT_SO_PRETTY") {
-            PrintMode::Formatted(FormattedMode::NotPretty)
-        } else {
-            PrintMode::Formatted(FormattedMode::Default)
-        };
-
-        let opt_bulk_exclusion = if matches.get_flag("NO_LIVE") {
-            Some(BulkExclusion::NoLive)
-        } else if matches.get_flag("NO_SNAP") {
-            Some(BulkExclusion::NoSnap)
-        } else {
-            None
-        };
-
-        if let Some(BulkExclusion::NoSnap) = opt_bulk_exclusion {
-            if let PrintMode::Formatted(FormattedMode::Default) = print_mode {
-                return Err(HttmError::new(
-                    "NO_SNAP is only available if RAW or ZEROS are specified.",
-                )
-                .into());
-            }
-        }
-
-        // force a raw mode if one is not set for no_snap mode
-        let opt_one_filesystem = matches.get_flag("ONE_FILESYSTEM");
-        let opt_recursive = matches.get_flag("RECURSIVE");
-
-        let opt_exact = matches.get_flag("EXACT");
-        let opt_no_filter = matches.get_flag("NO_FILTER");
-        let opt_no_hidden = matches.get_flag("FILTER_HIDDEN");
-        let opt_no_clones =
-            matches.get_flag("NO_CLONES") || std::env::var_os("HTTM_NO_CLONE").is_some();
-
-        let opt_last_snap = match matches
-            .get_one::<String>("LAST_SNAP")
-            .map(|inner| inner.as_str())
-        {
-            Some("" | "any") => Some(LastSnapMode::Any),
-            Some("none" | "without") => Some(LastSnapMode::Without),
-            Some("ditto") => Some(LastSnapMode::DittoOnly),
-            Some("no-ditto-inclusive") => Some(LastSnapMode::NoDittoInclusive),
-            Some("no-ditto-exclusive" | "no-ditto") => Some(LastSnapMode::NoDittoExclusive),
-            _ => None,
-        };
-
-        let opt_num_versions = match matches
-            .get_one::<String>("NUM_VERSIONS")
-            .map(|inner| inner.as_str())
-        {
-            Some("" | "all") => Some(NumVersionsMode::AllNumerals),
-            Some("graph") => Some(NumVersionsMode::AllGraph),
-            Some("single") => Some(NumVersionsMode::SingleAll),
-            Some("single-no-snap") => Some(NumVersionsMode::SingleNoSnap),
-            Some("single-with-snap") => Some(NumVersionsMode::SingleWithSnap),
-            Some("multiple") => Some(NumVersionsMode::Multiple),
-            _ => None,
-        };
-
-        if matches!(opt_num_versions, Some(NumVersionsMode::AllGraph))
-            && !matches!(print_mode, PrintMode::Formatted(FormattedMode::Default))
-        {
-            return Err(HttmError::new("The NUM_VERSIONS graph mode and the RAW or ZEROS display modes are an invalid combination.").into());
-        }
-
+        .arg(
+            Arg::new("DIFF_PAIR")
+                .long("diff-pair")
+                .value_names(["SNAP_FILE", "LIVE_FILE"])
+                .help("display a unified diff between the specified snapshot file and live file. Used internally by the DIFF preview.")
+                .value_parser(clap::value_parser!(PathBuf))
+                .num_args(2)
+                .hide(true)
+                .exclusive(true)
+                .display_order(45)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("PREVIEW_RENDER")
+                .long("preview-render")
+                .value_name("SELECTION")
+                .help("display the builtin preview of the path quoted within the specified selection line. Used internally by the \"builtin\" PREVIEW.")
+                .value_parser(clap::value_parser!(String))
+                .allow_hyphen_values(true)
+                .hide(true)
+                .exclusive(true)
+                .display_order(45)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("PREVIEW_EXEC")
+                .long("preview-exec")
+                .value_names(["COMMAND", "LIVE_FILE", "SELECTION"])
+                .help("execute the specified preview command directly, without any shell, upon the path quoted within the specified selection line. \
+                Used internally by PREVIEW, when PREVIEW_SHELL is not specified.")
+                .value_parser(clap::value_parser!(String))
+                .num_args(3)
+                .allow_hyphen_values(true)
+                .hide(true)
+                .conflicts_with_all(&["PREVIEW", "SELECT", "RESTORE", "BROWSE"])
+                .display_order(45)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("PREVIEW_VARIABLES")
+                .long("preview-variables")
+                .value_name("SNAP_FILE")
+                .help("print the snapshot name, snapshot creation time, and dataset of the specified snapshot file, as shell assignments. \
+                Used internally by PREVIEW commands which use the \"$snap_name\", \"$snap_time\", or \"$dataset\" variables.")
+                .value_parser(clap::value_parser!(PathBuf))
+                .hide(true)
+                .exclusive(true)
+                .display_order(45)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("PREVIEW_WRAP")
+                .long("preview-wrap")
+                .help("wrap long lines within the PREVIEW pane, when the pane is first displayed. \
+                Whether or not this flag is specified, wrapping may be toggled within the selection view with the \"ctrl+w\" key.")
+                .display_order(46)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("PREVIEW_LINE_NUMBERS")
+                .long("preview-line-numbers")
+                .visible_aliases(&["line-numbers"])
+                .help("display line numbers within the PREVIEW pane. The default \"cat\" previewer numbers each line. \
+                For user defined preview commands, httm sets the environment variable HTTM_PREVIEW_LINE_NUMBERS, \
+                and replaces the variable \"{line_numbers}\" within the command with \"--number\" (eg. --preview=\"bat --color=always {line_numbers} {snap_file}\"), \
+                or removes the variable, when this flag is not specified.")
+                .display_order(47)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("SUMMARY")
+                .long("summary")
+                .help("in the non-interactive deleted modes, instead of displaying each deleted file, display the number and the total size of deleted files per directory, and a grand total. \
+                The size of a deleted file is the size of its most recent snapshot version. Useful for deciding where to focus recovery efforts within large trees.")
+                .requires("DELETED")
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "NO_LIVE", "NO_SNAP", "LAST_SNAP"])
+                .display_order(48)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("AUTO_MOUNT_SNAPS")
+                .long("auto-mount-snaps")
+                .help("when httm finds no snapshots at a ZFS dataset's \".zfs/snapshot\" directory, for instance, because the dataset's snapdir property is \"disabled\", \
+                or when none of a NILFS2 device's snapshots are mounted, mount each of that dataset's snapshots, read-only, beneath a temporary directory, on demand, \
+                and unmount those snapshots before httm exits. NILFS2 snapshots are listed with \"lscp\", from nilfs-utils, and only checkpoints marked as snapshots may be mounted. \
+                Note: This is a ZFS and NILFS2 only option which requires super user privileges.")
+                .display_order(49)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("PREVIEW_TIMEOUT")
+                .long("preview-timeout")
+                .help("the number of seconds after which a PREVIEW command is killed, so that a runaway preview command never hangs the selection view. The default is 10 seconds.")
+                .value_parser(clap::value_parser!(u64))
+                .display_order(50)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("BORG_REPO")
+                .long("borg-repo")
+                .help("use the archives of a borg repository as a source of file versions for a local directory. \
+                httm mounts the repository, via \"borg mount\", for the duration of the program, and unmounts it upon exit. \
+                This option requires a value, specified in the form <REPO>:<LOCAL_DIR> (eg. --borg-repo /backup/borg:/home). \
+                Any passphrase required must be entered when prompted, or supplied via BORG_PASSPHRASE.  This option may be specified multiple times.")
+                .value_parser(clap::builder::ValueParser::os_string())
+                .display_order(51)
+                .action(ArgAction::Append)
+        )
+        .arg(
+            Arg::new("RESTIC_REPO")
+                .long("restic-repo")
+                .help("use the snapshots of a restic repository as a source of file versions for a local directory. \
+                httm mounts the repository, via \"restic mount\", for the duration of the program, and unmounts it upon exit. \
+                This option requires a value, specified in the form <REPO>:<LOCAL_DIR> (eg. --restic-repo /backup/restic:/home). \
+                Any password required must be entered when prompted, or supplied via RESTIC_PASSWORD or RESTIC_PASSWORD_FILE.  This option may be specified multiple times.")
+                .value_parser(clap::builder::ValueParser::os_string())
+                .display_order(51)
+                .action(ArgAction::Append)
+        )
+        .arg(
+            Arg::new("RELATIVE")
+                .long("relative")
+                .help("display paths relative to a base directory, for piping into tools which expect relative paths. \
+                This argument optionally takes a value. The default value, \"dir\", displays paths relative to the requested directory, or, if none, the current working directory. \
+                The value \"mount\" displays each path relative to the mount of the dataset containing it, or, for an aliased path, relative to its local directory. \
+                Any other value is treated as a custom prefix directory. Paths which do not reside beneath their base are displayed in full.")
+                .value_parser(clap::value_parser!(String))
+                .num_args(0..=1)
+                .default_missing_value("dir")
+                .require_equals(true)
+                .display_order(52)
+                .action(ArgAction::Append)
+        )
+        .arg(
+            Arg::new("RESTORE_TO")
+                .long("restore-to")
+                .help("restore to the specified directory, instead of the current working directory.  \
+                When more than one snapshot version is restored, the directory structure the versions do not share is preserved beneath the destination. \
+                Restored files are named as in the default \"copy\" restore mode.  This option cannot be used with an overwrite restore mode. \
+                This option requires RESTORE or RESTORE_TREE.")
+                .value_parser(clap::value_parser!(PathBuf))
+                .display_order(53)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("VERIFY")
+                .long("verify")
+                .help("after each restore, verify the contents of every restored file matches the contents of its snapshot version, by hashing both. \
+                For a restored directory, httm verifies, and reports upon, every file within. Any mismatch is treated as a restore failure.")
+                .requires("RESTORE")
+                .display_order(54)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("LATEST_PER_DIR")
+                .long("latest-per-dir")
+                .visible_aliases(&["rollup"])
+                .help("for each file contained in the directories specified, display one row, which includes only the newest snapshot version, \
+                and whether that version differs from the live version (\"same\", \"differs\", or \"no-snap\"). \
+                Useful as an overview of what would change were the whole directory restored from the latest snapshot. \
+                In the RAW and ZEROS modes, httm prints only those newest snapshot versions which differ from their live versions. \
+                If no directory is specified, httm uses the current working directory.")
+                .conflicts_with_all(&["SELECT", "RESTORE", "BROWSE", "LAST_SNAP", "NUM_VERSIONS", "DELETED"])
+                .display_order(55)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("SNAPS_FOR_VERSION")
+                .long("snaps-for-version")
+                .visible_aliases(&["snaps-containing"])
+                .help("display the names of exactly those snapshots which contain a specific version of the input file/s. \
+                This argument requires a value, a path to the reference version (eg. one snapshot version displayed by httm), and every snapshot version which matches it is included. \
+                By default, versions match when their size and modify time are the same.  Specify \"--dedup-by=contents\" to match versions by their contents instead. \
+                Useful before pruning, to learn how many snapshots hold a particular version. \
+                Snapshot names are available for ZFS and btrfs snapshots.  For other snapshots, httm displays the snapshot version's path.")
+                .value_parser(clap::value_parser!(PathBuf))
+                .require_equals(true)
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "PRUNE", "LIST_SNAPS", "LAST_SNAP", "NUM_VERSIONS", "DELETED"])
+                .display_order(56)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("CONTINUE")
+                .long("continue")
+                .visible_aliases(&["keep-browsing"])
+                .help("after an interactive restore completes, or is declined or aborted, return to the browse view, with its prior query intact, \
+                so multiple files may be recovered in a single session.  Abort the browse view to quit.  With TUI, return to the TUI after each restore.  \
+                Note: This option requires either RESTORE or TUI.")
+                .display_order(57)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("EXPORT")
+                .long("export")
+                .help("write the versions of the input files, and a hash of the contents of each version, to a checksummed JSON file, instead of displaying them. \
+                Use \"--verify-export\" later to confirm the versions reviewed remain unchanged.  Note: the checksum detects accidental modification of the export file, \
+                but is not a cryptographic signature.")
+                .value_parser(clap::value_parser!(PathBuf))
+                .require_equals(true)
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "PRUNE", "LIST_SNAPS", "LAST_SNAP", "NUM_VERSIONS", "DELETED", "SNAPS_FOR_VERSION", "LATEST_PER_DIR"])
+                .display_order(58)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("VERIFY_EXPORT")
+                .long("verify-export")
+                .visible_aliases(&["import"])
+                .help("read a file created with \"--export\", confirm its checksum, and then confirm each version it references still exists, and still matches its exported hash. \
+                Each version is displayed with its status: unchanged, modified, or missing.  httm exits with an error if any version no longer matches.")
+                .value_parser(clap::value_parser!(PathBuf))
+                .require_equals(true)
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "PRUNE", "LIST_SNAPS", "LAST_SNAP", "NUM_VERSIONS", "DELETED", "SNAPS_FOR_VERSION", "LATEST_PER_DIR", "EXPORT"])
+                .display_order(59)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("FOLLOW")
+                .long("follow")
+                .visible_aliases(&["follow-symlinks"])
+                .help("when an input file is a symlink, resolve the symlink, and display the versions of its target, instead of the versions of the link itself. \
+                httm notes each input path it resolves to a target.  A symlink whose target cannot be resolved is looked up as the link itself.")
+                .display_order(60)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("TRAVERSE_NESTED")
+                .long("traverse-nested")
+                .help("btrfs snapshots do not include the contents of nested subvolumes, which appear on a snapshot only as empty directories. \
+                By default, httm warns when an input path is contained within a nested btrfs subvolume, which is not separately mounted. \
+                When this flag is specified, and the nested subvolume has snapper snapshots of its own (in its \".snapshots\" directory), \
+                httm searches those snapshots for versions of such paths instead.")
+                .display_order(61)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("EXCLUDE")
+                .long("exclude")
+                .visible_aliases(&["exclude-glob"])
+                .help("in the recursive or interactive modes, do not show, or descend into, files or directories which match the glob pattern specified. \
+                A pattern may contain '*', which matches any run of characters, and '?', which matches any single character. \
+                A pattern without a '/' is compared to the file name only (eg. \"node_modules\", or \"*.tmp\"), otherwise a pattern is compared to the full path (eg. \"/home/*/.cache\"). \
+                This argument may be specified multiple times, or as a comma delimited list.")
+                .value_delimiter(',')
+                .display_order(62)
+                .action(ArgAction::Append)
+        )
+        .arg(
+            Arg::new("STATS")
+                .long("stats")
+                .help("instead of displaying each version, display an aggregate report across all the input files: how many have snapshot versions, \
+                the total number of unique versions, the oldest and newest versions, and how many files and versions belong to each dataset. \
+                Useful when many files are requested via stdin.  Specify \"--json\" for JSON output.")
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "PRUNE", "LIST_SNAPS", "LAST_SNAP", "NUM_VERSIONS", "DELETED", "SNAPS_FOR_VERSION", "LATEST_PER_DIR", "EXPORT", "VERIFY_EXPORT"])
+                .display_order(63)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("SOFT_DELETE")
+                .long("soft-delete")
+                .visible_aliases(&["safe-delete"])
+                .help("delete the input files, but only once a snapshot is certain to contain the current version of each.  When the newest snapshot does not contain the current version of a file, \
+                or the input file is a directory, httm first takes a snapshot of its dataset, and then deletes the file only after the user's consent. \
+                Taking a new snapshot is only supported on ZFS datasets, and requires the appropriate privileges (root or 'zfs allow').")
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "PRUNE", "LIST_SNAPS", "LAST_SNAP", "NUM_VERSIONS", "DELETED", "SNAPS_FOR_VERSION", "LATEST_PER_DIR", "EXPORT", "VERIFY_EXPORT", "STATS", "OMIT_DITTO"])
+                .display_order(64)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("MIN_SIZE")
+                .long("min-size")
+                .help("in interactive browse, only display files at least this size.  Sizes may be specified in bytes, or with a suffix K, M, G, or T (powers of 1024), eg. \"10M\". \
+                Directories are never excluded by size, and are still searched.  Size filters require a metadata lookup for each entry.")
+                .value_parser(parse_size)
+                .require_equals(true)
+                .display_order(65)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("MAX_SIZE")
+                .long("max-size")
+                .help("in interactive browse, only display files no larger than this size.  Sizes may be specified in bytes, or with a suffix K, M, G, or T (powers of 1024), eg. \"512K\". \
+                Directories are never excluded by size, and are still searched.  Size filters require a metadata lookup for each entry.")
+                .value_parser(parse_size)
+                .require_equals(true)
+                .display_order(66)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("ENTRY_TYPE")
+                .long("type")
+                .help("in interactive browse, only display entries of this type: \"f\" (regular files), \"d\" (directories), or \"l\" (symlinks). \
+                Directories which are not displayed are still searched.")
+                .value_parser(["f", "d", "l"])
+                .require_equals(true)
+                .display_order(67)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("LOOKUP_CACHE")
+                .long("lookup-cache")
+                .help("remember the snapshot versions found for each file in an on-disk cache, beneath \"$XDG_CACHE_HOME/httm\" (or \"~/.cache/httm\"), \
+                to speed up repeated runs over the same files.  Because snapshots are immutable, the cached versions for a dataset remain valid until \
+                a snapshot of that dataset is taken or destroyed, or a mutable source, like a dated backup dir or a borg repo, is modified, at which time httm discards them.  You may also set the environment variable HTTM_LOOKUP_CACHE to enable.")
+                .display_order(68)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("FORCE_PRETTY")
+                .long("force-pretty")
+                .help("when $TERM is unset or \"dumb\", or when httm is run beneath watch, httm displays its ordinary output as if NOT_SO_PRETTY were specified, \
+                without border lines or colors.  Use this flag to display the pretty output anyway.")
+                .conflicts_with_all(&["RAW", "ZEROS", "CSV", "NOT_SO_PRETTY"])
+                .display_order(69)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("DATASET")
+                .long("dataset")
+                .help("pin the dataset mounts httm should consider (eg. --dataset /home --dataset /srv), to speed up startup on systems with many datasets, \
+                or to limit results to only those datasets.  httm will discover the snapshots of only these datasets, and paths on any other dataset will have no versions. \
+                This option requires the mount point of a supported dataset, and may be specified multiple times.")
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with("ALT_STORE")
+                .display_order(70)
+                .action(ArgAction::Append)
+        )
+        .arg(
+            Arg::new("DELETED_ORDER")
+                .long("deleted-order")
+                .value_parser(["deleted", "newest", "mtime", "name"])
+                .require_equals(true)
+                .help("order deleted files, within each directory, before those files are displayed or sent to the interactive view, \
+                so triage may begin with the most recently lost files.  The \"deleted\" option orders by an estimate of when each file was deleted, newest first. \
+                This estimate is the modify time of the file's parent directory on the newest snapshot which still contains the file, and the file was deleted sometime after. \
+                The \"newest\" (or \"mtime\") option orders by the modify time of each file's last snapshot version, newest first. \
+                The \"name\" option orders by file name.  When a deleted file appears on more than one snapshot, the \"deleted\" and \"newest\" options \
+                also display the file as found on the snapshot ranked latest.")
+                .requires("DELETED")
+                .display_order(71)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("PREVIEW_PAGER")
+                .long("preview-pager")
+                .help("pipe the output of any PREVIEW through the specified command (eg. --preview-pager=\"bat --color=always --paging=never\"), \
+                for instance, to highlight or otherwise format previews.  The command is executed by 'sh', and must read the preview on its stdin, \
+                and write to its stdout, as it is displayed within the preview pane, and cannot be interactive.")
+                .value_parser(clap::value_parser!(String))
+                .require_equals(true)
+                .display_order(72)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("SNAP_DIFF")
+                .long("snap-diff")
+                .value_names(["SNAP1", "SNAP2"])
+                .help("report the files added, removed and modified between two snapshots of the dataset which contains the input directory (or the present working directory, if none is specified), \
+                beneath that directory, with the size and modify time of each version. A snapshot may be specified by its name (eg. \"autosnap_2024-01-01_00:00:00_daily\"), \
+                by its full ZFS name (eg. \"rpool/data@autosnap_2024-01-01_00:00:00_daily\"), by any trailing portion of its snapshot mount's path (eg. \"5/snapshot\", for a Snapper snapshot), or by the path of its snapshot mount. \
+                Files whose size or modify time differ are hashed, and are reported as modified only if their contents differ.")
+                .value_parser(clap::value_parser!(String))
+                .num_args(2)
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "PRUNE", "ROLL_FORWARD", "NUM_VERSIONS", "FILE_MOUNT", "LIST_SNAPS", "SNAPSHOT", "DELETED", "RECURSIVE", "COMPARE_TWIN"])
+                .display_order(73)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("TUI")
+                .long("tui")
+                .help("browse a directory in a multi-pane view, as an alternative to the BROWSE, SELECT and RESTORE views: a file pane, a timeline of the selected file's snapshot versions, oldest first, \
+                and a preview pane, which displays either the selected version or a diff of the selected version against the live version. \
+                Keybindings: tab switches panes, enter opens a directory, left returns to the parent directory, \"d\" toggles the diff, \"r\" restores a copy of the selected version, \
+                \"y\" copies the selected path to the clipboard (by way of the OSC 52 escape sequence), and \"q\" quits. \
+                Note: This option requires that httm be built with the \"tui\" feature.")
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "PRUNE", "ROLL_FORWARD", "NUM_VERSIONS", "FILE_MOUNT", "LIST_SNAPS", "SNAPSHOT", "DELETED", "RECURSIVE", "COMPARE_TWIN", "SNAP_DIFF"])
+                .display_order(74)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("ON_CONFLICT")
+                .long("on-conflict")
+                .value_parser(["skip", "overwrite", "rename-numbered", "ask"])
+                .require_equals(true)
+                .help("in the non-overwrite RESTORE modes, how httm should proceed when a file already exists at the path to which a snapshot version would be restored. \
+                By default, httm refuses to restore to that path, and quits, before any file is restored. \
+                The \"skip\" option skips restoring that snapshot version, and continues with the rest.  The \"overwrite\" option overwrites the existing file. \
+                The \"rename-numbered\" option restores to the same path, with the lowest available number appended (eg. \"file.httm_restored.<timestamp>.1\"). \
+                The \"ask\" option asks the user to choose among these options, for each such file.  Each option applies per file, so a bulk restore of many files need not abort halfway through.")
+                .requires("RESTORE")
+                .display_order(75)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("RESTORE_TREE")
+                .long("restore-tree")
+                .value_name("TIMESTAMP")
+                .help("reconstruct the input directory (or the present working directory, if none is specified) as it was at the specified time, \
+                by copying, for every file ever found beneath that directory, including those beneath directories since deleted, the version from the newest snapshot created no later than the specified time, or the live version, if its modify time is no newer than the specified time. \
+                The reconstructed directory is a new directory, named in the same manner as a \"copy\" restore, beneath the present working directory, or RESTORE_TO. \
+                A timestamp may be specified in the form \"YYYY-MM-DD\", \"YYYY-MM-DD HH:MM:SS\", or \"YYYY-MM-DD-HH:MM:SS\", in the local time zone, unless UTC is specified, or as seconds since the epoch, in the form \"@SECONDS\". \
+                Note: httm cannot know when a file was deleted, and so a file which was deleted before the specified time is also restored, if a version exists which is no newer than the specified time.")
+                .value_parser(clap::value_parser!(String))
+                .require_equals(true)
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "PRUNE", "ROLL_FORWARD", "NUM_VERSIONS", "FILE_MOUNT", "LIST_SNAPS", "SNAPSHOT", "DELETED", "RECURSIVE", "COMPARE_TWIN", "SNAP_DIFF", "TUI"])
+                .display_order(76)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("COLLAPSE_IDENTICAL")
+                .long("collapse-identical")
+                .help("in the formatted display modes, display each run of adjacent snapshot versions whose contents are identical as a single row, \
+                annotated with the number of versions in that run and the range of their modify times, so the versions at which the contents actually changed stand out. \
+                By default, versions are deduplicated by size and modify time, so identical contents with different modify times (for instance, a file which was only touched) remain. \
+                Note: Each such version is hashed, which may be slow for large files.")
+                .conflicts_with_all(&["RAW", "ZEROS", "CSV", "JSON"])
+                .display_order(77)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("SINCE")
+                .long("since")
+                .value_name("TIMESTAMP")
+                .help("display only those snapshot versions whose modify time is no older than the specified time. \
+                A timestamp may be specified in the form \"YYYY-MM-DD\", \"YYYY-MM-DD HH:MM:SS\", or \"YYYY-MM-DD-HH:MM:SS\", in the local time zone, unless UTC is specified, \
+                as seconds since the epoch, in the form \"@SECONDS\", or relative to the present, as \"now\", \"today\", \"yesterday\", or \"<NUMBER> <UNIT> ago\" (eg. \"3 days ago\"). \
+                This filter is applied before any display or interactive selection, and before OMIT_DITTO and LAST_SNAP.")
+                .value_parser(clap::value_parser!(String))
+                .display_order(78)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("UNTIL")
+                .long("until")
+                .value_name("TIMESTAMP")
+                .help("display only those snapshot versions whose modify time is no newer than the specified time. \
+                A timestamp may be specified in any of the forms accepted by SINCE.")
+                .value_parser(clap::value_parser!(String))
+                .display_order(79)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("ZFS_LIST")
+                .long("zfs-list")
+                .help("discover ZFS snapshots, at startup, with a single \"zfs list\" command, instead of by reading the snapshot directory of each dataset. \
+                This may be much faster on systems with datasets which have thousands of snapshots, and works even if the \"snapdir\" property is \"hidden\" \
+                on platforms where reading a hidden snapshot directory fails.  If the \"zfs\" command is not available, httm falls back to reading the snapshot directory.")
+                .conflicts_with("ALT_STORE")
+                .display_order(80)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("DATED_BACKUP_DIR")
+                .long("dated-backup-dir")
+                .help("use the subdirectories of a directory of rsync-style, or rsnapshot, backups (eg. \"backup.0\", \"backup.1\", or \"2024-01-01\") as a source of file versions for a local directory. \
+                Each subdirectory is treated as a snapshot of the local directory, and may be a copy of the local directory itself, or may contain the full path of the local directory, \
+                perhaps beneath a single directory named for the backup point (eg. \"daily.0/localhost/home\"). \
+                Backups are ordered by the time specified in their names, where a name is a timestamp, and otherwise by their modify times. \
+                This option requires a value, specified in the form <BACKUP_DIR>:<LOCAL_DIR> (eg. --dated-backup-dir /backup/rsnapshot:/home).  This option may be specified multiple times.")
+                .value_parser(clap::builder::ValueParser::os_string())
+                .display_order(81)
+                .action(ArgAction::Append)
+        )
+        .arg(
+            Arg::new("KEEP_LAST")
+                .long("keep-last")
+                .value_name("NUMBER")
+                .help("in PRUNE mode, never prune the specified number of the most recent snapshots which contain each input file. \
+                Where a number of snapshots to omit is also specified via LIST_SNAPS, the greater number of snapshots is kept.")
+                .value_parser(clap::value_parser!(usize))
+                .require_equals(true)
+                .requires("PRUNE")
+                .display_order(82)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("OLDER_THAN")
+                .long("older-than")
+                .value_name("AGE")
+                .help("in PRUNE mode, prune only those snapshots created more than the specified age ago, specified in the form \"<NUMBER><UNIT>\", \
+                where the unit is one of \"s\", \"m\", \"h\", \"d\", or \"w\" (eg. \"30d\").  Snapshots whose creation time cannot be determined are never pruned.")
+                .value_parser(clap::value_parser!(String))
+                .require_equals(true)
+                .requires("PRUNE")
+                .display_order(83)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("DRY_RUN")
+                .long("dry-run")
+                .help("print exactly what httm would do, and exit, without modifying any file or snapshot. \
+                This option applies to every operation which writes: RESTORE (including any overwrite, and RESTORE_TREE), PRUNE, ROLL_FORWARD, SNAPSHOT, and SOFT_DELETE, \
+                as well as to any precautionary snapshot, or roll back to such a snapshot.  Source and destination paths, and snapshot names, are printed as they would be used.")
+                .display_order(84)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("IGNORE_VCS")
+                .long("ignore-vcs")
+                .help("when enumerating the entries of a directory, as in BROWSE, or RECURSIVE and DELETED modes, exclude those entries ignored by any \".gitignore\" or \".ignore\" file, \
+                in that entry's directory or in any directory above it, up to the root of its repository, as well as version control directories like \".git\".  \
+                As with ripgrep, a \".gitignore\" file applies only within a repository.  Deleted files are found and excluded in just the same manner.")
+                .display_order(85)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("GREP")
+                .long("grep")
+                .value_name("PATTERN")
+                .help("search every snapshot version of the input files, and their live versions, for lines which match the specified regular expression, \
+                and report which versions contain, and which lack, the pattern, oldest first.  Where an input is a directory, each version of that directory is searched, \
+                and the files beneath it which contain the pattern are listed.  Binary contents are searched as well, and the pattern is matched against each line, \
+                so a pattern cannot match across a line break.")
+                .value_parser(clap::value_parser!(String))
+                .require_equals(true)
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "PRUNE", "ROLL_FORWARD", "NUM_VERSIONS", "FILE_MOUNT", "LIST_SNAPS", "SNAPSHOT", "DELETED", "RECURSIVE", "COMPARE_TWIN", "SNAP_DIFF", "TUI", "RESTORE_TREE"])
+                .display_order(86)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("GREP_APPEARANCE")
+                .long("appearance")
+                .help("in GREP mode, also report the version in which the pattern first appears, the version in which it last appears, \
+                and, if the pattern no longer appears in the newest version, the version from which it has since been removed.")
+                .requires("GREP")
+                .display_order(87)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("BISECT")
+                .long("bisect")
+                .value_name("COMMAND")
+                .help("like \"git bisect\", binary search the versions of a single file, oldest first, and ending with the live version, if one exists, for the first version which is \"bad\". \
+                The oldest version must be \"good\", and the newest version must be \"bad\".  When a test COMMAND is specified, the command is run by the user's $SHELL (or, where httm does not know how that shell quotes, by \"sh\") once for each version tested, \
+                with the path of that version in the environment variable \"$snap_file\", and the path of the live file in \"$live_file\", in the manner of \"git bisect run\": \
+                a command which exits with status 0 marks the version \"good\", one which exits with status 125 skips the version, and any other status, from 1 to 127, marks the version \"bad\". \
+                Any other status aborts the search.  When no command is specified, httm asks the user, at the terminal, whether each version tested is good or bad.")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("")
+                .value_parser(clap::value_parser!(String))
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "PRUNE", "ROLL_FORWARD", "NUM_VERSIONS", "FILE_MOUNT", "LIST_SNAPS", "SNAPSHOT", "DELETED", "RECURSIVE", "COMPARE_TWIN", "SNAP_DIFF", "TUI", "RESTORE_TREE", "GREP"])
+                .display_order(88)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("SNAP_INFO")
+                .long("snap-info")
+                .help("display, for each snapshot version, the name and creation time of the snapshot which contains it, so that versions with identical modify times may be told apart. \
+                In the formatted modes, this information follows each path, in the CSV mode, it is two additional columns, and, in the JSON mode, it is two additional fields. \
+                The creation time of a ZFS snapshot is as reported by the \"zfs\" command.  The creation time of any other snapshot is the birth time of its snapshot mount, where available.")
+                .conflicts_with_all(&["RAW", "ZEROS"])
+                .display_order(89)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("NUMBERED")
+                .long("numbered")
+                .help("in the formatted display modes, number each snapshot version, such that the newest snapshot version is numbered 1, the next newest 2, and so on. \
+                These are the numbers by which SELECT_BY selects a version.")
+                .conflicts_with_all(&["RAW", "ZEROS", "CSV", "JSON"])
+                .display_order(90)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("SELECT_BY")
+                .long("select-by")
+                .value_name("INDEX")
+                .help("automatically select, for each input file, the snapshot version with the specified number, where the newest snapshot version is numbered 1, the next newest 2, and so on, \
+                see NUMBERED.  Like LAST_SNAP, when combined with SELECT, no interactive view is displayed, and the selection is printed as SELECT would print it, for instance, as a path or as contents. \
+                A file with fewer snapshot versions than the specified number has no selection.")
+                .value_parser(clap::value_parser!(usize))
+                .require_equals(true)
+                .conflicts_with_all(&["LAST_SNAP", "NUM_VERSIONS", "SNAPSHOT", "FILE_MOUNT", "ALT_REPLICATED", "REMOTE_DIR", "LOCAL_DIR", "PREVIEW"])
+                .display_order(91)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("EXPORT_VERSIONS")
+                .long("export-versions")
+                .value_name("DIR")
+                .help("materialize every unique snapshot version of the input files, and each live version, beneath the specified directory, for instance, for use with an external diff tool. \
+                Each version is placed in a subdirectory named for the creation time of its snapshot, where available, or otherwise its modify time, or, for a live version, in the subdirectory \"live\", \
+                at the same path as its live file, relative to the root directory.  Where possible, each version is hard linked, else, reflinked, else, copied.  Existing files are never overwritten.")
+                .value_parser(clap::value_parser!(PathBuf))
+                .require_equals(true)
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "PRUNE", "LIST_SNAPS", "LAST_SNAP", "NUM_VERSIONS", "DELETED", "SNAPS_FOR_VERSION", "LATEST_PER_DIR", "EXPORT", "VERIFY_EXPORT"])
+                .display_order(92)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("NO_HARD_LINKS")
+                .long("no-hard-links")
+                .help("by default, when restoring a directory tree, httm recreates, at the destination, any hard links among the files of that tree, \
+                such that several paths which share an inode in the snapshot will also share an inode once restored. \
+                Here, you may disable that behavior, and restore each such path as an independent copy.")
+                .display_order(93)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("PROFILE")
+                .long("profile")
+                .value_name("NAME")
+                .help("use the arguments of a named profile, such as a set of MAP_ALIASES, an ALT_STORE, or a preferred uniqueness level, \
+                for instance, a \"work\" and a \"homelab\" profile. \
+                A profile is a file, at \"$XDG_CONFIG_HOME/httm/profiles/<NAME>\" (or \"~/.config/httm/profiles/<NAME>\"), which contains one argument per line, \
+                in the form \"--map-aliases=/home/user:/srv/backup/user\". Blank lines, and lines beginning with '#', are ignored. \
+                Any argument specified on the command line takes precedence over that of the profile. \
+                You may also set the profile via the environment variable HTTM_PROFILE.")
+                .display_order(94)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("COMPLETIONS")
+                .long("completions")
+                .value_name("SHELL")
+                .value_parser(clap::value_parser!(Shell))
+                .help("print a shell completion script, for the shell specified, and then exit. \
+                For bash, zsh, and fish, the script also completes snapshot names, as values of LIST_SNAPS, by asking httm which snapshots exist at the time of completion. \
+                For instance, add \"source <(httm --completions=bash)\" to your \".bashrc\".")
+                .exclusive(true)
+                .display_order(95)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("COMPLETE_SNAP_NAMES")
+                .long("complete-snap-names")
+                .help("print the name of every snapshot of every dataset, one per line, and then exit, for use by shell completion scripts")
+                .hide(true)
+                .exclusive(true)
+                .display_order(96)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("EVERY")
+                .long("every")
+                .value_name("INTERVAL")
+                .help("in SNAPSHOT mode, take a snapshot of the datasets which contain the input files, and then another every interval specified, \
+                in the foreground, until interrupted. The interval is specified in the form \"<NUMBER><UNIT>\", where the unit is one of \"s\", \"m\", \"h\", \"d\", or \"w\" (eg. \"1h\"). \
+                An error upon a scheduled snapshot is reported, but does not stop the schedule.")
+                .value_parser(clap::value_parser!(String))
+                .requires("SNAPSHOT")
+                .display_order(97)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("KEEP")
+                .long("keep")
+                .value_name("NUMBER")
+                .help("in SNAPSHOT mode, after each snapshot is taken, keep only the specified number of the most recent snapshots, of each dataset snapshotted, \
+                which httm took with the snapshot suffix requested, and destroy the rest. httm marks each such snapshot with the ZFS user property \"httm:schedule\", \
+                whose value is the suffix. Snapshots without that exact mark, however they are named, are never destroyed.")
+                .value_parser(clap::value_parser!(usize))
+                .requires("SNAPSHOT")
+                .display_order(98)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("SYSTEMD")
+                .long("systemd")
+                .value_name("DIR")
+                .help("rather than snapshot in the foreground, write a systemd service and timer pair, to the directory specified (eg. \"/etc/systemd/system\"), \
+                which will take a snapshot, and apply any KEEP, at the interval specified via EVERY.")
+                .value_parser(clap::value_parser!(PathBuf))
+                .requires("EVERY")
+                .display_order(99)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("UNDO_LAST_RESTORE")
+                .long("undo-last-restore")
+                .help("undo the most recent restore recorded in httm's audit log, which has not already been undone, after asking for confirmation. \
+                Where the restore overwrote a live file, httm restores the previous state of that file, from the precautionary snapshot taken before the restore (see RESTORE=guard), \
+                or, without such a snapshot, from the most recent snapshot version which differs from the version restored. \
+                Where the restore created a copy, httm removes that copy. Specify again to step back through earlier restores.")
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "PRUNE", "ROLL_FORWARD", "RESTORE_TREE"])
+                .display_order(100)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("TRASH")
+                .long("trash")
+                .help("when restoring with RESTORE=overwrite or RESTORE=guard, before overwriting a live file, first move that live file into a trash directory, \
+                for instance, for filesystems upon which httm cannot take a precautionary snapshot.  The trash directory is the XDG trash (\"$XDG_DATA_HOME/Trash\" or \"~/.local/share/Trash\"), \
+                where that trash resides on the same filesystem as the live file, else a \".httm_trash\" directory at the root of the live file's filesystem. \
+                As with the XDG trash, each live file is placed beneath \"files\", and its original location and the time it was trashed are recorded beneath \"info\", \
+                such that most trash utilities may restore it.")
+                .requires("RESTORE")
+                .display_order(101)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("HASH_JOBS")
+                .long("hash-jobs")
+                .help("when comparing file contents, for instance, when DEDUP_BY=contents is specified, httm reads and hashes file versions upon a thread pool of its own, \
+                separate from the thread pool used to search directories, so that hashing may not saturate a disk and stall interactive views. \
+                This argument specifies the maximum number of files, or blocks of files, to hash at once.  The default is the number of available CPUs.")
+                .value_parser(clap::value_parser!(usize))
+                .require_equals(true)
+                .display_order(102)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("HASH_RATE")
+                .long("hash-rate")
+                .help("limit the rate at which httm reads file contents, in order to hash and compare them, to the specified number of bytes per second, across all hashing threads. \
+                Rates may be specified in bytes, or with a suffix K, M, G, or T (powers of 1024), eg. \"50M\".")
+                .value_parser(parse_size)
+                .require_equals(true)
+                .display_order(103)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("QUIET")
+                .short('q')
+                .long("quiet")
+                .help("by default, while httm searches for the snapshot versions of the paths given, httm displays, on stderr, a progress bar, \
+                with the number of snapshots scanned and versions found, or, before an interactive select view, a spinner.  Here, you may disable such progress indication.  \
+                Progress is never displayed where stderr is not a terminal.")
+                .display_order(104)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("DELETED_DEPTH")
+                .long("deleted-depth")
+                .help("when searching recursively for deleted files, limit how many levels of directories, behind a deleted directory, httm will search. \
+                A value of 0 has the same effect as DELETED=single.  Once the limit is reached, httm displays only those results already found, \
+                and warns that the results may be incomplete.")
+                .value_parser(clap::value_parser!(usize))
+                .require_equals(true)
+                .requires("DELETED")
+                .display_order(105)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("DELETED_TIMEOUT")
+                .long("deleted-timeout")
+                .help("when searching recursively for deleted files, bound how long, in seconds, httm may spend searching for deleted files within, and behind, any one directory. \
+                Once the timeout has elapsed, httm displays only those results already found, and warns that the results may be incomplete, \
+                such that a deep search, behind deleted directories, never hangs a browse session.")
+                .value_parser(clap::value_parser!(u64))
+                .require_equals(true)
+                .requires("DELETED")
+                .display_order(106)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("DELETED_REPORT")
+                .long("deleted-report")
+                .help("recursively search the directory given for deleted files, as with \"--deleted=only --recursive\", but, instead of displaying each version, \
+                print a manifest of each deleted file: its original path, the last snapshot which contains it, the path of that snapshot version, its size, and its modify time. \
+                The manifest may be printed as JSON (see JSON), or as CSV (see CSV).  In the RAW or ZEROS modes, httm prints only the original path of each deleted file, \
+                such that the manifest may be piped straight into a batch restore, for instance: \"httm --deleted-report --raw /home/user | httm --restore=last-snap\".")
+                .conflicts_with_all(&["BROWSE", "SELECT", "RESTORE", "SUMMARY", "NO_LIVE", "NO_SNAP", "LAST_SNAP", "DELETED"])
+                .display_order(107)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("CHANGE_DETECTION")
+                .long("change-detection")
+                .value_parser(["mtime", "ctime", "mtime+size", "content"])
+                .require_equals(true)
+                .help("select how httm judges whether one version of a file differs from another, when deduplicating versions, when omitting a snapshot version identical to the live version (OMIT_DITTO), \
+                and when determining whether the last snapshot version matches the live version (LAST_SNAP). \
+                The \"mtime+size\" option (the default) compares modify time and size. The \"mtime\" option compares only modify time. \
+                The \"ctime\" option compares only change time, which, unlike modify time, cannot be set from user space, but which may also change upon a change of ownership or permissions. \
+                The \"content\" option compares the contents of versions whose sizes match, and, when DEDUP_BY is not specified, also deduplicates versions by contents.")
+                .display_order(108)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("FORMAT")
+                .long("format")
+                .value_parser(clap::value_parser!(String))
+                .require_equals(true)
+                .help("display each version, or, with DELETED_REPORT, each deleted file, according to a template, in place of the ordinary output. \
+                A template names each column within braces, in the order desired, for instance: --format=\"{path}\\t{size}\\t{mtime}\\t{snapshot}\". \
+                Available columns are: \"path\", \"snap_path\", the path of the snapshot version, \"snapshot\", the snapshot name, \"size\", the human readable size, \"bytes\", the size in bytes, \
+                \"mtime\", the modify time as displayed, and \"timestamp\", the modify time as a sortable timestamp, as in CSV output. \
+                Columns which do not apply, such as the snapshot name of a live version, are left empty. \
+                The escapes \"\\t\" and \"\\n\" are accepted, and \"{{\" and \"}}\" produce literal braces. \
+                Each line is delimited by a NEWLINE character, or, when ZEROS is specified, by a NULL character.")
+                .conflicts_with_all(&["JSON", "CSV", "RAW", "NOT_SO_PRETTY"])
+                .display_order(109)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("BADGES")
+                .long("badges")
+                .help("in the interactive browse view, annotate each entry with a badge, like \"[3 snaps]\", the number of unique snapshot versions of that entry, or \"[deleted]\", for a deleted entry. \
+                Badges are computed in the background, upon a pool of their own, and each entry is displayed, with its badge, once its lookup completes.")
+                .display_order(110)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("PREVIEW_SHELL")
+                .long("preview-shell")
+                .help("execute a user defined PREVIEW command within a shell, via a bootstrap script, as httm once did by default, for instance, so the command may be a pipeline, or use other shell syntax. \
+                By default, httm instead executes a preview command directly, without any shell, by splitting the command into arguments on whitespace, as a shell would, respecting any quotes, \
+                and substituting each preview variable, like \"$snap_file\", as an argument, or within an argument, so that no path, however strangely named, is ever reparsed. \
+                Within the bootstrap script, the command itself is executed by the user's $SHELL, where that shell is a POSIX sh, like bash, zsh, or dash, or is fish, and otherwise by 'sh'. \
+                NOTE: 'bash', or, should 'bash' be unavailable, 'sh', is required to bootstrap any preview script.")
+                .requires("PREVIEW")
+                .display_order(111)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("DEBUG")
+                .long("debug")
+                .help("print configuration and debugging info")
+                .display_order(112)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("ZSH_HOT_KEYS")
+                .long("install-zsh-hot-keys")
+                .help("install zsh hot keys to the users home directory, and then exit")
+                .exclusive(true)
+                .display_order(113)
+                .action(ArgAction::SetTrue)
+        )
+}
+
+// when invoked via a symlink named for a mode, like "httm-restore" or "httm-deleted", httm behaves,
+// busybox style, as if that mode's arguments were specified.  A user may override the arguments
+// for any such name, or add new names, via an environment variable, like HTTM_RESTORE_ARGS.
+fn multi_call_args() -> Vec<OsString> {
+    let mut args: Vec<OsString> = std::env::args_os().collect();
+
+    if let Some(mode_args) = mode_args(&args) {
+        merge_args(&mut args, mode_args);
+    }
+
+    // as a profile's arguments are merged last, the arguments of the mode take precedence
+    if let Some(profile_args) = profile_args(&args) {
+        merge_args(&mut args, profile_args);
+    }
+
+    args
+}
+
+fn mode_args(args: &[OsString]) -> Option<Vec<String>> {
+    let mode_name = args
+        .first()
+        .and_then(|arg0| Path::new(arg0).file_name())
+        .and_then(|file_name| file_name.to_str())
+        .and_then(|file_name| file_name.strip_prefix("httm-"))?;
+
+    let env_key = format!("HTTM_{}_ARGS", mode_name.to_uppercase().replace('-', "_"));
+
+    let mode_args: Vec<String> = match std::env::var(&env_key) {
+        Ok(env_args) => env_args.split_whitespace().map(String::from).collect(),
+        Err(_) => match mode_name {
+            "restore" => vec!["--restore".to_owned()],
+            "deleted" => vec!["--deleted".to_owned()],
+            "browse" => vec!["--browse".to_owned()],
+            "select" => vec!["--select".to_owned()],
+            _ => return None,
+        },
+    };
+
+    Some(mode_args)
+}
+
+// a profile is a named set of arguments, such as a set of aliases, alternate datasets, and a
+// uniqueness level, kept in a file of one argument per line, and selected via --profile
+// or HTTM_PROFILE.  Blank lines, and lines beginning with '#', are ignored.
+fn profile_args(args: &[OsString]) -> Option<Vec<String>> {
+    let profile_name = profile_name(args)?;
+    let contents = std::fs::read_to_string(profile_path(&profile_name)?).ok()?;
+
+    let profile_args = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect();
+
+    Some(profile_args)
+}
+
+fn profile_name(args: &[OsString]) -> Option<String> {
+    let mut iter = args
+        .iter()
+        .skip(1)
+        .filter_map(|arg| arg.to_str())
+        .take_while(|arg| *arg != "--");
+
+    while let Some(arg) = iter.next() {
+        if arg == "--profile" {
+            return iter.next().map(String::from);
+        }
+
+        if let Some(profile_name) = arg.strip_prefix("--profile=") {
+            return Some(profile_name.to_owned());
+        }
+    }
+
+    std::env::var("HTTM_PROFILE").ok()
+}
+
+// a profile name names a file within the profiles dir, and so may never name a path outside of it
+fn is_valid_profile_name(profile_name: &str) -> bool {
+    !matches!(profile_name, "" | "." | "..") && !profile_name.contains(['/', '\0'])
+}
+
+// $XDG_CONFIG_HOME/httm/profiles/<NAME>, else $HOME/.config/httm/profiles/<NAME>
+fn profile_path(profile_name: &str) -> Option<PathBuf> {
+    if !is_valid_profile_name(profile_name) {
+        return None;
+    }
+
+    let config_home = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(xdg_config_home) => PathBuf::from(xdg_config_home),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(config_home.join("httm").join("profiles").join(profile_name))
+}
+
+// arguments the user specifies on the command line take precedence over those merged.  A flag may
+// take its value on the following line, as "--map-aliases" then "/home/user:/srv/backup/user", so
+// a flag and any values which follow it are kept, or dropped, together
+fn merge_args(args: &mut Vec<OsString>, merged_args: Vec<String>) {
+    let user_args: Vec<&str> = args.iter().skip(1).filter_map(|arg| arg.to_str()).collect();
+
+    let is_overridden = |flag: &str| {
+        user_args
+            .iter()
+            .any(|user_arg| user_arg.split('=').next().unwrap_or(user_arg) == flag)
+    };
+
+    let mut is_dropping = false;
+
+    let merged_args: Vec<OsString> = merged_args
+        .into_iter()
+        .filter(|merged_arg| {
+            let flag = merged_arg.split('=').next().unwrap_or(merged_arg);
+
+            if flag.starts_with('-') {
+                is_dropping = is_overridden(flag);
+            }
+
+            !is_dropping
+        })
+        .map(OsString::from)
+        .collect();
+
+    args.splice(1..1, merged_args);
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub paths: Vec<PathData>,
+    pub opt_recursive: bool,
+    pub opt_exact: bool,
+    pub opt_no_filter: bool,
+    pub opt_debug: bool,
+    pub opt_no_traverse: bool,
+    pub opt_omit_ditto: bool,
+    pub opt_no_hidden: bool,
+    pub opt_json: bool,
+    pub opt_one_filesystem: bool,
+    pub opt_no_clones: bool,
+    pub opt_diff_summary: bool,
+    pub opt_restore_journal: Option<PathBuf>,
+    pub restore_jobs: usize,
+    pub opt_lookup_timeout: Option<Duration>,
+    pub browse_sort: BrowseSort,
+    pub opt_exec: Option<String>,
+    pub exec_jobs: usize,
+    pub opt_preview_wrap: bool,
+    pub opt_preview_line_numbers: bool,
+    pub opt_summary: bool,
+    pub opt_auto_mount_snaps: bool,
+    pub preview_timeout: Duration,
+    pub opt_relative: Option<RelativeTo>,
+    pub opt_restore_to: Option<PathBuf>,
+    pub opt_verify: bool,
+    pub opt_continue: bool,
+    pub opt_follow: bool,
+    pub opt_traverse_nested: bool,
+    pub opt_exclude: Option<Vec<String>>,
+    pub opt_entry_filters: Option<EntryFilters>,
+    pub opt_lookup_cache: bool,
+    pub opt_deleted_order: Option<DeletedOrder>,
+    pub opt_preview_pager: Option<String>,
+    pub opt_on_conflict: Option<OnConflict>,
+    pub opt_collapse_identical: bool,
+    pub opt_time_window: Option<TimeWindow>,
+    pub opt_dry_run: bool,
+    pub opt_ignore_vcs: bool,
+    pub opt_snap_info: bool,
+    pub opt_select_by: Option<usize>,
+    pub opt_numbered: bool,
+    pub opt_no_hard_links: bool,
+    pub opt_trash: bool,
+    pub hash_jobs: usize,
+    pub opt_hash_rate: Option<u64>,
+    pub opt_quiet: bool,
+    pub opt_deleted_depth: Option<usize>,
+    pub opt_deleted_timeout: Option<Duration>,
+    pub opt_deleted_report: bool,
+    pub change_detection: ChangeDetection,
+    pub opt_format: Option<FormatTemplate>,
+    pub opt_zero: bool,
+    pub opt_badges: bool,
+    pub opt_preview_shell: bool,
+    pub dedup_by: DedupBy,
+    pub opt_bulk_exclusion: Option<BulkExclusion>,
+    pub opt_last_snap: Option<LastSnapMode>,
+    pub opt_preview: Option<String>,
+    pub opt_deleted_mode: Option<DeletedMode>,
+    pub opt_requested_dir: Option<PathBuf>,
+    pub requested_utc_offset: UtcOffset,
+    pub exec_mode: ExecMode,
+    pub print_mode: PrintMode,
+    pub dataset_collection: FilesystemInfo,
+    pub pwd: PathBuf,
+}
+
+impl Config {
+    pub fn new() -> HttmResult<Self> {
+        let arg_matches = parse_args();
+        let config = Config::from_matches(&arg_matches)?;
+        if config.opt_debug {
+            eprintln!("{config:#?}");
+        }
+        Ok(config)
+    }
+
+    fn from_matches(matches: &ArgMatches) -> HttmResult<Self> {
+        if matches.get_flag("ZSH_HOT_KEYS") {
+            install_hot_keys()?
+        }
+
+        if let Some(shell) = matches.get_one::<Shell>("COMPLETIONS") {
+            Completions::print(*shell)?
+        }
+
+        let requested_utc_offset = if matches.get_flag("UTC") {
+            UtcOffset::UTC
+        } else {
+            // this fn is surprisingly finicky. it needs to be done
+            // when program is not multithreaded, etc., so we don't even print an
+            // error and we just default to UTC if something fails
+            UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC)
+        };
+
+        let opt_debug = matches.get_flag("DEBUG");
+
+        // a profile's arguments have already been merged, but a missing profile should not go unnoticed
+        let opt_profile_name = matches
+            .get_one::<String>("PROFILE")
+            .cloned()
+            .or_else(|| std::env::var("HTTM_PROFILE").ok());
+
+        if let Some(profile_name) = opt_profile_name {
+            if !is_valid_profile_name(&profile_name) {
+                let msg = format!(
+                    "The profile specified, {:?}, is not a valid profile name.  A profile name may not contain a path separator.",
+                    profile_name
+                );
+                return Err(HttmError::new(&msg).into());
+            }
+
+            match profile_path(&profile_name) {
+                Some(path) if path.is_file() => {}
+                opt_path => {
+                    let msg = format!(
+                        "The profile specified, {:?}, could not be found at: {:?}",
+                        profile_name,
+                        opt_path.unwrap_or_default()
+                    );
+                    return Err(HttmError::new(&msg).into());
+                }
+            }
+        }
+
+        // current working directory will be helpful in a number of places
+        let pwd = pwd()?;
+
+        // obtain a map of datasets, a map of snapshot directories, and possibly a map of
+        // alternate filesystems and map of aliases if the user requests
+        let mut opt_map_aliases: Option<Vec<String>> =
+            matches.get_raw("MAP_ALIASES").map(|aliases| {
+                aliases
+                    .map(|os_str| os_str.to_string_lossy().to_string())
+                    .collect()
+            });
+
+        let opt_alt_store: Option<FilesystemType> = match matches
+            .get_one::<String>("ALT_STORE")
+            .map(|inner| inner.as_str())
+        {
+            Some("timemachine") => Some(FilesystemType::Apfs),
+            Some("restic") => Some(FilesystemType::Restic(None)),
+            _ => None,
+        };
+
+        if opt_alt_store.is_some() && opt_map_aliases.is_some() {
+            eprintln!(
+                "WARN: httm has disabled any MAP_ALIASES in preference to an ALT_STORE specified."
+            );
+            opt_map_aliases = None;
+        }
+
+        let opt_raw_snap_mounts: Option<Vec<String>> =
+            matches.get_raw("SNAP_MOUNT").map(|snap_mounts| {
+                snap_mounts
+                    .map(|os_str| os_str.to_string_lossy().to_string())
+                    .collect()
+            });
+
+        let opt_raw_borg_repos: Option<Vec<String>> =
+            matches.get_raw("BORG_REPO").map(|borg_repos| {
+                borg_repos
+                    .map(|os_str| os_str.to_string_lossy().to_string())
+                    .collect()
+            });
+
+        let opt_raw_restic_repos: Option<Vec<String>> =
+            matches.get_raw("RESTIC_REPO").map(|restic_repos| {
+                restic_repos
+                    .map(|os_str| os_str.to_string_lossy().to_string())
+                    .collect()
+            });
+
+        let opt_raw_dated_backup_dirs: Option<Vec<String>> = matches
+            .get_raw("DATED_BACKUP_DIR")
+            .map(|dated_backup_dirs| {
+                dated_backup_dirs
+                    .map(|os_str| os_str.to_string_lossy().to_string())
+                    .collect()
+            });
+
+        let opt_pinned_datasets: Option<Vec<PathBuf>> = matches
+            .get_many::<PathBuf>("DATASET")
+            .map(|datasets| datasets.cloned().collect());
+
+        let opt_alt_replicated = matches.get_flag("ALT_REPLICATED");
+        let opt_zfs_list = matches.get_flag("ZFS_LIST");
+        let opt_remote_dir = matches.get_one::<String>("REMOTE_DIR");
+        let opt_local_dir = matches.get_one::<String>("LOCAL_DIR");
+
+        let dataset_collection = FilesystemInfo::new(
+            opt_alt_replicated,
+            opt_debug,
+            opt_remote_dir,
+            opt_local_dir,
+            opt_map_aliases,
+            opt_alt_store,
+            opt_raw_snap_mounts,
+            opt_raw_borg_repos,
+            opt_raw_restic_repos,
+            opt_raw_dated_backup_dirs,
+            opt_pinned_datasets,
+            opt_zfs_list,
+            pwd.clone(),
+        )?;
+
+        if matches.get_flag("COMPLETE_SNAP_NAMES") {
+            Completions::print_snap_names(&dataset_collection)?
+        }
+
+        let opt_json = matches.get_flag("JSON");
+        let opt_zero = matches.get_flag("ZEROS");
+
+        let mut print_mode = if matches.get_flag("CSV") {
+            PrintMode::Raw(RawMode::Csv)
+        } else if matches.get_flag("ZEROS") && !matches.get_flag("NOT_SO_PRETTY") {
+            PrintMode::Raw(RawMode::Zero)
+        } else if matches.get_flag("RAW") {
+            PrintMode::Raw(RawMode::Newline)
+        } else if matches.get_flag("NOT_SO_PRETTY") {
+            PrintMode::Formatted(FormattedMode::NotPretty)
+        } else if !matches.get_flag("FORCE_PRETTY") && Terminal::is_plain() {
+            // a dumb terminal or watch would only display the escape codes and border lines as garbage
+            PrintMode::Formatted(FormattedMode::NotPretty)
+        } else {
+            PrintMode::Formatted(FormattedMode::Default)
+        };
+
+        let opt_bulk_exclusion = if matches.get_flag("NO_LIVE") {
+            Some(BulkExclusion::NoLive)
+        } else if matches.get_flag("NO_SNAP") {
+            Some(BulkExclusion::NoSnap)
+        } else {
+            None
+        };
+
+        if let Some(BulkExclusion::NoSnap) = opt_bulk_exclusion {
+            if let PrintMode::Formatted(FormattedMode::Default) = print_mode {
+                return Err(HttmError::new(
+                    "NO_SNAP is only available if RAW or ZEROS are specified.",
+                )
+                .into());
+            }
+        }
+
+        // force a raw mode if one is not set for no_snap mode
+        let opt_one_filesystem = matches.get_flag("ONE_FILESYSTEM");
+        let opt_recursive = matches.get_flag("RECURSIVE") || matches.get_flag("DELETED_REPORT");
+
+        let opt_exact = matches.get_flag("EXACT");
+        let opt_no_filter = matches.get_flag("NO_FILTER");
+        let opt_no_hidden = matches.get_flag("FILTER_HIDDEN");
+        let opt_entry_filters = {
+            let opt_min_size = matches.get_one::<u64>("MIN_SIZE").copied();
+            let opt_max_size = matches.get_one::<u64>("MAX_SIZE").copied();
+            let opt_entry_type = match matches.get_one::<String>("ENTRY_TYPE").map(String::as_str) {
+                Some("f") => Some(EntryType::File),
+                Some("d") => Some(EntryType::Dir),
+                Some("l") => Some(EntryType::Symlink),
+                _ => None,
+            };
+
+            if opt_min_size.is_none() && opt_max_size.is_none() && opt_entry_type.is_none() {
+                None
+            } else {
+                Some(EntryFilters {
+                    opt_min_size,
+                    opt_max_size,
+                    opt_entry_type,
+                })
+            }
+        };
+        let opt_exclude: Option<Vec<String>> = matches
+            .get_many::<String>("EXCLUDE")
+            .map(|patterns| patterns.map(|pattern| pattern.to_owned()).collect());
+        let opt_no_clones =
+            matches.get_flag("NO_CLONES") || std::env::var_os("HTTM_NO_CLONE").is_some();
+        let opt_diff_summary = matches.get_flag("DIFF_SUMMARY");
+        let opt_lookup_timeout = matches
+            .get_one::<u64>("LOOKUP_TIMEOUT")
+            .map(|millis| Duration::from_millis(*millis));
+        let opt_auto_mount_snaps = matches.get_flag("AUTO_MOUNT_SNAPS");
+        let opt_summary = matches.get_flag("SUMMARY");
+        let preview_timeout = matches
+            .get_one::<u64>("PREVIEW_TIMEOUT")
+            .map(|secs| Duration::from_secs(*secs))
+            .unwrap_or(DEFAULT_PREVIEW_TIMEOUT);
+        let opt_preview_wrap = matches.get_flag("PREVIEW_WRAP");
+        let opt_preview_line_numbers = matches.get_flag("PREVIEW_LINE_NUMBERS");
+
+        let opt_preview_shell = matches.get_flag("PREVIEW_SHELL");
+        let opt_exec = matches.get_one::<String>("EXEC").cloned();
+        let exec_jobs = match matches.get_one::<usize>("EXEC_JOBS") {
+            Some(0) => {
+                return Err(HttmError::new("EXEC_JOBS must be a value greater than 0.").into())
+            }
+            Some(value) => *value,
+            None => std::thread::available_parallelism()
+                .map(|value| value.get())
+                .unwrap_or(1usize),
+        };
+        let opt_verify = matches.get_flag("VERIFY");
+        let opt_continue = matches.get_flag("CONTINUE");
+
+        if opt_continue && !matches.contains_id("RESTORE") && !matches.get_flag("TUI") {
+            return Err(HttmError::new("CONTINUE requires either RESTORE or TUI.").into());
+        }
+        let opt_follow = matches.get_flag("FOLLOW");
+        let opt_collapse_identical = matches.get_flag("COLLAPSE_IDENTICAL");
+        let opt_badges = matches.get_flag("BADGES");
+
+        let opt_format = match matches.get_one::<String>("FORMAT") {
+            Some(template) => Some(FormatTemplate::new(template)?),
+            None => None,
+        };
+        let opt_deleted_report = matches.get_flag("DELETED_REPORT");
+        let opt_deleted_depth = matches.get_one::<usize>("DELETED_DEPTH").copied();
+        let opt_deleted_timeout = matches
+            .get_one::<u64>("DELETED_TIMEOUT")
+            .map(|secs| Duration::from_secs(*secs));
+        let opt_quiet = matches.get_flag("QUIET");
+        let opt_trash = matches.get_flag("TRASH");
+        let opt_no_hard_links = matches.get_flag("NO_HARD_LINKS");
+        let opt_numbered = matches.get_flag("NUMBERED");
+        let opt_select_by = match matches.get_one::<usize>("SELECT_BY") {
+            Some(0) => {
+                return Err(HttmError::new(
+                    "SELECT_BY must be a value greater than 0, as the newest snapshot version is numbered 1.",
+                )
+                .into())
+            }
+            opt_index => opt_index.copied(),
+        };
+        let opt_snap_info = matches.get_flag("SNAP_INFO");
+        let opt_dry_run = matches.get_flag("DRY_RUN");
+        let opt_ignore_vcs = matches.get_flag("IGNORE_VCS");
+
+        let opt_since = matches
+            .get_one::<String>("SINCE")
+            .map(|input| parse_timestamp(input, requested_utc_offset))
+            .transpose()?;
+
+        let opt_until = matches
+            .get_one::<String>("UNTIL")
+            .map(|input| parse_timestamp(input, requested_utc_offset))
+            .transpose()?;
+
+        let opt_time_window = match (opt_since, opt_until) {
+            (None, None) => None,
+            (Some(since), Some(until)) if since > until => {
+                return Err(HttmError::new("SINCE must be no newer than UNTIL.").into());
+            }
+            (opt_since, opt_until) => Some(TimeWindow {
+                opt_since,
+                opt_until,
+            }),
+        };
+        let opt_preview_pager = matches.get_one::<String>("PREVIEW_PAGER").cloned();
+        let opt_lookup_cache =
+            matches.get_flag("LOOKUP_CACHE") || std::env::var_os("HTTM_LOOKUP_CACHE").is_some();
+        let opt_traverse_nested = matches.get_flag("TRAVERSE_NESTED");
+        let opt_restore_journal = matches.get_one::<PathBuf>("RESTORE_JOURNAL").cloned();
+        let restore_jobs = match matches.get_one::<usize>("RESTORE_JOBS") {
+            Some(0) => {
+                return Err(HttmError::new("RESTORE_JOBS must be a value greater than 0.").into())
+            }
+            Some(value) => *value,
+            None => std::thread::available_parallelism()
+                .map(|value| value.get())
+                .unwrap_or(1usize),
+        };
+
+        let hash_jobs = match matches.get_one::<usize>("HASH_JOBS") {
+            Some(0) => {
+                return Err(HttmError::new("HASH_JOBS must be a value greater than 0.").into())
+            }
+            Some(value) => *value,
+            None => std::thread::available_parallelism()
+                .map(|value| value.get())
+                .unwrap_or(1usize),
+        };
+        let opt_hash_rate = match matches.get_one::<u64>("HASH_RATE") {
+            Some(0) => {
+                return Err(HttmError::new("HASH_RATE must be a value greater than 0.").into())
+            }
+            opt_value => opt_value.copied(),
+        };
+
+        let opt_last_snap = match matches
+            .get_one::<String>("LAST_SNAP")
+            .map(|inner| inner.as_str())
+        {
+            Some("" | "any") => Some(LastSnapMode::Any),
+            Some("none" | "without") => Some(LastSnapMode::Without),
+            Some("ditto") => Some(LastSnapMode::DittoOnly),
+            Some("no-ditto-inclusive") => Some(LastSnapMode::NoDittoInclusive),
+            Some("no-ditto-exclusive" | "no-ditto") => Some(LastSnapMode::NoDittoExclusive),
+            _ => None,
+        };
+
+        let opt_num_versions = match matches
+            .get_one::<String>("NUM_VERSIONS")
+            .map(|inner| inner.as_str())
+        {
+            Some("" | "all") => Some(NumVersionsMode::AllNumerals),
+            Some("graph") => Some(NumVersionsMode::AllGraph),
+            Some("single") => Some(NumVersionsMode::SingleAll),
+            Some("single-no-snap") => Some(NumVersionsMode::SingleNoSnap),
+            Some("single-with-snap") => Some(NumVersionsMode::SingleWithSnap),
+            Some("multiple") => Some(NumVersionsMode::Multiple),
+            _ => None,
+        };
+
+        if matches!(opt_num_versions, Some(NumVersionsMode::AllGraph))
+            && !matches!(print_mode, PrintMode::Formatted(FormattedMode::Default))
+        {
+            return Err(HttmError::new("The NUM_VERSIONS graph mode and the RAW or ZEROS display modes are an invalid combination.").into());
+        }
+
         let opt_mount_display = match matches
             .get_one::<String>("FILE_MOUNT")
             .map(|inner| inner.as_str())
@@ -781,6 +2298,10 @@ impl Config {
         {
             Some("" | "default") => Some("default".to_owned()),
             Some(user_defined) => Some(user_defined.to_string()),
+            // the DIFF preview is our own diff, see DIFF_PAIR
+            None if matches.get_flag("DIFF") && matches.contains_id("SELECT") => {
+                Some("diff".to_owned())
+            }
             None => None,
         };
 
@@ -791,13 +2312,27 @@ impl Config {
             Some("" | "all") => Some(DeletedMode::All),
             Some("single") => Some(DeletedMode::DepthOfOne),
             Some("only") => Some(DeletedMode::Only),
+            // a deleted report is always of deleted files only, at any depth, see DeletedReport
+            _ if matches.get_flag("DELETED_REPORT") => Some(DeletedMode::Only),
             _ => None,
         };
 
         let opt_select_mode = matches.get_one::<String>("SELECT");
         let opt_restore_mode = matches.get_one::<String>("RESTORE");
 
-        let opt_interactive_mode = if let Some(var_restore_mode) = opt_restore_mode {
+        // the "last-snap" restore modes restore without any interactive view, see NonInteractiveRestore
+        let opt_batch_restore_mode = match opt_restore_mode.map(|inner| inner.as_str()) {
+            Some("last-snap") => Some(RestoreMode::CopyOnly),
+            Some("last-snap-overwrite") => {
+                Some(RestoreMode::Overwrite(RestoreSnapGuard::NotGuarded))
+            }
+            Some("last-snap-guard") => Some(RestoreMode::Overwrite(RestoreSnapGuard::Guarded)),
+            _ => None,
+        };
+
+        let opt_interactive_mode = if opt_batch_restore_mode.is_some() {
+            None
+        } else if let Some(var_restore_mode) = opt_restore_mode {
             let mut restore_mode = var_restore_mode.to_string();
 
             if let Ok(env_restore_mode) = std::env::var("HTTM_RESTORE_MODE") {
@@ -812,12 +2347,21 @@ impl Config {
                     RestoreSnapGuard::NotGuarded,
                 ))),
                 "copy-and-preserve" => Some(InteractiveMode::Restore(RestoreMode::CopyAndPreserve)),
+                "merge" => Some(InteractiveMode::Restore(RestoreMode::Merge(
+                    RestoreSnapGuard::NotGuarded,
+                ))),
+                "merge-guard" => Some(InteractiveMode::Restore(RestoreMode::Merge(
+                    RestoreSnapGuard::Guarded,
+                ))),
                 _ => Some(InteractiveMode::Restore(RestoreMode::CopyOnly)),
             }
         } else if opt_select_mode.is_some() || opt_preview.is_some() {
             match opt_select_mode.map(|inner| inner.as_str()) {
                 Some("contents") => Some(InteractiveMode::Select(SelectMode::Contents)),
+                Some("raw-contents") => Some(InteractiveMode::Select(SelectMode::RawContents)),
                 Some("preview") => Some(InteractiveMode::Select(SelectMode::Preview)),
+                Some("pairs") => Some(InteractiveMode::Select(SelectMode::Pairs)),
+                Some("edit") => Some(InteractiveMode::Select(SelectMode::Edit)),
                 Some(_) | None => Some(InteractiveMode::Select(SelectMode::Path)),
             }
         // simply enable browse mode -- if deleted mode not enabled but recursive search is specified,
@@ -828,18 +2372,132 @@ impl Config {
             None
         };
 
+        let opt_restore_to = match matches.get_one::<PathBuf>("RESTORE_TO") {
+            Some(_)
+                if matches!(
+                    opt_interactive_mode,
+                    Some(InteractiveMode::Restore(RestoreMode::Overwrite(_)))
+                ) || matches!(opt_batch_restore_mode, Some(RestoreMode::Overwrite(_))) =>
+            {
+                return Err(HttmError::new(
+                    "RESTORE_TO cannot be used with an overwrite restore mode, which always restores to the live file location.",
+                )
+                .into());
+            }
+            Some(_)
+                if matches!(
+                    opt_interactive_mode,
+                    Some(InteractiveMode::Restore(RestoreMode::Merge(_)))
+                ) =>
+            {
+                return Err(HttmError::new(
+                    "RESTORE_TO cannot be used with the merge restore mode, which always writes to the live file location.",
+                )
+                .into());
+            }
+            Some(_) if opt_restore_mode.is_none() && !matches.contains_id("RESTORE_TREE") => {
+                return Err(HttmError::new("RESTORE_TO requires RESTORE or RESTORE_TREE.").into());
+            }
+            Some(restore_to) if !restore_to.is_dir() => {
+                let msg = format!(
+                    "RESTORE_TO destination does not exist, or is not a directory: {restore_to:?}"
+                );
+                return Err(HttmError::new(&msg).into());
+            }
+            Some(restore_to) => Some(restore_to.canonicalize()?),
+            None => None,
+        };
+
+        if opt_trash
+            && !matches!(
+                opt_interactive_mode,
+                Some(InteractiveMode::Restore(RestoreMode::Overwrite(_)))
+            )
+            && !matches!(opt_batch_restore_mode, Some(RestoreMode::Overwrite(_)))
+        {
+            return Err(HttmError::new(
+                "TRASH requires an overwrite restore mode, as only an overwrite restore replaces a live file.",
+            )
+            .into());
+        }
+
+        // which snapshots contain a version requires every snapshot, so we process any DEDUP_BY value
+        // here, as the method by which we compare versions, before we disable deduplication below
+        let opt_version_spec = match matches.get_one::<PathBuf>("SNAPS_FOR_VERSION") {
+            Some(reference) if reference.symlink_metadata().is_err() => {
+                let msg = format!(
+                    "SNAPS_FOR_VERSION reference version does not exist: {:?}",
+                    reference
+                );
+                return Err(HttmError::new(&msg).into());
+            }
+            Some(reference) => Some(VersionSpec {
+                reference: reference.to_owned(),
+                by_contents: matches
+                    .get_one::<String>("DEDUP_BY")
+                    .is_some_and(|dedup_by| {
+                        matches!(dedup_by.as_str(), "contents" | "content-by-blocks")
+                    }),
+            }),
+            None => None,
+        };
+
+        let change_detection = match matches
+            .get_one::<String>("CHANGE_DETECTION")
+            .map(|inner| inner.as_str())
+        {
+            Some("mtime") => ChangeDetection::Mtime,
+            Some("ctime") => ChangeDetection::Ctime,
+            Some("content") => ChangeDetection::Contents,
+            _ => ChangeDetection::MtimeAndSize,
+        };
+
         let dedup_by = match matches
             .get_one::<String>("DEDUP_BY")
             .map(|inner| inner.as_str())
         {
             _ if matches.get_flag("PRUNE") => DedupBy::Disable,
+            _ if opt_version_spec.is_some() => DedupBy::Disable,
             Some("all" | "no-filter" | "disable") => DedupBy::Disable,
             Some("contents") => DedupBy::Contents,
+            Some("content-by-blocks") => DedupBy::ContentsByBlocks,
             Some("metadata" | _) => DedupBy::Metadata,
             _ if matches.contains_id("LIST_SNAPS") => DedupBy::Disable,
+            None if matches!(change_detection, ChangeDetection::Contents) => DedupBy::Contents,
             None => DedupBy::Metadata,
         };
 
+        let browse_sort = match matches
+            .get_one::<String>("BROWSE_SORT")
+            .map(|inner| inner.as_str())
+        {
+            Some("dirs-first") => BrowseSort::DirsFirst,
+            Some("newest" | "mtime") => BrowseSort::Newest,
+            Some("largest" | "size") => BrowseSort::Largest,
+            _ => BrowseSort::Disable,
+        };
+
+        let opt_deleted_order = match matches
+            .get_one::<String>("DELETED_ORDER")
+            .map(|inner| inner.as_str())
+        {
+            Some("deleted") => Some(DeletedOrder::Deleted),
+            Some("newest" | "mtime") => Some(DeletedOrder::Mtime),
+            Some("name") => Some(DeletedOrder::Name),
+            _ => None,
+        };
+
+        let opt_on_conflict = match matches
+            .get_one::<String>("ON_CONFLICT")
+            .map(|inner| inner.as_str())
+        {
+            Some("skip") => Some(OnConflict::Skip),
+            Some("overwrite") => Some(OnConflict::Overwrite),
+            Some("rename-numbered") => Some(OnConflict::RenameNumbered),
+            Some("ask") => Some(OnConflict::Ask),
+            _ => None,
+        };
+
         if opt_no_hidden && !opt_recursive && opt_interactive_mode.is_none() {
             return Err(HttmError::new(
                 "FILTER_HIDDEN is only available if either an interactive mode or recursive mode is specified.",
@@ -847,12 +2505,30 @@ impl Config {
             .into());
         }
 
-        // if in last snap and select mode we will want to return a raw value,
+        if opt_entry_filters.is_some() && opt_interactive_mode.is_none() {
+            return Err(HttmError::new(
+                "MIN_SIZE, MAX_SIZE, and ENTRY_TYPE are only available in an interactive mode.",
+            )
+            .into());
+        }
+
+        if opt_exclude.is_some() && !opt_recursive && opt_interactive_mode.is_none() {
+            return Err(HttmError::new(
+                "EXCLUDE is only available if either an interactive mode or recursive mode is specified.",
+            )
+            .into());
+        }
+
+        // if in last snap (or select by) and select mode we will want to return a raw value,
         // better to have this here. It's more confusing if we work this logic later, I think.
-        if opt_last_snap.is_some()
+        if (opt_last_snap.is_some() || opt_select_by.is_some())
             && matches!(opt_interactive_mode, Some(InteractiveMode::Select(_)))
         {
-            print_mode = PrintMode::Raw(RawMode::Newline)
+            print_mode = if opt_zero {
+                PrintMode::Raw(RawMode::Zero)
+            } else {
+                PrintMode::Raw(RawMode::Newline)
+            }
         }
 
         let opt_snap_file_mount =
@@ -871,6 +2547,43 @@ impl Config {
                 None
             };
 
+        let opt_schedule_spec = match &opt_snap_file_mount {
+            Some(snapshot_suffix)
+                if matches.contains_id("EVERY") || matches.contains_id("KEEP") =>
+            {
+                let opt_every = matches
+                    .get_one::<String>("EVERY")
+                    .map(|input| {
+                        parse_age(input)
+                            .filter(|interval| !interval.is_zero())
+                            .ok_or_else(|| {
+                                let msg = format!(
+                                    "Could not parse interval: {input:?}.  Please specify an interval in the form \"<NUMBER><UNIT>\" (eg. \"30m\", \"1h\", or \"1d\")."
+                                );
+                                HttmError::new(&msg)
+                            })
+                    })
+                    .transpose()?;
+
+                let opt_keep = matches.get_one::<usize>("KEEP").copied();
+
+                if opt_keep == Some(0) {
+                    return Err(HttmError::new(
+                        "KEEP requires a number of snapshots greater than zero, as httm will not destroy the snapshot it has just taken.",
+                    )
+                    .into());
+                }
+
+                Some(ScheduleSpec {
+                    snapshot_suffix: snapshot_suffix.to_owned(),
+                    opt_every,
+                    opt_keep,
+                    opt_systemd_dir: matches.get_one::<PathBuf>("SYSTEMD").cloned(),
+                })
+            }
+            _ => None,
+        };
+
         let opt_snap_mode_filters = if matches.contains_id("LIST_SNAPS") {
             // allow selection of snaps to prune in prune mode
             let select_mode = matches!(opt_interactive_mode, Some(InteractiveMode::Select(_)));
@@ -885,25 +2598,180 @@ impl Config {
                     select_mode,
                     omit_num_snaps: 0usize,
                     name_filters: None,
+                    opt_older_than: None,
                 }),
             }
         } else {
             None
         };
 
+        // prune selection by count and by age, see KEEP_LAST and OLDER_THAN
+        let opt_keep_last = matches.get_one::<usize>("KEEP_LAST").copied();
+
+        let opt_older_than = matches
+            .get_one::<String>("OLDER_THAN")
+            .map(|input| {
+                parse_age(input).ok_or_else(|| {
+                    let msg = format!(
+                        "Could not parse age: {input:?}.  Please specify an age in the form \"<NUMBER><UNIT>\" (eg. \"30d\", \"12h\", or \"2w\")."
+                    );
+                    HttmError::new(&msg)
+                })
+            })
+            .transpose()?;
+
+        let opt_snap_mode_filters = if opt_keep_last.is_some() || opt_older_than.is_some() {
+            let select_mode = matches!(opt_interactive_mode, Some(InteractiveMode::Select(_)));
+
+            let mut filters = opt_snap_mode_filters.unwrap_or(ListSnapsFilters {
+                select_mode,
+                omit_num_snaps: 0usize,
+                name_filters: None,
+                opt_older_than: None,
+            });
+
+            // where both are specified, keep the greater number of snapshots
+            if let Some(keep_last) = opt_keep_last {
+                filters.omit_num_snaps = filters.omit_num_snaps.max(keep_last);
+            }
+
+            filters.opt_older_than = opt_older_than;
+
+            Some(filters)
+        } else {
+            opt_snap_mode_filters
+        };
+
+        let opt_twin_dirs = match matches.get_one::<String>("COMPARE_TWIN") {
+            Some(value) => {
+                let Some((local_dir, twin_dir)) = value.split_once(':') else {
+                    return Err(HttmError::new(
+                        "Must use specified delimiter (':') between directories for COMPARE_TWIN.",
+                    )
+                    .into());
+                };
+
+                // canonicalize, so our input paths, which are canonical, can be stripped of the local dir
+                let local_dir = PathBuf::from(local_dir).canonicalize()?;
+                let twin_dir = PathBuf::from(twin_dir).canonicalize()?;
+
+                Some(TwinDirs {
+                    local_dir,
+                    twin_dir,
+                })
+            }
+            None => None,
+        };
+
         let mut exec_mode = if let Some(full_snap_name) = matches.get_one::<String>("ROLL_FORWARD")
         {
             ExecMode::RollForward(full_snap_name.to_owned())
+        } else if let Some(twin_dirs) = opt_twin_dirs {
+            ExecMode::CompareTwin(twin_dirs)
+        } else if let Some(pattern) = matches.get_one::<String>("GREP") {
+            let regex = Regex::new(pattern).map_err(|err| {
+                let msg =
+                    format!("GREP pattern {pattern:?} is not a valid regular expression: {err}");
+                HttmError::new(&msg)
+            })?;
+
+            ExecMode::Grep(GrepSpec {
+                regex,
+                opt_appearance: matches.get_flag("GREP_APPEARANCE"),
+            })
+        } else if let Some(command) = matches.get_one::<String>("BISECT") {
+            // the user is asked about each version, when no test command is specified
+            ExecMode::Bisect(Some(command.to_owned()).filter(|command| !command.is_empty()))
+        } else if matches.get_flag("UNDO_LAST_RESTORE") {
+            ExecMode::UndoRestore
+        } else if let Some(timestamp) = matches.get_one::<String>("RESTORE_TREE") {
+            ExecMode::RestoreTree(parse_timestamp(timestamp, requested_utc_offset)?)
+        } else if matches.get_flag("TUI") {
+            if cfg!(not(feature = "tui")) {
+                return Err(HttmError::new(
+                    "TUI requires that httm be built with the \"tui\" feature.",
+                )
+                .into());
+            }
+
+            ExecMode::Tui
+        } else if let Some(mut pair) = matches.get_many::<String>("SNAP_DIFF") {
+            match (pair.next(), pair.next()) {
+                (Some(first), Some(second)) => ExecMode::SnapshotDiffReport(SnapDiffPair {
+                    first: first.to_owned(),
+                    second: second.to_owned(),
+                }),
+                _ => {
+                    return Err(
+                        HttmError::new("SNAP_DIFF requires the names of two snapshots.").into(),
+                    )
+                }
+            }
+        } else if let Some(mut pair) = matches.get_many::<PathBuf>("DIFF_PAIR") {
+            match (pair.next(), pair.next()) {
+                (Some(snap_file), Some(live_file)) => ExecMode::Diff(Some(DiffPair {
+                    snap_file: snap_file.to_owned(),
+                    live_file: live_file.to_owned(),
+                })),
+                _ => {
+                    return Err(HttmError::new(
+                        "DIFF_PAIR requires both a snapshot file and a live file.",
+                    )
+                    .into())
+                }
+            }
+        } else if let Some(raw_input) = matches.get_one::<String>("PREVIEW_RENDER") {
+            ExecMode::PreviewRender(raw_input.to_owned())
+        } else if let Some(mut preview_exec) = matches.get_many::<String>("PREVIEW_EXEC") {
+            match (
+                preview_exec.next(),
+                preview_exec.next(),
+                preview_exec.next(),
+            ) {
+                (Some(command), Some(live_file), Some(raw_input)) => ExecMode::PreviewExec(
+                    command.to_owned(),
+                    live_file.to_owned(),
+                    raw_input.to_owned(),
+                ),
+                _ => {
+                    return Err(HttmError::new(
+                        "PREVIEW_EXEC requires a command, a live file, and a selection.",
+                    )
+                    .into())
+                }
+            }
+        } else if let Some(snap_file) = matches.get_one::<PathBuf>("PREVIEW_VARIABLES") {
+            ExecMode::PreviewVariables(snap_file.to_owned())
+        } else if matches.get_flag("DIFF") && opt_interactive_mode.is_none() {
+            ExecMode::Diff(None)
         } else if let Some(num_versions_mode) = opt_num_versions {
             ExecMode::NumVersions(num_versions_mode)
+        } else if matches.get_flag("LATEST_PER_DIR") {
+            ExecMode::LatestPerDir
+        } else if let Some(version_spec) = opt_version_spec {
+            ExecMode::SnapsForVersion(version_spec)
+        } else if let Some(export_file) = matches.get_one::<PathBuf>("EXPORT") {
+            ExecMode::Export(export_file.to_owned())
+        } else if let Some(export_file) = matches.get_one::<PathBuf>("VERIFY_EXPORT") {
+            ExecMode::VerifyExport(export_file.to_owned())
+        } else if let Some(target_dir) = matches.get_one::<PathBuf>("EXPORT_VERSIONS") {
+            ExecMode::LinkFarm(target_dir.to_owned())
+        } else if matches.get_flag("STATS") {
+            ExecMode::Stats
+        } else if matches.get_flag("SOFT_DELETE") {
+            ExecMode::SoftDelete
         } else if let Some(mount_display) = opt_mount_display {
             ExecMode::MountsForFiles(mount_display)
         } else if matches.get_flag("PRUNE") {
             ExecMode::Prune(opt_snap_mode_filters)
         } else if opt_snap_mode_filters.is_some() {
             ExecMode::SnapsForFiles(opt_snap_mode_filters)
+        } else if let Some(schedule_spec) = opt_schedule_spec {
+            ExecMode::SnapSchedule(schedule_spec)
         } else if let Some(requested_snapshot_suffix) = opt_snap_file_mount {
             ExecMode::SnapFileMount(requested_snapshot_suffix.to_string())
+        } else if let Some(batch_restore_mode) = opt_batch_restore_mode {
+            ExecMode::NonInteractiveRestore(batch_restore_mode)
         } else if let Some(interactive_mode) = opt_interactive_mode {
             ExecMode::Interactive(interactive_mode)
         } else if opt_deleted_mode.is_some() {
@@ -923,12 +2791,33 @@ impl Config {
         // paths are immediately converted to our PathData struct
         let opt_os_values = matches.get_many::<PathBuf>("INPUT_FILES");
 
-        let paths: Vec<PathData> = Self::paths(opt_os_values, &exec_mode, &pwd)?;
+        let paths: Vec<PathData> = Self::paths(
+            opt_os_values,
+            &exec_mode,
+            &pwd,
+            &dataset_collection.map_of_datasets,
+        )?;
 
         // for exec_modes in which we can only take a single directory, process how we handle those here
         let opt_requested_dir: Option<PathBuf> =
             Self::opt_requested_dir(&mut exec_mode, &mut opt_deleted_mode, &paths, &pwd)?;
 
+        let opt_relative = match matches.get_one::<String>("RELATIVE").map(|s| s.as_str()) {
+            None => None,
+            Some("mount") => Some(RelativeTo::DatasetMount),
+            Some("dir") => {
+                let requested_dir = opt_requested_dir.as_deref().unwrap_or(pwd.as_path());
+                Some(RelativeTo::Dir(requested_dir.into()))
+            }
+            Some(prefix) => {
+                let prefix = Path::new(prefix);
+                let prefix = prefix
+                    .canonicalize()
+                    .unwrap_or_else(|_| prefix.to_path_buf());
+                Some(RelativeTo::Dir(prefix.into_boxed_path()))
+            }
+        };
+
         if opt_one_filesystem && opt_requested_dir.is_none() {
             return Err(HttmError::new(
                 "ONE_FILESYSTEM requires a requested path for RECURSIVE search",
@@ -984,6 +2873,50 @@ impl Config {
             opt_json,
             opt_one_filesystem,
             opt_no_clones,
+            opt_diff_summary,
+            opt_restore_journal,
+            restore_jobs,
+            opt_lookup_timeout,
+            browse_sort,
+            opt_exec,
+            exec_jobs,
+            opt_preview_wrap,
+            opt_preview_line_numbers,
+            opt_summary,
+            opt_auto_mount_snaps,
+            preview_timeout,
+            opt_relative,
+            opt_restore_to,
+            opt_verify,
+            opt_continue,
+            opt_follow,
+            opt_traverse_nested,
+            opt_exclude,
+            opt_entry_filters,
+            opt_lookup_cache,
+            opt_deleted_order,
+            opt_preview_pager,
+            opt_on_conflict,
+            opt_collapse_identical,
+            opt_time_window,
+            opt_dry_run,
+            opt_ignore_vcs,
+            opt_snap_info,
+            opt_select_by,
+            opt_numbered,
+            opt_no_hard_links,
+            opt_trash,
+            hash_jobs,
+            opt_hash_rate,
+            opt_quiet,
+            opt_deleted_depth,
+            opt_deleted_timeout,
+            opt_deleted_report,
+            change_detection,
+            opt_format,
+            opt_zero,
+            opt_badges,
+            opt_preview_shell,
             dedup_by,
             requested_utc_offset,
             exec_mode,
@@ -1001,6 +2934,7 @@ impl Config {
         opt_os_values: Option<ValuesRef<'_, PathBuf>>,
         exec_mode: &ExecMode,
         pwd: &Path,
+        map_of_datasets: &MapOfDatasets,
     ) -> HttmResult<Vec<PathData>> {
         let mut paths = if let Some(input_files) = opt_os_values {
             input_files
@@ -1012,7 +2946,7 @@ impl Config {
                 .map(|pd| {
                     // but what about snapshot paths?
                     // here we strip the additional snapshot VFS bits and make them look like live versions
-                    match ZfsSnapPathGuard::new(&pd) {
+                    match ZfsSnapPathGuard::new_with_datasets(&pd, map_of_datasets) {
                         Some(spd) if !matches!(exec_mode, ExecMode::MountsForFiles(_)) => spd
                             .live_path()
                             .map(|path| path.into())
@@ -1028,15 +2962,36 @@ impl Config {
                 // input, and waiting on one input from stdin is pretty silly
                 ExecMode::Interactive(_)
                 | ExecMode::NonInteractiveRecursive(_)
-                | ExecMode::RollForward(_) => {
+                | ExecMode::RollForward(_)
+                | ExecMode::SnapshotDiffReport(_)
+                | ExecMode::Tui
+                | ExecMode::RestoreTree(_)
+                | ExecMode::Diff(Some(_))
+                | ExecMode::PreviewRender(_)
+                | ExecMode::PreviewExec(_, _, _)
+                | ExecMode::PreviewVariables(_)
+                | ExecMode::LatestPerDir
+                | ExecMode::VerifyExport(_)
+                | ExecMode::UndoRestore => {
                     vec![PathData::from(pwd)]
                 }
                 ExecMode::BasicDisplay
                 | ExecMode::SnapFileMount(_)
+                | ExecMode::SnapSchedule(_)
                 | ExecMode::Prune(_)
                 | ExecMode::MountsForFiles(_)
                 | ExecMode::SnapsForFiles(_)
-                | ExecMode::NumVersions(_) => Self::read_stdin()?,
+                | ExecMode::NumVersions(_)
+                | ExecMode::CompareTwin(_)
+                | ExecMode::Diff(None)
+                | ExecMode::NonInteractiveRestore(_)
+                | ExecMode::SnapsForVersion(_)
+                | ExecMode::Export(_)
+                | ExecMode::Stats
+                | ExecMode::Grep(_)
+                | ExecMode::Bisect(_)
+                | ExecMode::LinkFarm(_)
+                | ExecMode::SoftDelete => Self::read_stdin()?,
             }
         };
 
@@ -1144,10 +3099,30 @@ impl Config {
             ExecMode::BasicDisplay
             | ExecMode::RollForward(_)
             | ExecMode::SnapFileMount(_)
+            | ExecMode::SnapSchedule(_)
             | ExecMode::Prune(_)
             | ExecMode::MountsForFiles(_)
             | ExecMode::SnapsForFiles(_)
-            | ExecMode::NumVersions(_) => {
+            | ExecMode::NumVersions(_)
+            | ExecMode::CompareTwin(_)
+            | ExecMode::SnapshotDiffReport(_)
+            | ExecMode::Tui
+            | ExecMode::RestoreTree(_)
+            | ExecMode::Diff(_)
+            | ExecMode::PreviewRender(_)
+            | ExecMode::PreviewExec(_, _, _)
+            | ExecMode::PreviewVariables(_)
+            | ExecMode::NonInteractiveRestore(_)
+            | ExecMode::LatestPerDir
+            | ExecMode::SnapsForVersion(_)
+            | ExecMode::Export(_)
+            | ExecMode::VerifyExport(_)
+            | ExecMode::Stats
+            | ExecMode::Grep(_)
+            | ExecMode::Bisect(_)
+            | ExecMode::LinkFarm(_)
+            | ExecMode::SoftDelete
+            | ExecMode::UndoRestore => {
                 // in non-interactive mode / display mode, requested dir is just a file
                 // like every other file and pwd must be the requested working dir.
                 None
@@ -1194,6 +3169,7 @@ impl Config {
             select_mode,
             omit_num_snaps,
             name_filters,
+            opt_older_than: None,
         })
     }
 }
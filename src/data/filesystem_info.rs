@@ -17,6 +17,9 @@
 
 use crate::filesystem::aliases::MapOfAliases;
 use crate::filesystem::alts::MapOfAlts;
+use crate::filesystem::borg::BorgRepos;
+use crate::filesystem::dated_backups::DatedBackupDirs;
+use crate::filesystem::explicit_snaps::MapOfExplicitSnaps;
 use crate::filesystem::mounts::{
     BaseFilesystemInfo,
     FilesystemType,
@@ -25,7 +28,10 @@ use crate::filesystem::mounts::{
     TM_DIR_LOCAL_PATH,
     TM_DIR_REMOTE_PATH,
 };
+use crate::filesystem::restic::ResticRepos;
 use crate::filesystem::snaps::MapOfSnaps;
+use crate::filesystem::ssh_aliases::SshAliases;
+use crate::filesystem::time_machine::TimeMachineLocal;
 use crate::library::results::{HttmError, HttmResult};
 use std::path::{Path, PathBuf};
 
@@ -41,6 +47,14 @@ pub struct FilesystemInfo {
     pub opt_map_of_alts: Option<MapOfAlts>,
     // key: local dir, val: (remote dir, fstype)
     pub opt_map_of_aliases: Option<MapOfAliases>,
+    // key: local dir, val: vec snap mounts specified by the user
+    pub opt_map_of_explicit_snaps: Option<MapOfExplicitSnaps>,
+    // borg repos to be mounted once exec() begins, see BORG_REPO
+    pub opt_borg_repos: Option<BorgRepos>,
+    // restic repos to be mounted once exec() begins, see RESTIC_REPO
+    pub opt_restic_repos: Option<ResticRepos>,
+    // remote dirs to be mounted, via sshfs, once exec() begins, see MAP_ALIASES
+    pub opt_ssh_aliases: Option<SshAliases>,
     // opt single dir to to be filtered re: btrfs common snap dir
     pub opt_common_snap_dir: Option<Box<Path>>,
     // opt possible opt store type
@@ -55,9 +69,19 @@ impl FilesystemInfo {
         opt_local_dir: Option<&String>,
         opt_raw_aliases: Option<Vec<String>>,
         opt_alt_store: Option<FilesystemType>,
+        opt_raw_snap_mounts: Option<Vec<String>>,
+        opt_raw_borg_repos: Option<Vec<String>>,
+        opt_raw_restic_repos: Option<Vec<String>>,
+        opt_raw_dated_backup_dirs: Option<Vec<String>>,
+        opt_pinned_datasets: Option<Vec<PathBuf>>,
+        opt_zfs_list: bool,
         pwd: PathBuf,
     ) -> HttmResult<FilesystemInfo> {
-        let mut base_fs_info = BaseFilesystemInfo::new(opt_debug, &opt_alt_store)?;
+        let mut base_fs_info =
+            BaseFilesystemInfo::new(opt_debug, &opt_alt_store, opt_pinned_datasets, opt_zfs_list)?;
+
+        let opt_raw_aliases = MapOfAliases::raw_values(opt_raw_aliases);
+        let opt_ssh_aliases = SshAliases::new(opt_raw_aliases.as_deref())?;
 
         // only create a map of aliases if necessary (aliases conflicts with alt stores)
         let opt_map_of_aliases = MapOfAliases::new(
@@ -68,6 +92,24 @@ impl FilesystemInfo {
             &pwd,
         )?;
 
+        let opt_borg_repos = BorgRepos::new(opt_raw_borg_repos)?;
+        let opt_restic_repos = ResticRepos::new(opt_raw_restic_repos)?;
+
+        // user specified snapshot mounts are the only version source for their local dirs
+        // borg archives, restic snapshots and the snapshots of SSH aliases are mounted, and then
+        // treated just like user specified snapshot mounts, as are the subdirectories of dated backup dirs
+        let opt_map_of_explicit_snaps = [
+            opt_borg_repos.as_ref().map(BorgRepos::local_dirs),
+            opt_restic_repos.as_ref().map(ResticRepos::local_dirs),
+            opt_ssh_aliases.as_ref().map(SshAliases::local_dirs),
+            DatedBackupDirs::new(opt_raw_dated_backup_dirs)?,
+        ]
+        .into_iter()
+        .fold(
+            MapOfExplicitSnaps::new(opt_raw_snap_mounts, &pwd)?,
+            MapOfExplicitSnaps::merge,
+        );
+
         // prep any blob repos
         let mut opt_alt_store = opt_alt_store;
 
@@ -75,12 +117,14 @@ impl FilesystemInfo {
             Some(ref repo_type) => {
                 base_fs_info.from_blob_repo(&repo_type, opt_debug)?;
             }
-            None if base_fs_info.map_of_datasets.is_empty() => {
+            None if base_fs_info.map_of_datasets.is_empty()
+                && opt_map_of_explicit_snaps.is_none() =>
+            {
                 // auto enable time machine alt store on mac when no datasets available, no working aliases, and paths exist
                 if cfg!(target_os = "macos")
                     && opt_map_of_aliases.is_none()
-                    && TM_DIR_REMOTE_PATH.exists()
-                    && TM_DIR_LOCAL_PATH.exists()
+                    && ((TM_DIR_REMOTE_PATH.exists() && TM_DIR_LOCAL_PATH.exists())
+                        || !TimeMachineLocal::snap_names().is_empty())
                 {
                     opt_alt_store.replace(FilesystemType::Apfs);
                     base_fs_info.from_blob_repo(&FilesystemType::Apfs, opt_debug)?;
@@ -111,6 +155,10 @@ impl FilesystemInfo {
             opt_map_of_alts,
             opt_common_snap_dir,
             opt_map_of_aliases,
+            opt_map_of_explicit_snaps,
+            opt_borg_repos,
+            opt_restic_repos,
+            opt_ssh_aliases,
             opt_alt_store,
         })
     }
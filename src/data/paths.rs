@@ -17,28 +17,41 @@
 
 use super::selection::SelectionCandidate;
 use crate::background::recursive::PathProvenance;
-use crate::config::generate::PrintMode;
-use crate::filesystem::mounts::{FilesystemType, IsFilterDir, MaxLen};
-use crate::library::file_ops::HashFileContents;
+use crate::config::generate::{ChangeDetection, EntryType, PrintMode};
+use crate::display::snap_info::SnapInfo;
+use crate::filesystem::mounts::{FilesystemType, IsFilterDir, MapOfDatasets, MaxLen};
+use crate::library::file_ops::{HashFileContents, HASH_BLOCK_SIZE};
+use crate::library::hash_pool::HashPool;
+use crate::library::ignore_files::IgnoreFiles;
 use crate::library::results::{HttmError, HttmResult};
-use crate::library::utility::{date_string, display_human_size, DateFormat, HttmIsDir};
+use crate::library::utility::{
+    date_string,
+    display_human_size,
+    display_path,
+    glob_match,
+    DateFormat,
+    HttmIsDir,
+};
 use crate::{
     BTRFS_SNAPPER_HIDDEN_DIRECTORY,
+    BTRFS_SNAP_GUARD_DIRECTORY,
     GLOBAL_CONFIG,
     ZFS_HIDDEN_DIRECTORY,
     ZFS_SNAPSHOT_DIRECTORY,
 };
+use rayon::prelude::*;
 use realpath_ext::{realpath, RealpathFlags};
 use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
 use std::cmp::{Ord, Ordering, PartialOrd};
 use std::ffi::OsStr;
 use std::fs::{symlink_metadata, DirEntry, FileType, Metadata};
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
+use std::os::unix::ffi::OsStrExt;
 use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
-use std::sync::{LazyLock, OnceLock};
-use std::time::SystemTime;
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 static OPT_REQUESTED_DIR_DEV: LazyLock<u64> = LazyLock::new(|| {
     GLOBAL_CONFIG
@@ -129,6 +142,21 @@ impl BasicDirEntryInfo {
     }
 
     pub fn all_exclusions(&self) -> bool {
+        // user specified exclusions apply even when dataset filtering is disabled
+        if self.is_user_excluded() {
+            return false;
+        }
+
+        if GLOBAL_CONFIG.opt_ignore_vcs
+            && IgnoreFiles::is_ignored(
+                self.path(),
+                self.filetype()
+                    .map_or(false, |file_type| file_type.is_dir()),
+            )
+        {
+            return false;
+        }
+
         if GLOBAL_CONFIG.opt_no_filter {
             return true;
         }
@@ -157,6 +185,57 @@ impl BasicDirEntryInfo {
         true
     }
 
+    // unlike all_exclusions, these filters only determine whether an entry is displayed,
+    // so directories which do not match are still searched, see EntryFilters
+    pub fn entry_filters(&self) -> bool {
+        let Some(entry_filters) = &GLOBAL_CONFIG.opt_entry_filters else {
+            return true;
+        };
+
+        let Some(file_type) = self.opt_filetype() else {
+            return false;
+        };
+
+        let is_type_match = match entry_filters.opt_entry_type {
+            Some(EntryType::File) => file_type.is_file(),
+            Some(EntryType::Dir) => file_type.is_dir(),
+            Some(EntryType::Symlink) => file_type.is_symlink(),
+            None => true,
+        };
+
+        if !is_type_match {
+            return false;
+        }
+
+        if file_type.is_dir()
+            || (entry_filters.opt_min_size.is_none() && entry_filters.opt_max_size.is_none())
+        {
+            return true;
+        }
+
+        // the live path of a deleted file does not exist, so we have no size to compare
+        let Ok(size) = self.path().symlink_metadata().map(|md| md.len()) else {
+            return true;
+        };
+
+        entry_filters.opt_min_size.map_or(true, |min| size >= min)
+            && entry_filters.opt_max_size.map_or(true, |max| size <= max)
+    }
+
+    fn is_user_excluded(&self) -> bool {
+        let Some(patterns) = &GLOBAL_CONFIG.opt_exclude else {
+            return false;
+        };
+
+        patterns.iter().any(|pattern| {
+            if pattern.contains('/') {
+                glob_match(pattern.as_bytes(), self.path().as_os_str().as_bytes())
+            } else {
+                glob_match(pattern.as_bytes(), self.filename().as_bytes())
+            }
+        })
+    }
+
     fn is_path_excluded(&self) -> bool {
         // FYI path is always a relative path, but no need to canonicalize as
         // partial eq for paths is comparison of components iter
@@ -165,7 +244,10 @@ impl BasicDirEntryInfo {
         // never check the hidden snapshot directory for live files (duh)
         // didn't think this was possible until I saw a SMB share return
         // a .zfs dir entry
-        if path.ends_with(ZFS_HIDDEN_DIRECTORY) || path.ends_with(BTRFS_SNAPPER_HIDDEN_DIRECTORY) {
+        if path.ends_with(ZFS_HIDDEN_DIRECTORY)
+            || path.ends_with(BTRFS_SNAPPER_HIDDEN_DIRECTORY)
+            || path.ends_with(BTRFS_SNAP_GUARD_DIRECTORY)
+        {
             return true;
         }
 
@@ -195,7 +277,7 @@ impl BasicDirEntryInfo {
 
 impl Into<SelectionCandidate> for BasicDirEntryInfo {
     fn into(self) -> SelectionCandidate {
-        unsafe { std::mem::transmute(self) }
+        SelectionCandidate::new(self.path, self.opt_filetype)
     }
 }
 
@@ -250,6 +332,12 @@ impl From<BasicDirEntryInfo> for PathData {
 }
 
 impl PathData {
+    // for paths and metadata we have already obtained, see LookupCache
+    #[inline(always)]
+    pub fn from_parts(path_buf: PathBuf, metadata: Option<PathMetadata>) -> Self {
+        Self { path_buf, metadata }
+    }
+
     #[inline(always)]
     pub fn new(path: &Path, opt_metadata: Option<Metadata>) -> Self {
         // canonicalize() on any path that DNE will throw an error
@@ -284,7 +372,30 @@ impl PathData {
         let self_hash = HashFileContents::path_to_hash(self.path());
         let other_hash = HashFileContents::path_to_hash(other.path());
 
-        self_hash.cmp(&other_hash) == Ordering::Equal
+        // a version we cannot read is never the same as another
+        matches!((self_hash, other_hash), (Ok(self_hash), Ok(other_hash)) if self_hash == other_hash)
+    }
+
+    // whether two versions differ, as judged by CHANGE_DETECTION
+    pub fn is_same_version(&self, other: &Self) -> bool {
+        match (self.opt_metadata(), other.opt_metadata()) {
+            (Some(self_md), Some(other_md))
+                if matches!(GLOBAL_CONFIG.change_detection, ChangeDetection::Contents) =>
+            {
+                self_md.size() == other_md.size() && self.is_same_file_contents(other)
+            }
+            (Some(self_md), Some(other_md)) => self_md.is_same_change(other_md),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_same_file_blocks(&self, other: &Self) -> bool {
+        let self_container = CompareContentsContainer::by_blocks(self.clone());
+        let other_container = CompareContentsContainer::by_blocks(other.clone());
+
+        self_container.size() == other_container.size()
+            && self_container.cmp_file_blocks(&other_container).is_eq()
     }
 }
 
@@ -380,6 +491,7 @@ pub struct AliasedPath<'a> {
 
 pub struct ZfsSnapPathGuard<'a> {
     inner: &'a PathData,
+    dataset_mount: &'a Path,
 }
 
 impl<'a> std::ops::Deref for ZfsSnapPathGuard<'a> {
@@ -392,18 +504,52 @@ impl<'a> std::ops::Deref for ZfsSnapPathGuard<'a> {
 
 impl<'a> ZfsSnapPathGuard<'a> {
     pub fn new(pathdata: &'a PathData) -> Option<Self> {
-        if !Self::is_zfs_snap_path(pathdata) {
-            return None;
-        }
+        Self::new_with_datasets(pathdata, &GLOBAL_CONFIG.dataset_collection.map_of_datasets)
+    }
 
-        Some(Self { inner: pathdata })
+    // for use before our GLOBAL_CONFIG is available
+    pub fn new_with_datasets(
+        pathdata: &'a PathData,
+        map_of_datasets: &MapOfDatasets,
+    ) -> Option<Self> {
+        let dataset_mount = Self::zfs_dataset_mount(&pathdata.path_buf, map_of_datasets)?;
+
+        Some(Self {
+            inner: pathdata,
+            dataset_mount,
+        })
+    }
+
+    // a substring match on ".zfs/snapshot" misfires for live paths within dirs which merely happen to be
+    // so named, perhaps on a non-ZFS filesystem, so here we match component-wise, and only those
+    // snapshot dirs which are anchored to a known ZFS dataset mount.  The most proximate such dataset wins.
+    fn zfs_dataset_mount<'b>(path: &'b Path, map_of_datasets: &MapOfDatasets) -> Option<&'b Path> {
+        path.ancestors()
+            // a snapshot path must name a snapshot beneath the snapshot dir
+            .skip(1)
+            .filter(|ancestor| ancestor.ends_with(ZFS_SNAPSHOT_DIRECTORY))
+            .filter_map(|snap_dir| snap_dir.parent().and_then(|hidden_dir| hidden_dir.parent()))
+            .find(|dataset_mount| {
+                map_of_datasets
+                    .get(*dataset_mount)
+                    .is_some_and(|md| md.fs_type == FilesystemType::Zfs)
+            })
     }
 
-    pub fn is_zfs_snap_path(pathdata: &'a PathData) -> bool {
-        pathdata
+    // the snapshot name, and the path relative to the snapshot
+    fn snap_name_and_relative(&self) -> Option<(&'a OsStr, &'a Path)> {
+        let snap_name_and_relative = self
+            .inner
             .path_buf
-            .to_string_lossy()
-            .contains(ZFS_SNAPSHOT_DIRECTORY)
+            .strip_prefix(self.dataset_mount)
+            .ok()?
+            .strip_prefix(ZFS_SNAPSHOT_DIRECTORY)
+            .ok()?;
+
+        let snap_name = snap_name_and_relative.iter().next()?;
+        let relative = snap_name_and_relative.strip_prefix(snap_name).ok()?;
+
+        Some((snap_name, relative))
     }
 }
 
@@ -414,17 +560,8 @@ impl<'a> PathDeconstruction<'a> for ZfsSnapPathGuard<'_> {
     }
 
     fn live_path(&self) -> Option<PathBuf> {
-        self.inner
-            .path_buf
-            .to_string_lossy()
-            .split_once(&format!("{ZFS_SNAPSHOT_DIRECTORY}/"))
-            .and_then(|(proximate_dataset_mount, relative_and_snap_name)| {
-                relative_and_snap_name
-                    .split_once("/")
-                    .map(|(_snap_name, relative)| {
-                        PathBuf::from(proximate_dataset_mount).join(Path::new(relative))
-                    })
-            })
+        self.snap_name_and_relative()
+            .map(|(_snap_name, relative)| self.dataset_mount.join(relative))
     }
 
     fn target(&self, proximate_dataset_mount: &Path) -> Option<PathBuf> {
@@ -459,22 +596,19 @@ impl<'a> PathDeconstruction<'a> for ZfsSnapPathGuard<'_> {
     }
 
     fn source(&self, _opt_proximate_dataset_mount: Option<&'a Path>) -> Option<PathBuf> {
-        let path_string = &self.inner.path_buf.to_string_lossy();
-
-        let (dataset_path, relative_and_snap) =
-            path_string.split_once(&format!("{ZFS_SNAPSHOT_DIRECTORY}/"))?;
-
-        let (snap_name, _relative) = relative_and_snap
-            .split_once('/')
-            .unwrap_or_else(|| (relative_and_snap, ""));
+        let (snap_name, _relative) = self.snap_name_and_relative()?;
 
         match GLOBAL_CONFIG
             .dataset_collection
             .map_of_datasets
-            .get(Path::new(dataset_path))
+            .get(self.dataset_mount)
         {
             Some(md) if md.fs_type == FilesystemType::Zfs => {
-                let res = format!("{}@{snap_name}", md.source.to_string_lossy());
+                let res = format!(
+                    "{}@{}",
+                    md.source.to_string_lossy(),
+                    snap_name.to_string_lossy()
+                );
                 Some(PathBuf::from(res))
             }
             Some(_md) => {
@@ -502,10 +636,64 @@ impl Serialize for PathData {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("PathData", 2)?;
+        // for a ZFS snapshot version, the source is the snapshot name, "pool/dataset@snap",
+        // for any other version, the source is simply the dataset containing the path
+        let (opt_dataset, opt_snapshot) = match ZfsSnapPathGuard::new(self) {
+            Some(spd) => {
+                let opt_snapshot = spd
+                    .source(None)
+                    .map(|snap| snap.to_string_lossy().into_owned());
+                let opt_dataset = opt_snapshot
+                    .as_deref()
+                    .and_then(|snap| snap.split_once('@'))
+                    .map(|(dataset, _snap_name)| dataset.to_owned());
+
+                (opt_dataset, opt_snapshot)
+            }
+            None => (
+                self.source(None)
+                    .map(|dataset| dataset.to_string_lossy().into_owned()),
+                None,
+            ),
+        };
+
+        let num_fields = if GLOBAL_CONFIG.opt_snap_info { 6 } else { 4 };
+
+        let mut state = serializer.serialize_struct("PathData", num_fields)?;
 
-        state.serialize_field("path", &self.path_buf)?;
+        state.serialize_field("path", &display_path(self))?;
         state.serialize_field("metadata", &self.metadata)?;
+        state.serialize_field("dataset", &opt_dataset)?;
+        state.serialize_field("snapshot", &opt_snapshot)?;
+
+        // see SNAP_INFO
+        if GLOBAL_CONFIG.opt_snap_info {
+            let opt_snap_info = SnapInfo::new(self);
+
+            let opt_creation = opt_snap_info.as_ref().and_then(|snap_info| {
+                snap_info
+                    .opt_creation
+                    .map(|creation| match GLOBAL_CONFIG.print_mode {
+                        PrintMode::Raw(_) => date_string(
+                            GLOBAL_CONFIG.requested_utc_offset,
+                            &creation,
+                            DateFormat::Timestamp,
+                        ),
+                        _ => date_string(
+                            GLOBAL_CONFIG.requested_utc_offset,
+                            &creation,
+                            DateFormat::Display,
+                        ),
+                    })
+            });
+
+            state.serialize_field(
+                "snapshot_name",
+                &opt_snap_info.as_ref().map(|snap_info| &snap_info.name),
+            )?;
+            state.serialize_field("snapshot_creation", &opt_creation)?;
+        }
+
         state.end()
     }
 }
@@ -536,10 +724,11 @@ impl Serialize for PathMetadata {
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Copy, Clone, Debug)]
 pub struct PathMetadata {
     size: u64,
     modify_time: SystemTime,
+    change_time: SystemTime,
 }
 
 impl PathMetadata {
@@ -550,20 +739,81 @@ impl PathMetadata {
         md.modified().ok().map(|time| PathMetadata {
             size: md.len(),
             modify_time: time,
+            change_time: u64::try_from(md.ctime())
+                .ok()
+                .and_then(|secs| {
+                    UNIX_EPOCH.checked_add(Duration::new(secs, md.ctime_nsec() as u32))
+                })
+                .unwrap_or(time),
         })
     }
 
-    // using ctime instead of mtime might be more correct as mtime can be trivially changed from user space
-    // but I think we want to use mtime here? People should be able to make a snapshot "unique" with only mtime?
+    #[inline(always)]
+    pub fn from_parts(size: u64, modify_time: SystemTime, change_time: SystemTime) -> Self {
+        PathMetadata {
+            size,
+            modify_time,
+            change_time,
+        }
+    }
+
+    // mtime can be trivially changed from user space, see CHANGE_DETECTION for when ctime is preferred
     #[inline(always)]
     pub fn mtime(&self) -> SystemTime {
         self.modify_time
     }
 
+    #[inline(always)]
+    pub fn ctime(&self) -> SystemTime {
+        self.change_time
+    }
+
     #[inline(always)]
     pub fn size(&self) -> u64 {
         self.size
     }
+
+    // the portion of the metadata which, per CHANGE_DETECTION, distinguishes one version from another
+    #[inline(always)]
+    fn change_key(&self) -> (SystemTime, u64) {
+        match GLOBAL_CONFIG.change_detection {
+            ChangeDetection::Mtime => (self.modify_time, 0),
+            ChangeDetection::Ctime => (self.change_time, 0),
+            ChangeDetection::MtimeAndSize | ChangeDetection::Contents => {
+                (self.modify_time, self.size)
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_same_change(&self, other: &Self) -> bool {
+        self.change_key() == other.change_key()
+    }
+
+    // order first by CHANGE_DETECTION, so that versions which are the same change sort adjacent, for dedup
+    #[inline(always)]
+    pub fn cmp_change(&self, other: &Self) -> Ordering {
+        self.change_key()
+            .cmp(&other.change_key())
+            .then_with(|| self.cmp(other))
+    }
+}
+
+// equality remains size and modify time, whatever the policy, as a copy preserves both but never the ctime
+impl PartialEq for PathMetadata {
+    #[inline(always)]
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size && self.modify_time == other.modify_time
+    }
+}
+
+impl Eq for PathMetadata {}
+
+impl Hash for PathMetadata {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.size.hash(state);
+        self.modify_time.hash(state);
+    }
 }
 
 impl PartialOrd for PathMetadata {
@@ -576,6 +826,7 @@ impl PartialOrd for PathMetadata {
 impl Ord for PathMetadata {
     #[inline(always)]
     fn cmp(&self, other: &Self) -> Ordering {
+        // like equality, ordering is by modify time and size alone, see cmp_change for CHANGE_DETECTION
         let time_order: Ordering = self.mtime().cmp(&other.mtime());
 
         if time_order.is_ne() {
@@ -594,12 +845,16 @@ pub const PHANTOM_SIZE: u64 = 0u64;
 pub const PHANTOM_PATH_METADATA: PathMetadata = PathMetadata {
     size: PHANTOM_SIZE,
     modify_time: PHANTOM_DATE,
+    change_time: PHANTOM_DATE,
 };
 
 #[derive(Debug)]
 pub struct CompareContentsContainer {
     pathdata: PathData,
-    hash: OnceLock<u64>,
+    // None, where the file could not be read
+    hash: OnceLock<Option<String>>,
+    // when compared by blocks, the digests of those leading blocks which we have already read
+    opt_block_hashes: Option<Mutex<Vec<String>>>,
 }
 
 impl Eq for CompareContentsContainer {}
@@ -623,7 +878,10 @@ impl Ord for CompareContentsContainer {
         let size_order: Ordering = self.size().cmp(&other.size());
 
         if size_order.is_eq() {
-            let contents_order = self.cmp_file_contents(other);
+            let contents_order = match (&self.opt_block_hashes, &other.opt_block_hashes) {
+                (Some(_), Some(_)) => self.cmp_file_blocks(other),
+                _ => self.cmp_file_contents(other),
+            };
 
             return contents_order;
         }
@@ -647,11 +905,27 @@ impl From<PathData> for CompareContentsContainer {
         Self {
             pathdata,
             hash: OnceLock::new(),
+            opt_block_hashes: None,
         }
     }
 }
 
 impl CompareContentsContainer {
+    // compares contents block by block, such that files which differ early need not be read in full
+    pub fn by_blocks(pathdata: PathData) -> Self {
+        Self {
+            pathdata,
+            hash: OnceLock::new(),
+            opt_block_hashes: Some(Mutex::new(Vec::new())),
+        }
+    }
+
+    // directories, symlinks and special files have no contents to compare
+    #[inline(always)]
+    pub fn is_file(&self) -> bool {
+        self.pathdata.path().is_file() && !self.pathdata.path().is_symlink()
+    }
+
     #[inline(always)]
     pub fn mtime(&self) -> SystemTime {
         self.pathdata.metadata_infallible().modify_time
@@ -664,18 +938,196 @@ impl CompareContentsContainer {
 
     #[allow(unused_assignments)]
     pub fn cmp_file_contents(&self, other: &Self) -> Ordering {
-        let (self_hash, other_hash): (&u64, &u64) = rayon::join(
+        let (self_hash, other_hash): (&Option<String>, &Option<String>) = rayon::join(
             || {
                 self.hash
-                    .get_or_init(|| HashFileContents::path_to_hash(self.pathdata.path()))
+                    .get_or_init(|| HashFileContents::path_to_hash(self.pathdata.path()).ok())
             },
             || {
                 other
                     .hash
-                    .get_or_init(|| HashFileContents::path_to_hash(other.pathdata.path()))
+                    .get_or_init(|| HashFileContents::path_to_hash(other.pathdata.path()).ok())
             },
         );
 
-        self_hash.cmp(&other_hash)
+        match (self_hash, other_hash) {
+            (Some(self_hash), Some(other_hash)) => self_hash.cmp(other_hash),
+            // a version we cannot read is never the same as another
+            _ => self.pathdata.path().cmp(other.pathdata.path()),
+        }
+    }
+
+    // blocks are read in batches, one block per thread, and we stop at the first batch which differs
+    pub fn cmp_file_blocks(&self, other: &Self) -> Ordering {
+        let num_blocks = self.size().div_ceil(HASH_BLOCK_SIZE);
+        let batch_size = rayon::current_num_threads().max(1) as u64;
+
+        let mut batch_start = 0u64;
+
+        while batch_start < num_blocks {
+            let batch_end = (batch_start + batch_size).min(num_blocks);
+
+            let (opt_self_hashes, opt_other_hashes) = rayon::join(
+                || self.block_hashes(batch_start, batch_end),
+                || other.block_hashes(batch_start, batch_end),
+            );
+
+            // a version we cannot read is never the same as another
+            let (Some(self_hashes), Some(other_hashes)) = (opt_self_hashes, opt_other_hashes)
+            else {
+                return self.pathdata.path().cmp(other.pathdata.path());
+            };
+
+            let batch_order = self_hashes.cmp(&other_hashes);
+
+            if !batch_order.is_eq() {
+                return batch_order;
+            }
+
+            batch_start = batch_end;
+        }
+
+        Ordering::Equal
+    }
+
+    // None, where any block could not be read
+    fn block_hashes(&self, start: u64, end: u64) -> Option<Vec<String>> {
+        let Some(block_hashes) = &self.opt_block_hashes else {
+            return Some(Vec::new());
+        };
+
+        let num_cached = block_hashes.lock().map_or(0, |cached| cached.len() as u64);
+
+        // we must not hold the lock while we hash, lest another rayon task, upon this same
+        // thread, attempt to take the lock
+        if num_cached < end {
+            let new_hashes: Vec<String> = HashPool::install(|| {
+                (num_cached..end)
+                    .into_par_iter()
+                    .map(|block_idx| {
+                        HashFileContents::block_to_hash(self.pathdata.path(), block_idx)
+                    })
+                    .collect::<HttmResult<Vec<String>>>()
+            })
+            .ok()?;
+
+            if let Ok(mut cached) = block_hashes.lock() {
+                if cached.len() as u64 == num_cached {
+                    cached.extend(new_hashes);
+                }
+            }
+        }
+
+        match block_hashes.lock() {
+            Ok(cached) if cached.len() as u64 >= end => {
+                Some(cached[start as usize..end as usize].to_vec())
+            }
+            _ => (start..end)
+                .map(|block_idx| HashFileContents::block_to_hash(self.pathdata.path(), block_idx))
+                .collect::<HttmResult<Vec<String>>>()
+                .ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::mounts::{DatasetMetadata, LinkType};
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    fn datasets(mounts: &[(&str, FilesystemType)]) -> MapOfDatasets {
+        mounts
+            .iter()
+            .map(|(mount, fs_type)| {
+                let md = DatasetMetadata {
+                    source: Path::new("pool/data").into(),
+                    fs_type: fs_type.clone(),
+                    link_type: LinkType::Local,
+                };
+
+                (Arc::from(Path::new(mount)), md)
+            })
+            .collect::<BTreeMap<Arc<Path>, DatasetMetadata>>()
+            .into()
+    }
+
+    fn live_path(path: &str, map_of_datasets: &MapOfDatasets) -> Option<PathBuf> {
+        let pathdata = PathData::new(Path::new(path), None);
+
+        ZfsSnapPathGuard::new_with_datasets(&pathdata, map_of_datasets)
+            .and_then(|spd| spd.live_path())
+    }
+
+    #[test]
+    fn snap_path_on_zfs_dataset() {
+        let map_of_datasets = datasets(&[("/srv/pool", FilesystemType::Zfs)]);
+
+        assert_eq!(
+            live_path(
+                "/srv/pool/.zfs/snapshot/snap_1/dir/file.txt",
+                &map_of_datasets
+            ),
+            Some(PathBuf::from("/srv/pool/dir/file.txt"))
+        );
+    }
+
+    #[test]
+    fn snap_path_on_most_proximate_dataset() {
+        let map_of_datasets = datasets(&[
+            ("/srv/pool", FilesystemType::Zfs),
+            ("/srv/pool/nested", FilesystemType::Zfs),
+        ]);
+
+        assert_eq!(
+            live_path(
+                "/srv/pool/nested/.zfs/snapshot/snap_1/file.txt",
+                &map_of_datasets
+            ),
+            Some(PathBuf::from("/srv/pool/nested/file.txt"))
+        );
+    }
+
+    #[test]
+    fn live_dir_merely_named_like_snapshot_dir() {
+        // not anchored to any ZFS dataset mount
+        let map_of_datasets = datasets(&[("/srv/pool", FilesystemType::Zfs)]);
+
+        assert_eq!(
+            live_path(
+                "/srv/pool/dir/.zfs/snapshot/snap_1/file.txt",
+                &map_of_datasets
+            ),
+            None
+        );
+
+        // anchored to a non-ZFS dataset mount
+        let map_of_datasets = datasets(&[("/srv/other", FilesystemType::Btrfs(None))]);
+
+        assert_eq!(
+            live_path("/srv/other/.zfs/snapshot/snap_1/file.txt", &map_of_datasets),
+            None
+        );
+    }
+
+    #[test]
+    fn snapshot_dir_must_match_whole_components() {
+        let map_of_datasets = datasets(&[("/srv/pool", FilesystemType::Zfs)]);
+
+        assert_eq!(
+            live_path(
+                "/srv/pool/my.zfs/snapshot/snap_1/file.txt",
+                &map_of_datasets
+            ),
+            None
+        );
+        assert_eq!(
+            live_path("/srv/pool/.zfs/snapshots/snap_1/file.txt", &map_of_datasets),
+            None
+        );
+
+        // the snapshot dir itself names no snapshot
+        assert_eq!(live_path("/srv/pool/.zfs/snapshot", &map_of_datasets), None);
     }
 }
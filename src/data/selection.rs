@@ -16,7 +16,7 @@
 // that was distributed with this source code.
 
 use crate::background::recursive::PathProvenance;
-use crate::config::generate::{DedupBy, FormattedMode, PrintMode};
+use crate::config::generate::{BrowseSort, DedupBy, FormattedMode, PrintMode};
 use crate::data::paths::PathData;
 use crate::display::wrapper::DisplayWrapper;
 use crate::library::results::HttmResult;
@@ -29,6 +29,14 @@ use std::fs::FileType;
 use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
 
+// badge lookups run upon a pool of their own, so they neither stall, nor wait upon, the directory walk
+static BADGE_POOL: LazyLock<Option<rayon::ThreadPool>> = LazyLock::new(|| {
+    rayon::ThreadPoolBuilder::new()
+        .thread_name(|idx| format!("httm-badge-{idx}"))
+        .build()
+        .ok()
+});
+
 // these represent the items ready for selection and preview
 // contains everything one needs to request preview and paint with
 // LsColors -- see preview_view, preview for how preview is done
@@ -36,11 +44,17 @@ use std::sync::LazyLock;
 pub struct SelectionCandidate {
     path: PathBuf,
     opt_filetype: Option<FileType>,
+    // see BADGES, always None unless requested
+    opt_badge: Option<Box<str>>,
 }
 
 impl SelectionCandidate {
     pub fn new(path: PathBuf, opt_filetype: Option<FileType>) -> Self {
-        Self { path, opt_filetype }
+        Self {
+            path,
+            opt_filetype,
+            opt_badge: None,
+        }
     }
 
     pub fn path(&self) -> &Path {
@@ -58,6 +72,40 @@ impl SelectionCandidate {
         }
     }
 
+    // skim never redraws an entry already displayed, so the entry is only handed on, to be sent
+    // to the view, once its badge is ready
+    pub fn with_badge(mut self, then: impl FnOnce(Self) + Send + 'static) {
+        // phantom entries are those deleted, and need no lookup
+        if self.opt_filetype.is_none() {
+            self.opt_badge = Some("[deleted]".into());
+            return then(self);
+        }
+
+        let op = move || {
+            self.opt_badge = Some(Self::badge_text(&self.path).into());
+            then(self)
+        };
+
+        match BADGE_POOL.as_ref() {
+            Some(pool) => pool.spawn(op),
+            None => op(),
+        }
+    }
+
+    fn badge_text(path: &Path) -> String {
+        let pathdata = PathData::from(path);
+
+        let num_snaps = Versions::new(&pathdata, &GLOBAL_CONFIG)
+            .map(|versions| versions.into_inner().1.len())
+            .unwrap_or_default();
+
+        match num_snaps {
+            0 => String::new(),
+            1 => "[1 snap]".to_string(),
+            num_snaps => format!("[{num_snaps} snaps]"),
+        }
+    }
+
     fn preview_view(&self) -> HttmResult<String> {
         // generate a config for display
         let display_config: Config = Config::from(self);
@@ -117,7 +165,12 @@ impl SkimItem for SelectionCandidate {
         self.display_name()
     }
     fn display(&self, _context: DisplayContext<'_>) -> AnsiString {
-        AnsiString::parse(&paint_string(self, &self.display_name()))
+        let painted = paint_string(self, &self.display_name());
+
+        match self.opt_badge.as_deref() {
+            Some(badge) if !badge.is_empty() => AnsiString::parse(&format!("{painted} {badge}")),
+            _ => AnsiString::parse(&painted),
+        }
     }
     fn output(&self) -> Cow<str> {
         self.path.to_string_lossy()
@@ -143,6 +196,50 @@ impl From<Vec<PathData>> for Config {
             opt_json: false,
             opt_one_filesystem: false,
             opt_no_clones: false,
+            opt_diff_summary: config.opt_diff_summary,
+            opt_restore_journal: None,
+            restore_jobs: config.restore_jobs,
+            opt_lookup_timeout: None,
+            browse_sort: BrowseSort::Disable,
+            opt_exec: None,
+            exec_jobs: 1,
+            opt_preview_wrap: false,
+            opt_preview_line_numbers: false,
+            opt_summary: false,
+            opt_auto_mount_snaps: false,
+            preview_timeout: config.preview_timeout,
+            opt_relative: config.opt_relative.clone(),
+            opt_restore_to: config.opt_restore_to.clone(),
+            opt_verify: config.opt_verify,
+            opt_continue: config.opt_continue,
+            opt_follow: config.opt_follow,
+            opt_traverse_nested: config.opt_traverse_nested,
+            opt_exclude: config.opt_exclude.clone(),
+            opt_entry_filters: config.opt_entry_filters.clone(),
+            opt_lookup_cache: config.opt_lookup_cache,
+            opt_deleted_order: config.opt_deleted_order.clone(),
+            opt_preview_pager: config.opt_preview_pager.clone(),
+            opt_on_conflict: None,
+            opt_collapse_identical: false,
+            opt_time_window: None,
+            opt_dry_run: false,
+            opt_ignore_vcs: false,
+            opt_snap_info: config.opt_snap_info,
+            opt_select_by: None,
+            opt_numbered: config.opt_numbered,
+            opt_no_hard_links: false,
+            opt_trash: false,
+            hash_jobs: config.hash_jobs,
+            opt_hash_rate: config.opt_hash_rate,
+            opt_quiet: true,
+            opt_deleted_depth: None,
+            opt_deleted_timeout: None,
+            opt_deleted_report: false,
+            change_detection: config.change_detection.clone(),
+            opt_format: None,
+            opt_zero: false,
+            opt_badges: false,
+            opt_preview_shell: false,
             opt_bulk_exclusion: None,
             opt_last_snap: None,
             opt_preview: None,
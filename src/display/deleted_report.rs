@@ -0,0 +1,232 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::{FormattedMode, PrintMode, RawMode};
+use crate::display::format_template::{FormatField, FormatTemplate};
+use crate::display::snap_info::SnapInfo;
+use crate::library::cleanup::CleanupGuard;
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{
+    csv_field,
+    csv_header,
+    date_string,
+    delimiter,
+    display_human_size,
+    print_output_buf,
+    split_snap_path,
+    DateFormat,
+};
+use crate::lookup::versions::VersionsMap;
+use crate::GLOBAL_CONFIG;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use std::time::SystemTime;
+
+static DELETED_REPORT: LazyLock<Mutex<Vec<DeletedRecord>>> =
+    LazyLock::new(|| Mutex::new(Vec::new()));
+
+// one row of the manifest, each deleted file, and the last snapshot version which contains it
+#[derive(Debug, Clone)]
+struct DeletedRecord {
+    path: PathBuf,
+    snap_path: PathBuf,
+    opt_snap_name: Option<String>,
+    size: u64,
+    modify_time: SystemTime,
+}
+
+impl Serialize for DeletedRecord {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("DeletedRecord", 5)?;
+
+        state.serialize_field("path", &self.path)?;
+        state.serialize_field("snapshot", &self.opt_snap_name)?;
+        state.serialize_field("snapshot_path", &self.snap_path)?;
+        state.serialize_field("size", &self.size)?;
+        state.serialize_field("modify_time", &self.modify_time())?;
+        state.end()
+    }
+}
+
+impl DeletedRecord {
+    fn modify_time(&self) -> String {
+        date_string(
+            GLOBAL_CONFIG.requested_utc_offset,
+            &self.modify_time,
+            DateFormat::Timestamp,
+        )
+    }
+}
+
+pub struct DeletedReport;
+
+impl DeletedReport {
+    // like DeletedSummary, deleted files are collected as found, and printed once the search completes
+    pub fn record(versions_map: &VersionsMap) -> HttmResult<()> {
+        let mut report = DELETED_REPORT
+            .lock()
+            .map_err(|_err| HttmError::new("Could not obtain a lock on the deleted report."))?;
+
+        versions_map.iter().for_each(|(pseudo_live, snaps)| {
+            let Some(last_snap) = snaps.last() else {
+                return;
+            };
+
+            // the files behind a deleted directory are reported by themselves, so a restore of
+            // the manifest never restores the same file twice
+            if last_snap
+                .path()
+                .symlink_metadata()
+                .is_ok_and(|md| md.is_dir())
+            {
+                return;
+            }
+
+            let metadata = last_snap.metadata_infallible();
+
+            let opt_snap_name =
+                split_snap_path(last_snap.path()).and_then(|(snap_mount, _dataset, _relative)| {
+                    SnapInfo::snap_mount_name(snap_mount)
+                });
+
+            report.push(DeletedRecord {
+                path: pseudo_live.path().to_path_buf(),
+                snap_path: last_snap.path().to_path_buf(),
+                opt_snap_name,
+                size: metadata.size(),
+                modify_time: metadata.mtime(),
+            });
+        });
+
+        Ok(())
+    }
+
+    pub fn print() -> HttmResult<()> {
+        let mut report = DELETED_REPORT
+            .lock()
+            .map_err(|_err| HttmError::new("Could not obtain a lock on the deleted report."))?;
+
+        if report.is_empty() {
+            eprintln!("NOTICE: httm could not find any deleted files.");
+            return Ok(());
+        }
+
+        report.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+
+        if GLOBAL_CONFIG.opt_json {
+            return print_output_buf(&Self::to_json(&report));
+        }
+
+        let output_buf: String = match &GLOBAL_CONFIG.opt_format {
+            Some(template) => report
+                .iter()
+                .map(|record| Self::render(template, record))
+                .collect(),
+            None if matches!(GLOBAL_CONFIG.print_mode, PrintMode::Raw(RawMode::Csv)) => {
+                let header =
+                    csv_header(&["path", "snapshot", "snapshot_path", "size", "modify_time"]);
+                let rows: String = report.iter().map(Self::format_line).collect();
+
+                header + &rows
+            }
+            None => report.iter().map(Self::format_line).collect(),
+        };
+
+        print_output_buf(&output_buf)
+    }
+
+    fn to_json(report: &[DeletedRecord]) -> String {
+        let res = match GLOBAL_CONFIG.print_mode {
+            PrintMode::Formatted(FormattedMode::Default) => serde_json::to_string_pretty(report),
+            _ => serde_json::to_string(report),
+        };
+
+        match res {
+            Ok(s) => {
+                let delimiter = delimiter();
+                format!("{s}{delimiter}")
+            }
+            Err(error) => {
+                eprintln!("Error: {error}");
+                CleanupGuard::exit(1)
+            }
+        }
+    }
+
+    // see FORMAT, here, "path" is the path of the deleted file, and "snap_path" is its last snapshot version
+    fn render(template: &FormatTemplate, record: &DeletedRecord) -> String {
+        let delimiter = delimiter();
+
+        let line = template.render(|field| match field {
+            FormatField::Path => record.path.display().to_string(),
+            FormatField::SnapPath => record.snap_path.display().to_string(),
+            FormatField::Snapshot => record.opt_snap_name.clone().unwrap_or_default(),
+            FormatField::Size => display_human_size(record.size),
+            FormatField::Bytes => record.size.to_string(),
+            FormatField::Mtime => date_string(
+                GLOBAL_CONFIG.requested_utc_offset,
+                &record.modify_time,
+                DateFormat::Display,
+            ),
+            FormatField::Timestamp => record.modify_time(),
+        });
+
+        format!("{line}{delimiter}")
+    }
+
+    // the raw newline and zero modes print only each path, for input to a batch restore
+    fn format_line(record: &DeletedRecord) -> String {
+        let delimiter = delimiter();
+
+        match &GLOBAL_CONFIG.print_mode {
+            PrintMode::Formatted(FormattedMode::Default) => format!(
+                "{} {:>12} {:?} : {:?}{}",
+                record.modify_time(),
+                display_human_size(record.size),
+                record.path,
+                record.snap_path,
+                delimiter
+            ),
+            PrintMode::Formatted(FormattedMode::NotPretty) => format!(
+                "{}\t{}\t{}\t{}\t{}{}",
+                record.path.display(),
+                record.opt_snap_name.as_deref().unwrap_or_default(),
+                record.snap_path.display(),
+                record.size,
+                record.modify_time(),
+                delimiter
+            ),
+            PrintMode::Raw(RawMode::Csv) => format!(
+                "{},{},{},{},{}{}",
+                csv_field(&record.path.to_string_lossy()),
+                csv_field(record.opt_snap_name.as_deref().unwrap_or_default()),
+                csv_field(&record.snap_path.to_string_lossy()),
+                record.size,
+                record.modify_time(),
+                delimiter
+            ),
+            PrintMode::Raw(RawMode::Newline | RawMode::Zero) => {
+                format!("{}{}", record.path.display(), delimiter)
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::{FormattedMode, PrintMode, RawMode};
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{csv_field, delimiter, display_human_size, print_output_buf};
+use crate::lookup::versions::VersionsMap;
+use crate::GLOBAL_CONFIG;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+// key: directory which contained the deleted files, val: totals for that directory
+static DELETED_SUMMARY: LazyLock<Mutex<BTreeMap<PathBuf, DeletedTotals>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+#[derive(Debug, Clone, Copy, Default)]
+struct DeletedTotals {
+    count: usize,
+    size: u64,
+}
+
+pub struct DeletedSummary;
+
+impl DeletedSummary {
+    // deleted files are aggregated instead of displayed, the size of a deleted file
+    // is the size of its most recent snapshot version
+    pub fn record(versions_map: &VersionsMap) -> HttmResult<()> {
+        let mut summary = DELETED_SUMMARY
+            .lock()
+            .map_err(|_err| HttmError::new("Could not obtain a lock on the deleted summary."))?;
+
+        versions_map.iter().for_each(|(pseudo_live, snaps)| {
+            // the files behind a deleted directory are recorded by themselves, so a deleted
+            // directory is neither counted as a file, nor is its size added to the total
+            if snaps
+                .last()
+                .is_some_and(|snap| snap.path().symlink_metadata().is_ok_and(|md| md.is_dir()))
+            {
+                return;
+            }
+
+            let dir = pseudo_live
+                .path()
+                .parent()
+                .unwrap_or_else(|| Path::new("/"))
+                .to_path_buf();
+
+            let size = snaps
+                .last()
+                .and_then(|snap| *snap.opt_metadata())
+                .map(|md| md.size())
+                .unwrap_or_default();
+
+            let totals = summary.entry(dir).or_default();
+            totals.count += 1;
+            totals.size += size;
+        });
+
+        Ok(())
+    }
+
+    pub fn print() -> HttmResult<()> {
+        let summary = DELETED_SUMMARY
+            .lock()
+            .map_err(|_err| HttmError::new("Could not obtain a lock on the deleted summary."))?;
+
+        if summary.is_empty() {
+            eprintln!("NOTICE: httm could not find any deleted files.");
+            return Ok(());
+        }
+
+        let grand_total = summary
+            .values()
+            .fold(DeletedTotals::default(), |acc, totals| DeletedTotals {
+                count: acc.count + totals.count,
+                size: acc.size + totals.size,
+            });
+
+        let output_buf: String = summary
+            .iter()
+            .map(|(dir, totals)| Self::format_line(&dir.to_string_lossy(), totals))
+            .chain(std::iter::once(Self::format_line("total", &grand_total)))
+            .collect();
+
+        print_output_buf(&output_buf)
+    }
+
+    fn format_line(label: &str, totals: &DeletedTotals) -> String {
+        let delimiter = delimiter();
+
+        match &GLOBAL_CONFIG.print_mode {
+            PrintMode::Formatted(FormattedMode::Default) => format!(
+                "{:>8} deleted {:>12} : {}{}",
+                totals.count,
+                display_human_size(totals.size),
+                label,
+                delimiter
+            ),
+            PrintMode::Raw(RawMode::Csv) => {
+                format!(
+                    "{},{},{}{}",
+                    csv_field(label),
+                    totals.count,
+                    totals.size,
+                    delimiter
+                )
+            }
+            PrintMode::Formatted(FormattedMode::NotPretty) | PrintMode::Raw(_) => {
+                format!("{}\t{}\t{}{}", label, totals.count, totals.size, delimiter)
+            }
+        }
+    }
+}
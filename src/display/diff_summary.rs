@@ -0,0 +1,137 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::{Config, FormattedMode, PrintMode};
+use crate::data::paths::PathData;
+use crate::display::versions::{NOT_SO_PRETTY_FIXED_WIDTH_PADDING, PRETTY_FIXED_WIDTH_PADDING};
+use crate::library::utility::delimiter;
+use hashbrown::HashMap;
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+// files larger than this are not worth reading back only for a summary
+const DIFF_SUMMARY_MAX_SIZE: u64 = 1_048_576;
+
+// key: (previous version, current version), val: opt summary (None if not a text file, etc.)
+static DIFF_SUMMARY_CACHE: LazyLock<Mutex<HashMap<(PathBuf, PathBuf), Option<DiffSummary>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffSummary {
+    added: usize,
+    removed: usize,
+}
+
+impl std::fmt::Display for DiffSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "+{}/-{}", self.added, self.removed)
+    }
+}
+
+impl DiffSummary {
+    // for an ordered sequence of versions, returns the summary of each version's delta
+    // against its predecessor. the first version has no predecessor, and so has no summary
+    pub fn sequence(versions: &[&PathData]) -> Vec<Option<DiffSummary>> {
+        let first = std::iter::once(None);
+
+        let rest: Vec<Option<DiffSummary>> = versions
+            .par_windows(2)
+            .map(|window| Self::cached(window[0], window[1]))
+            .collect();
+
+        first.chain(rest).collect()
+    }
+
+    pub fn append_to_line(line: &str, summary: &DiffSummary, config: &Config) -> String {
+        let padding = match config.print_mode {
+            PrintMode::Formatted(FormattedMode::NotPretty) => NOT_SO_PRETTY_FIXED_WIDTH_PADDING,
+            _ => PRETTY_FIXED_WIDTH_PADDING,
+        };
+
+        let delimiter = delimiter();
+
+        format!(
+            "{}{padding}{summary}{delimiter}",
+            line.trim_end_matches(delimiter)
+        )
+    }
+
+    fn cached(previous: &PathData, current: &PathData) -> Option<DiffSummary> {
+        let key = (previous.path().to_path_buf(), current.path().to_path_buf());
+
+        if let Ok(cache) = DIFF_SUMMARY_CACHE.lock() {
+            if let Some(cached) = cache.get(&key) {
+                return *cached;
+            }
+        }
+
+        let res = Self::new(previous, current);
+
+        if let Ok(mut cache) = DIFF_SUMMARY_CACHE.lock() {
+            cache.insert(key, res);
+        }
+
+        res
+    }
+
+    fn new(previous: &PathData, current: &PathData) -> Option<DiffSummary> {
+        let previous_lines = Self::read_text(previous)?;
+        let current_lines = Self::read_text(current)?;
+
+        // a multiset comparison of lines: cheaper than a full LCS diff, and the same
+        // result for the common case of lines added or removed, but moved lines don't count
+        let mut counts: HashMap<&str, isize> = HashMap::new();
+
+        previous_lines
+            .lines()
+            .for_each(|line| *counts.entry(line).or_default() -= 1);
+        current_lines
+            .lines()
+            .for_each(|line| *counts.entry(line).or_default() += 1);
+
+        let (added, removed) = counts
+            .values()
+            .fold((0usize, 0usize), |(added, removed), count| {
+                if count.is_positive() {
+                    (added + count.unsigned_abs(), removed)
+                } else {
+                    (added, removed + count.unsigned_abs())
+                }
+            });
+
+        Some(DiffSummary { added, removed })
+    }
+
+    fn read_text(pathdata: &PathData) -> Option<String> {
+        let metadata = pathdata.opt_metadata().as_ref()?;
+
+        if metadata.size() > DIFF_SUMMARY_MAX_SIZE || !Self::is_file(pathdata.path()) {
+            return None;
+        }
+
+        let bytes = std::fs::read(pathdata.path()).ok()?;
+
+        String::from_utf8(bytes).ok()
+    }
+
+    fn is_file(path: &Path) -> bool {
+        path.symlink_metadata()
+            .map(|md| md.file_type().is_file())
+            .unwrap_or(false)
+    }
+}
@@ -0,0 +1,149 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::library::results::{HttmError, HttmResult};
+
+// a column which may be named within a FORMAT template, as "{name}"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatField {
+    Path,
+    SnapPath,
+    Snapshot,
+    Size,
+    Bytes,
+    Mtime,
+    Timestamp,
+}
+
+impl FormatField {
+    fn new(name: &str) -> Option<Self> {
+        let field = match name {
+            "path" => FormatField::Path,
+            "snap_path" => FormatField::SnapPath,
+            "snapshot" => FormatField::Snapshot,
+            "size" => FormatField::Size,
+            "bytes" => FormatField::Bytes,
+            "mtime" => FormatField::Mtime,
+            "timestamp" => FormatField::Timestamp,
+            _ => return None,
+        };
+
+        Some(field)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    Field(FormatField),
+}
+
+#[derive(Debug, Clone)]
+pub struct FormatTemplate {
+    segments: Vec<Segment>,
+}
+
+impl FormatTemplate {
+    // "{{" and "}}" are literal braces, and "\t", "\n" and "\\" are unescaped, as a shell will pass each verbatim
+    pub fn new(template: &str) -> HttmResult<Self> {
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    let mut is_terminated = false;
+
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            is_terminated = true;
+                            break;
+                        }
+
+                        name.push(c);
+                    }
+
+                    let Some(field) = FormatField::new(name.trim()).filter(|_| is_terminated)
+                    else {
+                        let msg = format!(
+                            "FORMAT template contains an unknown or unterminated field: {{{name}. \
+                            Available fields are: path, snap_path, snapshot, size, bytes, mtime, timestamp."
+                        );
+                        return Err(HttmError::new(&msg).into());
+                    };
+
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+
+                    segments.push(Segment::Field(field));
+                }
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some('\\') => literal.push('\\'),
+                    Some(other) => {
+                        literal.push('\\');
+                        literal.push(other);
+                    }
+                    None => literal.push('\\'),
+                },
+                _ => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        if !segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Field(_)))
+        {
+            return Err(HttmError::new(
+                "FORMAT template must contain at least one field, such as {path}.",
+            )
+            .into());
+        }
+
+        Ok(Self { segments })
+    }
+
+    // the caller supplies the value of each field, and terminates the line
+    pub fn render<F>(&self, field_value: F) -> String
+    where
+        F: Fn(&FormatField) -> String,
+    {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(literal) => literal.to_owned(),
+                Segment::Field(field) => field_value(field),
+            })
+            .collect()
+    }
+}
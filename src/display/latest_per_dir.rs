@@ -0,0 +1,191 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::{DedupBy, FormattedMode, PrintMode, RawMode};
+use crate::data::paths::PathData;
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{csv_field, date_string, delimiter, display_human_size, DateFormat};
+use crate::{DisplayWrapper, GLOBAL_CONFIG};
+use std::fs::read_dir;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LatestStatus {
+    Same,
+    Differs,
+    NoSnap,
+}
+
+impl LatestStatus {
+    fn new(live_version: &PathData, opt_latest: Option<&PathData>) -> Self {
+        let Some(latest) = opt_latest else {
+            return LatestStatus::NoSnap;
+        };
+
+        let is_same = match GLOBAL_CONFIG.dedup_by {
+            DedupBy::Contents => live_version.is_same_file_contents(latest),
+            DedupBy::ContentsByBlocks => live_version.is_same_file_blocks(latest),
+            DedupBy::Metadata | DedupBy::Disable => live_version.is_same_version(latest),
+        };
+
+        if is_same {
+            LatestStatus::Same
+        } else {
+            LatestStatus::Differs
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            LatestStatus::Same => "same",
+            LatestStatus::Differs => "differs",
+            LatestStatus::NoSnap => "no-snap",
+        }
+    }
+}
+
+pub struct LatestPerDir;
+
+impl LatestPerDir {
+    // the files contained in each requested dir, as a batch, to be looked up together
+    pub fn entries(dirs: &[PathData]) -> HttmResult<Vec<PathData>> {
+        let mut entries: Vec<PathData> = Vec::new();
+
+        for dir in dirs {
+            if !dir.path().is_dir() {
+                let msg = format!("LATEST_PER_DIR requires a directory: {:?}", dir.path());
+                return Err(HttmError::new(&msg).into());
+            }
+
+            read_dir(dir.path())?
+                .flatten()
+                .filter(|entry| entry.file_type().is_ok_and(|file_type| !file_type.is_dir()))
+                .map(|entry| PathData::from(entry.path()))
+                .for_each(|pathdata| entries.push(pathdata));
+        }
+
+        if entries.is_empty() {
+            return Err(HttmError::new(
+                "The directories requested contain no files which could be rolled up.",
+            )
+            .into());
+        }
+
+        entries.sort_unstable();
+        entries.dedup();
+
+        Ok(entries)
+    }
+}
+
+impl<'a> DisplayWrapper<'a> {
+    // one row per file: whether the newest snapshot version differs from the live version,
+    // and the newest snapshot version itself, so the user may see what would change were
+    // the whole dir restored from the latest snapshot
+    pub fn format_as_latest_per_dir(&self) -> String {
+        let delimiter = delimiter();
+
+        let rows: Vec<(LatestStatus, &PathData, Option<&PathData>)> = self
+            .iter()
+            .map(|(live_version, snaps)| {
+                let opt_latest = snaps.last();
+                let status = LatestStatus::new(live_version, opt_latest);
+
+                (status, live_version, opt_latest)
+            })
+            .collect();
+
+        match &self.config.print_mode {
+            PrintMode::Formatted(formatted_mode) => {
+                let status_padding = rows
+                    .iter()
+                    .map(|(status, _live, _latest)| status.as_str().len())
+                    .max()
+                    .unwrap_or_default();
+
+                let size_padding = rows
+                    .iter()
+                    .filter_map(|(_status, _live, opt_latest)| *opt_latest)
+                    .map(|latest| display_human_size(latest.metadata_infallible().size()).len())
+                    .max()
+                    .unwrap_or_default();
+
+                let padding = match formatted_mode {
+                    FormattedMode::Default => "  ",
+                    FormattedMode::NotPretty => "\t",
+                };
+
+                rows.iter()
+                    .map(|(status, live_version, opt_latest)| match opt_latest {
+                        Some(latest) => {
+                            let metadata = latest.metadata_infallible();
+
+                            format!(
+                                "{:<status_padding$}{padding}{}{padding}{:>size_padding$}{padding}\"{}\"{delimiter}",
+                                status.as_str(),
+                                date_string(
+                                    self.config.requested_utc_offset,
+                                    &metadata.mtime(),
+                                    DateFormat::Display
+                                ),
+                                display_human_size(metadata.size()),
+                                latest.path().display()
+                            )
+                        }
+                        None => format!(
+                            "{:<status_padding$}{padding}\"{}\"{delimiter}",
+                            status.as_str(),
+                            live_version.path().display()
+                        ),
+                    })
+                    .collect()
+            }
+            PrintMode::Raw(RawMode::Csv) => rows
+                .iter()
+                .map(|(status, live_version, opt_latest)| match opt_latest {
+                    Some(latest) => {
+                        let metadata = latest.metadata_infallible();
+
+                        format!(
+                            "{},{},{},{},{}{delimiter}",
+                            status.as_str(),
+                            date_string(
+                                self.config.requested_utc_offset,
+                                &metadata.mtime(),
+                                DateFormat::Timestamp
+                            ),
+                            metadata.size(),
+                            csv_field(&latest.path().to_string_lossy()),
+                            csv_field(&live_version.path().to_string_lossy())
+                        )
+                    }
+                    None => format!(
+                        "{},,,,{}{delimiter}",
+                        status.as_str(),
+                        csv_field(&live_version.path().to_string_lossy())
+                    ),
+                })
+                .collect(),
+            // for piping, only those newest versions which differ from their live versions
+            PrintMode::Raw(_) => rows
+                .iter()
+                .filter(|(status, _live, _latest)| matches!(status, LatestStatus::Differs))
+                .filter_map(|(_status, _live, opt_latest)| *opt_latest)
+                .map(|latest| format!("{}{delimiter}", latest.path().display()))
+                .collect(),
+        }
+    }
+}
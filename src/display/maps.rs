@@ -18,7 +18,8 @@
 use crate::config::generate::{FormattedMode, PrintMode, RawMode};
 use crate::data::paths::{PathData, ZfsSnapPathGuard};
 use crate::display::versions::{NOT_SO_PRETTY_FIXED_WIDTH_PADDING, QUOTATION_MARKS_LEN};
-use crate::library::utility::delimiter;
+use crate::library::cleanup::CleanupGuard;
+use crate::library::utility::{csv_field, csv_header, delimiter, display_path};
 use crate::{MountsForFiles, SnapNameMap, VersionsMap, GLOBAL_CONFIG};
 use serde::ser::SerializeMap;
 use serde::{Serialize, Serializer};
@@ -28,6 +29,8 @@ use std::ops::Deref;
 #[derive(Debug)]
 pub struct PrintAsMap {
     inner: BTreeMap<String, Vec<String>>,
+    // the CSV header, for each key and each of its values
+    columns: [&'static str; 2],
 }
 
 impl Deref for PrintAsMap {
@@ -40,7 +43,10 @@ impl Deref for PrintAsMap {
 
 impl From<BTreeMap<String, Vec<String>>> for PrintAsMap {
     fn from(map: BTreeMap<String, Vec<String>>) -> Self {
-        Self { inner: map }
+        Self {
+            inner: map,
+            columns: ["path", "value"],
+        }
     }
 }
 
@@ -79,7 +85,10 @@ impl<'a> From<&MountsForFiles<'a>> for PrintAsMap {
                 (pathdata.path().to_string_lossy().to_string(), res)
             })
             .collect();
-        Self { inner }
+        Self {
+            inner,
+            columns: ["path", "mount"],
+        }
     }
 }
 
@@ -90,12 +99,15 @@ impl From<&VersionsMap> for PrintAsMap {
             .map(|(key, values)| {
                 let res = values
                     .iter()
-                    .map(|value| value.path().to_string_lossy().to_string())
+                    .map(|value| display_path(value).to_string())
                     .collect();
-                (key.path().to_string_lossy().to_string(), res)
+                (display_path(key).to_string(), res)
             })
             .collect();
-        Self { inner }
+        Self {
+            inner,
+            columns: ["path", "snapshot_path"],
+        }
     }
 }
 
@@ -105,7 +117,10 @@ impl From<&SnapNameMap> for PrintAsMap {
             .iter()
             .map(|(key, value)| (key.path().to_string_lossy().to_string(), value.clone()))
             .collect();
-        Self { inner }
+        Self {
+            inner,
+            columns: ["path", "snapshot"],
+        }
     }
 }
 
@@ -116,31 +131,17 @@ impl std::string::ToString for PrintAsMap {
         }
 
         match &GLOBAL_CONFIG.print_mode {
+            PrintMode::Raw(RawMode::Csv) => self.to_csv(),
             PrintMode::Raw(_) => {
-                let delimiter = if let PrintMode::Raw(RawMode::Csv) = GLOBAL_CONFIG.print_mode {
-                    ','
-                } else {
-                    delimiter()
-                };
-
-                let last = self.values().len() - 1;
+                let delimiter = delimiter();
 
-                self.values().flatten().enumerate().fold(
-                    String::new(),
-                    |mut buffer, (idx, value)| {
+                self.values()
+                    .flatten()
+                    .fold(String::new(), |mut buffer, value| {
                         buffer.push_str(value);
-
-                        if let PrintMode::Raw(RawMode::Csv) = GLOBAL_CONFIG.print_mode {
-                            if last == idx {
-                                buffer.push('\n');
-                                return buffer;
-                            }
-                        }
-
                         buffer.push(delimiter);
                         buffer
-                    },
-                )
+                    })
             }
             PrintMode::Formatted(_) => self.format(),
         }
@@ -155,6 +156,18 @@ impl PrintAsMap {
         )
     }
 
+    // one row for each key and value pair, so each row has the same number of fields
+    pub fn to_csv(&self) -> String {
+        let delimiter = delimiter();
+
+        self.iter()
+            .flat_map(|(key, values)| values.iter().map(move |value| (key, value)))
+            .fold(csv_header(&self.columns), |mut buffer, (key, value)| {
+                buffer += &format!("{},{}{}", csv_field(key), csv_field(value), delimiter);
+                buffer
+            })
+    }
+
     pub fn to_json(&self) -> String {
         let res = match GLOBAL_CONFIG.print_mode {
             PrintMode::Formatted(FormattedMode::Default) => serde_json::to_string_pretty(&self),
@@ -168,7 +181,7 @@ impl PrintAsMap {
             }
             Err(error) => {
                 eprintln!("Error: {error}");
-                std::process::exit(1)
+                CleanupGuard::exit(1)
             }
         }
     }
@@ -179,7 +192,7 @@ impl PrintAsMap {
         let write_out_buffer = self
             .iter()
             .filter(|(_key, values)| {
-                if GLOBAL_CONFIG.opt_last_snap.is_some() {
+                if GLOBAL_CONFIG.opt_last_snap.is_some() || GLOBAL_CONFIG.opt_select_by.is_some() {
                     !values.is_empty()
                 } else {
                     true
@@ -221,7 +234,7 @@ impl PrintAsMap {
                     &GLOBAL_CONFIG.print_mode,
                     PrintMode::Formatted(FormattedMode::NotPretty)
                 ) {
-                    format!("{display_path}:{values_string}\n")
+                    format!("{display_path}:{values_string}{}", delimiter())
                 } else {
                     values_string
                 }
@@ -18,7 +18,7 @@
 use crate::config::generate::{FormattedMode, NumVersionsMode, PrintMode, RawMode};
 use crate::data::paths::PathData;
 use crate::display::maps::PrintAsMap;
-use crate::library::utility::delimiter;
+use crate::library::utility::{csv_field, delimiter};
 use crate::lookup::versions::VersionsMap;
 use crate::{DisplayWrapper, GLOBAL_CONFIG};
 
@@ -114,9 +114,11 @@ impl<'a> DisplayWrapper<'a> {
                         delimiter,
                         width = padding
                     )),
-                    PrintMode::Raw(RawMode::Csv) => {
-                        Some(format!("{},{num_versions}{}", display_path, delimiter))
-                    }
+                    PrintMode::Raw(RawMode::Csv) => Some(format!(
+                        "{},{num_versions}{}",
+                        csv_field(&display_path.to_string()),
+                        delimiter
+                    )),
                     PrintMode::Raw(_) if total_num_paths == 1 => {
                         Some(format!("{num_versions}{}", delimiter))
                     }
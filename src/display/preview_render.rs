@@ -0,0 +1,314 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{hex_dump, print_output_buf, HEX_DUMP_MAX_BYTES};
+use crate::GLOBAL_CONFIG;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command as ExecProcess, Stdio};
+
+const PREVIEW_HEAD_LINES: usize = 40;
+const PREVIEW_TAIL_LINES: usize = 10;
+const PREVIEW_MAX_DIR_ENTRIES: usize = 100;
+// never read more than this much of a file, so a preview of a huge file remains instant
+const PREVIEW_MAX_BYTES: u64 = 1_048_576;
+
+// built-in previews, which require neither 'bash' nor any other executable, see the "builtin" PREVIEW
+pub struct PreviewRender;
+
+impl PreviewRender {
+    // the raw input is the selection line, as given to us by the interactive view, see PREVIEW_RENDER
+    pub fn exec(raw_input: &str) -> HttmResult<()> {
+        // like the bootstrap script, the border lines of the selection view have nothing to preview
+        if raw_input.starts_with('─') && raw_input.ends_with('─') {
+            return print_output_buf("--\n");
+        }
+
+        let snap_file = raw_input
+            .split('"')
+            .nth(1)
+            .filter(|snap_file| !snap_file.is_empty())
+            .ok_or_else(|| HttmError::new("Snap file path is empty."))?;
+
+        let output_buf = Self::render(Path::new(snap_file))?;
+
+        print_output_buf(&output_buf)
+    }
+
+    // the preview, piped through the user's PREVIEW_PAGER, if any
+    pub fn render_and_page(path: &Path) -> HttmResult<String> {
+        let output_buf = Self::render(path)?;
+
+        match &GLOBAL_CONFIG.opt_preview_pager {
+            Some(pager) => Self::page(pager, output_buf),
+            None => Ok(output_buf),
+        }
+    }
+
+    pub fn page(pager: &str, output_buf: String) -> HttmResult<String> {
+        let mut spawned = ExecProcess::new("sh")
+            .arg("-c")
+            .arg(pager)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        // write on another thread, so a pager which reads only part of its input can never deadlock us
+        let opt_stdin_handle = spawned.stdin.take().map(|mut stdin| {
+            std::thread::spawn(move || {
+                let _ = stdin.write_all(output_buf.as_bytes());
+            })
+        });
+
+        let mut paged = String::new();
+
+        if let Some(mut stdout) = spawned.stdout.take() {
+            stdout.read_to_string(&mut paged)?;
+        }
+
+        if let Some(handle) = opt_stdin_handle {
+            let _ = handle.join();
+        }
+
+        spawned.wait()?;
+
+        Ok(paged)
+    }
+
+    pub fn render(path: &Path) -> HttmResult<String> {
+        let md = path.symlink_metadata()?;
+        let file_type = md.file_type();
+
+        if file_type.is_symlink() {
+            return Ok(format!(
+                "symlink: {:?} -> {:?}\n",
+                path,
+                std::fs::read_link(path)?
+            ));
+        }
+
+        if file_type.is_dir() {
+            return Self::directory(path);
+        }
+
+        if !file_type.is_file() {
+            return Ok(format!("special file: {:?}\n", path));
+        }
+
+        if md.len() == 0 {
+            return Ok(format!("WARN: {:?} is empty\n", path));
+        }
+
+        let mut contents = Vec::new();
+
+        std::fs::OpenOptions::new()
+            .read(true)
+            .open(path)?
+            .take(PREVIEW_MAX_BYTES)
+            .read_to_end(&mut contents)?;
+
+        if let Some((format, width, height)) = Self::image_dimensions(&contents) {
+            return Ok(format!(
+                "image: {:?}, {format}, {width}x{height} pixels, {} bytes\n",
+                path,
+                md.len()
+            ));
+        }
+
+        let is_truncated = md.len() > contents.len() as u64;
+
+        let text = match std::str::from_utf8(&contents) {
+            Ok(text) => text,
+            // a multibyte character may have been cut short at our read limit
+            Err(err) if is_truncated && err.error_len().is_none() => {
+                // SAFETY: valid_up_to() guarantees these bytes are valid UTF-8
+                unsafe { std::str::from_utf8_unchecked(&contents[..err.valid_up_to()]) }
+            }
+            Err(_) => {
+                let truncated = if md.len() > HEX_DUMP_MAX_BYTES as u64 {
+                    format!(
+                        "... ({} more bytes not shown)\n",
+                        md.len() - HEX_DUMP_MAX_BYTES as u64
+                    )
+                } else {
+                    String::new()
+                };
+
+                return Ok(format!(
+                    "binary file: {:?}, {} bytes\n{}{truncated}",
+                    path,
+                    md.len(),
+                    hex_dump(&contents)
+                ));
+            }
+        };
+
+        Ok(Self::head_and_tail(text, is_truncated, md.len()))
+    }
+
+    // the first and the last lines of a text file, like 'head' and 'tail'
+    fn head_and_tail(text: &str, is_truncated: bool, file_len: u64) -> String {
+        let lines: Vec<&str> = text.lines().collect();
+
+        let number = |idx: usize, line: &str| {
+            if GLOBAL_CONFIG.opt_preview_line_numbers {
+                format!("{:>6}\t{line}\n", idx + 1)
+            } else {
+                format!("{line}\n")
+            }
+        };
+
+        // when we did not read the whole file, we cannot know its last lines
+        if is_truncated {
+            let head: String = lines
+                .iter()
+                .take(PREVIEW_HEAD_LINES)
+                .enumerate()
+                .map(|(idx, line)| number(idx, line))
+                .collect();
+
+            return format!(
+                "{head}... (file is {file_len} bytes, only its first lines are shown)\n"
+            );
+        }
+
+        if lines.len() <= PREVIEW_HEAD_LINES + PREVIEW_TAIL_LINES {
+            return lines
+                .iter()
+                .enumerate()
+                .map(|(idx, line)| number(idx, line))
+                .collect();
+        }
+
+        let tail_start = lines.len() - PREVIEW_TAIL_LINES;
+
+        let head: String = lines[..PREVIEW_HEAD_LINES]
+            .iter()
+            .enumerate()
+            .map(|(idx, line)| number(idx, line))
+            .collect();
+
+        let tail: String = lines[tail_start..]
+            .iter()
+            .enumerate()
+            .map(|(idx, line)| number(tail_start + idx, line))
+            .collect();
+
+        format!(
+            "{head}... ({} lines not shown)\n{tail}",
+            tail_start - PREVIEW_HEAD_LINES
+        )
+    }
+
+    fn directory(path: &Path) -> HttmResult<String> {
+        let mut entries: Vec<String> = std::fs::read_dir(path)?
+            .flatten()
+            .map(|dir_entry| {
+                let name = dir_entry.file_name().to_string_lossy().into_owned();
+
+                match dir_entry.file_type() {
+                    Ok(file_type) if file_type.is_dir() => format!("{name}/"),
+                    _ => name,
+                }
+            })
+            .collect();
+
+        entries.sort_unstable();
+
+        let listing: String = entries
+            .iter()
+            .take(PREVIEW_MAX_DIR_ENTRIES)
+            .map(|entry| format!("{entry}\n"))
+            .collect();
+
+        let truncated = if entries.len() > PREVIEW_MAX_DIR_ENTRIES {
+            format!(
+                "... ({} more entries not shown)\n",
+                entries.len() - PREVIEW_MAX_DIR_ENTRIES
+            )
+        } else {
+            String::new()
+        };
+
+        Ok(format!(
+            "directory: {:?}, {} entries\n{listing}{truncated}",
+            path,
+            entries.len()
+        ))
+    }
+
+    // read only the headers of a few common image formats, we never decode the image itself
+    fn image_dimensions(contents: &[u8]) -> Option<(&'static str, u32, u32)> {
+        let be_u16 = |idx: usize| -> Option<u32> {
+            contents
+                .get(idx..idx + 2)
+                .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]) as u32)
+        };
+        let le_u16 = |idx: usize| -> Option<u32> {
+            contents
+                .get(idx..idx + 2)
+                .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as u32)
+        };
+        let be_u32 = |idx: usize| -> Option<u32> {
+            contents
+                .get(idx..idx + 4)
+                .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        };
+        let le_i32 = |idx: usize| -> Option<u32> {
+            contents.get(idx..idx + 4).map(|bytes| {
+                i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]).unsigned_abs()
+            })
+        };
+
+        if contents.starts_with(b"\x89PNG\r\n\x1a\n") {
+            return Some(("PNG", be_u32(16)?, be_u32(20)?));
+        }
+
+        if contents.starts_with(b"GIF87a") || contents.starts_with(b"GIF89a") {
+            return Some(("GIF", le_u16(6)?, le_u16(8)?));
+        }
+
+        // the size of the BMP info header, which follows the file header, is one of a few known values
+        let is_bmp = contents.starts_with(b"BM")
+            && contents
+                .get(14..18)
+                .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .is_some_and(|header_size| [12, 40, 56, 108, 124].contains(&header_size));
+
+        if is_bmp {
+            return Some(("BMP", le_i32(18)?, le_i32(22)?));
+        }
+
+        if contents.starts_with(&[0xFF, 0xD8]) {
+            // walk the JPEG segments until we find a start of frame, which holds the dimensions
+            let mut idx = 2;
+
+            while let (Some(0xFF), Some(marker)) = (contents.get(idx), contents.get(idx + 1)) {
+                match marker {
+                    // the start of frame markers, excepting DHT, JPG, and DAC
+                    0xC0..=0xCF if ![0xC4, 0xC8, 0xCC].contains(marker) => {
+                        return Some(("JPEG", be_u16(idx + 7)?, be_u16(idx + 5)?));
+                    }
+                    _ => idx += 2 + be_u16(idx + 2)? as usize,
+                }
+            }
+        }
+
+        None
+    }
+}
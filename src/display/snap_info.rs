@@ -0,0 +1,151 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::{Config, FormattedMode, PrintMode};
+use crate::data::paths::{PathData, PathDeconstruction, ZfsSnapPathGuard};
+use crate::display::versions::{NOT_SO_PRETTY_FIXED_WIDTH_PADDING, PRETTY_FIXED_WIDTH_PADDING};
+use crate::filesystem::mounts::FilesystemType;
+use crate::library::utility::{date_string, delimiter, split_snap_path, DateFormat};
+use crate::zfs::run_command::RunZFSCommand;
+use crate::{BTRFS_SNAPPER_SUFFIX, GLOBAL_CONFIG, ZFS_SNAPSHOT_DIRECTORY};
+use hashbrown::HashMap;
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::SystemTime;
+
+// key: full snapshot name, "pool/dataset@snap", val: creation time, obtained with a single "zfs list"
+// and only upon first use, as the zfs command is not available, or necessary, on every system
+static ZFS_CREATION_TIMES: LazyLock<HashMap<String, SystemTime>> = LazyLock::new(|| {
+    RunZFSCommand::new()
+        .and_then(|zfs_command| zfs_command.all_snapshots_by_creation())
+        .map(|snapshots| snapshots.into_iter().collect())
+        .unwrap_or_default()
+});
+
+// the snapshot which contains a snapshot version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapInfo {
+    pub name: String,
+    pub opt_creation: Option<SystemTime>,
+}
+
+impl std::fmt::Display for SnapInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.opt_creation {
+            Some(creation) => write!(f, "[{}, created {}]", self.name, self.date(creation)),
+            None => write!(f, "[{}]", self.name),
+        }
+    }
+}
+
+impl SnapInfo {
+    // live versions are not contained by any snapshot, and so have no snap info
+    pub fn new(pathdata: &PathData) -> Option<Self> {
+        if let Some(spd) = ZfsSnapPathGuard::new(pathdata) {
+            let full_name = spd.source(None)?.to_string_lossy().into_owned();
+            let (_dataset, snap_name) = full_name.split_once('@')?;
+
+            return Some(Self {
+                name: snap_name.to_owned(),
+                opt_creation: ZFS_CREATION_TIMES.get(&full_name).copied(),
+            });
+        }
+
+        let (snap_mount, _dataset, _relative) = split_snap_path(pathdata.path())?;
+
+        Some(Self {
+            name: Self::snap_mount_name(snap_mount)?,
+            opt_creation: snap_mount.metadata().and_then(|md| md.created()).ok(),
+        })
+    }
+
+    // as above, but for a snap mount itself, like "/pool/data/.zfs/snapshot/snap_1"
+    pub fn from_snap_mount(snap_mount: &Path) -> Option<Self> {
+        let opt_zfs_dataset = snap_mount
+            .parent()
+            .filter(|snap_dir| snap_dir.ends_with(ZFS_SNAPSHOT_DIRECTORY))
+            .and_then(|snap_dir| snap_dir.parent())
+            .and_then(|hidden_dir| hidden_dir.parent())
+            .and_then(|dataset_mount| {
+                GLOBAL_CONFIG
+                    .dataset_collection
+                    .map_of_datasets
+                    .get(dataset_mount)
+            })
+            .filter(|md| md.fs_type == FilesystemType::Zfs);
+
+        if let Some(md) = opt_zfs_dataset {
+            let snap_name = snap_mount.file_name()?.to_string_lossy().into_owned();
+            let full_name = format!("{}@{}", md.source.to_string_lossy(), snap_name);
+
+            return Some(Self {
+                name: snap_name,
+                opt_creation: ZFS_CREATION_TIMES.get(&full_name).copied(),
+            });
+        }
+
+        Some(Self {
+            name: Self::snap_mount_name(snap_mount)?,
+            opt_creation: snap_mount.metadata().and_then(|md| md.created()).ok(),
+        })
+    }
+
+    pub fn append_to_line(line: &str, snap_info: &SnapInfo, config: &Config) -> String {
+        let padding = match config.print_mode {
+            PrintMode::Formatted(FormattedMode::NotPretty) => NOT_SO_PRETTY_FIXED_WIDTH_PADDING,
+            _ => PRETTY_FIXED_WIDTH_PADDING,
+        };
+
+        let delimiter = delimiter();
+
+        format!(
+            "{}{padding}{snap_info}{delimiter}",
+            line.trim_end_matches(delimiter)
+        )
+    }
+
+    pub fn creation_timestamp(&self) -> String {
+        self.opt_creation
+            .map(|creation| {
+                date_string(
+                    GLOBAL_CONFIG.requested_utc_offset,
+                    &creation,
+                    DateFormat::Timestamp,
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    fn date(&self, creation: SystemTime) -> String {
+        date_string(
+            GLOBAL_CONFIG.requested_utc_offset,
+            &creation,
+            DateFormat::Display,
+        )
+    }
+
+    // a Snapper snap mount, like ".snapshots/5/snapshot", is named for its numbered dir
+    pub fn snap_mount_name(snap_mount: &Path) -> Option<String> {
+        let name = if snap_mount.ends_with(BTRFS_SNAPPER_SUFFIX) {
+            snap_mount.parent()?.file_name()?
+        } else {
+            snap_mount.file_name()?
+        };
+
+        Some(name.to_string_lossy().into_owned())
+    }
+}
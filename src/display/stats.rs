@@ -0,0 +1,207 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::{FormattedMode, PrintMode};
+use crate::data::paths::PathDeconstruction;
+use crate::library::cleanup::CleanupGuard;
+use crate::library::utility::{date_string, delimiter, DateFormat};
+use crate::lookup::versions::VersionsMap;
+use crate::GLOBAL_CONFIG;
+use serde::ser::{SerializeMap, SerializeStruct};
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct DatasetStats {
+    num_paths: usize,
+    num_versions: usize,
+}
+
+// an aggregate report over every requested path, rather than a row per version
+#[derive(Debug, Default)]
+pub struct VersionStats {
+    num_paths: usize,
+    num_with_snaps: usize,
+    num_versions: usize,
+    opt_oldest: Option<SystemTime>,
+    opt_newest: Option<SystemTime>,
+    per_dataset: BTreeMap<PathBuf, DatasetStats>,
+}
+
+impl From<&VersionsMap> for VersionStats {
+    fn from(versions_map: &VersionsMap) -> Self {
+        let mut stats = VersionStats::default();
+
+        versions_map.iter().for_each(|(live_version, snaps)| {
+            stats.num_paths += 1;
+            stats.num_versions += snaps.len();
+
+            if !snaps.is_empty() {
+                stats.num_with_snaps += 1;
+            }
+
+            snaps
+                .iter()
+                .filter_map(|snap| snap.opt_metadata().as_ref())
+                .map(|metadata| metadata.mtime())
+                .for_each(|mtime| {
+                    stats.opt_oldest =
+                        Some(stats.opt_oldest.map_or(mtime, |oldest| oldest.min(mtime)));
+                    stats.opt_newest =
+                        Some(stats.opt_newest.map_or(mtime, |newest| newest.max(mtime)));
+                });
+
+            let dataset = live_version
+                .proximate_dataset()
+                .map(|dataset| dataset.to_path_buf())
+                .unwrap_or_default();
+
+            let dataset_stats = stats.per_dataset.entry(dataset).or_default();
+
+            dataset_stats.num_paths += 1;
+            dataset_stats.num_versions += snaps.len();
+        });
+
+        stats
+    }
+}
+
+impl VersionStats {
+    fn opt_date(opt_time: Option<SystemTime>, date_format: DateFormat) -> Option<String> {
+        opt_time.map(|time| date_string(GLOBAL_CONFIG.requested_utc_offset, &time, date_format))
+    }
+
+    pub fn to_json(&self) -> String {
+        let res = match GLOBAL_CONFIG.print_mode {
+            PrintMode::Formatted(FormattedMode::Default) => serde_json::to_string_pretty(self),
+            _ => serde_json::to_string(self),
+        };
+
+        match res {
+            Ok(s) => {
+                let delimiter = delimiter();
+                format!("{s}{delimiter}")
+            }
+            Err(error) => {
+                eprintln!("Error: {error}");
+                CleanupGuard::exit(1)
+            }
+        }
+    }
+
+    fn format(&self) -> String {
+        let delimiter = delimiter();
+        let none = "-".to_owned();
+
+        let oldest = Self::opt_date(self.opt_oldest, DateFormat::Display).unwrap_or(none.clone());
+        let newest = Self::opt_date(self.opt_newest, DateFormat::Display).unwrap_or(none);
+
+        let mut buffer = format!(
+            "Paths requested:\t{}{delimiter}\
+            With snapshots:\t\t{}{delimiter}\
+            Without snapshots:\t{}{delimiter}\
+            Unique versions:\t{}{delimiter}\
+            Oldest version:\t\t{}{delimiter}\
+            Newest version:\t\t{}{delimiter}",
+            self.num_paths,
+            self.num_with_snaps,
+            self.num_paths - self.num_with_snaps,
+            self.num_versions,
+            oldest,
+            newest,
+        );
+
+        buffer.push_str(&format!("Per dataset:{delimiter}"));
+
+        self.per_dataset
+            .iter()
+            .for_each(|(dataset, dataset_stats)| {
+                buffer.push_str(&format!(
+                    "\t{:?}:\t{} paths, {} versions{delimiter}",
+                    dataset, dataset_stats.num_paths, dataset_stats.num_versions
+                ))
+            });
+
+        buffer
+    }
+}
+
+impl std::string::ToString for VersionStats {
+    fn to_string(&self) -> String {
+        if GLOBAL_CONFIG.opt_json {
+            return self.to_json();
+        }
+
+        self.format()
+    }
+}
+
+impl Serialize for DatasetStats {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("DatasetStats", 2)?;
+
+        state.serialize_field("paths", &self.num_paths)?;
+        state.serialize_field("versions", &self.num_versions)?;
+        state.end()
+    }
+}
+
+struct PerDataset<'a>(&'a BTreeMap<PathBuf, DatasetStats>);
+
+impl<'a> Serialize for PerDataset<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_map(Some(self.0.len()))?;
+
+        for (dataset, dataset_stats) in self.0.iter() {
+            state.serialize_entry(&dataset.to_string_lossy(), dataset_stats)?;
+        }
+
+        state.end()
+    }
+}
+
+impl Serialize for VersionStats {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("VersionStats", 7)?;
+
+        state.serialize_field("paths", &self.num_paths)?;
+        state.serialize_field("with_snapshots", &self.num_with_snaps)?;
+        state.serialize_field("without_snapshots", &(self.num_paths - self.num_with_snaps))?;
+        state.serialize_field("unique_versions", &self.num_versions)?;
+        state.serialize_field(
+            "oldest_version",
+            &Self::opt_date(self.opt_oldest, DateFormat::Timestamp),
+        )?;
+        state.serialize_field(
+            "newest_version",
+            &Self::opt_date(self.opt_newest, DateFormat::Timestamp),
+        )?;
+        state.serialize_field("per_dataset", &PerDataset(&self.per_dataset))?;
+        state.end()
+    }
+}
@@ -0,0 +1,416 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::{FormattedMode, PrintMode};
+use crate::data::paths::PathData;
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::print_output_buf;
+use crate::lookup::versions::VersionsMap;
+use crate::GLOBAL_CONFIG;
+use nu_ansi_term::Color::{Cyan, Green, Red};
+use std::path::Path;
+
+const DIFF_CONTEXT_LINES: usize = 3;
+// Myers' trace grows as (edit distance) x (lines remaining), once past this budget, about 32MiB
+// of isize entries, we give up on finding a minimal diff, and simply delete and insert the remainder
+const DIFF_MAX_TRACE_ENTRIES: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+// the diff as runs of lines either common to both files, or changed, for merging one change at a time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffSegment<'a> {
+    Common(Vec<&'a str>),
+    Changed {
+        old_lines: Vec<&'a str>,
+        new_lines: Vec<&'a str>,
+    },
+}
+
+pub struct UnifiedDiff<'a> {
+    old_label: &'a Path,
+    new_label: &'a Path,
+    old_lines: Vec<&'a str>,
+    new_lines: Vec<&'a str>,
+    is_colored: bool,
+}
+
+impl<'a> UnifiedDiff<'a> {
+    // for each input file, diff the most recent snapshot version which differs from the live version
+    pub fn exec_last_snap(versions_map: &VersionsMap) -> HttmResult<()> {
+        versions_map
+            .iter()
+            .try_for_each(|(live_version, snaps)| {
+                let opt_last_unique = snaps
+                    .iter()
+                    .rev()
+                    .find(|snap| snap.opt_metadata() != live_version.opt_metadata());
+
+                match opt_last_unique {
+                    Some(snap_version) => Self::exec_pair(snap_version.path(), live_version.path()),
+                    None => {
+                        eprintln!(
+                            "NOTICE: httm could not find a snapshot version which differs from the live version: {:?}",
+                            live_version.path()
+                        );
+                        Ok(())
+                    }
+                }
+            })
+    }
+
+    pub fn exec_pair(snap_path: &Path, live_path: &Path) -> HttmResult<()> {
+        let is_colored = matches!(
+            GLOBAL_CONFIG.print_mode,
+            PrintMode::Formatted(FormattedMode::Default)
+        );
+
+        let output_buf = Self::pair_buffer(snap_path, live_path, is_colored)?;
+
+        print_output_buf(&output_buf)
+    }
+
+    pub fn pair_buffer(snap_path: &Path, live_path: &Path, is_colored: bool) -> HttmResult<String> {
+        if !snap_path.is_file() {
+            let msg = format!("Path is not a file: {:?}", snap_path);
+            return Err(HttmError::new(&msg).into());
+        }
+
+        let old_bytes = std::fs::read(snap_path)?;
+        // a live version which no longer exists is diffed as if empty
+        let new_bytes = if PathData::from(live_path).opt_metadata().is_some() {
+            std::fs::read(live_path)?
+        } else {
+            Vec::new()
+        };
+
+        let output_buf = match (Self::as_text(&old_bytes), Self::as_text(&new_bytes)) {
+            (Some(old_text), Some(new_text)) => {
+                UnifiedDiff::new(snap_path, live_path, old_text, new_text, is_colored).to_string()
+            }
+            _ if old_bytes == new_bytes => String::new(),
+            _ => format!(
+                "Binary files {} and {} differ\n",
+                snap_path.display(),
+                live_path.display()
+            ),
+        };
+
+        Ok(output_buf)
+    }
+
+    pub fn as_text(bytes: &[u8]) -> Option<&str> {
+        if bytes.contains(&0u8) {
+            return None;
+        }
+
+        std::str::from_utf8(bytes).ok()
+    }
+
+    fn new(
+        old_label: &'a Path,
+        new_label: &'a Path,
+        old_text: &'a str,
+        new_text: &'a str,
+        is_colored: bool,
+    ) -> Self {
+        Self {
+            old_label,
+            new_label,
+            old_lines: old_text.lines().collect(),
+            new_lines: new_text.lines().collect(),
+            is_colored,
+        }
+    }
+
+    pub fn segments(old_text: &'a str, new_text: &'a str) -> Vec<DiffSegment<'a>> {
+        let diff = Self::new(Path::new(""), Path::new(""), old_text, new_text, false);
+
+        let mut segments: Vec<DiffSegment<'a>> = Vec::new();
+
+        diff.edits()
+            .into_iter()
+            .for_each(|edit| match (edit, segments.last_mut()) {
+                (Edit::Equal(a, _), Some(DiffSegment::Common(lines))) => {
+                    lines.push(diff.old_lines[a])
+                }
+                (Edit::Equal(a, _), _) => {
+                    segments.push(DiffSegment::Common(vec![diff.old_lines[a]]))
+                }
+                (Edit::Delete(a), Some(DiffSegment::Changed { old_lines, .. })) => {
+                    old_lines.push(diff.old_lines[a])
+                }
+                (Edit::Delete(a), _) => segments.push(DiffSegment::Changed {
+                    old_lines: vec![diff.old_lines[a]],
+                    new_lines: Vec::new(),
+                }),
+                (Edit::Insert(b), Some(DiffSegment::Changed { new_lines, .. })) => {
+                    new_lines.push(diff.new_lines[b])
+                }
+                (Edit::Insert(b), _) => segments.push(DiffSegment::Changed {
+                    old_lines: Vec::new(),
+                    new_lines: vec![diff.new_lines[b]],
+                }),
+            });
+
+        segments
+    }
+
+    fn edits(&self) -> Vec<Edit> {
+        let old = &self.old_lines;
+        let new = &self.new_lines;
+
+        // trim common prefix and suffix, which is the common case, before the more expensive search
+        let prefix = old
+            .iter()
+            .zip(new.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let old_middle = &old[prefix..old.len() - suffix];
+        let new_middle = &new[prefix..new.len() - suffix];
+
+        let mut edits: Vec<Edit> = (0..prefix).map(|idx| Edit::Equal(idx, idx)).collect();
+
+        edits.extend(
+            Self::myers(old_middle, new_middle)
+                .into_iter()
+                .map(|edit| match edit {
+                    Edit::Equal(a, b) => Edit::Equal(a + prefix, b + prefix),
+                    Edit::Delete(a) => Edit::Delete(a + prefix),
+                    Edit::Insert(b) => Edit::Insert(b + prefix),
+                }),
+        );
+
+        edits.extend(
+            (0..suffix).map(|idx| Edit::Equal(old.len() - suffix + idx, new.len() - suffix + idx)),
+        );
+
+        edits
+    }
+
+    // see: Eugene W. Myers, "An O(ND) Difference Algorithm and Its Variations"
+    fn myers(old: &[&str], new: &[&str]) -> Vec<Edit> {
+        if old.is_empty() || new.is_empty() {
+            return (0..old.len())
+                .map(Edit::Delete)
+                .chain((0..new.len()).map(Edit::Insert))
+                .collect();
+        }
+
+        let n = old.len() as isize;
+        let m = new.len() as isize;
+        let max = n + m;
+        let offset = max as usize;
+        let width = 2 * offset + 1;
+
+        let mut v: Vec<isize> = vec![0; width];
+        let mut trace: Vec<Vec<isize>> = Vec::new();
+
+        let mut is_found = false;
+
+        for d in 0..=max {
+            if trace.len().saturating_mul(width) > DIFF_MAX_TRACE_ENTRIES {
+                break;
+            }
+
+            trace.push(v.clone());
+
+            for k in (-d..=d).step_by(2) {
+                let idx = (k + max) as usize;
+
+                let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                    v[idx + 1]
+                } else {
+                    v[idx - 1] + 1
+                };
+                let mut y = x - k;
+
+                while x < n && y < m && old[x as usize] == new[y as usize] {
+                    x += 1;
+                    y += 1;
+                }
+
+                v[idx] = x;
+
+                if x >= n && y >= m {
+                    is_found = true;
+                    break;
+                }
+            }
+
+            if is_found {
+                break;
+            }
+        }
+
+        if !is_found {
+            return (0..old.len())
+                .map(Edit::Delete)
+                .chain((0..new.len()).map(Edit::Insert))
+                .collect();
+        }
+
+        // backtrack through the trace, from the end, to recover the edit script
+        let mut edits: Vec<Edit> = Vec::new();
+        let (mut x, mut y) = (n, m);
+
+        for (d, v) in trace.iter().enumerate().rev() {
+            let d = d as isize;
+            let k = x - y;
+
+            let prev_k =
+                if k == -d || (k != d && v[(k - 1 + max) as usize] < v[(k + 1 + max) as usize]) {
+                    k + 1
+                } else {
+                    k - 1
+                };
+            let prev_x = v[(prev_k + max) as usize];
+            let prev_y = prev_x - prev_k;
+
+            while x > prev_x && y > prev_y {
+                edits.push(Edit::Equal((x - 1) as usize, (y - 1) as usize));
+                x -= 1;
+                y -= 1;
+            }
+
+            if d > 0 {
+                if x == prev_x {
+                    edits.push(Edit::Insert((y - 1) as usize));
+                } else {
+                    edits.push(Edit::Delete((x - 1) as usize));
+                }
+            }
+
+            x = prev_x;
+            y = prev_y;
+        }
+
+        edits.reverse();
+        edits
+    }
+
+    fn hunks(edits: &[Edit]) -> Vec<std::ops::Range<usize>> {
+        let mut hunks: Vec<std::ops::Range<usize>> = Vec::new();
+
+        edits
+            .iter()
+            .enumerate()
+            .filter(|(_idx, edit)| !matches!(edit, Edit::Equal(_, _)))
+            .for_each(|(idx, _edit)| {
+                let start = idx.saturating_sub(DIFF_CONTEXT_LINES);
+                let end = (idx + DIFF_CONTEXT_LINES + 1).min(edits.len());
+
+                match hunks.last_mut() {
+                    Some(last) if start <= last.end => last.end = end,
+                    _ => hunks.push(start..end),
+                }
+            });
+
+        hunks
+    }
+
+    fn hunk_header(edits: &[Edit]) -> String {
+        let (mut old_start, mut new_start) = (None, None);
+        let (mut old_len, mut new_len) = (0usize, 0usize);
+
+        edits.iter().for_each(|edit| match edit {
+            Edit::Equal(a, b) => {
+                old_start.get_or_insert(*a);
+                new_start.get_or_insert(*b);
+                old_len += 1;
+                new_len += 1;
+            }
+            Edit::Delete(a) => {
+                old_start.get_or_insert(*a);
+                old_len += 1;
+            }
+            Edit::Insert(b) => {
+                new_start.get_or_insert(*b);
+                new_len += 1;
+            }
+        });
+
+        // by convention, an empty range begins at the line before, and line numbers begin at 1
+        let format_range = |opt_start: Option<usize>, len: usize| match opt_start {
+            Some(start) => format!("{},{}", start + 1, len),
+            None => "0,0".to_owned(),
+        };
+
+        format!(
+            "@@ -{} +{} @@",
+            format_range(old_start, old_len),
+            format_range(new_start, new_len)
+        )
+    }
+}
+
+impl<'a> std::fmt::Display for UnifiedDiff<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let edits = self.edits();
+        let hunks = Self::hunks(&edits);
+
+        if hunks.is_empty() {
+            return Ok(());
+        }
+
+        let paint = |line: String, color: nu_ansi_term::Color| {
+            if self.is_colored {
+                color.paint(line).to_string()
+            } else {
+                line
+            }
+        };
+
+        let mut buffer = format!(
+            "--- {}\n+++ {}\n",
+            self.old_label.display(),
+            self.new_label.display()
+        );
+
+        hunks.into_iter().for_each(|range| {
+            let hunk = &edits[range];
+
+            buffer += &paint(Self::hunk_header(hunk), Cyan);
+            buffer.push('\n');
+
+            hunk.iter().for_each(|edit| {
+                let line = match edit {
+                    Edit::Equal(a, _) => format!(" {}", self.old_lines[*a]),
+                    Edit::Delete(a) => paint(format!("-{}", self.old_lines[*a]), Red),
+                    Edit::Insert(b) => paint(format!("+{}", self.new_lines[*b]), Green),
+                };
+
+                buffer += &line;
+                buffer.push('\n');
+            });
+        });
+
+        write!(f, "{buffer}")
+    }
+}
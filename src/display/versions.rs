@@ -16,12 +16,19 @@
 // that was distributed with this source code.
 
 use crate::config::generate::{BulkExclusion, Config, FormattedMode, PrintMode, RawMode};
-use crate::data::paths::{PathData, PHANTOM_DATE, PHANTOM_SIZE};
+use crate::data::paths::{CompareContentsContainer, PathData, PHANTOM_DATE, PHANTOM_SIZE};
+use crate::display::diff_summary::DiffSummary;
+use crate::display::format_template::{FormatField, FormatTemplate};
+use crate::display::snap_info::SnapInfo;
 use crate::filesystem::mounts::IsFilterDir;
 use crate::library::utility::{
+    csv_field,
+    csv_header,
     date_string,
     delimiter,
     display_human_size,
+    display_path,
+    paint_snap_path,
     paint_string,
     DateFormat,
 };
@@ -30,7 +37,6 @@ use crate::DisplayWrapper;
 use std::borrow::Cow;
 use std::ops::Deref;
 use terminal_size::{terminal_size, Height, Width};
-use time::UtcOffset;
 
 // 2 space wide padding - used between date and size, and size and path
 pub const PRETTY_FIXED_WIDTH_PADDING: &str = "  ";
@@ -75,8 +81,21 @@ impl<'a> DisplayWrapper<'a> {
     fn raw(&self, raw_mode: &RawMode) -> String {
         let delimiter = delimiter();
 
+        let header = match raw_mode {
+            RawMode::Csv if self.config.opt_snap_info => csv_header(&[
+                "modify_time",
+                "size",
+                "path",
+                "snapshot",
+                "snapshot_created",
+            ]),
+            RawMode::Csv => csv_header(&["modify_time", "size", "path"]),
+            RawMode::Newline | RawMode::Zero => String::new(),
+        };
+
         // else re compute for each instance and print per instance, now with uniform padding
-        self.iter()
+        let rows: String = self
+            .iter()
             .map(|(key, values)| {
                 let keys: Vec<&PathData> = vec![key];
                 let values: Vec<&PathData> = values.iter().collect();
@@ -93,8 +112,37 @@ impl<'a> DisplayWrapper<'a> {
                 display_set
                     .iter()
                     .flatten()
-                    .map(|path_data| {
-                        path_data.raw(raw_mode, delimiter, self.config.requested_utc_offset)
+                    .map(|path_data| path_data.raw(raw_mode, delimiter, self.config))
+                    .collect::<String>()
+            })
+            .collect();
+
+        header + &rows
+    }
+
+    // see FORMAT, snaps then live, each version upon its own line
+    pub fn format_with_template(&self, template: &FormatTemplate) -> String {
+        let delimiter = delimiter();
+
+        self.iter()
+            .map(|(key, values)| {
+                let keys: Vec<&PathData> = vec![key];
+                let values: Vec<&PathData> = values.iter().collect();
+
+                DisplaySet::from((keys, values))
+            })
+            .map(|display_set| {
+                display_set
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, snap_or_live_set)| (DisplaySetType::from(idx), snap_or_live_set))
+                    .filter(|(display_set_type, _snap_or_live_set)| {
+                        display_set_type.filter_bulk_exclusions(&self.config)
+                    })
+                    .flat_map(|(display_set_type, snap_or_live_set)| {
+                        snap_or_live_set.iter().map(move |path_data| {
+                            path_data.render(template, &display_set_type, delimiter, self.config)
+                        })
                     })
                     .collect::<String>()
             })
@@ -165,6 +213,17 @@ impl<'a> DisplaySet<'a> {
     pub fn format(&self, config: &Config, padding_collection: &PaddingCollection) -> String {
         let mut border: String = padding_collection.fancy_border_string.to_string();
 
+        // snaps then live, each compared to its predecessor
+        let opt_diff_summaries: Option<Vec<Option<DiffSummary>>> = if config.opt_diff_summary {
+            let versions: Vec<&PathData> = self.iter().flatten().copied().collect();
+            Some(DiffSummary::sequence(&versions))
+        } else {
+            None
+        };
+
+        // numbers are right aligned, and as wide as the largest number
+        let number_width = self.inner[0].len().to_string().len();
+
         // get the display buffer for each set snaps and live
         self.iter()
             .enumerate()
@@ -175,10 +234,60 @@ impl<'a> DisplaySet<'a> {
             .fold(
                 String::new(),
                 |mut display_set_buffer, (display_set_type, snap_or_live_set)| {
-                    let mut component_buffer: String = snap_or_live_set
+                    let summary_offset = match &display_set_type {
+                        DisplaySetType::IsSnap => 0usize,
+                        DisplaySetType::IsLive => self.inner[0].len(),
+                    };
+
+                    // each run is displayed as a single row, see COLLAPSE_IDENTICAL
+                    let runs: Vec<(usize, usize)> = match &display_set_type {
+                        DisplaySetType::IsSnap if config.opt_collapse_identical => {
+                            Self::identical_runs(snap_or_live_set)
+                        }
+                        _ => (0..snap_or_live_set.len()).map(|idx| (idx, 1)).collect(),
+                    };
+
+                    let mut component_buffer: String = runs
                         .iter()
-                        .map(|path_data| {
-                            path_data.format(config, &display_set_type, padding_collection)
+                        .map(|(idx, run_len)| {
+                            let path_data = snap_or_live_set[*idx];
+
+                            let mut line =
+                                path_data.format(config, &display_set_type, padding_collection);
+
+                            // the newest snapshot version is numbered 1, see NUMBERED
+                            if config.opt_numbered {
+                                let opt_number = match &display_set_type {
+                                    DisplaySetType::IsSnap => Some(snap_or_live_set.len() - *idx),
+                                    DisplaySetType::IsLive => None,
+                                };
+
+                                line =
+                                    Self::prepend_number(&line, opt_number, number_width, config);
+                            }
+
+                            if *run_len > 1 {
+                                line = Self::append_run(
+                                    &line,
+                                    &snap_or_live_set[*idx..*idx + *run_len],
+                                    config,
+                                );
+                            }
+
+                            if let Some(Some(summary)) = opt_diff_summaries
+                                .as_ref()
+                                .and_then(|summaries| summaries.get(summary_offset + *idx))
+                            {
+                                line = DiffSummary::append_to_line(&line, summary, config);
+                            }
+
+                            // see SNAP_INFO
+                            match config.opt_snap_info.then(|| SnapInfo::new(path_data)) {
+                                Some(Some(snap_info)) => {
+                                    SnapInfo::append_to_line(&line, &snap_info, config)
+                                }
+                                _ => line,
+                            }
                         })
                         .collect();
 
@@ -224,6 +333,80 @@ impl<'a> DisplaySet<'a> {
                 },
             )
     }
+
+    // adjacent snapshot versions with identical contents, as the index of the first version
+    // of each run, and the number of versions in that run
+    fn identical_runs(snaps: &[&PathData]) -> Vec<(usize, usize)> {
+        let containers: Vec<CompareContentsContainer> = snaps
+            .iter()
+            .map(|path_data| CompareContentsContainer::from((*path_data).clone()))
+            .collect();
+
+        let is_identical = |a: &CompareContentsContainer, b: &CompareContentsContainer| {
+            a.is_file() && b.is_file() && a.size() == b.size() && a.cmp_file_contents(b).is_eq()
+        };
+
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+
+        containers
+            .iter()
+            .enumerate()
+            .for_each(|(idx, container)| match runs.last_mut() {
+                Some((start, run_len)) if is_identical(&containers[*start], container) => {
+                    *run_len += 1;
+                }
+                _ => runs.push((idx, 1)),
+            });
+
+        runs
+    }
+
+    fn prepend_number(
+        line: &str,
+        opt_number: Option<usize>,
+        number_width: usize,
+        config: &Config,
+    ) -> String {
+        let number = opt_number
+            .map(|number| number.to_string())
+            .unwrap_or_default();
+
+        match config.print_mode {
+            PrintMode::Formatted(FormattedMode::NotPretty) => {
+                format!("{number}{NOT_SO_PRETTY_FIXED_WIDTH_PADDING}{line}")
+            }
+            _ => format!("{number:>number_width$}{PRETTY_FIXED_WIDTH_PADDING}{line}"),
+        }
+    }
+
+    fn append_run(line: &str, run: &[&PathData], config: &Config) -> String {
+        let padding = match config.print_mode {
+            PrintMode::Formatted(FormattedMode::NotPretty) => NOT_SO_PRETTY_FIXED_WIDTH_PADDING,
+            _ => PRETTY_FIXED_WIDTH_PADDING,
+        };
+
+        let date = |path_data: &PathData| {
+            date_string(
+                config.requested_utc_offset,
+                &path_data.metadata_infallible().mtime(),
+                DateFormat::Display,
+            )
+        };
+
+        let (Some(first), Some(last)) = (run.first(), run.last()) else {
+            return line.to_string();
+        };
+
+        let delimiter = delimiter();
+
+        format!(
+            "{}{padding}[{} identical versions, {} to {}]{delimiter}",
+            line.trim_end_matches(delimiter),
+            run.len(),
+            date(first),
+            date(last)
+        )
+    }
 }
 
 impl PathData {
@@ -249,7 +432,7 @@ impl PathData {
                 } else {
                     Cow::Borrowed(&padding_collection.phantom_size_pad_str)
                 };
-                let path = self.path().to_string_lossy();
+                let path = display_path(self);
                 let padding = NOT_SO_PRETTY_FIXED_WIDTH_PADDING;
                 (size, path, padding)
             }
@@ -268,14 +451,15 @@ impl PathData {
                     ))
                 };
                 let path = {
-                    let path_buf = &self.path();
+                    let path_str = display_path(self);
 
                     // paint the live strings with ls colors - idx == 1 is 2nd or live set
+                    // relative snapshot paths are painted like any other path
                     let painted_path_str = match display_set_type {
-                        DisplaySetType::IsLive => {
-                            paint_string(self, path_buf.to_str().unwrap_or_default())
+                        DisplaySetType::IsSnap if config.opt_relative.is_none() => {
+                            paint_snap_path(self)
                         }
-                        DisplaySetType::IsSnap => path_buf.to_string_lossy(),
+                        _ => paint_string(self, &path_str),
                     };
 
                     Cow::Owned(format!(
@@ -302,11 +486,50 @@ impl PathData {
         };
 
         format!(
-            "{}{}{}{}{}\n",
-            display_date, display_padding, display_size, display_padding, display_path
+            "{}{}{}{}{}{}",
+            display_date,
+            display_padding,
+            display_size,
+            display_padding,
+            display_path,
+            delimiter()
         )
     }
 
+    pub fn render(
+        &self,
+        template: &FormatTemplate,
+        display_set_type: &DisplaySetType,
+        delimiter: char,
+        config: &Config,
+    ) -> String {
+        let is_snap = matches!(display_set_type, DisplaySetType::IsSnap);
+
+        // phantom values, such as the size of a live file which does not exist, are left empty
+        let line = template.render(|field| match (field, self.opt_metadata()) {
+            (FormatField::Path, _) => display_path(self),
+            (FormatField::SnapPath, _) if is_snap => display_path(self),
+            (FormatField::Snapshot, _) if is_snap => SnapInfo::new(self)
+                .map(|snap_info| snap_info.name)
+                .unwrap_or_default(),
+            (FormatField::Size, Some(md)) => display_human_size(md.size()),
+            (FormatField::Bytes, Some(md)) => md.size().to_string(),
+            (FormatField::Mtime, Some(md)) => date_string(
+                config.requested_utc_offset,
+                &md.mtime(),
+                DateFormat::Display,
+            ),
+            (FormatField::Timestamp, Some(md)) => date_string(
+                config.requested_utc_offset,
+                &md.mtime(),
+                DateFormat::Timestamp,
+            ),
+            _ => String::new(),
+        });
+
+        format!("{line}{delimiter}")
+    }
+
     fn warning_underlying_snaps<'a>(&'a self, config: &Config) -> &'a str {
         match ProximateDatasetAndOptAlts::new(self).ok() {
             None => {
@@ -324,34 +547,56 @@ impl PathData {
         }
     }
 
-    pub fn raw(
-        &self,
-        raw_mode: &RawMode,
-        delimiter: char,
-        requested_utc_offset: UtcOffset,
-    ) -> String {
+    pub fn raw(&self, raw_mode: &RawMode, delimiter: char, config: &Config) -> String {
         match raw_mode {
-            RawMode::Csv => match self.opt_metadata() {
-                Some(md) => {
-                    let date =
-                        date_string(requested_utc_offset, &md.mtime(), DateFormat::Timestamp);
-
-                    let size = md.size();
+            RawMode::Csv => {
+                // two additional columns, the snapshot name and creation time, see SNAP_INFO
+                let snap_info_columns = if config.opt_snap_info {
+                    match SnapInfo::new(self) {
+                        Some(snap_info) => {
+                            format!(
+                                ",{},{}",
+                                csv_field(&snap_info.name),
+                                snap_info.creation_timestamp()
+                            )
+                        }
+                        None => ",,".to_string(),
+                    }
+                } else {
+                    String::new()
+                };
 
-                    format!(
-                        "{},{},\"{}\"{}",
-                        date,
-                        size,
-                        self.path().to_string_lossy(),
-                        delimiter
-                    )
-                }
-                None => {
-                    format!(",,\"{}\"{}", self.path().to_string_lossy(), delimiter)
+                match self.opt_metadata() {
+                    Some(md) => {
+                        let date = date_string(
+                            config.requested_utc_offset,
+                            &md.mtime(),
+                            DateFormat::Timestamp,
+                        );
+
+                        let size = md.size();
+
+                        format!(
+                            "{},{},{}{}{}",
+                            date,
+                            size,
+                            csv_field(&display_path(self)),
+                            snap_info_columns,
+                            delimiter
+                        )
+                    }
+                    None => {
+                        format!(
+                            ",,{}{}{}",
+                            csv_field(&display_path(self)),
+                            snap_info_columns,
+                            delimiter
+                        )
+                    }
                 }
-            },
+            }
             RawMode::Newline | RawMode::Zero => {
-                format!("{}{}", self.path().to_string_lossy(), delimiter)
+                format!("{}{}", display_path(self), delimiter)
             }
         }
     }
@@ -384,7 +629,7 @@ impl PaddingCollection {
                         display_human_size(metadata.size()),
                         width = size_padding_len
                     );
-                    let path = path_data.path().to_string_lossy();
+                    let path = display_path(path_data);
 
                     (date, size, path)
                 };
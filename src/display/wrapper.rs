@@ -18,6 +18,7 @@
 use crate::config::generate::{BulkExclusion, Config, ExecMode, FormattedMode, PrintMode};
 use crate::data::paths::PathData;
 use crate::display::maps::PrintAsMap;
+use crate::library::cleanup::CleanupGuard;
 use crate::library::utility::delimiter;
 use crate::lookup::versions::VersionsMap;
 use serde::ser::SerializeMap;
@@ -36,8 +37,9 @@ impl<'a> std::string::ToString for DisplayWrapper<'a> {
             ExecMode::NumVersions(num_versions_mode) => {
                 self.format_as_num_versions(num_versions_mode)
             }
+            ExecMode::LatestPerDir => self.format_as_latest_per_dir(),
             _ => {
-                if self.config.opt_last_snap.is_some() {
+                if self.config.opt_last_snap.is_some() || self.config.opt_select_by.is_some() {
                     let printable_map = PrintAsMap::from(&self.map);
                     return printable_map.to_string();
                 }
@@ -46,6 +48,10 @@ impl<'a> std::string::ToString for DisplayWrapper<'a> {
                     return self.to_json();
                 }
 
+                if let Some(template) = &self.config.opt_format {
+                    return self.format_with_template(template);
+                }
+
                 self.format()
             }
         }
@@ -78,7 +84,7 @@ impl<'a> DisplayWrapper<'a> {
             }
             Err(error) => {
                 eprintln!("Error: {error}");
-                std::process::exit(1)
+                CleanupGuard::exit(1)
             }
         }
     }
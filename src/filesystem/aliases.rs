@@ -16,11 +16,22 @@
 // that was distributed with this source code.
 
 use crate::filesystem::mounts::{DatasetMetadata, FilesystemType};
+use crate::filesystem::ssh_aliases::SshAliases;
 use crate::library::results::{HttmError, HttmResult};
+use hashbrown::HashMap;
 use std::collections::BTreeMap;
 use std::ops::Deref;
+use std::os::unix::fs::MetadataExt;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+
+const ALIAS_MARKER_FILE_NAME: &str = ".httm-alias";
+
+type MarkerAlias = Option<(Box<Path>, Box<Path>)>;
+
+// key: a dir searched, val: the alias declared by the nearest marker file at or above that dir
+static MARKER_ALIASES: LazyLock<Mutex<HashMap<Box<Path>, MarkerAlias>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RemotePathAndFsType {
@@ -48,14 +59,9 @@ impl Deref for MapOfAliases {
 }
 
 impl MapOfAliases {
-    pub fn new(
-        map_of_datasets: &BTreeMap<Arc<Path>, DatasetMetadata>,
-        opt_raw_aliases: Option<Vec<String>>,
-        opt_remote_dir: Option<&String>,
-        opt_local_dir: Option<&String>,
-        pwd: &Path,
-    ) -> HttmResult<Option<MapOfAliases>> {
-        let alias_values: Option<Vec<String>> = match std::env::var_os("HTTM_MAP_ALIASES") {
+    // the environment variable wins over any aliases specified at the command line
+    pub fn raw_values(opt_raw_aliases: Option<Vec<String>>) -> Option<Vec<String>> {
+        match std::env::var_os("HTTM_MAP_ALIASES") {
             Some(env_map_alias) => Some(
                 env_map_alias
                     .to_string_lossy()
@@ -64,8 +70,17 @@ impl MapOfAliases {
                     .collect(),
             ),
             None => opt_raw_aliases,
-        };
+        }
+    }
 
+    // expects raw values as returned by fn raw_values()
+    pub fn new(
+        map_of_datasets: &BTreeMap<Arc<Path>, DatasetMetadata>,
+        alias_values: Option<Vec<String>>,
+        opt_remote_dir: Option<&String>,
+        opt_local_dir: Option<&String>,
+        pwd: &Path,
+    ) -> HttmResult<Option<MapOfAliases>> {
         let opt_snap_dir: Option<Box<Path>> = if let Some(value) = opt_remote_dir {
             Some(Box::from(Path::new(&value)))
         } else if std::env::var_os("HTTM_REMOTE_DIR").is_some() {
@@ -75,7 +90,9 @@ impl MapOfAliases {
             std::env::var_os("HTTM_SNAP_POINT").map(|s| Box::from(Path::new(&s)))
         };
 
-        if alias_values.is_none() && opt_snap_dir.is_none() {
+        let opt_marker_alias = Self::from_marker_file(pwd);
+
+        if alias_values.is_none() && opt_snap_dir.is_none() && opt_marker_alias.is_none() {
             return Ok(None);
         }
 
@@ -87,8 +104,15 @@ impl MapOfAliases {
 
         let mut aliases_iter: Vec<(Box<Path>, Box<Path>)> = match alias_values {
             Some(input_aliases) => {
+                // SSH aliases are mounted, and then treated just like user specified snapshot mounts,
+                // see SshAliases
                 let res: Option<Vec<(Box<Path>, Box<Path>)>> = input_aliases
                     .iter()
+                    .filter(|alias| {
+                        !alias
+                            .split_once(':')
+                            .is_some_and(|(_local_dir, rest)| SshAliases::is_ssh_url(rest))
+                    })
                     .map(|alias| {
                         alias
                             .split_once(':')
@@ -96,11 +120,26 @@ impl MapOfAliases {
                     })
                     .collect();
 
-                res.ok_or_else(|| {
+                let res = res.ok_or_else(|| {
                     HttmError::new(
                         "Must use specified delimiter (':') between aliases for MAP_ALIASES.",
                     )
-                })?
+                })?;
+
+                if let Some((_local_dir, remote_url)) = res
+                    .iter()
+                    .find(|(_local_dir, remote_dir)| Self::is_remote_url(remote_dir))
+                {
+                    let msg = format!(
+                        "httm can only browse snapshots over SSH directly (eg. \"ssh://host/pool/dataset\"): {:?}.  \
+                        Instead, mount the remote dataset locally (eg. with NFS or SMB), \
+                        and specify that mount point as the REMOTE_DIR of the alias.",
+                        remote_url
+                    );
+                    return Err(HttmError::new(&msg).into());
+                }
+
+                res
             }
             None => Vec::new(),
         };
@@ -118,6 +157,16 @@ impl MapOfAliases {
             aliases_iter.push(value)
         }
 
+        // aliases explicitly specified by the user win over any discovered by marker file
+        if let Some(value) = opt_marker_alias {
+            if aliases_iter
+                .iter()
+                .all(|(local_dir, _)| local_dir != &value.0)
+            {
+                aliases_iter.push(value)
+            }
+        }
+
         let map_of_aliases: BTreeMap<Box<Path>, RemotePathAndFsType> = aliases_iter
             .into_iter()
             .filter_map(|(local_dir, snap_dir)| {
@@ -162,4 +211,69 @@ impl MapOfAliases {
 
         Ok(Some(map_of_aliases.into()))
     }
+
+    // "ssh://host/pool/dataset" is not a path, although, because the scheme contains our delimiter,
+    // it will parse as one
+    fn is_remote_url(remote_dir: &Path) -> bool {
+        remote_dir.to_str().is_some_and(|s| s.contains("://"))
+    }
+
+    // like .git discovery, search upward from the working directory for a marker file,
+    // whose first non-empty, non-comment line declares the snapshot location (a remote dir)
+    // for the tree rooted at the directory containing the marker file.  As with .git discovery,
+    // the search stops at the mount point of the filesystem which contains the working directory,
+    // and each dir searched is remembered, so no marker file is read twice
+    fn from_marker_file(pwd: &Path) -> MarkerAlias {
+        let mut cache = MARKER_ALIASES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let opt_pwd_dev = pwd.metadata().ok().map(|md| md.dev());
+
+        let mut searched: Vec<&Path> = Vec::new();
+        let mut res: MarkerAlias = None;
+
+        for dir in pwd.ancestors() {
+            if let Some(cached) = cache.get(dir) {
+                res = cached.clone();
+                break;
+            }
+
+            if dir.metadata().ok().map(|md| md.dev()) != opt_pwd_dev {
+                break;
+            }
+
+            searched.push(dir);
+
+            if let Ok(contents) = std::fs::read_to_string(dir.join(ALIAS_MARKER_FILE_NAME)) {
+                res = Self::parse_marker_file(dir, &contents);
+                break;
+            }
+        }
+
+        searched.into_iter().for_each(|dir| {
+            cache.insert(dir.into(), res.clone());
+        });
+
+        res
+    }
+
+    fn parse_marker_file(local_dir: &Path, contents: &str) -> MarkerAlias {
+        let Some(remote_dir) = contents
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+        else {
+            eprintln!(
+                "WARN: Alias marker file contains no snapshot location, ignoring: {:?}",
+                local_dir.join(ALIAS_MARKER_FILE_NAME)
+            );
+            return None;
+        };
+
+        // a relative remote dir is relative to the marker file's directory
+        let remote_dir = local_dir.join(remote_dir);
+
+        Some((local_dir.into(), remote_dir.into()))
+    }
 }
@@ -0,0 +1,236 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::filesystem::explicit_snaps::MapOfExplicitSnaps;
+use crate::library::cleanup::CleanupGuard;
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::private_temp_dir;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command as ExecProcess;
+use std::sync::{LazyLock, Mutex, OnceLock, PoisonError};
+
+// borg repos we have mounted, to be unmounted at exit
+static BORG_MOUNTED_REPOS: LazyLock<Mutex<Vec<PathBuf>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+// the private dir beneath which we mount each repo
+static BORG_BASE_DIR: OnceLock<PathBuf> = OnceLock::new();
+// key: local dir, val: the local dir within each archive of the borg repos we have mounted
+static BORG_SNAP_MOUNTS: OnceLock<BTreeMap<Box<Path>, Vec<Box<Path>>>> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BorgRepo {
+    repo: String,
+    local_dir: Box<Path>,
+}
+
+// borg repos are only mounted once exec() has begun, and not while the config is built, so the
+// cleanup guard held by main() is always in place to unmount them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BorgRepos {
+    inner: Vec<BorgRepo>,
+}
+
+impl BorgRepos {
+    // each value is of the form <REPO>:<LOCAL_DIR>.  Because a remote repo may itself contain
+    // a colon (eg. "ssh://user@host:2222/repo"), we split on the last colon.
+    pub fn new(opt_raw_borg_repos: Option<Vec<String>>) -> HttmResult<Option<Self>> {
+        let Some(raw_borg_repos) = opt_raw_borg_repos else {
+            return Ok(None);
+        };
+
+        let mut inner = Vec::new();
+
+        for raw in raw_borg_repos.iter() {
+            let Some((repo, local_dir)) = raw.rsplit_once(':') else {
+                return Err(HttmError::new(&format!(
+                    "A borg repo must be specified in the form <REPO>:<LOCAL_DIR>: {raw:?}"
+                ))
+                .into());
+            };
+
+            match Path::new(local_dir).canonicalize() {
+                Ok(local_dir) if local_dir.is_dir() => inner.push(BorgRepo {
+                    repo: repo.to_owned(),
+                    local_dir: local_dir.into_boxed_path(),
+                }),
+                _ => {
+                    eprintln!(
+                        "WARN: A local directory specified for a borg repo does not exist: {local_dir:?}"
+                    );
+                }
+            }
+        }
+
+        if inner.is_empty() {
+            return Err(HttmError::new(
+                "None of the local directories specified for borg repos exist.  Quitting.",
+            )
+            .into());
+        }
+
+        Ok(Some(Self { inner }))
+    }
+
+    // borg archives are treated just like user specified snapshot mounts of their local dirs, which
+    // are known before the repos are mounted, see fn snap_mounts()
+    pub fn local_dirs(&self) -> MapOfExplicitSnaps {
+        let map: BTreeMap<Box<Path>, Vec<Box<Path>>> = self
+            .inner
+            .iter()
+            .map(|borg_repo| (borg_repo.local_dir.clone(), Vec::new()))
+            .collect();
+
+        map.into()
+    }
+
+    // borg mounts each archive of a repo as a directory beneath the mount point, and each archive
+    // contains the backed up paths, less any leading "/", so the local dir within each archive
+    // may simply be used as an explicit snapshot mount of that local dir
+    pub fn mount(&self) -> HttmResult<()> {
+        let borg_command = which::which("borg").map_err(|_err| {
+            HttmError::new(
+                "'borg' command not found. Make sure the command 'borg' is in your path.",
+            )
+        })?;
+
+        // recorded before anything is mounted beneath it, so cleanup may remove it
+        let base_dir = private_temp_dir("httm-borg")?;
+        let base_dir = BORG_BASE_DIR.get_or_init(|| base_dir);
+
+        let mut map: BTreeMap<Box<Path>, Vec<Box<Path>>> = BTreeMap::new();
+
+        for (idx, BorgRepo { repo, local_dir }) in self.inner.iter().enumerate() {
+            // anything mounted once cleanup has begun would never be unmounted
+            if CleanupGuard::is_started() {
+                break;
+            }
+
+            let mount_point = base_dir.join(idx.to_string());
+
+            std::fs::create_dir(&mount_point)?;
+
+            // inherit stdin and stderr, so borg may prompt for any passphrase
+            let is_mounted = ExecProcess::new(&borg_command)
+                .arg("mount")
+                .arg(repo)
+                .arg(&mount_point)
+                .status()
+                .map(|status| status.success())
+                .unwrap_or(false);
+
+            if !is_mounted {
+                eprintln!("WARN: httm could not mount borg repo: {repo:?}");
+                let _ = std::fs::remove_dir(&mount_point);
+                continue;
+            }
+
+            BORG_MOUNTED_REPOS
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(mount_point.clone());
+
+            let relative_dir = local_dir.strip_prefix("/").unwrap_or(local_dir);
+
+            let snap_mounts: Vec<Box<Path>> = std::fs::read_dir(&mount_point)?
+                .flatten()
+                .map(|archive| archive.path().join(relative_dir))
+                .filter(|snap_mount| snap_mount.is_dir())
+                .map(|snap_mount| snap_mount.into_boxed_path())
+                .collect();
+
+            if snap_mounts.is_empty() {
+                eprintln!(
+                    "WARN: No archive of borg repo {repo:?} contains the local directory specified: {local_dir:?}"
+                );
+                continue;
+            }
+
+            map.entry(local_dir.clone())
+                .or_default()
+                .extend(snap_mounts);
+        }
+
+        if map.is_empty() {
+            return Err(HttmError::new(
+                "None of the borg repos specified could be mounted, or contain their local directories.  Quitting.",
+            )
+            .into());
+        }
+
+        let _ = BORG_SNAP_MOUNTS.set(map);
+
+        Ok(())
+    }
+
+    pub fn snap_mounts(local_dir: &Path) -> &'static [Box<Path>] {
+        BORG_SNAP_MOUNTS
+            .get()
+            .and_then(|map| map.get(local_dir))
+            .map_or(&[], |snap_mounts| snap_mounts.as_slice())
+    }
+
+    // key: snap mount, val: local dir
+    pub fn mounted() -> impl Iterator<Item = (&'static Path, &'static Path)> {
+        BORG_SNAP_MOUNTS
+            .get()
+            .into_iter()
+            .flatten()
+            .flat_map(|(local_dir, snap_mounts)| {
+                snap_mounts
+                    .iter()
+                    .map(move |snap_mount| (snap_mount.as_ref(), local_dir.as_ref()))
+            })
+    }
+
+    // unmount and remove only those dirs we created, and never recursively
+    pub fn cleanup() {
+        let mut mounted = BORG_MOUNTED_REPOS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let Some(base_dir) = BORG_BASE_DIR.get() else {
+            return;
+        };
+
+        if !mounted.is_empty() {
+            let Ok(borg_command) = which::which("borg") else {
+                eprintln!(
+                    "WARN: 'borg' command not found.  Borg repos mounted by httm remain mounted beneath: {base_dir:?}"
+                );
+                return;
+            };
+
+            mounted.drain(..).for_each(|mount_point| {
+                let is_unmounted = ExecProcess::new(&borg_command)
+                    .arg("umount")
+                    .arg(&mount_point)
+                    .output()
+                    .map(|output| output.status.success())
+                    .unwrap_or(false);
+
+                if !is_unmounted {
+                    eprintln!("WARN: httm could not unmount borg repo: {mount_point:?}");
+                    return;
+                }
+
+                let _ = std::fs::remove_dir(&mount_point);
+            });
+        }
+
+        let _ = std::fs::remove_dir(base_dir);
+    }
+}
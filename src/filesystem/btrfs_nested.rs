@@ -0,0 +1,266 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::filesystem::mounts::FilesystemType;
+use crate::lookup::versions::RelativePathAndSnapMounts;
+use crate::{BTRFS_SNAPPER_HIDDEN_DIRECTORY, BTRFS_SNAPPER_SUFFIX, GLOBAL_CONFIG};
+use hashbrown::HashSet;
+use std::fs::read_dir;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+// btrfs always assigns this inode number to the root directory of a subvolume
+pub const BTRFS_SUBVOL_ROOT_INODE: u64 = 256;
+
+// we only warn once per nested subvolume, no matter how many paths it contains
+static ANNOTATED_SUBVOLS: LazyLock<Mutex<HashSet<PathBuf>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+// a btrfs snapshot does not include the contents of any subvolume nested within the snapshotted
+// subvolume, nested subvolumes appear, on the snapshot, only as empty directories.  So a path within
+// a nested subvolume, which is not otherwise mounted, can have no versions on the snapshots of its
+// proximate dataset.  Here, we detect such a boundary, and, if the nested subvolume has snapper
+// snapshots of its own, we may search those instead, see TRAVERSE_NESTED
+#[derive(Debug, Clone)]
+pub struct BtrfsNestedSubvol {
+    subvol_root: PathBuf,
+    relative_path: PathBuf,
+    snap_mounts: Vec<Box<Path>>,
+}
+
+impl BtrfsNestedSubvol {
+    pub fn new(path: &Path, proximate_dataset: &Path) -> Option<Self> {
+        let is_btrfs = GLOBAL_CONFIG
+            .dataset_collection
+            .map_of_datasets
+            .get(proximate_dataset)
+            .is_some_and(|md| matches!(md.fs_type, FilesystemType::Btrfs(_)));
+
+        if !is_btrfs {
+            return None;
+        }
+
+        let subvol_root = Self::subvol_root(path, proximate_dataset, |ancestor| {
+            ancestor
+                .symlink_metadata()
+                .is_ok_and(|md| md.file_type().is_dir() && md.ino() == BTRFS_SUBVOL_ROOT_INODE)
+        })?;
+
+        let relative_path = path.strip_prefix(subvol_root).ok()?.to_path_buf();
+
+        // only read the nested subvolume's snapshots when we may search them
+        let snap_mounts: Vec<Box<Path>> = if GLOBAL_CONFIG.opt_traverse_nested {
+            Self::snapper_snap_mounts(subvol_root)
+        } else {
+            Vec::new()
+        };
+
+        Some(Self {
+            subvol_root: subvol_root.to_path_buf(),
+            relative_path,
+            snap_mounts,
+        })
+    }
+
+    // the most proximate nested subvolume root containing path, the proximate dataset is itself
+    // a subvolume root, so we only inspect the paths below it
+    fn subvol_root<'a>(
+        path: &'a Path,
+        proximate_dataset: &Path,
+        is_subvol_root: impl Fn(&Path) -> bool,
+    ) -> Option<&'a Path> {
+        path.ancestors()
+            .take_while(|ancestor| *ancestor != proximate_dataset)
+            .filter(|ancestor| ancestor.starts_with(proximate_dataset))
+            .find(|ancestor| is_subvol_root(ancestor))
+    }
+
+    // snapper keeps each snapshot of a subvolume at .snapshots/<number>/snapshot
+    fn snapper_snap_mounts(subvol_root: &Path) -> Vec<Box<Path>> {
+        read_dir(subvol_root.join(BTRFS_SNAPPER_HIDDEN_DIRECTORY))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path().join(BTRFS_SNAPPER_SUFFIX))
+            .filter(|snap_mount| snap_mount.exists())
+            .map(|snap_mount| snap_mount.into_boxed_path())
+            .collect()
+    }
+
+    pub fn search_bundle(&self) -> Option<RelativePathAndSnapMounts<'_>> {
+        if self.snap_mounts.is_empty() {
+            return None;
+        }
+
+        Some(RelativePathAndSnapMounts {
+            relative_path: &self.relative_path,
+            snap_mounts: &self.snap_mounts,
+            dataset_of_interest: &self.subvol_root,
+        })
+    }
+
+    pub fn annotate(&self, proximate_dataset: &Path) {
+        let Ok(mut annotated) = ANNOTATED_SUBVOLS.lock() else {
+            return;
+        };
+
+        if !annotated.insert(self.subvol_root.clone()) {
+            return;
+        }
+
+        let hint = if GLOBAL_CONFIG.opt_traverse_nested {
+            "The nested subvolume has no snapper snapshots of its own to search."
+        } else {
+            "If the nested subvolume has snapper snapshots of its own, specify \"--traverse-nested\" to search them."
+        };
+
+        eprintln!(
+            "WARN: {:?} is a nested btrfs subvolume.  Snapshots of {:?} do not include its contents, \
+            which will appear only as an empty directory.  {}",
+            self.subvol_root, proximate_dataset, hint
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a nested subvolume layout, as the set of dirs which are subvolume roots
+    fn is_subvol_root_of<'a>(roots: &'a [&'a str]) -> impl Fn(&Path) -> bool + 'a {
+        move |ancestor| roots.iter().any(|root| Path::new(root) == ancestor)
+    }
+
+    #[test]
+    fn path_within_nested_subvol() {
+        let roots = ["/mnt/data", "/mnt/data/nested"];
+        let path = Path::new("/mnt/data/nested/dir/file.txt");
+
+        let subvol_root =
+            BtrfsNestedSubvol::subvol_root(path, Path::new("/mnt/data"), is_subvol_root_of(&roots));
+
+        assert_eq!(subvol_root, Some(Path::new("/mnt/data/nested")));
+        assert_eq!(
+            path.strip_prefix(subvol_root.unwrap()).unwrap(),
+            Path::new("dir/file.txt")
+        );
+    }
+
+    #[test]
+    fn most_proximate_of_several_nested_subvols() {
+        let roots = ["/mnt/data", "/mnt/data/outer", "/mnt/data/outer/inner"];
+
+        let subvol_root = BtrfsNestedSubvol::subvol_root(
+            Path::new("/mnt/data/outer/inner/file.txt"),
+            Path::new("/mnt/data"),
+            is_subvol_root_of(&roots),
+        );
+
+        assert_eq!(subvol_root, Some(Path::new("/mnt/data/outer/inner")));
+
+        let subvol_root = BtrfsNestedSubvol::subvol_root(
+            Path::new("/mnt/data/outer/file.txt"),
+            Path::new("/mnt/data"),
+            is_subvol_root_of(&roots),
+        );
+
+        assert_eq!(subvol_root, Some(Path::new("/mnt/data/outer")));
+    }
+
+    #[test]
+    fn path_without_nested_subvol() {
+        let roots = ["/mnt/data", "/mnt/data/nested"];
+
+        // the proximate dataset is a subvolume root, but is not nested
+        let subvol_root = BtrfsNestedSubvol::subvol_root(
+            Path::new("/mnt/data/dir/file.txt"),
+            Path::new("/mnt/data"),
+            is_subvol_root_of(&roots),
+        );
+
+        assert_eq!(subvol_root, None);
+
+        // nor is any subvolume above the proximate dataset
+        let subvol_root = BtrfsNestedSubvol::subvol_root(
+            Path::new("/mnt/data/dir/file.txt"),
+            Path::new("/mnt/data/dir"),
+            is_subvol_root_of(&roots),
+        );
+
+        assert_eq!(subvol_root, None);
+    }
+
+    #[test]
+    fn nested_subvol_is_proximate_dataset() {
+        let roots = ["/mnt/data", "/mnt/data/nested"];
+
+        // a nested subvolume which is mounted is its own proximate dataset, and has no boundary
+        let subvol_root = BtrfsNestedSubvol::subvol_root(
+            Path::new("/mnt/data/nested/file.txt"),
+            Path::new("/mnt/data/nested"),
+            is_subvol_root_of(&roots),
+        );
+
+        assert_eq!(subvol_root, None);
+    }
+
+    #[test]
+    fn snapper_snap_mounts_of_nested_subvol() {
+        let subvol_root =
+            std::env::temp_dir().join(format!("httm-test-nested-{}", std::process::id()));
+        let snapshots = subvol_root.join(BTRFS_SNAPPER_HIDDEN_DIRECTORY);
+
+        std::fs::create_dir_all(snapshots.join("1").join(BTRFS_SNAPPER_SUFFIX)).unwrap();
+        std::fs::create_dir_all(snapshots.join("2").join(BTRFS_SNAPPER_SUFFIX)).unwrap();
+        // an incomplete snapshot, without a snapshot dir, is not a snap mount
+        std::fs::create_dir_all(snapshots.join("3")).unwrap();
+
+        let mut snap_mounts = BtrfsNestedSubvol::snapper_snap_mounts(&subvol_root);
+        snap_mounts.sort();
+
+        let _ = std::fs::remove_dir_all(&subvol_root);
+
+        assert_eq!(
+            snap_mounts,
+            vec![
+                snapshots
+                    .join("1")
+                    .join(BTRFS_SNAPPER_SUFFIX)
+                    .into_boxed_path(),
+                snapshots
+                    .join("2")
+                    .join(BTRFS_SNAPPER_SUFFIX)
+                    .into_boxed_path(),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_subvol_without_snapper_snapshots() {
+        let subvol_root =
+            std::env::temp_dir().join(format!("httm-test-nested-empty-{}", std::process::id()));
+
+        std::fs::create_dir_all(&subvol_root).unwrap();
+
+        let snap_mounts = BtrfsNestedSubvol::snapper_snap_mounts(&subvol_root);
+
+        let _ = std::fs::remove_dir_all(&subvol_root);
+
+        assert!(snap_mounts.is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::filesystem::explicit_snaps::MapOfExplicitSnaps;
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::parse_timestamp;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use time::UtcOffset;
+
+pub struct DatedBackupDirs;
+
+impl DatedBackupDirs {
+    // each value is of the form <BACKUP_DIR>:<LOCAL_DIR>.  Each subdirectory of the backup dir,
+    // like "backup.0" or "2024-01-01", is a backup of the local dir, and is treated just like
+    // an explicit snapshot mount of that local dir
+    pub fn new(
+        opt_raw_dated_backup_dirs: Option<Vec<String>>,
+    ) -> HttmResult<Option<MapOfExplicitSnaps>> {
+        let Some(raw_dated_backup_dirs) = opt_raw_dated_backup_dirs else {
+            return Ok(None);
+        };
+
+        let mut map: BTreeMap<Box<Path>, Vec<Box<Path>>> = BTreeMap::new();
+
+        for raw in raw_dated_backup_dirs.iter() {
+            let Some((backup_dir, local_dir)) = raw.split_once(':') else {
+                return Err(HttmError::new(&format!(
+                    "A dated backup directory must be specified in the form <BACKUP_DIR>:<LOCAL_DIR>: {raw:?}"
+                ))
+                .into());
+            };
+
+            let local_dir = match Path::new(local_dir).canonicalize() {
+                Ok(local_dir) if local_dir.is_dir() => local_dir,
+                _ => {
+                    eprintln!(
+                        "WARN: A local directory specified for a dated backup directory does not exist: {local_dir:?}"
+                    );
+                    continue;
+                }
+            };
+
+            let Ok(read_dir) = std::fs::read_dir(backup_dir) else {
+                eprintln!(
+                    "WARN: A dated backup directory specified could not be read: {backup_dir:?}"
+                );
+                continue;
+            };
+
+            let mut backups: Vec<(SystemTime, PathBuf)> = read_dir
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|backup| backup.is_dir())
+                .filter_map(|backup| {
+                    Self::snap_mount(&backup, &local_dir)
+                        .map(|snap_mount| (Self::timestamp(&backup), snap_mount))
+                })
+                .collect();
+
+            if backups.is_empty() {
+                eprintln!(
+                    "WARN: No subdirectory of dated backup directory {backup_dir:?} contains the local directory specified: {local_dir:?}"
+                );
+                continue;
+            }
+
+            // oldest first, like the snapshots of any other source
+            backups.sort_by(|a, b| a.0.cmp(&b.0));
+
+            map.entry(local_dir.into_boxed_path()).or_default().extend(
+                backups
+                    .into_iter()
+                    .map(|(_timestamp, snap_mount)| snap_mount.into_boxed_path()),
+            );
+        }
+
+        if map.is_empty() {
+            return Err(HttmError::new(
+                "None of the dated backup directories specified contain their local directories.  Quitting.",
+            )
+            .into());
+        }
+
+        Ok(Some(map.into()))
+    }
+
+    // a backup may be a copy of the local dir itself, or, like "rsync -R" and rsnapshot backups,
+    // may contain the full path of the local dir, perhaps beneath a single directory named for
+    // the backup point (eg. "daily.0/localhost/home")
+    fn snap_mount(backup: &Path, local_dir: &Path) -> Option<PathBuf> {
+        let relative_dir = local_dir.strip_prefix("/").unwrap_or(local_dir);
+
+        let full_path = backup.join(relative_dir);
+
+        if full_path.is_dir() {
+            return Some(full_path);
+        }
+
+        let opt_backup_point = std::fs::read_dir(backup)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path().join(relative_dir))
+            .find(|path| path.is_dir());
+
+        if opt_backup_point.is_some() {
+            return opt_backup_point;
+        }
+
+        // a copy of the local dir itself should contain at least one of its entries
+        let is_copy = std::fs::read_dir(local_dir)
+            .ok()?
+            .flatten()
+            .any(|entry| backup.join(entry.file_name()).symlink_metadata().is_ok());
+
+        is_copy.then(|| backup.to_path_buf())
+    }
+
+    // the time of a backup is taken from its name, like "2024-01-01" or "2024-01-01-12:00:00",
+    // and otherwise, like "backup.0", from its modify time.  Here, the time is only used to
+    // order backups, so parsing every name as UTC is fine.
+    fn timestamp(backup: &Path) -> SystemTime {
+        backup
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| parse_timestamp(name, UtcOffset::UTC).ok())
+            .or_else(|| backup.metadata().ok().and_then(|md| md.modified().ok()))
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    }
+}
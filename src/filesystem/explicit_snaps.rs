@@ -0,0 +1,131 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::library::results::{HttmError, HttmResult};
+use std::collections::BTreeMap;
+use std::ops::Deref;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapOfExplicitSnaps {
+    inner: BTreeMap<Box<Path>, Vec<Box<Path>>>,
+}
+
+impl From<BTreeMap<Box<Path>, Vec<Box<Path>>>> for MapOfExplicitSnaps {
+    fn from(map: BTreeMap<Box<Path>, Vec<Box<Path>>>) -> Self {
+        Self { inner: map }
+    }
+}
+
+impl Deref for MapOfExplicitSnaps {
+    type Target = BTreeMap<Box<Path>, Vec<Box<Path>>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl MapOfExplicitSnaps {
+    // each value is either <SNAP_MOUNT> or <LOCAL_DIR>:<SNAP_MOUNT>, where a missing
+    // local dir defaults to the working directory
+    pub fn new(opt_raw_snap_mounts: Option<Vec<String>>, pwd: &Path) -> HttmResult<Option<Self>> {
+        let Some(raw_snap_mounts) = opt_raw_snap_mounts else {
+            return Ok(None);
+        };
+
+        let mut map: BTreeMap<Box<Path>, Vec<Box<Path>>> = BTreeMap::new();
+
+        raw_snap_mounts
+            .iter()
+            .map(|raw| match raw.split_once(':') {
+                Some((local_dir, snap_mount)) => (Path::new(local_dir), Path::new(snap_mount)),
+                None => (pwd, Path::new(raw)),
+            })
+            // paths are looked up by their canonical form, so a relative or symlinked dir must be
+            // canonicalized here, else it would never match
+            .filter_map(|(local_dir, snap_mount)| {
+                let local_dir = match local_dir.canonicalize() {
+                    Ok(local_dir) if local_dir.is_dir() => local_dir,
+                    _ => {
+                        eprintln!(
+                            "WARN: A local directory specified for a snapshot mount does not exist: {:?}",
+                            local_dir
+                        );
+                        return None;
+                    }
+                };
+
+                let snap_mount = match snap_mount.canonicalize() {
+                    Ok(snap_mount) if snap_mount.is_dir() => snap_mount,
+                    _ => {
+                        eprintln!(
+                            "WARN: A snapshot mount specified does not exist, or is not mounted: {:?}",
+                            snap_mount
+                        );
+                        return None;
+                    }
+                };
+
+                Some((local_dir, snap_mount))
+            })
+            .for_each(|(local_dir, snap_mount)| {
+                map.entry(local_dir.into_boxed_path())
+                    .or_default()
+                    .push(snap_mount.into_boxed_path())
+            });
+
+        if map.is_empty() {
+            return Err(HttmError::new(
+                "None of the snapshot mounts specified exist, or are mounted.  Quitting.",
+            )
+            .into());
+        }
+
+        Ok(Some(map.into()))
+    }
+
+    // combine user specified snapshot mounts with those from another source, like borg archives
+    pub fn merge(opt_left: Option<Self>, opt_right: Option<Self>) -> Option<Self> {
+        match (opt_left, opt_right) {
+            (Some(mut left), Some(right)) => {
+                right
+                    .inner
+                    .into_iter()
+                    .for_each(|(local_dir, snap_mounts)| {
+                        left.inner.entry(local_dir).or_default().extend(snap_mounts)
+                    });
+                Some(left)
+            }
+            (opt_left, opt_right) => opt_left.or(opt_right),
+        }
+    }
+
+    // the most proximate local dir, and the path relative to that local dir, if path
+    // resides under a local dir for which the user has specified snapshot mounts
+    pub fn local_dir_and_relative_path<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Option<(&'a Path, &'a Path)> {
+        path.ancestors().find_map(|ancestor| {
+            self.get_key_value(ancestor).and_then(|(local_dir, _)| {
+                path.strip_prefix(ancestor)
+                    .ok()
+                    .map(|relative_path| (local_dir.as_ref(), relative_path))
+            })
+        })
+    }
+}
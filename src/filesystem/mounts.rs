@@ -16,6 +16,7 @@
 // that was distributed wth this source code.
 
 use crate::filesystem::snaps::MapOfSnaps;
+use crate::filesystem::time_machine::TimeMachineLocal;
 use crate::library::results::{HttmError, HttmResult};
 use crate::library::utility::{find_common_path, get_mount_command};
 use crate::{
@@ -42,6 +43,8 @@ pub const ZFS_FSTYPE: &str = "zfs";
 pub const NILFS2_FSTYPE: &str = "nilfs2";
 pub const BTRFS_FSTYPE: &str = "btrfs";
 pub const SMB_FSTYPE: &str = "smbfs";
+pub const CIFS_FSTYPE: &str = "cifs";
+pub const SMB3_FSTYPE: &str = "smb3";
 pub const NFS_FSTYPE: &str = "nfs";
 pub const AFP_FSTYPE: &str = "afpfs";
 pub const RESTIC_FSTYPE: &str = "restic";
@@ -70,6 +73,7 @@ pub enum FilesystemType {
     Nilfs2,
     Apfs,
     Restic(Option<Box<ResticAdditionalData>>),
+    ShadowCopy,
 }
 
 impl FilesystemType {
@@ -194,7 +198,12 @@ pub struct BaseFilesystemInfo {
 impl BaseFilesystemInfo {
     // divide by the type of system we are on
     // Linux allows us the read proc mounts
-    pub fn new(opt_debug: bool, opt_alt_store: &Option<FilesystemType>) -> HttmResult<Self> {
+    pub fn new(
+        opt_debug: bool,
+        opt_alt_store: &Option<FilesystemType>,
+        opt_pinned_datasets: Option<Vec<PathBuf>>,
+        opt_zfs_list: bool,
+    ) -> HttmResult<Self> {
         let (mut raw_datasets, filter_dirs_set) = if PROC_MOUNTS.exists() {
             Self::from_file(&PROC_MOUNTS, opt_alt_store)?
         } else if ETC_MNT_TAB.exists() {
@@ -203,7 +212,16 @@ impl BaseFilesystemInfo {
             Self::from_mount_cmd(opt_alt_store)?
         };
 
-        let map_of_snaps = MapOfSnaps::new(&mut raw_datasets, opt_debug)?;
+        let opt_pinned = opt_pinned_datasets
+            .map(|pinned_datasets| Self::pinned(pinned_datasets, &raw_datasets))
+            .transpose()?;
+
+        let map_of_snaps = MapOfSnaps::new(
+            &mut raw_datasets,
+            opt_pinned.as_ref(),
+            opt_zfs_list,
+            opt_debug,
+        )?;
 
         let map_of_datasets = {
             MapOfDatasets {
@@ -224,6 +242,32 @@ impl BaseFilesystemInfo {
         })
     }
 
+    // the user may pin which dataset mounts httm considers, see DATASET.  We still know of every
+    // other dataset, so a path on an unpinned dataset never resolves to some pinned ancestor,
+    // but we never search for the snapshots of an unpinned dataset, and so it has no versions
+    fn pinned(
+        pinned_datasets: Vec<PathBuf>,
+        raw_datasets: &BTreeMap<Arc<Path>, DatasetMetadata>,
+    ) -> HttmResult<BTreeSet<Arc<Path>>> {
+        pinned_datasets
+            .into_iter()
+            .map(|pinned| {
+                let canonical = pinned.canonicalize().unwrap_or(pinned);
+
+                raw_datasets
+                    .get_key_value(canonical.as_path())
+                    .map(|(mount, _dataset_info)| mount.clone())
+                    .ok_or_else(|| {
+                        let msg = format!(
+                            "httm could not find a supported dataset mounted at: {:?}",
+                            canonical
+                        );
+                        HttmError::new(&msg).into()
+                    })
+            })
+            .collect()
+    }
+
     // parsing from proc mounts is both faster and necessary for certain btrfs features
     // for instance, allows us to read subvolumes mounts, like "/@" or "/@home"
     fn from_file(
@@ -282,6 +326,27 @@ impl BaseFilesystemInfo {
                     )),
                     _ => Either::Right(dest_path),
                 },
+                // servers such as NetApp and Windows hide their snapshot directories entirely, but
+                // will enumerate their "previous versions", if asked
+                CIFS_FSTYPE | SMB3_FSTYPE => match FilesystemType::new(&dest_path) {
+                    Some(fs_type) => Either::Left((
+                        dest_path,
+                        DatasetMetadata {
+                            source: mount_info.source.into_boxed_path(),
+                            fs_type,
+                            link_type: LinkType::Network,
+                        },
+                    )),
+                    // the server is only asked for its previous versions once a path on the share is looked up
+                    None => Either::Left((
+                        dest_path,
+                        DatasetMetadata {
+                            source: mount_info.source.into_boxed_path(),
+                            fs_type: FilesystemType::ShadowCopy,
+                            link_type: LinkType::Network,
+                        },
+                    )),
+                },
                 BTRFS_FSTYPE => {
                     let keyed_options: BTreeMap<&str, &str> = mount_info
                         .options
@@ -469,8 +534,13 @@ impl BaseFilesystemInfo {
                     .collect();
 
                 if retained_keys.is_empty() {
+                    // restic snapshots are read through a restic FUSE mount, which serves
+                    // the same role for restic as .zfs/snapshot does for ZFS, see also ResticRepos
                     return Err(HttmError::new(
-                        "No supported Restic datasets were found on the system.",
+                        "No supported Restic datasets were found on the system.  \
+                        httm reads Restic snapshots from a mounted repository.  \
+                        Mount the repository first, for instance: 'restic -r /path/to/repo mount /path/to/mountpoint', \
+                        or specify the repository with RESTIC_REPO, and httm will mount it for you.",
                     )
                     .into());
                 }
@@ -491,7 +561,10 @@ impl BaseFilesystemInfo {
                                 .into());
                 }
 
-                if !TM_DIR_REMOTE_PATH.exists() && !TM_DIR_LOCAL_PATH.exists() {
+                if !TM_DIR_REMOTE_PATH.exists()
+                    && !TM_DIR_LOCAL_PATH.exists()
+                    && TimeMachineLocal::snap_names().is_empty()
+                {
                     return Err(HttmError::new(
                                     "Neither a local nor a remote Time Machine path seems to exist for this system."
                                 )
@@ -514,7 +587,7 @@ impl BaseFilesystemInfo {
 
         let datasets = BTreeMap::from([(Arc::from(ROOT_PATH.as_ref()), metadata)]);
 
-        let snaps = MapOfSnaps::new(&datasets, opt_debug)?;
+        let snaps = MapOfSnaps::new(&datasets, None, false, opt_debug)?;
 
         *self = Self {
             map_of_datasets: datasets.into(),
@@ -0,0 +1,71 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::library::results::{HttmError, HttmResult};
+use std::path::Path;
+use std::process::Command as ExecProcess;
+use which::which;
+
+// only checkpoints the user has marked as snapshots ("ss" mode), and not the ordinary,
+// continuously taken, checkpoints ("cp" mode), may be mounted
+const NILFS2_SNAPSHOT_MODE: &str = "ss";
+
+pub struct Nilfs2Snapshots;
+
+impl Nilfs2Snapshots {
+    // lscp prints a header line and then a line per checkpoint, like so:
+    //
+    //                  CNO        DATE     TIME  MODE  FLG      BLKCNT       ICNT
+    //                    2  2011-07-14 10:00:31   ss    -           29         30
+    pub fn checkpoint_numbers(device: &Path) -> HttmResult<Vec<u64>> {
+        let lscp_command = which("lscp").map_err(|_err| {
+            HttmError::new(
+                "'lscp' command not found. Make sure the command 'lscp', from nilfs-utils, is in your path.",
+            )
+        })?;
+
+        let output = ExecProcess::new(lscp_command)
+            .arg("-s")
+            .arg(device)
+            .output()?;
+
+        if !output.status.success() {
+            let msg = format!(
+                "lscp could not list the snapshots of {:?}: {}",
+                device,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return Err(HttmError::new(&msg).into());
+        }
+
+        let checkpoint_numbers = std::str::from_utf8(&output.stdout)?
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+
+                match fields.as_slice() {
+                    [cno, _date, _time, mode, ..] if *mode == NILFS2_SNAPSHOT_MODE => {
+                        cno.parse::<u64>().ok()
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+
+        Ok(checkpoint_numbers)
+    }
+}
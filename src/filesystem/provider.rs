@@ -0,0 +1,161 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::filesystem::mounts::{DatasetMetadata, FilesystemType};
+use crate::filesystem::snaps::MapOfSnaps;
+use crate::GLOBAL_CONFIG;
+use hashbrown::HashMap;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Once};
+
+// what a provider may need to know, beyond the dataset itself, to enumerate its snapshots
+pub struct ProviderContext<'a> {
+    pub map_of_datasets: &'a BTreeMap<Arc<Path>, DatasetMetadata>,
+    pub opt_zfs_snap_names: Option<&'a HashMap<String, Vec<String>>>,
+    pub opt_debug: bool,
+}
+
+// a source of versions for the datasets of a single filesystem type.  A new snapshot engine needs
+// only a FilesystemType variant, which is detected at startup, an implementation of this trait,
+// and an entry in PROVIDERS
+pub trait VersionProvider: Sync {
+    // whether this provider supplies the versions of datasets of the filesystem type detected
+    fn detects(&self, fs_type: &FilesystemType) -> bool;
+
+    // every snapshot mount of a dataset, within which a path on that dataset may have a version
+    fn snap_mounts(
+        &self,
+        mount: &Path,
+        dataset_info: &DatasetMetadata,
+        context: &ProviderContext,
+    ) -> Vec<Box<Path>>;
+
+    // where the version of a path, relative to its dataset, would be found within a snapshot mount
+    #[inline(always)]
+    fn versioned_path(&self, snap_mount: &Path, relative_path: &Path) -> PathBuf {
+        snap_mount.join(relative_path)
+    }
+}
+
+// consulted in order, so the first provider which detects a filesystem type is its provider
+static PROVIDERS: &[&dyn VersionProvider] = &[&ZfsProvider, &BtrfsProvider, &DefinedMountsProvider];
+
+impl FilesystemType {
+    pub fn provider(&self) -> &'static dyn VersionProvider {
+        PROVIDERS
+            .iter()
+            .copied()
+            .find(|provider| provider.detects(self))
+            .unwrap_or(&DefinedMountsProvider)
+    }
+}
+
+// the provider of a dataset's versions, as detected at startup
+pub fn provider_for_dataset(dataset_of_interest: &Path) -> &'static dyn VersionProvider {
+    GLOBAL_CONFIG
+        .dataset_collection
+        .map_of_datasets
+        .get(dataset_of_interest)
+        .map(|dataset_info| dataset_info.fs_type.provider())
+        .unwrap_or(&DefinedMountsProvider)
+}
+
+// snapshot mounts are found at locations defined by the filesystem, or by the user
+pub struct DefinedMountsProvider;
+
+impl VersionProvider for DefinedMountsProvider {
+    // any filesystem may have snapshot mounts at defined locations, so this provider is our last resort
+    fn detects(&self, _fs_type: &FilesystemType) -> bool {
+        true
+    }
+
+    fn snap_mounts(
+        &self,
+        mount: &Path,
+        dataset_info: &DatasetMetadata,
+        _context: &ProviderContext,
+    ) -> Vec<Box<Path>> {
+        MapOfSnaps::from_defined_mounts(mount, dataset_info)
+    }
+}
+
+pub struct ZfsProvider;
+
+impl VersionProvider for ZfsProvider {
+    fn detects(&self, fs_type: &FilesystemType) -> bool {
+        matches!(fs_type, FilesystemType::Zfs)
+    }
+
+    fn snap_mounts(
+        &self,
+        mount: &Path,
+        dataset_info: &DatasetMetadata,
+        context: &ProviderContext,
+    ) -> Vec<Box<Path>> {
+        match context.opt_zfs_snap_names {
+            Some(snap_names) => MapOfSnaps::from_zfs_snap_names(mount, dataset_info, snap_names),
+            None => MapOfSnaps::from_defined_mounts(mount, dataset_info),
+        }
+    }
+}
+
+pub struct BtrfsProvider;
+
+impl VersionProvider for BtrfsProvider {
+    // btrfs datasets without additional data are not local, and have only defined mounts
+    fn detects(&self, fs_type: &FilesystemType) -> bool {
+        matches!(fs_type, FilesystemType::Btrfs(Some(_)))
+    }
+
+    fn snap_mounts(
+        &self,
+        mount: &Path,
+        dataset_info: &DatasetMetadata,
+        context: &ProviderContext,
+    ) -> Vec<Box<Path>> {
+        // btrfs Some mounts are potential local mount
+        let FilesystemType::Btrfs(Some(additional_data)) = &dataset_info.fs_type else {
+            return MapOfSnaps::from_defined_mounts(mount, dataset_info);
+        };
+
+        let map = MapOfSnaps::from_btrfs_cmd(
+            mount,
+            dataset_info,
+            &additional_data.base_subvol,
+            context.map_of_datasets,
+            context.opt_debug,
+        );
+
+        if map.is_empty() {
+            static NOTICE_FALLBACK: Once = Once::new();
+
+            NOTICE_FALLBACK.call_once(|| {
+                eprintln!(
+                    "NOTICE: Falling back to detection of btrfs snapshot mounts perhaps defined by Snapper re: mount: {:?}",
+                    mount
+                );
+            });
+
+            return MapOfSnaps::from_defined_mounts(mount, dataset_info);
+        }
+
+        additional_data.snap_names.get_or_init(|| map.clone());
+
+        map.into_keys().collect()
+    }
+}
@@ -0,0 +1,282 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::filesystem::explicit_snaps::MapOfExplicitSnaps;
+use crate::library::cleanup::CleanupGuard;
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{fuse_unmount, private_temp_dir};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command as ExecProcess, Stdio};
+use std::sync::{LazyLock, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+
+// "restic mount" serves its repo until unmounted, so we hold each child, to be reaped at exit
+static RESTIC_MOUNTED_REPOS: LazyLock<Mutex<Vec<(PathBuf, Child)>>> =
+    LazyLock::new(|| Mutex::new(Vec::new()));
+// the private dir beneath which we mount each repo
+static RESTIC_BASE_DIR: OnceLock<PathBuf> = OnceLock::new();
+// key: local dir, val: the local dir within each snapshot of the restic repos we have mounted
+static RESTIC_SNAP_MOUNTS: OnceLock<BTreeMap<Box<Path>, Vec<Box<Path>>>> = OnceLock::new();
+
+// how long we wait for "restic mount" to exit once its repo is unmounted
+const RESTIC_EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ResticRepo {
+    repo: String,
+    local_dir: Box<Path>,
+}
+
+// as with borg repos, restic repos are only mounted once exec() has begun, so the cleanup guard
+// held by main() is always in place to unmount them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResticRepos {
+    inner: Vec<ResticRepo>,
+}
+
+impl ResticRepos {
+    // each value is of the form <REPO>:<LOCAL_DIR>.  Because a remote repo may itself contain
+    // a colon (eg. "sftp:user@host:/srv/restic"), we split on the last colon.
+    pub fn new(opt_raw_restic_repos: Option<Vec<String>>) -> HttmResult<Option<Self>> {
+        let Some(raw_restic_repos) = opt_raw_restic_repos else {
+            return Ok(None);
+        };
+
+        let mut inner = Vec::new();
+
+        for raw in raw_restic_repos.iter() {
+            let Some((repo, local_dir)) = raw.rsplit_once(':') else {
+                return Err(HttmError::new(&format!(
+                    "A restic repo must be specified in the form <REPO>:<LOCAL_DIR>: {raw:?}"
+                ))
+                .into());
+            };
+
+            match Path::new(local_dir).canonicalize() {
+                Ok(local_dir) if local_dir.is_dir() => inner.push(ResticRepo {
+                    repo: repo.to_owned(),
+                    local_dir: local_dir.into_boxed_path(),
+                }),
+                _ => {
+                    eprintln!(
+                        "WARN: A local directory specified for a restic repo does not exist: {local_dir:?}"
+                    );
+                }
+            }
+        }
+
+        if inner.is_empty() {
+            return Err(HttmError::new(
+                "None of the local directories specified for restic repos exist.  Quitting.",
+            )
+            .into());
+        }
+
+        Ok(Some(Self { inner }))
+    }
+
+    // restic snapshots are treated just like user specified snapshot mounts of their local dirs,
+    // which are known before the repos are mounted, see fn snap_mounts()
+    pub fn local_dirs(&self) -> MapOfExplicitSnaps {
+        let map: BTreeMap<Box<Path>, Vec<Box<Path>>> = self
+            .inner
+            .iter()
+            .map(|restic_repo| (restic_repo.local_dir.clone(), Vec::new()))
+            .collect();
+
+        map.into()
+    }
+
+    // restic mounts each snapshot of a repo as a directory beneath "snapshots", named for its time,
+    // and each snapshot contains the backed up paths, less any leading "/", so the local dir within
+    // each snapshot may simply be used as an explicit snapshot mount of that local dir
+    pub fn mount(&self) -> HttmResult<()> {
+        let restic_command = which::which("restic").map_err(|_err| {
+            HttmError::new(
+                "'restic' command not found. Make sure the command 'restic' is in your path.",
+            )
+        })?;
+
+        // recorded before anything is mounted beneath it, so cleanup may remove it
+        let base_dir = private_temp_dir("httm-restic")?;
+        let base_dir = RESTIC_BASE_DIR.get_or_init(|| base_dir);
+
+        let mut map: BTreeMap<Box<Path>, Vec<Box<Path>>> = BTreeMap::new();
+
+        for (idx, ResticRepo { repo, local_dir }) in self.inner.iter().enumerate() {
+            let mount_point = base_dir.join(idx.to_string());
+
+            std::fs::create_dir(&mount_point)?;
+
+            if !Self::mount_repo(&restic_command, repo, &mount_point)? {
+                eprintln!("WARN: httm could not mount restic repo: {repo:?}");
+                let _ = std::fs::remove_dir(&mount_point);
+                continue;
+            }
+
+            let relative_dir = local_dir.strip_prefix("/").unwrap_or(local_dir);
+
+            // "latest" is a symlink to the newest snapshot, and so is skipped as a duplicate
+            let snap_mounts: Vec<Box<Path>> = std::fs::read_dir(mount_point.join("snapshots"))?
+                .flatten()
+                .filter(|snapshot| {
+                    snapshot
+                        .file_type()
+                        .is_ok_and(|file_type| file_type.is_dir())
+                })
+                .map(|snapshot| snapshot.path().join(relative_dir))
+                .filter(|snap_mount| snap_mount.is_dir())
+                .map(|snap_mount| snap_mount.into_boxed_path())
+                .collect();
+
+            if snap_mounts.is_empty() {
+                eprintln!(
+                    "WARN: No snapshot of restic repo {repo:?} contains the local directory specified: {local_dir:?}"
+                );
+                continue;
+            }
+
+            map.entry(local_dir.clone())
+                .or_default()
+                .extend(snap_mounts);
+        }
+
+        if map.is_empty() {
+            return Err(HttmError::new(
+                "None of the restic repos specified could be mounted, or contain their local directories.  Quitting.",
+            )
+            .into());
+        }
+
+        let _ = RESTIC_SNAP_MOUNTS.set(map);
+
+        Ok(())
+    }
+
+    // "restic mount" never returns while its repo is mounted, so we spawn it, and wait until either
+    // its snapshots dir appears, or it exits
+    fn mount_repo(restic_command: &Path, repo: &str, mount_point: &Path) -> HttmResult<bool> {
+        let mut mounted = RESTIC_MOUNTED_REPOS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // anything mounted once cleanup has begun would never be unmounted
+        if CleanupGuard::is_started() {
+            return Ok(false);
+        }
+
+        // inherit stdin and stderr, so restic may prompt for any password
+        let child = ExecProcess::new(restic_command)
+            .arg("--repo")
+            .arg(repo)
+            .arg("mount")
+            .arg(mount_point)
+            .stdout(Stdio::null())
+            .spawn()?;
+
+        // recorded before the mount appears, so cleanup reaps the child, even if httm is
+        // interrupted while restic prompts for a password
+        mounted.push((mount_point.to_path_buf(), child));
+        drop(mounted);
+
+        let snapshots_dir = mount_point.join("snapshots");
+
+        loop {
+            if snapshots_dir.is_dir() {
+                return Ok(true);
+            }
+
+            let mut mounted = RESTIC_MOUNTED_REPOS
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+
+            let Some(idx) = mounted
+                .iter()
+                .position(|(existing, _child)| existing == mount_point)
+            else {
+                // cleanup has already reaped the child
+                return Ok(false);
+            };
+
+            if !matches!(mounted[idx].1.try_wait(), Ok(None)) {
+                mounted.remove(idx);
+                return Ok(false);
+            }
+
+            drop(mounted);
+
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    pub fn snap_mounts(local_dir: &Path) -> &'static [Box<Path>] {
+        RESTIC_SNAP_MOUNTS
+            .get()
+            .and_then(|map| map.get(local_dir))
+            .map_or(&[], |snap_mounts| snap_mounts.as_slice())
+    }
+
+    // key: snap mount, val: local dir
+    pub fn mounted() -> impl Iterator<Item = (&'static Path, &'static Path)> {
+        RESTIC_SNAP_MOUNTS
+            .get()
+            .into_iter()
+            .flatten()
+            .flat_map(|(local_dir, snap_mounts)| {
+                snap_mounts
+                    .iter()
+                    .map(move |snap_mount| (snap_mount.as_ref(), local_dir.as_ref()))
+            })
+    }
+
+    // once its repo is unmounted, "restic mount" exits on its own, else we kill it.  We remove only
+    // those dirs we created, and never recursively
+    pub fn cleanup() {
+        let mut mounted = RESTIC_MOUNTED_REPOS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let Some(base_dir) = RESTIC_BASE_DIR.get() else {
+            return;
+        };
+
+        mounted.drain(..).for_each(|(mount_point, mut child)| {
+            if matches!(child.try_wait(), Ok(None)) {
+                fuse_unmount(&mount_point);
+
+                let start = Instant::now();
+
+                while matches!(child.try_wait(), Ok(None)) {
+                    if start.elapsed() > RESTIC_EXIT_TIMEOUT {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        break;
+                    }
+
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+            }
+
+            if std::fs::remove_dir(&mount_point).is_err() {
+                eprintln!("WARN: httm could not unmount restic repo: {mount_point:?}");
+            }
+        });
+
+        let _ = std::fs::remove_dir(base_dir);
+    }
+}
@@ -0,0 +1,186 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::filesystem::mounts::FilesystemType;
+use crate::library::results::HttmResult;
+use crate::GLOBAL_CONFIG;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Once, RwLock};
+
+// every previous version exposed by an SMB server is addressable, within its share, by a path
+// component of the form "@GMT-YYYY.MM.DD-HH.MM.SS", although such components never appear in a readdir
+pub const SHADOW_COPY_TOKEN_PREFIX: &str = "@GMT-";
+
+// a share's previous versions are only enumerated, across the network, once a path on that share is looked up
+static SHADOW_COPY_SNAPS: LazyLock<RwLock<BTreeMap<PathBuf, &'static [Box<Path>]>>> =
+    LazyLock::new(|| RwLock::new(BTreeMap::new()));
+
+// the size of the header of the kernel's struct smb_snapshot_array, which precedes the snapshot tokens
+#[cfg(target_os = "linux")]
+const SNAPSHOT_ARRAY_HEADER_SIZE: usize = 12;
+// the first query must be exactly this size, as it only asks the server how large the list will be
+#[cfg(target_os = "linux")]
+const MIN_SNAPSHOT_ARRAY_SIZE: usize = 16;
+// the largest response the kernel will copy to us is CIFSMaxBufSize, whose upper limit is this value
+#[cfg(target_os = "linux")]
+const CIFS_MAX_BUF_SIZE: usize = 130048;
+
+#[cfg(target_os = "linux")]
+nix::ioctl_read_bad!(
+    cifs_enumerate_snapshots,
+    nix::request_code_read!(0xCF, 0x06, SNAPSHOT_ARRAY_HEADER_SIZE),
+    u8
+);
+
+pub struct ShadowCopy;
+
+impl ShadowCopy {
+    // shadow copy tokens of an SMB share, as enumerated by the server, via the cifs client's ioctl,
+    // much like "smbinfo list-snapshots"
+    #[cfg(target_os = "linux")]
+    pub fn snap_tokens(share_mount: &Path) -> HttmResult<Vec<String>> {
+        use std::os::fd::AsRawFd;
+
+        let dir = std::fs::File::open(share_mount)?;
+
+        // first, a zeroed buffer asks only for the number and total size of snapshots
+        let mut header = [0u8; MIN_SNAPSHOT_ARRAY_SIZE];
+        unsafe { cifs_enumerate_snapshots(dir.as_raw_fd(), header.as_mut_ptr()) }?;
+
+        let number_of_snapshots = Self::read_u32(&header, 0);
+        let snapshot_array_size = Self::read_u32(&header, 8) as usize;
+
+        if number_of_snapshots == 0 {
+            return Ok(Vec::new());
+        }
+
+        // then, a non-zero number of snapshots asks for the list itself
+        let mut buffer =
+            vec![0u8; CIFS_MAX_BUF_SIZE.max(SNAPSHOT_ARRAY_HEADER_SIZE + snapshot_array_size)];
+        buffer[..4].copy_from_slice(&number_of_snapshots.to_ne_bytes());
+        // the kernel sizes its request to the server by the array size we give it
+        buffer[8..12].copy_from_slice(&(snapshot_array_size as u32).to_ne_bytes());
+        unsafe { cifs_enumerate_snapshots(dir.as_raw_fd(), buffer.as_mut_ptr()) }?;
+
+        let snapshot_array_size = Self::read_u32(&buffer, 8) as usize;
+        let array_end = (SNAPSHOT_ARRAY_HEADER_SIZE + snapshot_array_size).min(buffer.len());
+
+        // tokens are null terminated UTF-16LE strings
+        let utf16: Vec<u16> = buffer[SNAPSHOT_ARRAY_HEADER_SIZE..array_end]
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+
+        let tokens = utf16
+            .split(|code_unit| *code_unit == 0)
+            .map(String::from_utf16_lossy)
+            .filter(|token| token.starts_with(SHADOW_COPY_TOKEN_PREFIX))
+            .collect();
+
+        Ok(tokens)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn snap_tokens(_share_mount: &Path) -> HttmResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn read_u32(buffer: &[u8], offset: usize) -> u32 {
+        u32::from_ne_bytes([
+            buffer[offset],
+            buffer[offset + 1],
+            buffer[offset + 2],
+            buffer[offset + 3],
+        ])
+    }
+
+    pub fn snap_mounts(dataset_of_interest: &Path) -> &'static [Box<Path>] {
+        let is_shadow_copy = GLOBAL_CONFIG
+            .dataset_collection
+            .map_of_datasets
+            .get(dataset_of_interest)
+            .is_some_and(|dataset_info| matches!(dataset_info.fs_type, FilesystemType::ShadowCopy));
+
+        if !is_shadow_copy {
+            return &[];
+        }
+
+        if let Some(cached) = SHADOW_COPY_SNAPS
+            .read()
+            .ok()
+            .and_then(|cached| cached.get(dataset_of_interest).copied())
+        {
+            return cached;
+        }
+
+        let Ok(mut cached) = SHADOW_COPY_SNAPS.write() else {
+            return &[];
+        };
+
+        // another thread may have enumerated this share's previous versions while we waited
+        if let Some(snap_mounts) = cached.get(dataset_of_interest) {
+            return snap_mounts;
+        }
+
+        let snap_mounts: Vec<Box<Path>> = match Self::snap_tokens(dataset_of_interest) {
+            Ok(tokens) => Self::resolvable(dataset_of_interest, tokens),
+            Err(err) => {
+                eprintln!("WARN: httm could not enumerate the previous versions of {dataset_of_interest:?}: {err}");
+                Vec::new()
+            }
+        };
+
+        let snap_mounts: &'static [Box<Path>] = Box::leak(snap_mounts.into_boxed_slice());
+
+        cached.insert(dataset_of_interest.to_path_buf(), snap_mounts);
+
+        snap_mounts
+    }
+
+    // the Linux cifs client sends a path containing a @GMT- component to the server as is, so
+    // such a path only resolves where the server itself understands the convention, as Samba's
+    // shadow_copy2 module does.  Other servers only offer previous versions via a "snapshot=" mount
+    fn resolvable(share_mount: &Path, mut tokens: Vec<String>) -> Vec<Box<Path>> {
+        // servers usually list the newest version first, but a token's fixed width timestamp sorts
+        // lexically, so we order them oldest first ourselves, and the last is always the newest
+        tokens.sort();
+
+        let snap_mounts: Vec<Box<Path>> = tokens
+            .into_iter()
+            .map(|token| share_mount.join(token).into_boxed_path())
+            .collect();
+
+        match snap_mounts.last() {
+            Some(newest) if newest.symlink_metadata().is_err() => {
+                static NOTICE_UNRESOLVED: Once = Once::new();
+
+                NOTICE_UNRESOLVED.call_once(|| {
+                    eprintln!(
+                        "NOTICE: The server enumerates previous versions of {:?}, but does not resolve @GMT- paths.  \
+                        Mount a previous version with the cifs \"snapshot=\" option, and specify it via SNAP_MOUNT, to view it.",
+                        share_mount
+                    );
+                });
+
+                Vec::new()
+            }
+            _ => snap_mounts,
+        }
+    }
+}
@@ -17,8 +17,11 @@
 
 use super::mounts::ROOT_PATH;
 use crate::filesystem::mounts::{DatasetMetadata, FilesystemType, BTRFS_ROOT_SUBVOL, PROC_MOUNTS};
+use crate::filesystem::provider::ProviderContext;
+use crate::filesystem::time_machine::TimeMachineLocal;
 use crate::library::results::{HttmError, HttmResult};
 use crate::library::utility::{get_btrfs_command, user_has_effective_root};
+use crate::zfs::run_command::RunZFSCommand;
 use crate::{
     BTRFS_SNAPPER_HIDDEN_DIRECTORY,
     BTRFS_SNAPPER_SUFFIX,
@@ -27,9 +30,10 @@ use crate::{
     TM_DIR_REMOTE,
     ZFS_SNAPSHOT_DIRECTORY,
 };
+use hashbrown::HashMap;
 use proc_mounts::MountIter;
 use rayon::prelude::*;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::read_dir;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
@@ -56,47 +60,41 @@ impl Deref for MapOfSnaps {
 }
 
 impl MapOfSnaps {
-    // fans out precompute of snap mounts to the appropriate function based on fstype
+    // fans out precompute of snap mounts to the appropriate provider based on fstype
     pub fn new(
         map_of_datasets: &BTreeMap<Arc<Path>, DatasetMetadata>,
+        opt_pinned: Option<&BTreeSet<Arc<Path>>>,
+        opt_zfs_list: bool,
         opt_debug: bool,
     ) -> HttmResult<Self> {
-        let map_of_snaps: BTreeMap<Arc<Path>, Vec<Box<Path>>> = map_of_datasets
-            .par_iter()
-            .map(|(mount, dataset_info)| {      
-                let snap_mounts: Vec<Box<Path>> = match &dataset_info.fs_type {
-                    FilesystemType::Zfs | FilesystemType::Nilfs2 | FilesystemType::Apfs | FilesystemType::Restic(_) | FilesystemType::Btrfs(None) => {
-                        Self::from_defined_mounts(mount, dataset_info)
-                    }
-                    // btrfs Some mounts are potential local mount
-                    FilesystemType::Btrfs(Some(additional_data)) => {
-                        let map = Self::from_btrfs_cmd(
-                            mount,
-                            dataset_info,
-                            &additional_data.base_subvol,
-                            map_of_datasets,
-                            opt_debug,
-                        );
-
-                        if map.is_empty() {
-                            static NOTICE_FALLBACK: Once = Once::new();
-
-                            NOTICE_FALLBACK.call_once(|| {
-                                eprintln!(
-                                    "NOTICE: Falling back to detection of btrfs snapshot mounts perhaps defined by Snapper re: mount: {:?}", mount
-                                );
-                            });
+        // a single 'zfs list' for every dataset, instead of a readdir of each snapshot directory, see ZFS_LIST
+        let opt_zfs_snap_names = if opt_zfs_list
+            && map_of_datasets
+                .values()
+                .any(|dataset_info| matches!(dataset_info.fs_type, FilesystemType::Zfs))
+        {
+            Self::zfs_snap_names()
+        } else {
+            None
+        };
 
-                            Self::from_defined_mounts(mount, dataset_info)
-                        } else {
-                            additional_data.snap_names.get_or_init(|| {
-                                map.clone()
-                            });
+        let context = ProviderContext {
+            map_of_datasets,
+            opt_zfs_snap_names: opt_zfs_snap_names.as_ref(),
+            opt_debug,
+        };
 
-                            map.into_keys().collect()
-                        }
-                    }
-                };
+        let map_of_snaps: BTreeMap<Arc<Path>, Vec<Box<Path>>> = map_of_datasets
+            .par_iter()
+            .filter(|(mount, _dataset_info)| {
+                opt_pinned.map_or(true, |pinned| pinned.contains(*mount))
+            })
+            .map(|(mount, dataset_info)| {
+                let snap_mounts =
+                    dataset_info
+                        .fs_type
+                        .provider()
+                        .snap_mounts(mount, dataset_info, &context);
 
                 (mount.clone(), snap_mounts)
             })
@@ -129,6 +127,38 @@ impl MapOfSnaps {
         Ok(map_of_snaps.into())
     }
 
+    fn zfs_snap_names() -> Option<HashMap<String, Vec<String>>> {
+        match RunZFSCommand::new().and_then(|zfs_command| zfs_command.list_all_snapshots()) {
+            Ok(snap_names) => Some(snap_names),
+            Err(err) => {
+                eprintln!(
+                    "WARN: Falling back to detection of ZFS snapshot mounts via the snapshot directory: {}",
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    // snapshots are always available at the snapshot directory, even when snapdir is hidden, so
+    // we only need the snapshot names to build the snap mounts
+    pub fn from_zfs_snap_names(
+        mount: &Path,
+        dataset_info: &DatasetMetadata,
+        zfs_snap_names: &HashMap<String, Vec<String>>,
+    ) -> Vec<Box<Path>> {
+        let snap_dir = mount.join(ZFS_SNAPSHOT_DIRECTORY);
+
+        match zfs_snap_names.get(dataset_info.source.to_string_lossy().as_ref()) {
+            Some(names) => names
+                .iter()
+                .map(|snap_name| snap_dir.join(snap_name).into_boxed_path())
+                .collect(),
+            // a dataset without snapshots is not listed at all
+            None => Vec::new(),
+        }
+    }
+
     // build paths to all snap mounts
     pub fn from_btrfs_cmd(
         base_mount: &Path,
@@ -334,7 +364,7 @@ impl MapOfSnaps {
         }
     }
 
-    fn from_defined_mounts(
+    pub fn from_defined_mounts(
         mount_point_path: &Path,
         dataset_metadata: &DatasetMetadata,
     ) -> Vec<Box<Path>> {
@@ -395,6 +425,8 @@ impl MapOfSnaps {
                             .map(|path| path.into_boxed_path());
 
                         res.par_extend(local);
+                    } else {
+                        res.extend(TimeMachineLocal::mount());
                     }
 
                     if Path::new(&TM_DIR_REMOTE).exists() {
@@ -412,6 +444,8 @@ impl MapOfSnaps {
 
                     res
                 }
+                // previous versions are enumerated lazily, see ShadowCopy::snap_mounts
+                FilesystemType::ShadowCopy => Vec::new(),
                 FilesystemType::Nilfs2 => {
                     let source_path = dataset_metadata.source.as_ref();
 
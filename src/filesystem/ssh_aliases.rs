@@ -0,0 +1,328 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::filesystem::explicit_snaps::MapOfExplicitSnaps;
+use crate::library::cleanup::CleanupGuard;
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{fuse_unmount, private_temp_dir};
+use crate::{BTRFS_SNAPPER_HIDDEN_DIRECTORY, BTRFS_SNAPPER_SUFFIX, ZFS_SNAPSHOT_DIRECTORY};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command as ExecProcess;
+use std::sync::{LazyLock, Mutex, OnceLock, PoisonError};
+
+const SSH_ALIAS_SCHEMES: [&str; 2] = ["ssh://", "sftp://"];
+
+// sshfs mounts we have made, to be unmounted at exit
+static SSH_MOUNTED_DIRS: LazyLock<Mutex<Vec<PathBuf>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+// the private dir beneath which we mount each remote dir
+static SSH_BASE_DIR: OnceLock<PathBuf> = OnceLock::new();
+// key: local dir, val: each snapshot of the remote dataset, as mounted
+static SSH_SNAP_MOUNTS: OnceLock<BTreeMap<Box<Path>, Vec<Box<Path>>>> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SshAlias {
+    // "[user@]host"
+    host: String,
+    opt_port: Option<String>,
+    remote_dir: String,
+    local_dir: Box<Path>,
+}
+
+// an alias of the form <LOCAL_DIR>:ssh://[user@]host[:port]/<REMOTE_DIR>, where the remote dir
+// is the mount of a remote dataset.  As with borg repos, each remote dir is mounted, via sshfs,
+// once exec() has begun, so the cleanup guard held by main() is always in place to unmount it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshAliases {
+    inner: Vec<SshAlias>,
+}
+
+impl SshAliases {
+    pub fn is_ssh_url(remote_dir: &str) -> bool {
+        SSH_ALIAS_SCHEMES
+            .iter()
+            .any(|scheme| remote_dir.starts_with(scheme))
+    }
+
+    pub fn new(opt_raw_aliases: Option<&[String]>) -> HttmResult<Option<Self>> {
+        let Some(raw_aliases) = opt_raw_aliases else {
+            return Ok(None);
+        };
+
+        let mut inner = Vec::new();
+
+        for (local_dir, url) in raw_aliases
+            .iter()
+            .filter_map(|alias| alias.split_once(':'))
+            .filter(|(_local_dir, url)| Self::is_ssh_url(url))
+        {
+            let alias = Self::parse(local_dir, url)?;
+
+            if !alias.local_dir.is_dir() {
+                eprintln!(
+                    "WARN: An alias path specified does not exist, or is not mounted: {local_dir:?}"
+                );
+                continue;
+            }
+
+            inner.push(alias);
+        }
+
+        if inner.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self { inner }))
+    }
+
+    fn parse(local_dir: &str, url: &str) -> HttmResult<SshAlias> {
+        let invalid = || {
+            let msg = format!(
+                "An SSH alias must be specified in the form <LOCAL_DIR>:ssh://[user@]host[:port]/<REMOTE_DIR>: {url:?}"
+            );
+            HttmError::new(&msg)
+        };
+
+        let rest = SSH_ALIAS_SCHEMES
+            .iter()
+            .find_map(|scheme| url.strip_prefix(scheme))
+            .ok_or_else(invalid)?;
+
+        let (authority, remote_dir) = rest
+            .find('/')
+            .map(|idx| rest.split_at(idx))
+            .ok_or_else(invalid)?;
+
+        let (host, opt_port) = match authority.rsplit_once(':') {
+            Some((host, port)) if port.parse::<u16>().is_ok() => {
+                (host.to_owned(), Some(port.to_owned()))
+            }
+            Some(_) => return Err(invalid().into()),
+            None => (authority.to_owned(), None),
+        };
+
+        if host.is_empty() || host.ends_with('@') {
+            return Err(invalid().into());
+        }
+
+        let local_dir = Path::new(local_dir)
+            .canonicalize()
+            .unwrap_or_else(|_err| PathBuf::from(local_dir));
+
+        Ok(SshAlias {
+            host,
+            opt_port,
+            remote_dir: remote_dir.to_owned(),
+            local_dir: local_dir.into_boxed_path(),
+        })
+    }
+
+    // as with borg archives, the snapshots of the remote dataset are treated just like user
+    // specified snapshot mounts of their local dirs, see fn snap_mounts()
+    pub fn local_dirs(&self) -> MapOfExplicitSnaps {
+        let map: BTreeMap<Box<Path>, Vec<Box<Path>>> = self
+            .inner
+            .iter()
+            .map(|ssh_alias| (ssh_alias.local_dir.clone(), Vec::new()))
+            .collect();
+
+        map.into()
+    }
+
+    // sshfs reads each file only as it is requested, so a lookup fetches only metadata, and
+    // a restore streams the version down
+    pub fn mount(&self) -> HttmResult<()> {
+        let sshfs_command = which::which("sshfs").map_err(|_err| {
+            HttmError::new(
+                "'sshfs' command not found. Make sure the command 'sshfs' is in your path.",
+            )
+        })?;
+
+        // recorded before anything is mounted beneath it, so cleanup may remove it
+        let base_dir = private_temp_dir("httm-sshfs")?;
+        let base_dir = SSH_BASE_DIR.get_or_init(|| base_dir);
+
+        let mut map: BTreeMap<Box<Path>, Vec<Box<Path>>> = BTreeMap::new();
+
+        for (idx, ssh_alias) in self.inner.iter().enumerate() {
+            let remote = format!("{}:{}", ssh_alias.host, ssh_alias.remote_dir);
+
+            // anything mounted once cleanup has begun would never be unmounted, so we hold the lock
+            // until the mount is recorded
+            let mut mounted = SSH_MOUNTED_DIRS
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+
+            if CleanupGuard::is_started() {
+                break;
+            }
+
+            let mount_point = base_dir.join(idx.to_string());
+
+            std::fs::create_dir(&mount_point)?;
+
+            let mut sshfs = ExecProcess::new(&sshfs_command);
+
+            if let Some(port) = &ssh_alias.opt_port {
+                sshfs.arg("-p").arg(port);
+            }
+
+            // inherit stdin and stderr, so ssh may prompt for any password
+            let is_mounted = sshfs
+                .arg("-o")
+                .arg("ro")
+                .arg(&remote)
+                .arg(&mount_point)
+                .status()
+                .map(|status| status.success())
+                .unwrap_or(false);
+
+            if !is_mounted {
+                drop(mounted);
+                eprintln!("WARN: httm could not mount remote dir: {remote:?}");
+                let _ = std::fs::remove_dir(&mount_point);
+                continue;
+            }
+
+            mounted.push(mount_point.clone());
+            drop(mounted);
+
+            let snap_mounts = Self::remote_snap_mounts(&mount_point);
+
+            if snap_mounts.is_empty() {
+                eprintln!(
+                    "WARN: No snapshots were found beneath remote dir: {remote:?}.  The remote dir must be the mount of a ZFS dataset or Snapper-managed btrfs subvolume."
+                );
+                continue;
+            }
+
+            map.entry(ssh_alias.local_dir.clone())
+                .or_default()
+                .extend(snap_mounts);
+        }
+
+        if map.is_empty() {
+            return Err(HttmError::new(
+                "None of the remote dirs specified could be mounted, or contain snapshots.  Quitting.",
+            )
+            .into());
+        }
+
+        let _ = SSH_SNAP_MOUNTS.set(map);
+
+        Ok(())
+    }
+
+    // a remote ZFS dataset's snapshots are beneath ".zfs/snapshot", and a remote Snapper-managed
+    // subvolume's are at ".snapshots/<num>/snapshot"
+    fn remote_snap_mounts(mount_point: &Path) -> Vec<Box<Path>> {
+        let zfs_snap_dir = mount_point.join(ZFS_SNAPSHOT_DIRECTORY);
+
+        let (snap_dir, opt_suffix) = if zfs_snap_dir.is_dir() {
+            (zfs_snap_dir, None)
+        } else {
+            (
+                mount_point.join(BTRFS_SNAPPER_HIDDEN_DIRECTORY),
+                Some(BTRFS_SNAPPER_SUFFIX),
+            )
+        };
+
+        let Ok(entries) = std::fs::read_dir(&snap_dir) else {
+            return Vec::new();
+        };
+
+        entries
+            .flatten()
+            .map(|entry| match opt_suffix {
+                Some(suffix) => entry.path().join(suffix),
+                None => entry.path(),
+            })
+            .filter(|snap_mount| snap_mount.is_dir())
+            .map(|snap_mount| snap_mount.into_boxed_path())
+            .collect()
+    }
+
+    pub fn snap_mounts(local_dir: &Path) -> &'static [Box<Path>] {
+        SSH_SNAP_MOUNTS
+            .get()
+            .and_then(|map| map.get(local_dir))
+            .map_or(&[], |snap_mounts| snap_mounts.as_slice())
+    }
+
+    // key: snap mount, val: local dir
+    pub fn mounted() -> impl Iterator<Item = (&'static Path, &'static Path)> {
+        SSH_SNAP_MOUNTS
+            .get()
+            .into_iter()
+            .flatten()
+            .flat_map(|(local_dir, snap_mounts)| {
+                snap_mounts
+                    .iter()
+                    .map(move |snap_mount| (snap_mount.as_ref(), local_dir.as_ref()))
+            })
+    }
+
+    // unmount and remove only those dirs we created, and never recursively
+    pub fn cleanup() {
+        let mut mounted = SSH_MOUNTED_DIRS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let Some(base_dir) = SSH_BASE_DIR.get() else {
+            return;
+        };
+
+        mounted.drain(..).for_each(|mount_point| {
+            if !fuse_unmount(&mount_point) {
+                eprintln!("WARN: httm could not unmount remote dir: {mount_point:?}");
+                return;
+            }
+
+            let _ = std::fs::remove_dir(&mount_point);
+        });
+
+        let _ = std::fs::remove_dir(base_dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_host_port_and_remote_dir() {
+        let alias = SshAliases::parse("/", "ssh://user@backup:2222/tank/home").unwrap();
+
+        assert_eq!(alias.host, "user@backup");
+        assert_eq!(alias.opt_port.as_deref(), Some("2222"));
+        assert_eq!(alias.remote_dir, "/tank/home");
+
+        let alias = SshAliases::parse("/", "sftp://backup/tank/home").unwrap();
+
+        assert_eq!(alias.host, "backup");
+        assert_eq!(alias.opt_port, None);
+        assert_eq!(alias.remote_dir, "/tank/home");
+    }
+
+    #[test]
+    fn reject_malformed_urls() {
+        assert!(SshAliases::parse("/", "ssh://backup").is_err());
+        assert!(SshAliases::parse("/", "ssh:///tank/home").is_err());
+        assert!(SshAliases::parse("/", "ssh://backup:port/tank/home").is_err());
+        assert!(SshAliases::parse("/", "http://backup/tank/home").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::library::cleanup::CleanupGuard;
+use crate::library::utility::private_temp_dir;
+use std::path::{Path, PathBuf};
+use std::process::Command as ExecProcess;
+use std::sync::{LazyLock, Mutex, OnceLock, PoisonError};
+
+// the prefix of the name of every local snapshot taken by Time Machine
+const TM_LOCAL_SNAP_PREFIX: &str = "com.apple.TimeMachine.";
+// since Catalina, user data lives on a separate data volume, which is where Time Machine takes its snapshots
+const APFS_DATA_VOLUME: &str = "/System/Volumes/Data";
+
+// local snapshots we have mounted, to be unmounted at exit
+static TM_MOUNTED_SNAPS: LazyLock<Mutex<Vec<PathBuf>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+// the private dir beneath which we mount each local snapshot
+static TM_BASE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+// macOS only mounts local Time Machine snapshots, beneath TM_DIR_LOCAL, while the user is browsing
+// Time Machine, so, otherwise, we list the local snapshots ourselves, with tmutil, and mount each,
+// read only, beneath a temp dir
+pub struct TimeMachineLocal;
+
+impl TimeMachineLocal {
+    fn volume() -> &'static Path {
+        if Path::new(APFS_DATA_VOLUME).exists() {
+            return Path::new(APFS_DATA_VOLUME);
+        }
+
+        Path::new("/")
+    }
+
+    pub fn snap_names() -> Vec<String> {
+        let Ok(tmutil_command) = which::which("tmutil") else {
+            return Vec::new();
+        };
+
+        let Ok(output) = ExecProcess::new(tmutil_command)
+            .arg("listlocalsnapshots")
+            .arg(Self::volume())
+            .output()
+        else {
+            return Vec::new();
+        };
+
+        std::str::from_utf8(&output.stdout)
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with(TM_LOCAL_SNAP_PREFIX))
+            .map(str::to_owned)
+            .collect()
+    }
+
+    // snapshot mounts are the root of the data volume, so they are joined with the relative path
+    // of any file, without the additional "Data" dir, as with snapshots beneath TM_DIR_LOCAL
+    pub fn mount() -> Vec<Box<Path>> {
+        let Ok(mount_apfs_command) = which::which("mount_apfs") else {
+            eprintln!("WARN: 'mount_apfs' command not found, so httm cannot mount local Time Machine snapshots.");
+            return Vec::new();
+        };
+
+        let volume = Self::volume();
+
+        // recorded before anything is mounted beneath it, so cleanup may remove it
+        let base_dir = match private_temp_dir("httm-timemachine") {
+            Ok(base_dir) => TM_BASE_DIR.get_or_init(|| base_dir),
+            Err(err) => {
+                eprintln!("WARN: httm could not create a dir to mount local Time Machine snapshots: {err}");
+                return Vec::new();
+            }
+        };
+
+        let snap_mounts: Vec<Box<Path>> = Self::snap_names()
+            .into_iter()
+            // anything mounted once cleanup has begun would never be unmounted
+            .take_while(|_snap_name| !CleanupGuard::is_started())
+            .filter_map(|snap_name| {
+                let mount_point = base_dir.join(&snap_name);
+
+                std::fs::create_dir(&mount_point).ok()?;
+
+                let is_mounted = ExecProcess::new(&mount_apfs_command)
+                    .arg("-o")
+                    .arg("nobrowse,ro")
+                    .arg("-s")
+                    .arg(&snap_name)
+                    .arg(volume)
+                    .arg(&mount_point)
+                    .output()
+                    .map(|output| output.status.success())
+                    .unwrap_or(false);
+
+                if !is_mounted {
+                    let _ = std::fs::remove_dir(&mount_point);
+                    return None;
+                }
+
+                TM_MOUNTED_SNAPS
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(mount_point.clone());
+
+                Some(mount_point.into_boxed_path())
+            })
+            .collect();
+
+        if snap_mounts.is_empty() {
+            eprintln!(
+                "WARN: httm could not mount any local Time Machine snapshots.  Mounting snapshots requires root privileges."
+            );
+        }
+
+        snap_mounts
+    }
+
+    // unmount and remove only those dirs we created, and never recursively
+    pub fn cleanup() {
+        let mut mounted = TM_MOUNTED_SNAPS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let Some(base_dir) = TM_BASE_DIR.get() else {
+            return;
+        };
+
+        mounted.drain(..).for_each(|mount_point| {
+            let is_unmounted = ExecProcess::new("umount")
+                .arg(&mount_point)
+                .output()
+                .map(|output| output.status.success())
+                .unwrap_or(false);
+
+            if !is_unmounted {
+                eprintln!(
+                    "WARN: httm could not unmount local Time Machine snapshot: {mount_point:?}"
+                );
+                return;
+            }
+
+            let _ = std::fs::remove_dir(&mount_point);
+        });
+
+        let _ = std::fs::remove_dir(base_dir);
+    }
+}
@@ -0,0 +1,239 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::data::paths::PathData;
+use crate::library::i18n::Msg;
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::shell::UserShell;
+use crate::library::utility::{date_string, print_output_buf, DateFormat};
+use crate::lookup::versions::VersionsMap;
+use crate::GLOBAL_CONFIG;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::Command as ExecProcess;
+
+// like "git bisect run", a test command which exits with this status neither passes nor fails
+const SKIP_STATUS: i32 = 125;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Good,
+    Bad,
+    Skip,
+}
+
+enum Tester<'a> {
+    Command { shell: UserShell, script: &'a str },
+    User,
+}
+
+impl<'a> Tester<'a> {
+    fn new(opt_command: Option<&'a str>) -> HttmResult<Self> {
+        let Some(script) = opt_command else {
+            return Ok(Tester::User);
+        };
+
+        let shell = UserShell::new()?;
+
+        Ok(Tester::Command { shell, script })
+    }
+
+    fn verdict(&self, version: &PathData, live_path: &Path) -> HttmResult<Verdict> {
+        eprintln!(
+            "Testing: {:?} ({})",
+            version.path(),
+            BisectVersions::date(version)
+        );
+
+        match self {
+            Tester::Command { shell, script } => Self::run(&shell.path, script, version, live_path),
+            Tester::User => Self::ask(),
+        }
+    }
+
+    fn run(
+        shell: &Path,
+        script: &str,
+        version: &PathData,
+        live_path: &Path,
+    ) -> HttmResult<Verdict> {
+        let status = ExecProcess::new(shell)
+            .arg("-c")
+            .arg(script)
+            .env("snap_file", version.path())
+            .env("live_file", live_path)
+            .status()?;
+
+        match status.code() {
+            Some(0) => Ok(Verdict::Good),
+            Some(SKIP_STATUS) => Ok(Verdict::Skip),
+            Some(code) if (1..128).contains(&code) => Ok(Verdict::Bad),
+            Some(code) => {
+                let msg = format!("Bisect aborted.  The test command exited with status {code}.");
+                Err(HttmError::new(&msg).into())
+            }
+            None => Err(HttmError::new(
+                "Bisect aborted.  The test command was terminated by a signal.",
+            )
+            .into()),
+        }
+    }
+
+    // stdin may be a pipe, so, as with a prune, the user's answer is read from the terminal
+    fn ask() -> HttmResult<Verdict> {
+        let tty = std::fs::File::open("/dev/tty").map_err(|_err| {
+            HttmError::new("Could not open the terminal to ask whether the version is good or bad.")
+        })?;
+
+        let mut reader = BufReader::new(tty);
+
+        loop {
+            eprint!("{} ", Msg::BisectPrompt.text());
+
+            let mut input = String::new();
+
+            if reader.read_line(&mut input)? == 0 {
+                return Err(HttmError::new("Bisect aborted.  No answer was given.").into());
+            }
+
+            match input.trim().to_uppercase().as_str() {
+                "GOOD" | "G" => return Ok(Verdict::Good),
+                "BAD" | "B" => return Ok(Verdict::Bad),
+                "SKIP" | "S" => return Ok(Verdict::Skip),
+                // if not good, bad, or skip, then ask again
+                _ => {}
+            }
+        }
+    }
+}
+
+pub struct BisectVersions;
+
+impl BisectVersions {
+    pub fn exec(versions_map: &VersionsMap, opt_command: Option<&str>) -> HttmResult<()> {
+        let (live_version, snaps) = match versions_map.iter().collect::<Vec<_>>().as_slice() {
+            [(live_version, snaps)] => (*live_version, *snaps),
+            _ => {
+                return Err(HttmError::new("BISECT requires exactly one input file.").into());
+            }
+        };
+
+        // oldest first, as versions are ordered elsewhere, and the live version last, if it exists
+        let versions: Vec<&PathData> = snaps
+            .iter()
+            .chain(std::iter::once(live_version))
+            .filter(|version| version.opt_metadata().is_some())
+            .collect();
+
+        if versions.len() < 2 {
+            let msg = format!(
+                "httm requires at least two versions of a file to bisect, but found {} of: {:?}",
+                versions.len(),
+                live_version.path()
+            );
+            return Err(HttmError::new(&msg).into());
+        }
+
+        let tester = Tester::new(opt_command)?;
+        let live_path = live_version.path();
+
+        let mut good = 0usize;
+        let mut bad = versions.len() - 1;
+
+        if tester.verdict(versions[good], live_path)? != Verdict::Good {
+            return Err(HttmError::new(
+                "Bisect requires that the oldest version be good, but it was not marked good.",
+            )
+            .into());
+        }
+
+        if tester.verdict(versions[bad], live_path)? != Verdict::Bad {
+            return Err(HttmError::new(
+                "Bisect requires that the newest version be bad, but it was not marked bad.",
+            )
+            .into());
+        }
+
+        // the indices of the untested versions which lie between the newest good version
+        // and the oldest bad version
+        let mut candidates: Vec<usize> = (good + 1..bad).collect();
+        let mut skipped: Vec<usize> = Vec::new();
+
+        while !candidates.is_empty() {
+            let index = candidates[candidates.len() / 2];
+
+            match tester.verdict(versions[index], live_path)? {
+                Verdict::Good => {
+                    good = index;
+                    candidates.retain(|candidate| *candidate > index);
+                }
+                Verdict::Bad => {
+                    bad = index;
+                    candidates.retain(|candidate| *candidate < index);
+                }
+                Verdict::Skip => {
+                    skipped.push(index);
+                    candidates.retain(|candidate| *candidate != index);
+                }
+            }
+        }
+
+        skipped.retain(|index| (good + 1..bad).contains(index));
+
+        let output_buf = Self::report(&versions, good, bad, &skipped);
+
+        print_output_buf(&output_buf)
+    }
+
+    fn report(versions: &[&PathData], good: usize, bad: usize, skipped: &[usize]) -> String {
+        let line = |label: &str, version: &PathData| {
+            format!(
+                "{:<18}{}  {:?}\n",
+                label,
+                Self::date(version),
+                version.path()
+            )
+        };
+
+        let mut buffer = String::new();
+
+        // as with "git bisect", where skipped versions lie between the last good and first bad versions,
+        // the first bad version cannot be known, and any of them may be the first bad version
+        if skipped.is_empty() {
+            buffer += &line("first bad:", versions[bad]);
+        } else {
+            buffer += "The first bad version could be any of the following versions:\n";
+
+            skipped
+                .iter()
+                .chain(std::iter::once(&bad))
+                .for_each(|index| buffer += &line("possibly bad:", versions[*index]));
+        }
+
+        buffer += &line("last good:", versions[good]);
+
+        buffer
+    }
+
+    fn date(version: &PathData) -> String {
+        date_string(
+            GLOBAL_CONFIG.requested_utc_offset,
+            &version.metadata_infallible().mtime(),
+            DateFormat::Display,
+        )
+    }
+}
@@ -15,17 +15,84 @@
 // For the full copyright and license information, please view the LICENSE file
 // that was distributed with this source code.
 
+use crate::background::deleted::DeletedSearch;
 use crate::background::recursive::RecursiveSearch;
-use crate::data::paths::PathData;
+use crate::data::paths::{BasicDirEntryInfo, PathData};
 use crate::interactive::view_mode::ViewMode;
-use crate::library::results::{HttmError, HttmResult};
+use crate::library::i18n::Msg;
+use crate::library::results::{HttmAbort, HttmError, HttmResult};
+use crate::lookup::versions::Versions;
+use crate::zfs::snap_mounts::SnapshotMounts;
 use crate::GLOBAL_CONFIG;
 use crossbeam_channel::unbounded;
 use skim::prelude::*;
 use std::path::Path;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, AtomicU8};
+use std::sync::{LazyLock, Mutex};
 use std::thread::JoinHandle;
 
+// the query of the last browse session, so when the user continues browsing after a restore,
+// the browse view returns as the user left it, see CONTINUE
+static LAST_BROWSE_QUERY: LazyLock<Mutex<Option<String>>> = LazyLock::new(|| Mutex::new(None));
+
+// the outcome of the last snapshot taken from the browse view via ctrl+s, displayed upon return
+static LAST_BROWSE_NOTICE: LazyLock<Mutex<Option<String>>> = LazyLock::new(|| Mutex::new(None));
+
+// which entries the browse view displays, cycled by the user via ctrl+v, see AvailabilityFilter
+static AVAILABILITY_FILTER: AtomicU8 = AtomicU8::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AvailabilityFilter {
+    All,
+    WithSnaps,
+    DiffersFromLast,
+}
+
+impl AvailabilityFilter {
+    pub fn current() -> Self {
+        match AVAILABILITY_FILTER.load(Ordering::Relaxed) {
+            1 => AvailabilityFilter::WithSnaps,
+            2 => AvailabilityFilter::DiffersFromLast,
+            _ => AvailabilityFilter::All,
+        }
+    }
+
+    fn cycle() {
+        let next = match Self::current() {
+            AvailabilityFilter::All => 1,
+            AvailabilityFilter::WithSnaps => 2,
+            AvailabilityFilter::DiffersFromLast => 0,
+        };
+
+        AVAILABILITY_FILTER.store(next, Ordering::Relaxed);
+    }
+
+    pub fn text(&self) -> &'static str {
+        match self {
+            AvailabilityFilter::All => Msg::FilterAll.text(),
+            AvailabilityFilter::WithSnaps => Msg::FilterWithSnaps.text(),
+            AvailabilityFilter::DiffersFromLast => Msg::FilterDiffersFromLast.text(),
+        }
+    }
+
+    // runs upon the background search thread, so only entries which pass are ever sent to the view.
+    // A full lookup would stall the walk, so we only test whether snapshot versions exist, and,
+    // at most, compare the newest to the live version
+    pub fn admits(&self, basic_info: &BasicDirEntryInfo) -> bool {
+        match self {
+            AvailabilityFilter::All => true,
+            AvailabilityFilter::WithSnaps => {
+                Versions::any_exist(&PathData::from(basic_info.path()))
+            }
+            AvailabilityFilter::DiffersFromLast => {
+                let pathdata = PathData::from(basic_info.path());
+
+                Versions::newest(&pathdata).is_some_and(|newest| !newest.is_same_version(&pathdata))
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct InteractiveBrowse {
     pub selected_pathdata: Vec<PathData>,
@@ -83,7 +150,37 @@ impl InteractiveBrowse {
         }
     }
 
+    // a safety snapshot of the datasets which contain the highlighted entries, as with SNAPSHOT,
+    // an error is only a notice here, as the user may still wish to continue browsing
+    fn snapshot(selected_pathdata: &[PathData]) {
+        let notice =
+            match SnapshotMounts::take_for_paths(selected_pathdata, "httmSnapFileMount", None) {
+                Ok(snap_names) if GLOBAL_CONFIG.opt_dry_run => {
+                    format!(
+                        "httm would take a snapshot named: {}",
+                        snap_names.join(", ")
+                    )
+                }
+                Ok(snap_names) => format!("httm took a snapshot named: {}", snap_names.join(", ")),
+                Err(err) => format!("WARN: httm could not take a snapshot: {err}"),
+            };
+
+        if let Ok(mut last_notice) = LAST_BROWSE_NOTICE.lock() {
+            *last_notice = Some(notice);
+        }
+    }
+
     fn view(requested_dir: &Path) -> HttmResult<Self> {
+        // each time the user cycles the availability filter, the search begins again, and
+        // the view returns with the same query
+        loop {
+            if let Some(browse_result) = Self::view_once(requested_dir)? {
+                return Ok(browse_result);
+            }
+        }
+    }
+
+    fn view_once(requested_dir: &Path) -> HttmResult<Option<Self>> {
         // prep thread spawn
         let started = Arc::new(AtomicBool::new(false));
         let hangup = Arc::new(AtomicBool::new(false));
@@ -109,10 +206,23 @@ impl InteractiveBrowse {
             Self::malloc_trim();
         });
 
-        let header: String = ViewMode::Browse.print_header();
+        let mut header: String = ViewMode::Browse.print_header();
+
+        if let Some(notice) = LAST_BROWSE_NOTICE
+            .lock()
+            .ok()
+            .and_then(|mut last_notice| last_notice.take())
+        {
+            header += &format!("\n{notice}");
+        }
 
         let opt_multi = GLOBAL_CONFIG.opt_preview.is_none();
 
+        let opt_last_query: Option<String> = LAST_BROWSE_QUERY
+            .lock()
+            .ok()
+            .and_then(|last_query| last_query.clone());
+
         // create the skim component for previews
         let skim_opts = SkimOptionsBuilder::default()
             .preview_window(Some("up:50%"))
@@ -120,8 +230,10 @@ impl InteractiveBrowse {
             .nosort(true)
             .exact(GLOBAL_CONFIG.opt_exact)
             .header(Some(&header))
+            .query(opt_last_query.as_deref())
             .multi(opt_multi)
             .regex(false)
+            .bind(vec!["ctrl-v:accept", "ctrl-s:accept"])
             .build()
             .expect("Could not initialized skim options for browse_view");
 
@@ -130,13 +242,51 @@ impl InteractiveBrowse {
         // run_with() reads and shows items from the thread stream created above
         match skim::Skim::run_with(&skim_opts, Some(rx_item)) {
             Some(output) if output.is_abort => {
-                eprintln!("httm interactive file browse session was aborted.  Quitting.");
-                std::process::exit(0)
+                hangup_clone.store(true, Ordering::Relaxed);
+
+                Err(
+                    HttmAbort::new("httm interactive file browse session was aborted.  Quitting.")
+                        .into(),
+                )
+            }
+            Some(output) if output.final_key == Key::Ctrl('v') => {
+                hangup_clone.store(true, Ordering::Relaxed);
+
+                if let Ok(mut last_query) = LAST_BROWSE_QUERY.lock() {
+                    *last_query = Some(output.query.clone());
+                }
+
+                AvailabilityFilter::cycle();
+
+                Ok(None)
+            }
+            Some(output) if output.final_key == Key::Ctrl('s') => {
+                hangup_clone.store(true, Ordering::Relaxed);
+
+                if let Ok(mut last_query) = LAST_BROWSE_QUERY.lock() {
+                    *last_query = Some(output.query.clone());
+                }
+
+                let selected_pathdata: Vec<PathData> = output
+                    .selected_items
+                    .iter()
+                    .map(|item| PathData::from(Path::new(item.output().as_ref())))
+                    .collect();
+
+                Self::snapshot(&selected_pathdata);
+
+                Ok(None)
             }
             Some(output) => {
                 // hangup the channel so the background recursive search can gracefully cleanup and exit
                 hangup_clone.store(true, Ordering::Relaxed);
 
+                DeletedSearch::warn_if_truncated();
+
+                if let Ok(mut last_query) = LAST_BROWSE_QUERY.lock() {
+                    *last_query = Some(output.query.clone());
+                }
+
                 #[cfg(feature = "malloc_trim")]
                 #[cfg(target_os = "linux")]
                 #[cfg(target_env = "gnu")]
@@ -148,10 +298,10 @@ impl InteractiveBrowse {
                     .map(|item| PathData::from(Path::new(item.output().as_ref())))
                     .collect();
 
-                Ok(Self {
+                Ok(Some(Self {
                     selected_pathdata,
                     opt_background_handle: Some(background_handle),
-                })
+                }))
             }
             None => Err(HttmError::new("httm interactive file browse session failed.").into()),
         }
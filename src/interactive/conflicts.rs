@@ -0,0 +1,191 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::interactive::view_mode::{MultiSelect, ViewMode};
+use crate::library::file_ops::Copy;
+use crate::library::i18n::Msg;
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{date_string, display_human_size, DateFormat};
+use crate::GLOBAL_CONFIG;
+use hashbrown::HashSet;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+
+// a live file which is newer than the snapshot version which would overwrite it
+struct RestoreConflict {
+    snap_md: Metadata,
+    live_path: PathBuf,
+    live_md: Metadata,
+}
+
+impl RestoreConflict {
+    fn describe(&self) -> String {
+        let describe_md = |md: &Metadata| {
+            let modify_time = md
+                .modified()
+                .map(|time| {
+                    date_string(
+                        GLOBAL_CONFIG.requested_utc_offset,
+                        &time,
+                        DateFormat::Display,
+                    )
+                })
+                .unwrap_or_else(|_| "unknown".to_string());
+
+            format!("{}, {}", modify_time, display_human_size(md.len()))
+        };
+
+        format!(
+            "{:?}\n\t\tlive:\t\t{}\n\t\tsnapshot:\t{}\n",
+            self.live_path,
+            describe_md(&self.live_md),
+            describe_md(&self.snap_md)
+        )
+    }
+}
+
+pub enum ConflictResolution {
+    // the live files which the restore should leave untouched
+    Exclude(HashSet<PathBuf>),
+    Cancel,
+}
+
+pub struct RestoreConflicts {
+    conflicts: Vec<RestoreConflict>,
+}
+
+impl RestoreConflicts {
+    // a planning pass over the snapshot directory, before anything is copied, which finds every
+    // live file with a more recent modify time than its snapshot version
+    pub fn new(snap_dir: &Path, live_dir: &Path) -> HttmResult<Self> {
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+
+        Copy::collect_tree(snap_dir, live_dir, &mut dirs, &mut files)?;
+
+        let conflicts = files
+            .into_iter()
+            .filter_map(|(snap_path, live_path, _size)| {
+                let snap_md = snap_path.symlink_metadata().ok()?;
+                let live_md = live_path.symlink_metadata().ok()?;
+
+                if live_md.modified().ok()? > snap_md.modified().ok()? {
+                    return Some(RestoreConflict {
+                        snap_md,
+                        live_path,
+                        live_md,
+                    });
+                }
+
+                None
+            })
+            .collect();
+
+        Ok(Self { conflicts })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    pub fn resolve(&self) -> HttmResult<ConflictResolution> {
+        if self.is_empty() {
+            return Ok(ConflictResolution::Exclude(HashSet::new()));
+        }
+
+        let conflicts_buffer: String = self
+            .conflicts
+            .iter()
+            .map(|conflict| format!("\t{}", conflict.describe()))
+            .collect();
+
+        let resolve_buffer = format!(
+            "The following live file/s are newer than their snapshot versions, and would be overwritten by this restore:\n\n\
+            {conflicts_buffer}\n\
+            How should httm resolve these conflicts?\n\
+            ─────────────────────────────────────────────────────────────────────────────────────────\n\
+            {}\n\
+            {}\n\
+            {}\n\
+            {}",
+            Msg::KeepLive.text(),
+            Msg::TakeSnapshot.text(),
+            Msg::ChoosePerFile.text(),
+            Msg::Skip.text()
+        );
+
+        // loop until user makes a valid selection
+        loop {
+            let view_mode = ViewMode::Restore;
+
+            let selection = view_mode.view_buffer(&resolve_buffer, MultiSelect::Off)?;
+
+            let user_selection = selection
+                .get(0)
+                .ok_or_else(|| HttmError::new("Could not obtain the first match selected."))?;
+
+            match user_selection.as_str() {
+                keep if keep == Msg::KeepLive.text() => {
+                    return Ok(ConflictResolution::Exclude(self.all_live_paths()))
+                }
+                take if take == Msg::TakeSnapshot.text() => {
+                    return Ok(ConflictResolution::Exclude(HashSet::new()))
+                }
+                choose if choose == Msg::ChoosePerFile.text() => return self.choose_per_file(),
+                skip if skip == Msg::Skip.text() => return Ok(ConflictResolution::Cancel),
+                _ => {}
+            }
+        }
+    }
+
+    fn all_live_paths(&self) -> HashSet<PathBuf> {
+        self.conflicts
+            .iter()
+            .map(|conflict| conflict.live_path.clone())
+            .collect()
+    }
+
+    fn choose_per_file(&self) -> HttmResult<ConflictResolution> {
+        let paths_buffer: String = self
+            .conflicts
+            .iter()
+            .map(|conflict| format!("{}\n", conflict.live_path.to_string_lossy()))
+            .collect();
+
+        let choose_buffer = format!(
+            "Select each file which should take its snapshot version.  Any file not selected keeps its live version.\n\
+            ─────────────────────────────────────────────────────────────────────────────────────────\n\
+            {paths_buffer}"
+        );
+
+        let view_mode = ViewMode::Restore;
+
+        let selection = view_mode.view_buffer(&choose_buffer, MultiSelect::On)?;
+
+        let take_snapshot: HashSet<&Path> = selection.iter().map(Path::new).collect();
+
+        let excluded = self
+            .conflicts
+            .iter()
+            .map(|conflict| conflict.live_path.as_path())
+            .filter(|live_path| !take_snapshot.contains(live_path))
+            .map(Path::to_path_buf)
+            .collect();
+
+        Ok(ConflictResolution::Exclude(excluded))
+    }
+}
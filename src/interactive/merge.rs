@@ -0,0 +1,337 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::RestoreSnapGuard;
+use crate::data::paths::PathData;
+use crate::display::unified_diff::{DiffSegment, UnifiedDiff};
+use crate::interactive::restore::LiveFileGuard;
+use crate::interactive::view_mode::{MultiSelect, ViewMode};
+use crate::library::audit::AuditLog;
+use crate::library::i18n::{Consent, Msg};
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{create_new_file, exec_user_program, TempCopy};
+use crate::zfs::snap_guard::SnapGuard;
+use crate::GLOBAL_CONFIG;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+enum HunkChoice {
+    Live,
+    Snapshot,
+    Quit,
+}
+
+// merge a snapshot version into its live file, instead of overwriting the live file whole,
+// either via the user's MERGE_TOOL, or, by default, by asking which version to keep of each changed hunk
+pub struct InteractiveMerge<'a> {
+    snap_path: &'a Path,
+    live_path: &'a Path,
+    snap_guard: &'a RestoreSnapGuard,
+}
+
+impl<'a> InteractiveMerge<'a> {
+    pub fn new(snap_path: &'a Path, live_path: &'a Path, snap_guard: &'a RestoreSnapGuard) -> Self {
+        Self {
+            snap_path,
+            live_path,
+            snap_guard,
+        }
+    }
+
+    pub fn exec(&self) -> HttmResult<()> {
+        if !self.snap_path.is_file() {
+            let msg = format!("Path is not a file: {:?}", self.snap_path);
+            return Err(HttmError::new(&msg).into());
+        }
+
+        let snap_bytes = std::fs::read(self.snap_path)?;
+        // a live version which no longer exists is merged as if empty
+        let live_bytes = if PathData::from(self.live_path).opt_metadata().is_some() {
+            std::fs::read(self.live_path)?
+        } else {
+            Vec::new()
+        };
+
+        let (Some(snap_text), Some(live_text)) = (
+            UnifiedDiff::as_text(&snap_bytes),
+            UnifiedDiff::as_text(&live_bytes),
+        ) else {
+            let msg = format!(
+                "httm can only merge text files, and either the snapshot version or the live version is binary: {:?}",
+                self.live_path
+            );
+            return Err(HttmError::new(&msg).into());
+        };
+
+        let opt_merged = match std::env::var("MERGE_TOOL") {
+            Ok(merge_tool) if !merge_tool.trim().is_empty() => {
+                self.merge_with_tool(&merge_tool, &snap_bytes, &live_bytes)?
+            }
+            _ => self.merge_hunks(snap_text, live_text)?,
+        };
+
+        let Some(merged) = opt_merged else {
+            println!("User declined merge of: {:?}", self.snap_path);
+            return Ok(());
+        };
+
+        if merged == live_bytes {
+            println!(
+                "The merge leaves the live file unchanged: {:?}.  Nothing to do.",
+                self.live_path
+            );
+            return Ok(());
+        }
+
+        if GLOBAL_CONFIG.opt_dry_run {
+            println!(
+                "httm would write a merge from snapshot:\n\n\
+                \tsource:\t{:?}\n\
+                \ttarget:\t{:?}\n\n\
+                Dry run.  No files were restored.",
+                self.snap_path, self.live_path
+            );
+            return Ok(());
+        }
+
+        // tell the user what we're up to, and get consent
+        let merge_buffer = format!(
+            "httm will write the merge from snapshot to the live file:\n\n\
+            \tsource:\t{:?}\n\
+            \ttarget:\t{:?}\n\n\
+            {}\n\
+            ─────────────────────────────────────────────────────────────────────────────────────────\n\
+            {}",
+            self.snap_path,
+            self.live_path,
+            Msg::RestoreConsent.text(),
+            Consent::choices()
+        );
+
+        // loop until user consents or doesn't
+        loop {
+            let view_mode = ViewMode::Restore;
+
+            let selection = view_mode.view_buffer(&merge_buffer, MultiSelect::Off)?;
+
+            let user_consent = selection
+                .get(0)
+                .ok_or_else(|| HttmError::new("Could not obtain the first match selected."))?;
+
+            match Consent::from(user_consent.as_str()) {
+                Consent::Yes => break,
+                Consent::No => {
+                    println!("User declined merge of: {:?}", self.snap_path);
+                    return Ok(());
+                }
+                // if not yes or no, then noop and continue to the next iter of loop
+                Consent::Undecided => {}
+            }
+        }
+
+        LiveFileGuard::verify(self.live_path)?;
+
+        let opt_snap_guard = match self.snap_guard {
+            RestoreSnapGuard::Guarded => Some(SnapGuard::try_from(self.live_path)?),
+            RestoreSnapGuard::NotGuarded => None,
+        };
+
+        AuditLog::before_restore(self.live_path);
+
+        let res = self.write_merged(&merged);
+
+        AuditLog::restore(self.snap_path, self.live_path, &res);
+
+        if let Err(err) = res {
+            let Some(snap_guard) = opt_snap_guard else {
+                return Err(err);
+            };
+
+            let msg = format!(
+                "httm merge failed for the following reason: {}.\n\
+                Attempting roll back to precautionary pre-execution snapshot.",
+                err
+            );
+
+            eprintln!("{}", msg);
+
+            snap_guard
+                .rollback()
+                .map(|_| println!("Rollback succeeded."))?;
+
+            return Err(HttmError::new(
+                "httm merge failed, and was rolled back to the precautionary pre-execution snapshot.",
+            )
+            .into());
+        }
+
+        println!(
+            "httm merged from snapshot:\n\n\
+            \tsource:\t{:?}\n\
+            \ttarget:\t{:?}\n\n\
+            Merge completed successfully.",
+            self.snap_path, self.live_path
+        );
+
+        Ok(())
+    }
+
+    // the merge is written in place, after any precautionary snapshot is taken, so the live file keeps
+    // its inode, and with it, its ownership, xattrs, ACLs, and any hard links
+    fn write_merged(&self, merged: &[u8]) -> HttmResult<()> {
+        // a live version which no longer exists is created anew, and is never opened through a symlink
+        let mut file = if PathData::from(self.live_path).opt_metadata().is_some() {
+            OpenOptions::new()
+                .write(true)
+                .truncate(true)
+                .custom_flags(nix::fcntl::OFlag::O_NOFOLLOW.bits())
+                .open(self.live_path)?
+        } else {
+            create_new_file(self.live_path, 0o644)?
+        };
+
+        file.write_all(merged)?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+
+    // the merge tool edits a working copy of the live file, beside a read-only copy of the snapshot version,
+    // so nothing is written to the live file until the user consents to the result
+    fn merge_with_tool(
+        &self,
+        merge_tool: &str,
+        snap_bytes: &[u8],
+        live_bytes: &[u8],
+    ) -> HttmResult<Option<Vec<u8>>> {
+        let Some(file_name) = self.live_path.file_name() else {
+            let msg = format!("Could not determine a file name for: {:?}", self.live_path);
+            return Err(HttmError::new(&msg).into());
+        };
+
+        let working_copy = TempCopy::new(file_name, live_bytes, false)?;
+        let snap_copy = TempCopy::new(file_name, snap_bytes, true)?;
+
+        exec_user_program(merge_tool, &[working_copy.path(), snap_copy.path()])?;
+
+        Ok(Some(std::fs::read(working_copy.path())?))
+    }
+
+    // None, where the user quits before choosing a version of every hunk
+    fn merge_hunks(&self, snap_text: &str, live_text: &str) -> HttmResult<Option<Vec<u8>>> {
+        let segments = UnifiedDiff::segments(snap_text, live_text);
+
+        let num_hunks = segments
+            .iter()
+            .filter(|segment| matches!(segment, DiffSegment::Changed { .. }))
+            .count();
+
+        let mut hunk_num = 0usize;
+        let mut merged_lines: Vec<&str> = Vec::new();
+
+        for segment in segments.iter() {
+            match segment {
+                DiffSegment::Common(lines) => merged_lines.extend(lines),
+                DiffSegment::Changed {
+                    old_lines,
+                    new_lines,
+                } => {
+                    hunk_num += 1;
+
+                    match self.ask_hunk(hunk_num, num_hunks, old_lines, new_lines)? {
+                        HunkChoice::Live => merged_lines.extend(new_lines),
+                        HunkChoice::Snapshot => merged_lines.extend(old_lines),
+                        HunkChoice::Quit => return Ok(None),
+                    }
+                }
+            }
+        }
+
+        // lines are split without their line endings, so restore the live file's line endings,
+        // or, if the live file is empty, the snapshot version's
+        let model_text = if live_text.is_empty() {
+            snap_text
+        } else {
+            live_text
+        };
+
+        let line_ending = if model_text.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+
+        let mut merged = merged_lines.join(line_ending);
+
+        if !merged.is_empty() && model_text.ends_with('\n') {
+            merged += line_ending;
+        }
+
+        Ok(Some(merged.into_bytes()))
+    }
+
+    fn ask_hunk(
+        &self,
+        hunk_num: usize,
+        num_hunks: usize,
+        snap_lines: &[&str],
+        live_lines: &[&str],
+    ) -> HttmResult<HunkChoice> {
+        let hunk_buffer: String = snap_lines
+            .iter()
+            .map(|line| format!("-{line}\n"))
+            .chain(live_lines.iter().map(|line| format!("+{line}\n")))
+            .collect();
+
+        let ask_buffer = format!(
+            "Changed hunk {hunk_num} of {num_hunks}, as between the snapshot version (-) and the live version (+):\n\n\
+            \tsource:\t{:?}\n\
+            \ttarget:\t{:?}\n\n\
+            {hunk_buffer}\n\
+            Which version of this hunk should httm keep?\n\
+            ─────────────────────────────────────────────────────────────────────────────────────────\n\
+            {}\n\
+            {}\n\
+            {}",
+            self.snap_path,
+            self.live_path,
+            Msg::HunkLive.text(),
+            Msg::HunkSnapshot.text(),
+            Msg::HunkQuit.text()
+        );
+
+        // loop until user makes a valid selection
+        loop {
+            let view_mode = ViewMode::Restore;
+
+            let selection = view_mode.view_buffer(&ask_buffer, MultiSelect::Off)?;
+
+            let user_selection = selection
+                .get(0)
+                .ok_or_else(|| HttmError::new("Could not obtain the first match selected."))?;
+
+            match user_selection.as_str() {
+                live if live == Msg::HunkLive.text() => return Ok(HunkChoice::Live),
+                snap if snap == Msg::HunkSnapshot.text() => return Ok(HunkChoice::Snapshot),
+                quit if quit == Msg::HunkQuit.text() => return Ok(HunkChoice::Quit),
+                _ => {}
+            }
+        }
+    }
+}
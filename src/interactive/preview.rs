@@ -15,15 +15,37 @@
 // For the full copyright and license information, please view the LICENSE file
 // that was distributed with this source code.
 
+use crate::data::paths::PathData;
+use crate::display::snap_info::SnapInfo;
 use crate::interactive::view_mode::ViewMode;
 use crate::library::results::{HttmError, HttmResult};
+use crate::library::shell::{ShellKind, UserShell};
+use crate::library::utility::{print_output_buf, split_snap_path};
 use crate::GLOBAL_CONFIG;
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command as ExecProcess, Stdio};
+use std::time::{Duration, Instant};
 use which::which;
 
+const PREVIEW_POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+// variables which a user defined preview command may use, see PREVIEW
+const PREVIEW_VARIABLES: [&str; 5] = [
+    "snap_file",
+    "live_file",
+    "snap_name",
+    "snap_time",
+    "dataset",
+];
+
+// variables which describe the snapshot itself, and must be looked up per snapshot file, see PreviewVariables
+const SNAPSHOT_VARIABLES: [&str; 3] = ["snap_name", "snap_time", "dataset"];
+
 pub struct PreviewSelection {
     pub opt_preview_window: Option<String>,
     pub opt_preview_command: Option<String>,
+    pub opt_preview_exec: Option<PreviewExec>,
 }
 
 impl PreviewSelection {
@@ -37,36 +59,70 @@ impl PreviewSelection {
                     unreachable!()
                 };
 
-                let opt_preview_command = Some(Self::parse_preview_command(
-                    defined_command,
-                    opt_live_version,
-                )?);
+                let (preview_command, opt_preview_exec) =
+                    Self::parse_preview_command(defined_command, opt_live_version)?;
+
+                let preview_window = if GLOBAL_CONFIG.opt_preview_wrap {
+                    "up:50%:wrap"
+                } else {
+                    "up:50%"
+                };
 
                 PreviewSelection {
-                    opt_preview_window: Some("up:50%".to_owned()),
-                    opt_preview_command,
+                    opt_preview_window: Some(preview_window.to_owned()),
+                    opt_preview_command: Some(preview_command),
+                    opt_preview_exec,
                 }
             }
             _ => PreviewSelection {
                 opt_preview_window: Some(String::new()),
                 opt_preview_command: None,
+                opt_preview_exec: None,
             },
         };
 
         Ok(res)
     }
 
+    // the command for the interactive view to execute, and, unless PREVIEW_SHELL is specified,
+    // the command which that command, in turn, executes directly, see PreviewExec
     fn parse_preview_command(
         defined_command: &str,
         opt_live_version: &Option<String>,
-    ) -> HttmResult<String> {
-        let command = if defined_command == "default" {
+    ) -> HttmResult<(String, Option<PreviewExec>)> {
+        // our own previews need no bootstrap script, and therefore no 'bash' nor 'cut', see PREVIEW_RENDER
+        if defined_command == "builtin" {
+            let httm_exe = std::env::current_exe()?;
+
+            let command = format!("\"{}\" --preview-render {{}}", httm_exe.display());
+
+            let command = match &GLOBAL_CONFIG.opt_preview_pager {
+                Some(pager) => format!("{command} | {pager}"),
+                None => command,
+            };
+
+            return Ok((command, None));
+        }
+
+        let template = if defined_command == "default" {
             match opt_live_version {
                 Some(live_version) if PathBuf::from(live_version).exists() && which("bowie").is_ok() => {
-                    format!("bowie --direct \"$snap_file\" \"{live_version}\"")
+                    "bowie --direct \"$snap_file\" \"$live_file\"".to_owned()
                 },
                 _ => match which("cat") {
-                    Ok(_) => "if [[ -s \"$snap_file\" ]]; then cat \"$snap_file\"; else printf \"WARN: \"$snap_file\" is empty\"; fi".to_string(),
+                    Ok(_) => {
+                        let cat_command = if GLOBAL_CONFIG.opt_preview_line_numbers {
+                            "cat -n"
+                        } else {
+                            "cat"
+                        };
+
+                        if GLOBAL_CONFIG.opt_preview_shell {
+                            format!("if [ -s \"$snap_file\" ]; then {cat_command} \"$snap_file\"; else printf \"WARN: \"$snap_file\" is empty\"; fi")
+                        } else {
+                            format!("{cat_command} \"$snap_file\"")
+                        }
+                    },
                     Err(_) => {
                         return Err(HttmError::new(
                             "'cat' executable could not be found in the user's PATH. 'cat' is necessary for executing a bare preview command.",
@@ -75,6 +131,22 @@ impl PreviewSelection {
                     }
                 },
             }
+        } else if defined_command == "diff" {
+            // use this very executable to diff, see the hidden DIFF_PAIR arg
+            let httm_exe = std::env::current_exe()?;
+
+            match opt_live_version {
+                Some(_live_version) => format!(
+                    "{} --diff-pair \"$snap_file\" \"$live_file\"",
+                    ShellKind::Posix.quote(&httm_exe.to_string_lossy())
+                ),
+                None => {
+                    return Err(HttmError::new(
+                        "A live version is required to diff against, but a live version could not be determined.",
+                    )
+                    .into())
+                }
+            }
         } else {
             match defined_command.split_ascii_whitespace().next() {
                 Some(potential_executable) => {
@@ -90,38 +162,141 @@ impl PreviewSelection {
                 }
             }
 
-            let parsed_command = match opt_live_version {
-                Some(live_version) if defined_command.contains("{live_file}") && !PathBuf::from(live_version).exists() => {
+            // the older "{snap_file}" and "{live_file}" forms are simply the variables by another name
+            let parsed_command = defined_command
+                .replace("{snap_file}", "$snap_file")
+                .replace("{live_file}", "$live_file");
+
+            match opt_live_version {
+                Some(live_version) if Self::is_referenced(&parsed_command, "live_file") && !PathBuf::from(live_version).exists() => {
                     return Err(HttmError::new("User specified a preview variable for a live version, but a live version for the file selected does not exist.").into())
                 },
-                Some(live_version) => {
-                    defined_command
-                        .replace("{snap_file}", "\"$snap_file\"")
-                        .replace("{live_file}", format!("\"{live_version}\"").as_str())
-                },
-                None if defined_command.contains("{live_file}") => {
+                None if Self::is_referenced(&parsed_command, "live_file") => {
                     return Err(HttmError::new("User specified a preview variable for a live version, but a live version could not be determined.").into())
                 },
-                None => {
-                    defined_command
-                        .replace("{snap_file}", "\"$snap_file\"")
-                },
-            };
+                _ => {}
+            }
 
             // protect ourselves from command like cat
             // just waiting on stdin by appending the snap file
-            if parsed_command.contains("\"$snap_file\"") {
+            if Self::is_referenced(&parsed_command, "snap_file") {
                 parsed_command
             } else {
-                [defined_command, " \"$snap_file\""].into_iter().collect()
+                format!("{parsed_command} \"$snap_file\"")
             }
         };
 
+        // forward line numbers to the user's command, for instance, as flags to 'bat'
+        let line_numbers_flag = if GLOBAL_CONFIG.opt_preview_line_numbers {
+            "--number"
+        } else {
+            ""
+        };
+
+        let template = template.replace("{line_numbers}", line_numbers_flag);
+
+        if GLOBAL_CONFIG.opt_preview_shell {
+            // our own commands are written for a POSIX sh, whatever the user's shell
+            let user_shell = if matches!(defined_command, "default" | "diff") {
+                UserShell::posix()?
+            } else {
+                UserShell::new()?
+            };
+
+            let script = Self::shell_script(&template, opt_live_version, &user_shell)?;
+
+            return Ok((script, None));
+        }
+
+        // the interactive view executes its preview command within a shell, so here the only words that shell parses
+        // are those we have quoted, and this very executable, not any shell, executes the user's command, see PreviewExec
+        let httm_exe = std::env::current_exe()?;
+
+        let command = format!(
+            "{} --preview-timeout={} --preview-exec {} {} {{}}",
+            ShellKind::Posix.quote(&httm_exe.to_string_lossy()),
+            GLOBAL_CONFIG.preview_timeout.as_secs(),
+            ShellKind::Posix.quote(&template),
+            ShellKind::Posix.quote(opt_live_version.as_deref().unwrap_or_default())
+        );
+
+        let command = if GLOBAL_CONFIG.opt_preview_line_numbers {
+            format!("HTTM_PREVIEW_LINE_NUMBERS=1 {command}")
+        } else {
+            command
+        };
+
+        let command = match &GLOBAL_CONFIG.opt_preview_pager {
+            Some(pager) => format!("{command} | {pager}"),
+            None => command,
+        };
+
+        Ok((
+            command,
+            Some(PreviewExec::new(template, opt_live_version.clone())),
+        ))
+    }
+
+    // the bootstrap script is a POSIX sh script, but the user's command is executed by the user's own shell, see UserShell
+    fn shell_script(
+        template: &str,
+        opt_live_version: &Option<String>,
+        user_shell: &UserShell,
+    ) -> HttmResult<String> {
+        let user_command = Self::quote_variables(template, user_shell.kind);
+
+        // the live file is known now, so assign it in the user's shell's own syntax, but the other variables
+        // are only known once the bootstrap script runs, so the bootstrap script exports those
+        let user_command = match opt_live_version {
+            Some(live_version) if Self::is_referenced(&user_command, "live_file") => format!(
+                "{} {user_command}",
+                user_shell.kind.assignment("live_file", live_version)
+            ),
+            _ => user_command,
+        };
+
+        let mut exported: Vec<&str> = vec!["snap_file"];
+        let mut command = String::new();
+
+        if SNAPSHOT_VARIABLES
+            .iter()
+            .any(|name| Self::is_referenced(&user_command, name))
+        {
+            let httm_exe = std::env::current_exe()?;
+
+            command += &format!(
+                "eval \"$({} --preview-variables \"$snap_file\")\"; ",
+                ShellKind::Posix.quote(&httm_exe.to_string_lossy())
+            );
+
+            exported.extend(SNAPSHOT_VARIABLES);
+        }
+
+        command += &format!(
+            "export {}; {} -c {}",
+            exported.join(" "),
+            ShellKind::Posix.quote(&user_shell.path.to_string_lossy()),
+            ShellKind::Posix.quote(&user_command)
+        );
+
+        let command = if GLOBAL_CONFIG.opt_preview_line_numbers {
+            format!("export HTTM_PREVIEW_LINE_NUMBERS=1; {command}")
+        } else {
+            command
+        };
+
+        let command = match &GLOBAL_CONFIG.opt_preview_pager {
+            Some(pager) => format!("{{ {command}; }} | {pager}"),
+            None => command,
+        };
+
         match which("cut") {
             Ok(_) => {
                 let script = include_str!("../../scripts/preview-bootstrap.bash");
 
-                let res = script.replace("{command}", &command);
+                let res = script
+                    .replace("{timeout}", &GLOBAL_CONFIG.preview_timeout.as_secs().to_string())
+                    .replace("{command}", &command);
 
                 Ok(res)
             }
@@ -132,4 +307,433 @@ impl PreviewSelection {
             }
         }
     }
+
+    // double quote each preview variable the user has not already quoted, so that, for instance,
+    // "diff -u $snap_file $live_file" never splits a path which contains whitespace
+    fn quote_variables(command: &str, shell_kind: ShellKind) -> String {
+        let mut quoted = String::with_capacity(command.len());
+        let mut is_single_quoted = false;
+        let mut is_double_quoted = false;
+        let mut chars = command.char_indices();
+
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '\\' if !is_single_quoted || shell_kind.is_escaped_within_single_quotes() => {
+                    quoted.push(c);
+
+                    if let Some((_idx, escaped)) = chars.next() {
+                        quoted.push(escaped);
+                    }
+                }
+                '\'' if !is_double_quoted => {
+                    is_single_quoted = !is_single_quoted;
+                    quoted.push(c);
+                }
+                '"' if !is_single_quoted => {
+                    is_double_quoted = !is_double_quoted;
+                    quoted.push(c);
+                }
+                '$' if !is_single_quoted => match Self::variable_at(&command[idx..]) {
+                    Some((name, len)) => {
+                        if is_double_quoted {
+                            quoted += &shell_kind.variable(name);
+                        } else {
+                            quoted += &shell_kind.quoted_variable(name);
+                        }
+
+                        // variable names are ASCII, so each remaining byte is a char
+                        chars.nth(len - 2);
+                    }
+                    None => quoted.push(c),
+                },
+                _ => quoted.push(c),
+            }
+        }
+
+        quoted
+    }
+
+    // the preview variable, like "$live_file" or "${live_file}", at the start of the text, and its length
+    fn variable_at(text: &str) -> Option<(&'static str, usize)> {
+        PREVIEW_VARIABLES.iter().find_map(|name| {
+            let braced = format!("${{{name}}}");
+
+            if text.starts_with(&braced) {
+                return Some((*name, braced.len()));
+            }
+
+            let bare = format!("${name}");
+            let rest = text.strip_prefix(&bare)?;
+
+            // "$dataset" should not match "$datasets"
+            let is_whole_name = !rest.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_');
+
+            is_whole_name.then_some((*name, bare.len()))
+        })
+    }
+
+    fn is_referenced(command: &str, name: &str) -> bool {
+        command.match_indices('$').any(|(idx, _)| {
+            Self::variable_at(&command[idx..]).is_some_and(|(found, _len)| found == name)
+        })
+    }
+}
+
+// the preview variables which describe the snapshot which contains a snapshot file, printed as shell
+// assignments, for a preview command to eval, see the hidden PREVIEW_VARIABLES arg
+pub struct PreviewVariables;
+
+impl PreviewVariables {
+    pub fn exec(snap_file: &Path) -> HttmResult<()> {
+        let output_buf: String = Self::values(snap_file)
+            .iter()
+            .map(|(name, value)| format!("{name}={}\n", ShellKind::Posix.quote(value)))
+            .collect();
+
+        print_output_buf(&output_buf)
+    }
+
+    fn values(snap_file: &Path) -> [(&'static str, String); 3] {
+        let opt_snap_info = SnapInfo::new(&PathData::from(snap_file));
+
+        // every variable is always assigned, if only to an empty value, as the bootstrap script
+        // treats an unassigned variable as an error
+        let snap_name = opt_snap_info
+            .as_ref()
+            .map(|snap_info| snap_info.name.clone())
+            .unwrap_or_default();
+
+        let snap_time = opt_snap_info
+            .as_ref()
+            .map(|snap_info| snap_info.creation_timestamp())
+            .unwrap_or_default();
+
+        let dataset = split_snap_path(snap_file)
+            .map(|(_snap_mount, dataset, _relative)| dataset.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        [
+            ("snap_name", snap_name),
+            ("snap_time", snap_time),
+            ("dataset", dataset),
+        ]
+    }
+}
+
+// executes a preview command directly, without any shell, by splitting the command into arguments, as a shell would,
+// and substituting each preview variable within those arguments, so no value, like a path, is ever itself reparsed
+pub struct PreviewExec {
+    template: String,
+    opt_live_file: Option<String>,
+}
+
+impl PreviewExec {
+    pub fn new(template: String, opt_live_file: Option<String>) -> Self {
+        Self {
+            template,
+            opt_live_file,
+        }
+    }
+
+    // the raw input is the selection line, as given to us by the interactive view, see PREVIEW_EXEC
+    pub fn exec(template: &str, live_file: &str, raw_input: &str) -> HttmResult<()> {
+        // like the bootstrap script, the border lines of the selection view have nothing to preview
+        if raw_input.starts_with('─') && raw_input.ends_with('─') {
+            return print_output_buf("--\n");
+        }
+
+        let snap_file = Self::quoted_path(raw_input)
+            .ok_or_else(|| HttmError::new("Snap file path is empty."))?;
+
+        let opt_live_file = Some(live_file.to_owned()).filter(|live_file| !live_file.is_empty());
+
+        let (stdout_buf, stderr_buf) =
+            Self::new(template.to_owned(), opt_live_file).output(Path::new(snap_file))?;
+
+        // like the bootstrap script, display whatever the command writes to stderr too
+        print_output_buf(&(stdout_buf + &stderr_buf))
+    }
+
+    // the path is quoted within the selection line, and may itself contain quotes, so we take
+    // everything between the first quote and the last
+    fn quoted_path(raw_input: &str) -> Option<&str> {
+        let start = raw_input.find('"')? + 1;
+        let end = raw_input.rfind('"')?;
+
+        raw_input
+            .get(start..end)
+            .filter(|snap_file| !snap_file.is_empty())
+    }
+
+    pub fn output(&self, snap_file: &Path) -> HttmResult<(String, String)> {
+        let mut command = self.command(snap_file)?;
+
+        if GLOBAL_CONFIG.opt_preview_line_numbers {
+            command.env("HTTM_PREVIEW_LINE_NUMBERS", "1");
+        }
+
+        output_with_timeout(&mut command, snap_file)
+    }
+
+    fn command(&self, snap_file: &Path) -> HttmResult<ExecProcess> {
+        let snap_file_string = snap_file.to_string_lossy();
+
+        // only look up the snapshot variables when the command refers to them
+        let snapshot_values = if SNAPSHOT_VARIABLES
+            .iter()
+            .any(|name| PreviewSelection::is_referenced(&self.template, name))
+        {
+            PreviewVariables::values(snap_file).to_vec()
+        } else {
+            Vec::new()
+        };
+
+        let value_of = |name: &str| -> String {
+            match name {
+                "snap_file" => snap_file_string.to_string(),
+                "live_file" => self.opt_live_file.clone().unwrap_or_default(),
+                _ => snapshot_values
+                    .iter()
+                    .find(|(snapshot_name, _value)| *snapshot_name == name)
+                    .map(|(_name, value)| value.clone())
+                    .unwrap_or_default(),
+            }
+        };
+
+        let args = Self::split_args(&self.template, value_of)?;
+
+        let Some((program, args)) = args.split_first() else {
+            return Err(HttmError::new(
+                "httm could not determine a valid preview command from user's input.",
+            )
+            .into());
+        };
+
+        let mut command = ExecProcess::new(program);
+
+        command.args(args).stdin(Stdio::null());
+
+        Ok(command)
+    }
+
+    // split on whitespace outside of quotes, as a shell would, respecting quotes and backslash escapes,
+    // and substitute each preview variable, outside of single quotes, within the argument where it appears
+    fn split_args(template: &str, value_of: impl Fn(&str) -> String) -> HttmResult<Vec<String>> {
+        let mut args: Vec<String> = Vec::new();
+        // None between arguments, as opposed to an empty argument, like ""
+        let mut opt_arg: Option<String> = None;
+        let mut is_single_quoted = false;
+        let mut is_double_quoted = false;
+        let mut chars = template.char_indices();
+
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '\\' if !is_single_quoted => {
+                    if let Some((_idx, escaped)) = chars.next() {
+                        opt_arg.get_or_insert_with(String::new).push(escaped);
+                    }
+                }
+                '\'' if !is_double_quoted => {
+                    is_single_quoted = !is_single_quoted;
+                    opt_arg.get_or_insert_with(String::new);
+                }
+                '"' if !is_single_quoted => {
+                    is_double_quoted = !is_double_quoted;
+                    opt_arg.get_or_insert_with(String::new);
+                }
+                '$' if !is_single_quoted => match PreviewSelection::variable_at(&template[idx..]) {
+                    Some((name, len)) => {
+                        opt_arg
+                            .get_or_insert_with(String::new)
+                            .push_str(&value_of(name));
+
+                        // variable names are ASCII, so each remaining byte is a char
+                        chars.nth(len - 2);
+                    }
+                    None => opt_arg.get_or_insert_with(String::new).push(c),
+                },
+                _ if c.is_whitespace() && !is_single_quoted && !is_double_quoted => {
+                    if let Some(arg) = opt_arg.take() {
+                        args.push(arg);
+                    }
+                }
+                _ => opt_arg.get_or_insert_with(String::new).push(c),
+            }
+        }
+
+        if is_single_quoted || is_double_quoted {
+            return Err(HttmError::new(
+                "httm could not parse the preview command, as it contains an unterminated quote.",
+            )
+            .into());
+        }
+
+        args.extend(opt_arg);
+
+        Ok(args)
+    }
+}
+
+// execute a preview command, and kill it, should it outlive PREVIEW_TIMEOUT, so a runaway preview command
+// never hangs httm, returning what the command wrote to its stdout and its stderr
+pub fn output_with_timeout(
+    command: &mut ExecProcess,
+    snap_path: &Path,
+) -> HttmResult<(String, String)> {
+    let mut spawned = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // read output on other threads, so a full pipe can never block the child
+    // from exiting, while we wait here for it to exit, or time out
+    // a preview of a binary file may well not be valid UTF-8, which we must still display
+    let read_to_string = |mut reader: Box<dyn Read + Send>| {
+        std::thread::spawn(move || {
+            let mut output_buf = Vec::new();
+            let _ = reader.read_to_end(&mut output_buf);
+            String::from_utf8_lossy(&output_buf).into_owned()
+        })
+    };
+
+    let opt_stdout_handle = spawned
+        .stdout
+        .take()
+        .map(|stdout| read_to_string(Box::new(stdout)));
+    let opt_stderr_handle = spawned
+        .stderr
+        .take()
+        .map(|stderr| read_to_string(Box::new(stderr)));
+
+    let deadline = Instant::now() + GLOBAL_CONFIG.preview_timeout;
+
+    while spawned.try_wait()?.is_none() {
+        if Instant::now() >= deadline {
+            let _ = spawned.kill();
+            let _ = spawned.wait();
+
+            let msg = format!(
+                "Preview command timed out after {} seconds for path: {:?}",
+                GLOBAL_CONFIG.preview_timeout.as_secs(),
+                snap_path
+            );
+            return Err(HttmError::new(&msg).into());
+        }
+
+        std::thread::sleep(PREVIEW_POLL_INTERVAL);
+    }
+
+    let join = |opt_handle: Option<std::thread::JoinHandle<String>>| {
+        opt_handle
+            .and_then(|handle| handle.join().ok())
+            .unwrap_or_default()
+    };
+
+    Ok((join(opt_stdout_handle), join(opt_stderr_handle)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_path_between_first_and_last_quote() {
+        assert_eq!(
+            PreviewExec::quoted_path(
+                r#"Jan 01 2024 10:00:00  1.2 KiB  "/srv/a "quoted" file.txt""#
+            ),
+            Some(r#"/srv/a "quoted" file.txt"#)
+        );
+        assert_eq!(
+            PreviewExec::quoted_path(r#"Jan 01 2024  "/srv/file.txt""#),
+            Some("/srv/file.txt")
+        );
+        assert_eq!(PreviewExec::quoted_path(r#"Jan 01 2024  """#), None);
+        assert_eq!(PreviewExec::quoted_path(r#"Jan 01 2024  "/srv"#), None);
+        assert_eq!(PreviewExec::quoted_path("Jan 01 2024"), None);
+    }
+
+    #[test]
+    fn quote_posix_variables() {
+        let quote = |command| PreviewSelection::quote_variables(command, ShellKind::Posix);
+
+        assert_eq!(
+            quote("diff -u $snap_file ${live_file}"),
+            "diff -u \"${snap_file}\" \"${live_file}\""
+        );
+        // already double quoted, or followed by other characters
+        assert_eq!(quote("cat \"$snap_file.bak\""), "cat \"${snap_file}.bak\"");
+        // never within single quotes, nor when escaped
+        assert_eq!(quote("echo '$snap_file'"), "echo '$snap_file'");
+        assert_eq!(quote("echo \\$snap_file"), "echo \\$snap_file");
+        // a backslash is no escape within single quotes, so the quote ends before the variable
+        assert_eq!(
+            quote("echo 'it\\'s $snap_file'"),
+            "echo 'it\\'s \"${snap_file}\"'"
+        );
+        // only whole variable names
+        assert_eq!(quote("echo $datasets $HOME"), "echo $datasets $HOME");
+    }
+
+    #[test]
+    fn quote_fish_variables() {
+        let quote = |command| PreviewSelection::quote_variables(command, ShellKind::Fish);
+
+        assert_eq!(
+            quote("diff -u $snap_file ${live_file}"),
+            "diff -u \"$snap_file\" \"$live_file\""
+        );
+        assert_eq!(
+            quote("cat \"${snap_file}.bak\""),
+            "cat \"\"$snap_file\".bak\""
+        );
+        // fish, unlike a POSIX sh, escapes a single quote within single quotes
+        assert_eq!(
+            quote("echo 'it\\'s $snap_file'"),
+            "echo 'it\\'s $snap_file'"
+        );
+    }
+
+    fn value_of(name: &str) -> String {
+        match name {
+            "snap_file" => "/pool/.zfs/snapshot/snap 1/my file".to_string(),
+            "live_file" => "/pool/it's \"quoted\"".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    #[test]
+    fn split_args_with_variables() {
+        assert_eq!(
+            PreviewExec::split_args("diff -u $snap_file \"${live_file}\"", value_of).unwrap(),
+            vec![
+                "diff",
+                "-u",
+                "/pool/.zfs/snapshot/snap 1/my file",
+                "/pool/it's \"quoted\""
+            ]
+        );
+        assert_eq!(
+            PreviewExec::split_args("cat $snap_file.bak", value_of).unwrap(),
+            vec!["cat", "/pool/.zfs/snapshot/snap 1/my file.bak"]
+        );
+        assert_eq!(
+            PreviewExec::split_args("echo '$snap_file'", value_of).unwrap(),
+            vec!["echo", "$snap_file"]
+        );
+    }
+
+    #[test]
+    fn split_args_with_quotes_and_backslashes() {
+        assert_eq!(
+            PreviewExec::split_args("echo 'a b'  \"c d\" e\\ f \"\"", value_of).unwrap(),
+            vec!["echo", "a b", "c d", "e f", ""]
+        );
+        assert_eq!(
+            PreviewExec::split_args("echo \"a \\\"b\\\"\" 'c\\d'", value_of).unwrap(),
+            vec!["echo", "a \"b\"", "c\\d"]
+        );
+        assert!(PreviewExec::split_args("echo \"unterminated", value_of).is_err());
+        assert!(PreviewExec::split_args("echo 'unterminated", value_of).is_err());
+    }
 }
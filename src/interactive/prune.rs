@@ -16,11 +16,19 @@
 // that was distributed with this source code.
 
 use crate::config::generate::ListSnapsFilters;
+use crate::data::paths::{PathData, PathDeconstruction};
 use crate::interactive::view_mode::{MultiSelect, ViewMode};
+use crate::library::i18n::{Consent, Msg};
 use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{date_string, DateFormat};
 use crate::lookup::snap_names::SnapNameMap;
 use crate::lookup::versions::VersionsMap;
-use crate::zfs::run_command::RunZFSCommand;
+use crate::zfs::run_command::{RunZFSCommand, RunZpoolCommand};
+use crate::GLOBAL_CONFIG;
+use hashbrown::HashSet;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader};
+use std::time::{Duration, SystemTime};
 
 pub struct PruneSnaps;
 
@@ -29,7 +37,15 @@ impl PruneSnaps {
         versions_map: VersionsMap,
         opt_filters: &Option<ListSnapsFilters>,
     ) -> HttmResult<()> {
-        let snap_name_map: SnapNameMap = SnapNameMap::new(versions_map, opt_filters)?;
+        let snap_name_map: SnapNameMap = SnapNameMap::new(versions_map.clone(), opt_filters)?;
+
+        let snap_name_map = match opt_filters
+            .as_ref()
+            .and_then(|filters| filters.opt_older_than)
+        {
+            Some(older_than) => Self::older_than(snap_name_map, older_than)?,
+            None => snap_name_map,
+        };
 
         let select_mode = if let Some(filters) = opt_filters {
             filters.select_mode
@@ -37,21 +53,101 @@ impl PruneSnaps {
             false
         };
 
-        InteractivePrune::new(&snap_name_map, select_mode)
+        InteractivePrune::new(&versions_map, &snap_name_map, select_mode)
     }
 
-    fn prune(snap_name_map: &SnapNameMap) -> HttmResult<()> {
+    // only those snapshots created before the requested age, where a snapshot whose creation time
+    // cannot be determined is never pruned
+    fn older_than(snap_name_map: SnapNameMap, older_than: Duration) -> HttmResult<SnapNameMap> {
+        let cutoff = SystemTime::now()
+            .checked_sub(older_than)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
         let snapshot_names: Vec<String> = snap_name_map.values().flatten().cloned().collect();
 
+        let creation_times = RunZFSCommand::new()?.creation_times(&snapshot_names)?;
+
+        let inner: BTreeMap<PathData, Vec<String>> = snap_name_map
+            .iter()
+            .map(|(pathdata, snap_names)| {
+                let older: Vec<String> = snap_names
+                    .iter()
+                    .filter(|name| {
+                        creation_times
+                            .get(name.as_str())
+                            .map_or(false, |creation| creation < &cutoff)
+                    })
+                    .cloned()
+                    .collect();
+
+                (pathdata.clone(), older)
+            })
+            .filter(|(_pathdata, snap_names)| !snap_names.is_empty())
+            .collect();
+
+        if inner.is_empty() {
+            return Err(HttmError::new(
+                "No snapshot related to the file/s specified is older than the age specified. Quitting.",
+            )
+            .into());
+        }
+
+        Ok(inner.into())
+    }
+
+    // versions of the input files which exist only on the snapshots to be destroyed, and so
+    // would disappear entirely, as a version which also exists live, or on a snapshot to be kept, remains
+    fn disappearing_versions(
+        versions_map: &VersionsMap,
+        snap_names: &HashSet<&str>,
+    ) -> Vec<PathData> {
+        versions_map
+            .iter()
+            .flat_map(|(pathdata, snaps)| {
+                let opt_proximate_dataset = pathdata.proximate_dataset().ok();
+                let fs_type = pathdata.fs_type(opt_proximate_dataset);
+
+                let (destroyed, kept): (Vec<&PathData>, Vec<&PathData>) =
+                    snaps.iter().partition(|snap_pd| {
+                        SnapNameMap::snap_name(snap_pd, &fs_type, opt_proximate_dataset)
+                            .map_or(false, |name| {
+                                snap_names.contains(name.to_string_lossy().as_ref())
+                            })
+                    });
+
+                let mut disappearing: Vec<PathData> = destroyed
+                    .into_iter()
+                    .filter(|snap_pd| snap_pd.opt_metadata() != pathdata.opt_metadata())
+                    .filter(|snap_pd| {
+                        !kept
+                            .iter()
+                            .any(|kept_pd| kept_pd.opt_metadata() == snap_pd.opt_metadata())
+                    })
+                    .cloned()
+                    .collect();
+
+                // the same version may reside on more than one snapshot to be destroyed
+                disappearing.dedup_by_key(|snap_pd| snap_pd.opt_metadata());
+
+                disappearing
+            })
+            .collect()
+    }
+
+    fn prune(snapshot_names: &[String]) -> HttmResult<()> {
         let run_zfs = RunZFSCommand::new()?;
-        run_zfs.prune(&snapshot_names)
+        run_zfs.prune(snapshot_names)
     }
 }
 
 struct InteractivePrune;
 
 impl InteractivePrune {
-    fn new(snap_name_map: &SnapNameMap, select_mode: bool) -> HttmResult<()> {
+    fn new(
+        versions_map: &VersionsMap,
+        snap_name_map: &SnapNameMap,
+        select_mode: bool,
+    ) -> HttmResult<()> {
         let file_names_string: String =
             snap_name_map.keys().fold(String::new(), |mut buffer, key| {
                 buffer += format!("{:?}\n", key.path()).as_str();
@@ -67,26 +163,84 @@ impl InteractivePrune {
             let view_mode = ViewMode::Select(None);
             view_mode.view_buffer(&buffer, MultiSelect::On)?
         } else {
-            snap_name_map
-                .values()
-                .flatten()
-                .map(|name| format!("{name}\n"))
-                .collect()
+            snap_name_map.values().flatten().cloned().collect()
         };
 
-        let snap_names_string: String = snap_names
-            .into_iter()
-            .map(|name| format!("{name}\n"))
+        // prune exactly those snapshots displayed, which, in select mode, are only those selected
+        let mut snap_names: Vec<String> = snap_names
+            .iter()
+            .map(|name| name.trim().to_owned())
+            .filter(|name| !name.is_empty())
             .collect();
 
+        snap_names.sort();
+        snap_names.dedup();
+
+        if snap_names.is_empty() {
+            return Err(HttmError::new("No snapshots were selected to prune. Quitting.").into());
+        }
+
+        let snap_names_string: String = snap_names.iter().map(|name| format!("{name}\n")).collect();
+
+        let disappearing_string: String = {
+            let name_set: HashSet<&str> = snap_names.iter().map(|name| name.as_str()).collect();
+
+            let versions = PruneSnaps::disappearing_versions(versions_map, &name_set);
+
+            if versions.is_empty() {
+                "No version of the file/s will disappear, as each also exists live, or upon a snapshot which will be kept.\n".to_owned()
+            } else {
+                versions.iter().fold(
+                    "The following version/s of the file/s exist only upon these snapshot/s, and will disappear:\n\n".to_owned(),
+                    |mut buffer, snap_pd| {
+                        buffer += format!(
+                            "{:?} ({})\n",
+                            snap_pd.path(),
+                            date_string(
+                                GLOBAL_CONFIG.requested_utc_offset,
+                                &snap_pd.metadata_infallible().mtime(),
+                                DateFormat::Display
+                            )
+                        )
+                        .as_str();
+                        buffer
+                    },
+                )
+            }
+        };
+
+        let checkpoint_warning =
+            RunZpoolCommand::checkpoint_warning(snap_names.iter().map(|name| name.as_str()))
+                .map(|warning| warning + "\n")
+                .unwrap_or_default();
+
+        if GLOBAL_CONFIG.opt_dry_run {
+            println!(
+                "User has requested snapshots related to the following file/s be pruned:\n\n{}\n\
+                httm would destroy the following snapshot/s:\n\n{}\n\
+                {}\n\
+                {}\
+                Dry run.  No snapshots were pruned.",
+                file_names_string, snap_names_string, disappearing_string, checkpoint_warning
+            );
+
+            return Ok(());
+        }
+
         let prune_buffer = format!(
             "User has requested snapshots related to the following file/s be pruned:\n\n{}\n\
             httm will destroy the following snapshot/s:\n\n{}\n\
-            Before httm destroys these snapshot/s, it would like your consent. Continue? (YES/NO)\n\
+            {}\n\
+            {}\
+            {}\n\
             ─────────────────────────────────────────────────────────────────────────────\n\
-            YES\n\
-            NO",
-            file_names_string, snap_names_string
+            {}",
+            file_names_string,
+            snap_names_string,
+            disappearing_string,
+            checkpoint_warning,
+            Msg::PruneConsent.text(),
+            Consent::choices()
         );
 
         // loop until user consents or doesn't
@@ -99,9 +253,15 @@ impl InteractivePrune {
                 .get(0)
                 .ok_or_else(|| HttmError::new("Could not obtain the first match selected"))?;
 
-            match user_consent.to_ascii_uppercase().as_ref() {
-                "YES" | "Y" => {
-                    PruneSnaps::prune(snap_name_map)?;
+            match Consent::from(user_consent.as_str()) {
+                Consent::Yes => {
+                    if !Self::typed_consent(snap_names.len())? {
+                        break eprintln!(
+                            "User did not confirm the number of snapshots to destroy.  No files were pruned."
+                        );
+                    }
+
+                    PruneSnaps::prune(&snap_names)?;
 
                     let result_buffer = format!(
                         "httm pruned snapshots related to the following file/s:\n\n{}\n\
@@ -112,12 +272,28 @@ impl InteractivePrune {
 
                     break eprintln!("{result_buffer}");
                 }
-                "NO" | "N" => break eprintln!("User declined prune.  No files were pruned."),
+                Consent::No => break eprintln!("User declined prune.  No files were pruned."),
                 // if not yes or no, then noop and continue to the next iter of loop
-                _ => {}
+                Consent::Undecided => {}
             }
         }
 
         Ok(())
     }
+
+    // a selection may be made by muscle memory, so, as a destroy cannot be undone, the user must
+    // also type the number of snapshots to be destroyed, at the terminal, as stdin may be a pipe
+    fn typed_consent(num_snaps: usize) -> HttmResult<bool> {
+        let tty = std::fs::File::open("/dev/tty").map_err(|_err| {
+            HttmError::new("Could not open the terminal to confirm the snapshots to destroy.")
+        })?;
+
+        eprint!("{} ", Msg::PruneTypedConsent.text());
+
+        let mut input = String::new();
+
+        BufReader::new(tty).read_line(&mut input)?;
+
+        Ok(input.trim().parse::<usize>().ok() == Some(num_snaps))
+    }
 }
@@ -15,19 +15,158 @@
 // For the full copyright and license information, please view the LICENSE file
 // that was distributed with this source code.
 
-use crate::config::generate::{ExecMode, InteractiveMode, RestoreMode, RestoreSnapGuard};
-use crate::data::paths::{PathData, PathDeconstruction, ZfsSnapPathGuard};
+use crate::config::generate::{
+    ExecMode,
+    InteractiveMode,
+    OnConflict,
+    RestoreMode,
+    RestoreSnapGuard,
+};
+use crate::data::paths::{PathData, PathDeconstruction, PathMetadata, ZfsSnapPathGuard};
+use crate::interactive::conflicts::{ConflictResolution, RestoreConflicts};
+use crate::interactive::merge::InteractiveMerge;
+use crate::interactive::restore_queue::{RestoreJob, RestoreJournal, RestoreQueue};
 use crate::interactive::select::InteractiveSelect;
 use crate::interactive::view_mode::{MultiSelect, ViewMode};
-use crate::library::file_ops::Copy;
-use crate::library::results::{HttmError, HttmResult};
-use crate::library::utility::{date_string, DateFormat};
-use crate::zfs::snap_guard::SnapGuard;
+use crate::library::audit::AuditLog;
+use crate::library::file_ops::{Copy, Verify};
+use crate::library::i18n::{Consent, Msg};
+use crate::library::results::{HttmAbort, HttmError, HttmResult};
+use crate::library::trash::Trash;
+use crate::library::utility::{date_string, is_writable, split_snap_path, DateFormat};
+use crate::lookup::versions::VersionsMap;
+use crate::zfs::snap_guard::{PrecautionarySnapType, SnapGuard};
 use crate::GLOBAL_CONFIG;
+use hashbrown::{HashMap, HashSet};
 use nu_ansi_term::Color::LightYellow;
 use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
 use terminal_size::{Height, Width};
 
+// key: live path, val: its metadata as of the lookup of its versions, if the live path then existed
+static LIVE_AT_LOOKUP: LazyLock<Mutex<HashMap<PathBuf, Option<PathMetadata>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// the user may take quite a while to select and confirm a restore, and, in the meantime,
+// another process may have modified the live file, so, before we overwrite a live file,
+// make certain it is the same file we looked up
+pub struct LiveFileGuard;
+
+impl LiveFileGuard {
+    pub fn record(versions_map: &VersionsMap) {
+        let Ok(mut recorded) = LIVE_AT_LOOKUP.lock() else {
+            return;
+        };
+
+        versions_map.keys().for_each(|live_pathdata| {
+            recorded.insert(
+                live_pathdata.path().to_path_buf(),
+                *live_pathdata.opt_metadata(),
+            );
+        });
+    }
+
+    pub fn verify(live_path: &Path) -> HttmResult<()> {
+        let Ok(recorded) = LIVE_AT_LOOKUP.lock() else {
+            return Ok(());
+        };
+
+        // nothing to compare, perhaps the live path was never looked up
+        let Some(at_lookup) = recorded.get(live_path) else {
+            return Ok(());
+        };
+
+        let live_pathdata = PathData::from(live_path);
+
+        if live_pathdata.opt_metadata() == at_lookup {
+            return Ok(());
+        }
+
+        let msg = format!(
+            "httm will not overwrite {:?}, as the live file was modified, created, or removed since httm looked up its versions.  \
+            Please review the live file, and try the restore again.  Quitting.",
+            live_path
+        );
+
+        Err(HttmError::new(&msg).into())
+    }
+}
+
+// restore the last snapshot version of each path, without launching any interactive view or
+// asking for consent, for use in scripts and cron jobs, where no TTY may be available
+pub struct NonInteractiveRestore;
+
+impl NonInteractiveRestore {
+    pub fn exec(versions_map: &VersionsMap) -> HttmResult<()> {
+        let restore_dir = InteractiveRestore::restore_dir()?;
+
+        let opt_journal = GLOBAL_CONFIG
+            .opt_restore_journal
+            .as_deref()
+            .map(RestoreJournal::new)
+            .transpose()?;
+
+        let last_snaps: Vec<(&PathData, &PathData)> = versions_map
+            .iter()
+            .filter_map(|(live_pathdata, snaps)| {
+                let last_snap = snaps.last();
+
+                if last_snap.is_none() {
+                    eprintln!(
+                        "WARN: No last snap of {:?} is available for restore.  Perhaps you omitted identical files.",
+                        live_pathdata.path()
+                    );
+                }
+
+                last_snap.map(|snap_pathdata| (live_pathdata, snap_pathdata))
+            })
+            .collect();
+
+        // all the versions to be restored, so RESTORE_TO may preserve their directory structure
+        let snap_path_strings: Vec<String> = last_snaps
+            .iter()
+            .map(|(_live_pathdata, snap_pathdata)| {
+                snap_pathdata.path().to_string_lossy().into_owned()
+            })
+            .collect();
+
+        let jobs: Vec<RestoreJob> = last_snaps
+            .into_iter()
+            .filter(|(_live_pathdata, snap_pathdata)| match &opt_journal {
+                Some(journal) => !journal.is_completed(snap_pathdata.path()),
+                None => true,
+            })
+            .map(|(live_pathdata, snap_pathdata)| {
+                let interactive_restore = InteractiveRestore {
+                    _view_mode: ViewMode::Restore,
+                    snap_path_strings: snap_path_strings.clone(),
+                    opt_live_version: Some(live_pathdata.path().to_string_lossy().into_owned()),
+                };
+
+                interactive_restore
+                    .build_new_file_path(snap_pathdata, &restore_dir)
+                    .map(|opt_dst| {
+                        opt_dst.map(|dst| RestoreJob {
+                            src: snap_pathdata.path().to_path_buf(),
+                            dst,
+                            excluded: HashSet::new(),
+                        })
+                    })
+            })
+            .collect::<HttmResult<Vec<Option<RestoreJob>>>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        if jobs.is_empty() {
+            println!("No snapshot versions are available to restore.  Nothing to do.");
+            return Ok(());
+        }
+
+        InteractiveRestore::exec_jobs(jobs, opt_journal.as_ref())
+    }
+}
+
 pub struct InteractiveRestore {
     pub _view_mode: ViewMode,
     pub snap_path_strings: Vec<String>,
@@ -42,12 +181,360 @@ impl From<InteractiveSelect> for InteractiveRestore {
 
 impl InteractiveRestore {
     pub fn restore(&self) -> HttmResult<()> {
+        // a merge always writes to the live file, so there is no restore destination to determine
+        if let Some(RestoreMode::Merge(snap_guard)) = Self::restore_mode() {
+            return self.merge(snap_guard);
+        }
+
+        // determine where files will be sent before we ask for consent on any single file,
+        // so a read-only destination is caught now, rather than after a copy fails
+        let restore_dir = Self::restore_dir()?;
+
+        if self.snap_path_strings.len() > 1 || GLOBAL_CONFIG.opt_restore_journal.is_some() {
+            return self.restore_queued(&restore_dir);
+        }
+
+        self.snap_path_strings
+            .iter()
+            .try_for_each(|snap_path_string| self.restore_per_path(snap_path_string, &restore_dir))
+    }
+
+    fn merge(&self, snap_guard: &RestoreSnapGuard) -> HttmResult<()> {
         self.snap_path_strings
             .iter()
-            .try_for_each(|snap_path_string| self.restore_per_path(snap_path_string))
+            .try_for_each(|snap_path_string| {
+                let snap_pathdata = PathData::from(Path::new(snap_path_string));
+                let live_path = self.opt_live_version(&snap_pathdata)?;
+
+                InteractiveMerge::new(snap_pathdata.path(), &live_path, snap_guard).exec()
+            })
+    }
+
+    fn restore_dir() -> HttmResult<PathBuf> {
+        if let Some(restore_to) = GLOBAL_CONFIG.opt_restore_to.as_deref() {
+            if !is_writable(restore_to) {
+                let msg = format!(
+                    "The restore destination {:?} is not writable (perhaps it resides on a read-only mount).  Quitting.",
+                    restore_to
+                );
+                return Err(HttmError::new(&msg).into());
+            }
+
+            return Ok(restore_to.to_path_buf());
+        }
+
+        let pwd = GLOBAL_CONFIG.pwd.as_path();
+
+        if Self::is_overwrite() || is_writable(pwd) {
+            return Ok(pwd.to_path_buf());
+        }
+
+        // no one is available to choose an alternate destination
+        if matches!(GLOBAL_CONFIG.exec_mode, ExecMode::NonInteractiveRestore(_)) {
+            let msg = format!(
+                "The restore destination {:?} is not writable (perhaps it resides on a read-only mount).  \
+                Consider specifying an alternate destination with RESTORE_TO.  Quitting.",
+                pwd
+            );
+            return Err(HttmError::new(&msg).into());
+        }
+
+        Self::alternate_restore_dir(pwd)
+    }
+
+    fn alternate_restore_dir(read_only_dir: &Path) -> HttmResult<PathBuf> {
+        let candidates: Vec<PathBuf> = [std::env::var_os("HOME").map(PathBuf::from)]
+            .into_iter()
+            .flatten()
+            .chain([std::env::temp_dir(), PathBuf::from("/var/tmp")])
+            .filter(|dir| dir.is_dir() && is_writable(dir))
+            .fold(Vec::new(), |mut acc, dir| {
+                if !acc.contains(&dir) {
+                    acc.push(dir);
+                }
+                acc
+            });
+
+        if candidates.is_empty() {
+            let msg = format!(
+                "The restore destination {:?} is not writable (perhaps it resides on a read-only mount), \
+                and no alternate writable destination could be found.  Quitting.",
+                read_only_dir
+            );
+            return Err(HttmError::new(&msg).into());
+        }
+
+        let candidates_buffer: String = candidates
+            .iter()
+            .map(|dir| format!("{}\n", dir.to_string_lossy()))
+            .collect();
+
+        let alternate_buffer = format!(
+            "httm cannot restore to the working directory, as it is not writable:\n\n\
+            \tdestination:\t{read_only_dir:?}\n\n\
+            Please choose an alternate writable destination, or QUIT.\n\
+            ─────────────────────────────────────────────────────────────────────────────────────────\n\
+            {candidates_buffer}\
+            QUIT"
+        );
+
+        // loop until user selects a valid destination or quits
+        loop {
+            let view_mode = ViewMode::Restore;
+
+            let selection = view_mode.view_buffer(&alternate_buffer, MultiSelect::Off)?;
+
+            let user_selection = selection
+                .get(0)
+                .ok_or_else(|| HttmError::new("Could not obtain the first match selected."))?;
+
+            if user_selection.eq_ignore_ascii_case("QUIT") {
+                if GLOBAL_CONFIG.opt_continue {
+                    return Err(HttmError::new(
+                        "User declined to choose an alternate restore destination.",
+                    )
+                    .into());
+                }
+
+                return Err(HttmAbort::new(
+                    "User declined to choose an alternate restore destination.  Quitting.",
+                )
+                .into());
+            }
+
+            if let Some(dir) = candidates
+                .iter()
+                .find(|dir| dir.as_path() == Path::new(user_selection))
+            {
+                break Ok(dir.to_path_buf());
+            }
+        }
     }
 
-    fn restore_per_path(&self, snap_path_string: &str) -> HttmResult<()> {
+    fn restore_queued(&self, restore_dir: &Path) -> HttmResult<()> {
+        let opt_journal = GLOBAL_CONFIG
+            .opt_restore_journal
+            .as_deref()
+            .map(RestoreJournal::new)
+            .transpose()?;
+
+        let candidates: Vec<PathData> = self
+            .snap_path_strings
+            .iter()
+            .map(|snap_path_string| PathData::from(Path::new(snap_path_string)))
+            .filter(|snap_pathdata| match &opt_journal {
+                Some(journal) => !journal.is_completed(snap_pathdata.path()),
+                None => true,
+            })
+            .collect();
+
+        let num_skipped = self.snap_path_strings.len() - candidates.len();
+
+        let jobs: Vec<RestoreJob> = candidates
+            .iter()
+            .map(|snap_pathdata| {
+                self.build_new_file_path(snap_pathdata, restore_dir)
+                    .map(|opt_dst| {
+                        opt_dst.map(|dst| RestoreJob {
+                            src: snap_pathdata.path().to_path_buf(),
+                            dst,
+                            excluded: HashSet::new(),
+                        })
+                    })
+            })
+            .collect::<HttmResult<Vec<Option<RestoreJob>>>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let num_conflicts = candidates.len() - jobs.len();
+
+        if jobs.is_empty() {
+            if num_conflicts > 0 {
+                println!(
+                    "All {num_conflicts} remaining selected restore/s were skipped, as files already exist at their restore paths.  Nothing to do."
+                );
+                return Ok(());
+            }
+
+            println!(
+                "All {num_skipped} selected restore/s were already recorded as completed in the restore journal.  Nothing to do."
+            );
+            return Ok(());
+        }
+
+        let jobs_buffer: String = jobs
+            .iter()
+            .map(|job| format!("\tsource:\t{:?}\n\ttarget:\t{:?}\n", job.src, job.dst))
+            .collect();
+
+        let skipped_buffer = if num_skipped > 0 {
+            format!("httm will skip {num_skipped} restore/s already recorded as completed in the restore journal.\n\n")
+        } else {
+            String::new()
+        };
+
+        let conflicts_buffer = if num_conflicts > 0 {
+            format!("httm will skip {num_conflicts} restore/s, as files already exist at their restore paths.\n\n")
+        } else {
+            String::new()
+        };
+
+        // nothing will be written, so there is nothing to consent to
+        if GLOBAL_CONFIG.opt_dry_run {
+            print!("{skipped_buffer}{conflicts_buffer}");
+            return Self::exec_jobs(jobs, opt_journal.as_ref());
+        }
+
+        // tell the user what we're up to, and get consent
+        let restore_buffer = format!(
+            "httm will perform {} copies from snapshot, {} at a time:\n\n\
+            {jobs_buffer}\n\
+            {skipped_buffer}\
+            {conflicts_buffer}\
+            {}\n\
+            ─────────────────────────────────────────────────────────────────────────────────────────\n\
+            {}",
+            jobs.len(),
+            GLOBAL_CONFIG.restore_jobs,
+            Msg::RestoreConsent.text(),
+            Consent::batch_choices()
+        );
+
+        // loop until user consents or doesn't
+        loop {
+            let view_mode = ViewMode::Restore;
+
+            let selection = view_mode.view_buffer(&restore_buffer, MultiSelect::Off)?;
+
+            let user_consent = selection
+                .get(0)
+                .ok_or_else(|| HttmError::new("Could not obtain the first match selected."))?;
+
+            // the user would rather consent to each restore in turn, as with a single restore
+            if Consent::is_each(user_consent) {
+                return jobs.iter().try_for_each(|job| {
+                    self.restore_per_path(&job.src.to_string_lossy(), restore_dir)
+                });
+            }
+
+            match Consent::from(user_consent.as_str()) {
+                Consent::Yes => break,
+                Consent::No => {
+                    println!("User declined restore of {} file/s.", jobs.len());
+                    return Ok(());
+                }
+                // if not yes or no, then noop and continue to the next iter of loop
+                Consent::Undecided => {}
+            }
+        }
+
+        if Self::is_overwrite() {
+            jobs.iter()
+                .try_for_each(|job| LiveFileGuard::verify(&job.dst))?;
+        }
+
+        let jobs = Self::resolve_conflicts(jobs)?;
+
+        if jobs.is_empty() {
+            println!("User declined all remaining restore/s.  Nothing to do.");
+            return Ok(());
+        }
+
+        Self::exec_jobs(jobs, opt_journal.as_ref())
+    }
+
+    // as with a single restore, when overwriting a directory, ask what to do about any live file
+    // newer than its snapshot version, before we copy anything
+    fn resolve_conflicts(jobs: Vec<RestoreJob>) -> HttmResult<Vec<RestoreJob>> {
+        if !Self::is_overwrite() {
+            return Ok(jobs);
+        }
+
+        jobs.into_iter()
+            .filter_map(|mut job| {
+                if !job.src.is_dir() {
+                    return Some(Ok(job));
+                }
+
+                let resolution = RestoreConflicts::new(&job.src, &job.dst)
+                    .and_then(|conflicts| conflicts.resolve());
+
+                match resolution {
+                    Ok(ConflictResolution::Exclude(excluded)) => {
+                        job.excluded = excluded;
+                        Some(Ok(job))
+                    }
+                    Ok(ConflictResolution::Cancel) => {
+                        println!("User declined restore of: {:?}", job.src);
+                        None
+                    }
+                    Err(err) => Some(Err(err)),
+                }
+            })
+            .collect()
+    }
+
+    fn exec_jobs(jobs: Vec<RestoreJob>, opt_journal: Option<&RestoreJournal>) -> HttmResult<()> {
+        let snap_guards: Vec<SnapGuard> = match Self::restore_mode() {
+            Some(RestoreMode::Overwrite(RestoreSnapGuard::Guarded)) => {
+                Self::batch_snap_guards(&jobs)?
+            }
+            _ => Vec::new(),
+        };
+
+        let queue = RestoreQueue::new(
+            jobs,
+            GLOBAL_CONFIG.restore_jobs,
+            Self::should_preserve_attributes(),
+            opt_journal,
+        );
+
+        if let Err(err) = queue.exec() {
+            if snap_guards.is_empty() {
+                return Err(err);
+            }
+
+            let msg = format!(
+                "httm restore failed for the following reason: {}.\n\
+                Attempting roll back to precautionary pre-execution snapshot/s.",
+                err
+            );
+
+            eprintln!("{}", msg);
+
+            snap_guards
+                .iter()
+                .try_for_each(|snap_guard| snap_guard.rollback())
+                .map(|_| println!("Rollback succeeded."))?;
+
+            return Err(HttmError::new(
+                "httm restore failed, and was rolled back to the precautionary pre-execution snapshot/s.",
+            )
+            .into());
+        }
+
+        if GLOBAL_CONFIG.opt_dry_run {
+            println!("Dry run.  No files were restored.");
+            return Ok(());
+        }
+
+        let summary_string = LightYellow.paint(Self::summary_string());
+
+        println!("{summary_string}Restore completed successfully.");
+
+        Ok(())
+    }
+
+    // take only one precautionary snapshot per dataset, instead of one per file
+    fn batch_snap_guards(jobs: &[RestoreJob]) -> HttmResult<Vec<SnapGuard>> {
+        SnapGuard::from_paths(
+            jobs.iter().map(|job| job.dst.as_path()),
+            PrecautionarySnapType::PreRestore,
+        )
+    }
+
+    fn restore_per_path(&self, snap_path_string: &str, restore_dir: &Path) -> HttmResult<()> {
         // build pathdata from selection buffer parsed string
         //
         // request is also sanity check for snap path exists below when we check
@@ -55,20 +542,44 @@ impl InteractiveRestore {
         let snap_pathdata = PathData::from(Path::new(snap_path_string));
 
         // build new place to send file
-        let new_file_path_buf = self.build_new_file_path(&snap_pathdata)?;
+        let Some(new_file_path_buf) = self.build_new_file_path(&snap_pathdata, restore_dir)? else {
+            println!("Skipped restore of: {:?}", snap_pathdata.path());
+            return Ok(());
+        };
 
         let should_preserve = Self::should_preserve_attributes();
 
+        if GLOBAL_CONFIG.opt_dry_run {
+            if let Some(RestoreMode::Overwrite(RestoreSnapGuard::Guarded)) = Self::restore_mode() {
+                SnapGuard::try_from(new_file_path_buf.as_path())?;
+            }
+
+            if GLOBAL_CONFIG.opt_trash {
+                Trash::stage(&new_file_path_buf)?;
+            }
+
+            println!(
+                "httm would perform a copy from snapshot:\n\n\
+                \tsource:\t{:?}\n\
+                \ttarget:\t{new_file_path_buf:?}\n\n\
+                Dry run.  No files were restored.",
+                snap_pathdata.path()
+            );
+
+            return Ok(());
+        }
+
         // tell the user what we're up to, and get consent
         let restore_buffer = format!(
             "httm will perform a copy from snapshot:\n\n\
             \tsource:\t{:?}\n\
             \ttarget:\t{new_file_path_buf:?}\n\n\
-            Before httm performs a restore, it would like your consent. Continue? (YES/NO)\n\
+            {}\n\
             ─────────────────────────────────────────────────────────────────────────────────────────\n\
-            YES\n\
-            NO",
-            snap_pathdata.path()
+            {}",
+            snap_pathdata.path(),
+            Msg::RestoreConsent.text(),
+            Consent::choices()
         );
 
         // loop until user consents or doesn't
@@ -81,54 +592,101 @@ impl InteractiveRestore {
                 .get(0)
                 .ok_or_else(|| HttmError::new("Could not obtain the first match selected."))?;
 
-            match user_consent.to_ascii_uppercase().as_ref() {
-                "YES" | "Y" => {
-                    match GLOBAL_CONFIG.exec_mode {
-                        ExecMode::Interactive(InteractiveMode::Restore(
-                            RestoreMode::Overwrite(RestoreSnapGuard::Guarded),
-                        )) => {
-                            let snap_guard: SnapGuard =
-                                SnapGuard::try_from(new_file_path_buf.as_path())?;
-
-                            if let Err(err) = Copy::recursive(
-                                &snap_pathdata.path(),
-                                &new_file_path_buf,
-                                should_preserve,
-                            ) {
-                                let msg = format!(
-                                    "httm restore failed for the following reason: {}.\n\
-                            Attempting roll back to precautionary pre-execution snapshot.",
-                                    err
+            match Consent::from(user_consent.as_str()) {
+                Consent::Yes => {
+                    if Self::is_overwrite() {
+                        LiveFileGuard::verify(&new_file_path_buf)?;
+                    }
+
+                    // when overwriting a directory, ask what to do about any live file newer
+                    // than its snapshot version, before we copy anything
+                    let excluded = if Self::is_overwrite() && snap_pathdata.path().is_dir() {
+                        match RestoreConflicts::new(snap_pathdata.path(), &new_file_path_buf)?
+                            .resolve()?
+                        {
+                            ConflictResolution::Exclude(excluded) => excluded,
+                            ConflictResolution::Cancel => {
+                                break println!(
+                                    "User declined restore of: {:?}",
+                                    snap_pathdata.path()
                                 );
+                            }
+                        }
+                    } else {
+                        HashSet::new()
+                    };
 
-                                eprintln!("{}", msg);
+                    // the precautionary snapshot must capture the live file before it is moved to the trash
+                    let opt_snap_guard = match Self::restore_mode() {
+                        Some(RestoreMode::Overwrite(RestoreSnapGuard::Guarded)) => {
+                            Some(SnapGuard::try_from(new_file_path_buf.as_path())?)
+                        }
+                        _ => None,
+                    };
 
-                                snap_guard
-                                    .rollback()
-                                    .map(|_| println!("Rollback succeeded."))?;
+                    AuditLog::before_restore(&new_file_path_buf);
 
-                                std::process::exit(1);
-                            }
+                    if Self::is_overwrite() && GLOBAL_CONFIG.opt_trash {
+                        if let Some(trashed) = Trash::stage(&new_file_path_buf)? {
+                            eprintln!("httm moved the live file to the trash: {:?}", trashed);
                         }
-                        _ => {
-                            if let Err(err) = Copy::recursive(
-                                &snap_pathdata.path(),
-                                &new_file_path_buf,
-                                should_preserve,
-                            ) {
+                    }
+
+                    let copy_summary = match Copy::recursive_parallel_excluding(
+                        snap_pathdata.path(),
+                        &new_file_path_buf,
+                        should_preserve,
+                        GLOBAL_CONFIG.restore_jobs,
+                        &excluded,
+                    ) {
+                        Ok(copy_summary) => copy_summary,
+                        Err(err) => match opt_snap_guard {
+                            Some(snap_guard) => {
+                                AuditLog::restore(
+                                    snap_pathdata.path(),
+                                    &new_file_path_buf,
+                                    &Err::<(), _>(err.to_string().into()),
+                                );
+
+                                return Self::rollback(&snap_guard, err);
+                            }
+                            None => {
                                 let msg = format!(
                                     "httm restore failed for the following reason: {}.",
                                     err
                                 );
-                                return Err(HttmError::new(&msg).into());
+                                let res: HttmResult<()> = Err(HttmError::new(&msg).into());
+                                AuditLog::restore(snap_pathdata.path(), &new_file_path_buf, &res);
+                                return res;
                             }
-                        }
+                        },
+                    };
+
+                    let res_verify = if GLOBAL_CONFIG.opt_verify {
+                        Verify::recursive_excluding(
+                            snap_pathdata.path(),
+                            &new_file_path_buf,
+                            &excluded,
+                        )
+                    } else {
+                        Ok(())
+                    };
+
+                    AuditLog::restore(snap_pathdata.path(), &new_file_path_buf, &res_verify);
+
+                    // a restore we could not verify is as much a failure as one we could not copy
+                    if let Err(err) = res_verify {
+                        return match opt_snap_guard {
+                            Some(snap_guard) => Self::rollback(&snap_guard, err),
+                            None => Err(err),
+                        };
                     }
 
                     let result_buffer = format!(
                         "httm copied from snapshot:\n\n\
                             \tsource:\t{:?}\n\
-                            \ttarget:\t{new_file_path_buf:?}\n\n\
+                            \ttarget:\t{new_file_path_buf:?}\n\
+                            \tcopied:\t{copy_summary}\n\n\
                             Restore completed successfully.",
                         snap_pathdata.path()
                     );
@@ -137,17 +695,39 @@ impl InteractiveRestore {
 
                     break println!("{summary_string}{result_buffer}");
                 }
-                "NO" | "N" => {
+                Consent::No => {
                     break println!("User declined restore of: {:?}", snap_pathdata.path())
                 }
                 // if not yes or no, then noop and continue to the next iter of loop
-                _ => {}
+                Consent::Undecided => {}
             }
         }
 
         Ok(())
     }
 
+    fn rollback(
+        snap_guard: &SnapGuard,
+        err: Box<dyn std::error::Error + Send + Sync>,
+    ) -> HttmResult<()> {
+        let msg = format!(
+            "httm restore failed for the following reason: {}.\n\
+            Attempting roll back to precautionary pre-execution snapshot.",
+            err
+        );
+
+        eprintln!("{}", msg);
+
+        snap_guard
+            .rollback()
+            .map(|_| println!("Rollback succeeded."))?;
+
+        Err(HttmError::new(
+            "httm restore failed, and was rolled back to the precautionary pre-execution snapshot.",
+        )
+        .into())
+    }
+
     fn summary_string() -> String {
         let width = match terminal_size::terminal_size() {
             Some((Width(width), Height(_height))) => width as usize,
@@ -157,12 +737,22 @@ impl InteractiveRestore {
         format!("{:^width$}\n", "====> [ httm recovery summary ] <====")
     }
 
+    fn restore_mode() -> Option<&'static RestoreMode> {
+        match &GLOBAL_CONFIG.exec_mode {
+            ExecMode::Interactive(InteractiveMode::Restore(restore_mode))
+            | ExecMode::NonInteractiveRestore(restore_mode) => Some(restore_mode),
+            _ => None,
+        }
+    }
+
+    fn is_overwrite() -> bool {
+        matches!(Self::restore_mode(), Some(RestoreMode::Overwrite(_)))
+    }
+
     fn should_preserve_attributes() -> bool {
         matches!(
-            GLOBAL_CONFIG.exec_mode,
-            ExecMode::Interactive(InteractiveMode::Restore(
-                RestoreMode::CopyAndPreserve | RestoreMode::Overwrite(_)
-            ))
+            Self::restore_mode(),
+            Some(RestoreMode::CopyAndPreserve | RestoreMode::Overwrite(_))
         )
     }
 
@@ -176,18 +766,33 @@ impl InteractiveRestore {
         .ok_or_else(|| HttmError::new("Could not determine a possible live version.").into())
     }
 
-    fn build_new_file_path(&self, snap_pathdata: &PathData) -> HttmResult<PathBuf> {
+    // None where the user has chosen to skip a snapshot version, as a file already exists
+    // at the path to which it would be restored, see ON_CONFLICT
+    fn build_new_file_path(
+        &self,
+        snap_pathdata: &PathData,
+        restore_dir: &Path,
+    ) -> HttmResult<Option<PathBuf>> {
         // build new place to send file
-        if matches!(
-            GLOBAL_CONFIG.exec_mode,
-            ExecMode::Interactive(InteractiveMode::Restore(RestoreMode::Overwrite(_)))
-        ) {
+        if Self::is_overwrite() {
             // instead of just not naming the new file with extra info (date plus "httm_restored") and shoving that new file
             // into the pwd, here, we actually look for the original location of the file to make sure we overwrite it.
             // so, if you were in /etc and wanted to restore /etc/samba/smb.conf, httm will make certain to overwrite
             // at /etc/samba/smb.conf
+            let live_path = self.opt_live_version(snap_pathdata)?;
+
+            if let Some(parent) = live_path.parent() {
+                if !is_writable(parent) {
+                    let msg = format!(
+                        "httm cannot overwrite {:?}, as its parent directory is not writable (perhaps it resides on a read-only mount).  \
+                        Consider a non-overwrite restore mode, which permits an alternate destination.  Quitting.",
+                        live_path
+                    );
+                    return Err(HttmError::new(&msg).into());
+                }
+            }
 
-            return self.opt_live_version(snap_pathdata);
+            return Ok(Some(live_path));
         }
 
         let snap_filename = snap_pathdata
@@ -216,16 +821,127 @@ impl InteractiveRestore {
                 &snap_metadata.mtime(),
                 DateFormat::Timestamp,
             );
-        let new_file_dir = GLOBAL_CONFIG.pwd.as_path();
-        let new_file_path_buf: PathBuf = new_file_dir.join(new_filename);
+        let new_file_path_buf: PathBuf = match self.restore_to_sub_dir(snap_pathdata.path()) {
+            Some(sub_dir) => restore_dir.join(sub_dir).join(new_filename),
+            None => restore_dir.join(new_filename),
+        };
 
-        // don't let the user rewrite one restore over another in non-overwrite mode
-        if new_file_path_buf.exists() {
-            Err(
-                    HttmError::new("httm will not restore to that file location, as a file with the same path name already exists. Quitting.").into(),
-                )
-        } else {
-            Ok(new_file_path_buf)
+        if !new_file_path_buf.exists() {
+            return Ok(Some(new_file_path_buf));
+        }
+
+        Self::resolve_conflict(snap_pathdata.path(), new_file_path_buf)
+    }
+
+    // don't let the user rewrite one restore over another in non-overwrite mode,
+    // unless the user has requested some other resolution, see ON_CONFLICT
+    fn resolve_conflict(
+        snap_path: &Path,
+        new_file_path_buf: PathBuf,
+    ) -> HttmResult<Option<PathBuf>> {
+        match GLOBAL_CONFIG.opt_on_conflict {
+            None => Err(
+                HttmError::new("httm will not restore to that file location, as a file with the same path name already exists. Quitting.").into(),
+            ),
+            Some(OnConflict::Skip) => {
+                eprintln!(
+                    "NOTICE: Skipping restore of {:?}, as a file already exists at: {:?}",
+                    snap_path, new_file_path_buf
+                );
+                Ok(None)
+            }
+            Some(OnConflict::Overwrite) => Ok(Some(new_file_path_buf)),
+            Some(OnConflict::RenameNumbered) => Ok(Some(Self::numbered_path(&new_file_path_buf))),
+            Some(OnConflict::Ask) => Self::ask_conflict(snap_path, new_file_path_buf),
+        }
+    }
+
+    fn numbered_path(path: &Path) -> PathBuf {
+        (1usize..)
+            .map(|num| {
+                let mut numbered = path.as_os_str().to_os_string();
+                numbered.push(format!(".{num}"));
+                PathBuf::from(numbered)
+            })
+            .find(|numbered| !numbered.exists())
+            .expect("An unbounded range should always yield an available numbered path")
+    }
+
+    fn ask_conflict(snap_path: &Path, new_file_path_buf: PathBuf) -> HttmResult<Option<PathBuf>> {
+        let conflict_buffer = format!(
+            "A file already exists at the path to which httm would restore:\n\n\
+            \tsource:\t{snap_path:?}\n\
+            \ttarget:\t{new_file_path_buf:?}\n\n\
+            How should httm proceed?\n\
+            ─────────────────────────────────────────────────────────────────────────────────────────\n\
+            {}\n\
+            {}\n\
+            {}",
+            Msg::Skip.text(),
+            Msg::Overwrite.text(),
+            Msg::RenameNumbered.text()
+        );
+
+        // loop until user makes a valid selection
+        loop {
+            let view_mode = ViewMode::Restore;
+
+            let selection = view_mode.view_buffer(&conflict_buffer, MultiSelect::Off)?;
+
+            let user_selection = selection
+                .get(0)
+                .ok_or_else(|| HttmError::new("Could not obtain the first match selected."))?;
+
+            match user_selection.as_str() {
+                skip if skip == Msg::Skip.text() => return Ok(None),
+                overwrite if overwrite == Msg::Overwrite.text() => {
+                    return Ok(Some(new_file_path_buf))
+                }
+                rename if rename == Msg::RenameNumbered.text() => {
+                    return Ok(Some(Self::numbered_path(&new_file_path_buf)))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // when restoring more than one version to RESTORE_TO, the portion of a version's
+    // directory, relative to its snapshot, which it does not share with the other versions selected
+    fn restore_to_sub_dir<'a>(&self, snap_path: &'a Path) -> Option<&'a Path> {
+        if GLOBAL_CONFIG.opt_restore_to.is_none() || self.snap_path_strings.len() < 2 {
+            return None;
         }
+
+        let common_dir = self.common_relative_dir();
+
+        Self::relative_parent(snap_path)?
+            .strip_prefix(common_dir)
+            .ok()
+            .filter(|sub_dir| !sub_dir.as_os_str().is_empty())
+    }
+
+    // the deepest directory, relative to their snapshots, shared by all the versions selected
+    fn common_relative_dir(&self) -> PathBuf {
+        let mut relative_parents = self
+            .snap_path_strings
+            .iter()
+            .filter_map(|snap_path_string| Self::relative_parent(Path::new(snap_path_string)));
+
+        let Some(first) = relative_parents.next() else {
+            return PathBuf::new();
+        };
+
+        relative_parents.fold(first.to_path_buf(), |common, relative_parent| {
+            common
+                .components()
+                .zip(relative_parent.components())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _b)| a)
+                .collect()
+        })
+    }
+
+    fn relative_parent(snap_path: &Path) -> Option<&Path> {
+        split_snap_path(snap_path).and_then(|(_snap_mount, _dataset, relative)| relative.parent())
     }
 }
@@ -0,0 +1,268 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::library::audit::AuditLog;
+use crate::library::file_ops::{Copy, Verify};
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::trash::Trash;
+use crate::GLOBAL_CONFIG;
+use hashbrown::HashSet;
+use indicatif::ProgressBar;
+use nix::errno::Errno;
+use rayon::prelude::*;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const RESTORE_MAX_ATTEMPTS: usize = 3;
+const RESTORE_RETRY_BACKOFF: Duration = Duration::from_millis(250);
+const JOURNAL_DELIMITER: char = '\t';
+
+#[derive(Debug, Clone)]
+pub struct RestoreJob {
+    pub src: PathBuf,
+    pub dst: PathBuf,
+    // the live files within a directory which the restore should leave untouched, see RestoreConflicts
+    pub excluded: HashSet<PathBuf>,
+}
+
+// a journal records each completed restore, one per line, as "src<TAB>dst", so that an
+// interrupted batch restore may be resumed by specifying the same journal file again
+pub struct RestoreJournal {
+    path: PathBuf,
+    completed: Vec<PathBuf>,
+    // a dry run reads, but never writes, the journal, see DRY_RUN
+    file: Mutex<Option<std::fs::File>>,
+}
+
+impl RestoreJournal {
+    pub fn new(path: &Path) -> HttmResult<Self> {
+        let completed: Vec<PathBuf> = match std::fs::read_to_string(path) {
+            Ok(contents) => contents
+                .lines()
+                .filter_map(|line| line.split_once(JOURNAL_DELIMITER))
+                .map(|(src, _dst)| PathBuf::from(src))
+                .collect(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                let msg = format!("Could not read restore journal {:?}: {}", path, err);
+                return Err(HttmError::new(&msg).into());
+            }
+        };
+
+        let file = if GLOBAL_CONFIG.opt_dry_run {
+            None
+        } else {
+            Some(OpenOptions::new().create(true).append(true).open(path)?)
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            completed,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn is_completed(&self, src: &Path) -> bool {
+        self.completed.iter().any(|completed| completed == src)
+    }
+
+    fn record(&self, job: &RestoreJob) -> HttmResult<()> {
+        let line = format!(
+            "{}{JOURNAL_DELIMITER}{}\n",
+            job.src.to_string_lossy(),
+            job.dst.to_string_lossy()
+        );
+
+        let mut opt_file = self
+            .file
+            .lock()
+            .map_err(|_err| HttmError::new("Could not obtain a lock on the restore journal."))?;
+
+        let Some(file) = opt_file.as_mut() else {
+            return Ok(());
+        };
+
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+
+        Ok(())
+    }
+}
+
+pub struct RestoreQueue<'a> {
+    jobs: Vec<RestoreJob>,
+    num_threads: usize,
+    should_preserve: bool,
+    opt_journal: Option<&'a RestoreJournal>,
+}
+
+impl<'a> RestoreQueue<'a> {
+    pub fn new(
+        jobs: Vec<RestoreJob>,
+        num_threads: usize,
+        should_preserve: bool,
+        opt_journal: Option<&'a RestoreJournal>,
+    ) -> Self {
+        Self {
+            jobs,
+            num_threads,
+            should_preserve,
+            opt_journal,
+        }
+    }
+
+    pub fn exec(&self) -> HttmResult<()> {
+        if GLOBAL_CONFIG.opt_dry_run {
+            if GLOBAL_CONFIG.opt_trash {
+                self.jobs
+                    .iter()
+                    .try_for_each(|job| Trash::stage(&job.dst).map(|_| ()))?;
+            }
+
+            let jobs_buffer: String = self
+                .jobs
+                .iter()
+                .map(|job| format!("\tsource:\t{:?}\n\ttarget:\t{:?}\n", job.src, job.dst))
+                .collect();
+
+            println!(
+                "httm would perform {} copies from snapshot:\n\n{jobs_buffer}",
+                self.jobs.len()
+            );
+
+            return Ok(());
+        }
+
+        let progress_bar = ProgressBar::new(self.jobs.len() as u64);
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.num_threads)
+            .build()?;
+
+        let failures: Vec<(&RestoreJob, String)> = pool.install(|| {
+            self.jobs
+                .par_iter()
+                .filter_map(|job| {
+                    let res = self.exec_with_retry(job);
+
+                    progress_bar.inc(1);
+
+                    res.err().map(|err| (job, err.to_string()))
+                })
+                .collect()
+        });
+
+        progress_bar.finish_and_clear();
+
+        let num_succeeded = self.jobs.len() - failures.len();
+
+        eprintln!(
+            "httm restored {} of {} file/s from snapshot.",
+            num_succeeded,
+            self.jobs.len()
+        );
+
+        if failures.is_empty() {
+            return Ok(());
+        }
+
+        let failures_buffer: String = failures
+            .iter()
+            .map(|(job, err)| format!("\t{:?}: {}\n", job.src, err))
+            .collect();
+
+        let resume_hint = match self.opt_journal {
+            Some(journal) => format!(
+                "\nRestores which succeeded were recorded in the journal {:?}.  \
+                Specify the same journal to resume, and retry only those restores which failed.",
+                journal.path
+            ),
+            None => String::new(),
+        };
+
+        let msg = format!(
+            "httm could not restore the following {} file/s:\n{}{}",
+            failures.len(),
+            failures_buffer,
+            resume_hint
+        );
+
+        Err(HttmError::new(&msg).into())
+    }
+
+    fn exec_with_retry(&self, job: &RestoreJob) -> HttmResult<()> {
+        let res = self.exec_with_retry_impl(job);
+
+        AuditLog::restore(&job.src, &job.dst, &res);
+
+        res
+    }
+
+    fn exec_with_retry_impl(&self, job: &RestoreJob) -> HttmResult<()> {
+        AuditLog::before_restore(&job.dst);
+
+        // staged once, before any attempt, so a retry never trashes a partially restored file
+        if GLOBAL_CONFIG.opt_trash {
+            Trash::stage(&job.dst)?;
+        }
+
+        let mut attempt = 1usize;
+
+        loop {
+            match Copy::recursive_quiet_excluding(
+                &job.src,
+                &job.dst,
+                self.should_preserve,
+                &job.excluded,
+            ) {
+                Ok(_) => break,
+                Err(err) if attempt < RESTORE_MAX_ATTEMPTS && Self::is_transient(err.as_ref()) => {
+                    std::thread::sleep(RESTORE_RETRY_BACKOFF * attempt as u32);
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        if GLOBAL_CONFIG.opt_verify {
+            Verify::recursive_quiet_excluding(&job.src, &job.dst, &job.excluded)?;
+        }
+
+        if let Some(journal) = self.opt_journal {
+            journal.record(job)?;
+        }
+
+        Ok(())
+    }
+
+    // a retry cannot cure an error like a missing source, or a lack of permissions or space
+    fn is_transient(err: &(dyn std::error::Error + 'static)) -> bool {
+        let opt_errno = err
+            .downcast_ref::<std::io::Error>()
+            .and_then(|io_err| io_err.raw_os_error())
+            .map(Errno::from_raw)
+            .or_else(|| err.downcast_ref::<Errno>().copied());
+
+        matches!(
+            opt_errno,
+            Some(Errno::EINTR | Errno::EAGAIN | Errno::EBUSY | Errno::ETIMEDOUT | Errno::ENOBUFS)
+        )
+    }
+}
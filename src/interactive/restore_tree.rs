@@ -0,0 +1,265 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::data::paths::PathDeconstruction;
+use crate::display::snap_info::SnapInfo;
+use crate::interactive::restore_queue::{RestoreJob, RestoreQueue};
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{date_string, is_writable, DateFormat};
+use crate::lookup::versions::RelativePathAndSnapMounts;
+use crate::GLOBAL_CONFIG;
+use hashbrown::HashSet;
+use std::cmp::Reverse;
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// a tree from which versions may be restored
+enum TreeSource {
+    // a live file is the version as of the requested time, if no newer than that time
+    Live(PathBuf),
+    Snapshot(PathBuf),
+}
+
+pub struct RestoreTree;
+
+impl RestoreTree {
+    pub fn exec(as_of: &SystemTime) -> HttmResult<()> {
+        let requested = match GLOBAL_CONFIG.paths.as_slice() {
+            [requested] => requested,
+            _ => {
+                return Err(HttmError::new(
+                    "RESTORE_TREE requires exactly one directory, or none, in which case the present working directory is used.",
+                )
+                .into())
+            }
+        };
+
+        // a directory which has since been deleted may still be restored from its snapshots
+        if requested.path().exists() && !requested.path().is_dir() {
+            let msg = format!("RESTORE_TREE requires a directory: {:?}", requested.path());
+            return Err(HttmError::new(&msg).into());
+        }
+
+        let dataset_of_interest = requested.proximate_dataset()?;
+        let relative_path = requested.relative_path(dataset_of_interest)?;
+
+        let Some(snap_mounts) = RelativePathAndSnapMounts::new(relative_path, dataset_of_interest)
+            .map(|prox| prox.snap_mounts)
+        else {
+            let msg = format!(
+                "httm could not find any snapshots of the dataset which contains: {:?}",
+                requested.path()
+            );
+            return Err(HttmError::new(&msg).into());
+        };
+
+        let dst_root = Self::destination(requested.path(), as_of)?;
+
+        let sources = Self::sources_as_of(snap_mounts, relative_path, requested.path(), as_of);
+
+        let jobs = Self::versions_as_of(&sources, &dst_root, as_of);
+
+        if jobs.is_empty() {
+            let msg = format!(
+                "httm could not find any version, as of {}, of any file beneath: {:?}",
+                date_string(
+                    GLOBAL_CONFIG.requested_utc_offset,
+                    as_of,
+                    DateFormat::Display
+                ),
+                requested.path()
+            );
+            return Err(HttmError::new(&msg).into());
+        }
+
+        eprintln!(
+            "httm will restore {} file/s, as of {}, to: {:?}",
+            jobs.len(),
+            date_string(
+                GLOBAL_CONFIG.requested_utc_offset,
+                as_of,
+                DateFormat::Display
+            ),
+            dst_root
+        );
+
+        // preserve attributes, so the restored tree's timestamps are those of the versions restored
+        RestoreQueue::new(jobs, GLOBAL_CONFIG.restore_jobs, true, None).exec()?;
+
+        println!("{}", dst_root.display());
+
+        Ok(())
+    }
+
+    // like every other copy restore, a new directory, named for the requested directory and time,
+    // beneath the present working directory, or RESTORE_TO, so nothing is overwritten
+    fn destination(requested_dir: &Path, as_of: &SystemTime) -> HttmResult<PathBuf> {
+        let restore_dir = GLOBAL_CONFIG
+            .opt_restore_to
+            .as_deref()
+            .unwrap_or(GLOBAL_CONFIG.pwd.as_path());
+
+        if !is_writable(restore_dir) {
+            let msg = format!(
+                "The restore destination {:?} is not writable (perhaps it resides on a read-only mount).  \
+                Consider specifying an alternate destination with RESTORE_TO.  Quitting.",
+                restore_dir
+            );
+            return Err(HttmError::new(&msg).into());
+        }
+
+        let dir_name = requested_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "root".to_string());
+
+        let dst_root = restore_dir.join(format!(
+            "{}.httm_restored.{}",
+            dir_name.strip_prefix('.').unwrap_or(&dir_name),
+            date_string(
+                GLOBAL_CONFIG.requested_utc_offset,
+                as_of,
+                DateFormat::Timestamp
+            )
+        ));
+
+        if dst_root.exists() {
+            let msg = format!(
+                "httm will not restore to {:?}, as a file with the same path name already exists. Quitting.",
+                dst_root
+            );
+            return Err(HttmError::new(&msg).into());
+        }
+
+        Ok(dst_root)
+    }
+
+    // the live tree, then each snapshot created no later than the requested time, newest first.  A
+    // snapshot is selected by its creation time, not by the modify times of the files it contains,
+    // and a snapshot whose creation time cannot be determined is never selected
+    fn sources_as_of(
+        snap_mounts: &[Box<Path>],
+        relative_path: &Path,
+        requested_dir: &Path,
+        as_of: &SystemTime,
+    ) -> Vec<TreeSource> {
+        let mut snapshots: Vec<(SystemTime, PathBuf)> = snap_mounts
+            .iter()
+            .filter_map(|snap_mount| {
+                let Some(creation) = SnapInfo::from_snap_mount(snap_mount)
+                    .and_then(|snap_info| snap_info.opt_creation)
+                else {
+                    eprintln!(
+                        "WARN: httm could not determine the creation time of snapshot {:?}, and will not restore from it.",
+                        snap_mount
+                    );
+                    return None;
+                };
+
+                if creation > *as_of {
+                    return None;
+                }
+
+                Some((creation, snap_mount.join(relative_path)))
+            })
+            .collect();
+
+        snapshots.sort_by_key(|(creation, _root)| Reverse(*creation));
+
+        std::iter::once(TreeSource::Live(requested_dir.to_path_buf()))
+            .chain(
+                snapshots
+                    .into_iter()
+                    .map(|(_creation, root)| TreeSource::Snapshot(root)),
+            )
+            .filter(|source| source.root().is_dir())
+            .collect()
+    }
+
+    // each tree is walked in turn, one dir at a time, so files beneath directories which have since
+    // been deleted are found, as in the deleted modes, and, for each file, the first tree to contain
+    // a version wins.  Only the destination paths already claimed are held in memory.
+    fn versions_as_of(
+        sources: &[TreeSource],
+        dst_root: &Path,
+        as_of: &SystemTime,
+    ) -> Vec<RestoreJob> {
+        let mut claimed: HashSet<PathBuf> = HashSet::new();
+        let mut jobs: Vec<RestoreJob> = Vec::new();
+
+        sources.iter().for_each(|source| {
+            let mut dirs: Vec<(PathBuf, PathBuf)> =
+                vec![(source.root().to_path_buf(), dst_root.to_path_buf())];
+
+            while let Some((src_dir, dst_dir)) = dirs.pop() {
+                let entries = match read_dir(&src_dir) {
+                    Ok(entries) => entries,
+                    Err(err) => {
+                        eprintln!("WARN: httm could not read {src_dir:?}: {err}");
+                        continue;
+                    }
+                };
+
+                entries.flatten().for_each(|entry| {
+                    let src = entry.path();
+                    let dst = dst_dir.join(entry.file_name());
+
+                    let Ok(file_type) = entry.file_type() else {
+                        return;
+                    };
+
+                    if file_type.is_dir() {
+                        dirs.push((src, dst));
+                        return;
+                    }
+
+                    if claimed.contains(&dst) || !source.admits(&src, as_of) {
+                        return;
+                    }
+
+                    claimed.insert(dst.clone());
+                    jobs.push(RestoreJob {
+                        src,
+                        dst,
+                        excluded: HashSet::new(),
+                    });
+                });
+            }
+        });
+
+        jobs
+    }
+}
+
+impl TreeSource {
+    fn root(&self) -> &Path {
+        match self {
+            TreeSource::Live(root) | TreeSource::Snapshot(root) => root,
+        }
+    }
+
+    fn admits(&self, src: &Path, as_of: &SystemTime) -> bool {
+        match self {
+            TreeSource::Live(_) => src
+                .symlink_metadata()
+                .and_then(|md| md.modified())
+                .is_ok_and(|mtime| mtime <= *as_of),
+            TreeSource::Snapshot(_) => true,
+        }
+    }
+}
@@ -17,16 +17,35 @@
 
 use super::browse::InteractiveBrowse;
 use crate::config::generate::{PrintMode, SelectMode};
+use crate::data::paths::{PathData, PathDeconstruction, ZfsSnapPathGuard};
+use crate::display::preview_render::PreviewRender;
 use crate::display::wrapper::DisplayWrapper;
-use crate::interactive::preview::PreviewSelection;
+use crate::interactive::preview::{output_with_timeout, PreviewSelection};
+use crate::interactive::restore::LiveFileGuard;
 use crate::interactive::view_mode::{MultiSelect, ViewMode};
+use crate::library::content_hash::{ContentHasher, SelectedHasher};
+use crate::library::file_ops::HashFileContents;
 use crate::library::results::{HttmError, HttmResult};
-use crate::library::utility::{delimiter, print_output_buf};
+use crate::library::shell::ShellKind;
+use crate::library::utility::{
+    delimiter,
+    exec_user_program,
+    hex_dump,
+    print_output_buf,
+    TempCopy,
+    HEX_DUMP_MAX_BYTES,
+};
 use crate::lookup::versions::VersionsMap;
 use crate::{Config, GLOBAL_CONFIG};
+use skim::prelude::Key;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process::Command as ExecProcess;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// the user pressed ctrl+o in the select view, so open the selection in an editor,
+// whatever the requested select mode, see SelectMode::Edit
+static IS_EDIT_REQUESTED: AtomicBool = AtomicBool::new(false);
 
 #[allow(dead_code)]
 pub struct InteractiveSelect {
@@ -39,7 +58,11 @@ impl TryFrom<&mut InteractiveBrowse> for InteractiveSelect {
     type Error = Box<dyn std::error::Error + Send + Sync>;
 
     fn try_from(interactive_browse: &mut InteractiveBrowse) -> HttmResult<Self> {
-        let versions_map = VersionsMap::new(&GLOBAL_CONFIG, &interactive_browse.selected_pathdata)?;
+        let versions_map =
+            VersionsMap::with_progress(&GLOBAL_CONFIG, &interactive_browse.selected_pathdata)?;
+
+        // remember the live files as they were, before any overwrite restore, see LiveFileGuard
+        LiveFileGuard::record(&versions_map);
 
         // snap and live set has no snaps
         if versions_map.is_empty() {
@@ -67,7 +90,9 @@ impl TryFrom<&mut InteractiveBrowse> for InteractiveSelect {
 
         let view_mode = ViewMode::Select(opt_live_version.clone());
 
-        let snap_path_strings = if GLOBAL_CONFIG.opt_last_snap.is_some() {
+        let snap_path_strings = if GLOBAL_CONFIG.opt_last_snap.is_some()
+            || GLOBAL_CONFIG.opt_select_by.is_some()
+        {
             Self::last_snap(&versions_map)
         } else {
             // same stuff we do at fn exec, snooze...
@@ -89,7 +114,10 @@ impl TryFrom<&mut InteractiveBrowse> for InteractiveSelect {
             // loop until user selects a valid snapshot version
             loop {
                 // get the file name
-                let selected_line = view_mode.view_buffer(&selection_buffer, MultiSelect::On)?;
+                let (selected_line, final_key) =
+                    view_mode.view_buffer_with_key(&selection_buffer, MultiSelect::On)?;
+
+                IS_EDIT_REQUESTED.store(final_key == Key::Ctrl('o'), Ordering::Relaxed);
 
                 if let Some(background_handle) = interactive_browse.opt_background_handle.take() {
                     let _ = background_handle.join();
@@ -134,10 +162,17 @@ impl InteractiveSelect {
         map.iter()
             .filter_map(|(key, values)| {
                 if values.is_empty() {
-                    eprintln!(
-                        "WARN: No last snap of {:?} is available for selection.  Perhaps you omitted identical files.",
-                        key.path()
-                    );
+                    match GLOBAL_CONFIG.opt_select_by {
+                        Some(index) => eprintln!(
+                            "WARN: No snapshot version numbered {} of {:?} is available for selection.",
+                            index,
+                            key.path()
+                        ),
+                        None => eprintln!(
+                            "WARN: No last snap of {:?} is available for selection.  Perhaps you omitted identical files.",
+                            key.path()
+                        ),
+                    }
                     None
                 } else {
                     Some(values)
@@ -148,7 +183,79 @@ impl InteractiveSelect {
             .collect()
     }
 
+    fn binary_summary(snap_path: &Path, contents: &[u8]) -> HttmResult<String> {
+        let hash = HashFileContents::path_to_hash(snap_path)?;
+
+        let hex_dump = hex_dump(contents);
+
+        let truncated = if contents.len() > HEX_DUMP_MAX_BYTES {
+            format!(
+                "... ({} more bytes not shown, use \"--select=raw-contents\" to print raw bytes)\n",
+                contents.len() - HEX_DUMP_MAX_BYTES
+            )
+        } else {
+            String::new()
+        };
+
+        Ok(format!(
+            "binary file: {:?}, {} bytes, {} {}\n{hex_dump}{truncated}",
+            snap_path,
+            contents.len(),
+            SelectedHasher::ALGORITHM,
+            hash
+        ))
+    }
+
+    // the editor opens a read-only copy, so neither the editor's swap files, nor an accidental save,
+    // ever touch the snapshot
+    fn edit(snap_path: &Path) -> HttmResult<()> {
+        if !snap_path.is_file() {
+            let msg = format!("Path is not a file: {:?}", snap_path);
+            return Err(HttmError::new(&msg).into());
+        }
+
+        let Some(file_name) = snap_path.file_name() else {
+            let msg = format!("Could not determine a file name for: {:?}", snap_path);
+            return Err(HttmError::new(&msg).into());
+        };
+
+        let editor = ["VISUAL", "EDITOR"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| "vi".to_string());
+
+        let temp_copy = TempCopy::new(file_name, &std::fs::read(snap_path)?, true)?;
+
+        exec_user_program(&editor, &[temp_copy.path()])
+    }
+
+    fn opt_live_version(&self, snap_path: &Path) -> HttmResult<PathBuf> {
+        match &self.opt_live_version {
+            Some(live_version) => Some(PathBuf::from(live_version)),
+            None => {
+                let snap_pathdata = PathData::from(snap_path);
+                let opt_live_path = ZfsSnapPathGuard::new(&snap_pathdata)
+                    .and_then(|snap_guard| snap_guard.live_path());
+                opt_live_path
+            }
+        }
+        .ok_or_else(|| {
+            let msg = format!(
+                "Could not determine a possible live version for: {:?}",
+                snap_path
+            );
+            HttmError::new(&msg).into()
+        })
+    }
+
     pub fn print_selections(&self, select_mode: &SelectMode) -> HttmResult<()> {
+        let select_mode = if IS_EDIT_REQUESTED.load(Ordering::Relaxed) {
+            &SelectMode::Edit
+        } else {
+            select_mode
+        };
+
         self.snap_path_strings
             .iter()
             .map(Path::new)
@@ -170,7 +277,30 @@ impl InteractiveSelect {
 
                 print_output_buf(&output_buf)
             }
-            SelectMode::Contents => {
+            SelectMode::Pairs => {
+                let live_path = self.opt_live_version(snap_path)?;
+
+                let delimiter = delimiter();
+                let output_buf = match GLOBAL_CONFIG.print_mode {
+                    PrintMode::Raw(_) => {
+                        format!(
+                            "{}{delimiter}{}{delimiter}",
+                            snap_path.to_string_lossy(),
+                            live_path.to_string_lossy()
+                        )
+                    }
+                    PrintMode::Formatted(_) => {
+                        format!(
+                            "\"{}\" \"{}\"{delimiter}",
+                            snap_path.to_string_lossy(),
+                            live_path.to_string_lossy()
+                        )
+                    }
+                };
+
+                print_output_buf(&output_buf)
+            }
+            SelectMode::Contents | SelectMode::RawContents => {
                 if !snap_path.is_file() {
                     let msg = format!("Path is not a file: {:?}", snap_path);
                     return Err(HttmError::new(&msg).into());
@@ -179,58 +309,85 @@ impl InteractiveSelect {
                 let mut contents = Vec::new();
                 f.read_to_end(&mut contents)?;
 
-                // SAFETY: Panic here is not the end of the world as we are just printing the bytes.
-                // This is the same as simply `cat`-ing the file.
-                let output_buf = unsafe { std::str::from_utf8_unchecked(&contents) };
+                match std::str::from_utf8(&contents) {
+                    Ok(output_buf) => print_output_buf(output_buf),
+                    Err(_) if matches!(select_mode, SelectMode::Contents) => {
+                        let output_buf = Self::binary_summary(snap_path, &contents)?;
+
+                        print_output_buf(&output_buf)
+                    }
+                    Err(_) => {
+                        // SAFETY: Panic here is not the end of the world as we are just printing the bytes.
+                        // This is the same as simply `cat`-ing the file.
+                        let output_buf = unsafe { std::str::from_utf8_unchecked(&contents) };
 
-                print_output_buf(output_buf)
+                        print_output_buf(output_buf)
+                    }
+                }
+            }
+            SelectMode::Edit => Self::edit(snap_path),
+            SelectMode::Preview if GLOBAL_CONFIG.opt_preview.as_deref() == Some("builtin") => {
+                let output_buf = PreviewRender::render_and_page(snap_path)?;
+
+                print_output_buf(&output_buf)
             }
             SelectMode::Preview => {
                 let view_mode = &self.view_mode;
 
                 let preview_selection = PreviewSelection::new(&view_mode)?;
 
-                let cmd = if let Some(command) = preview_selection.opt_preview_command {
-                    command.replace("$snap_file", &format!("{:?}", snap_path))
-                } else {
-                    return Err(HttmError::new("Could not parse preview command").into());
-                };
+                let (output_buf, error_buf) = match (
+                    preview_selection.opt_preview_exec,
+                    preview_selection.opt_preview_command,
+                ) {
+                    // executed directly, without any shell, so we page the output ourselves, see PREVIEW_SHELL
+                    (Some(preview_exec), _) => {
+                        let (output_buf, error_buf) = preview_exec.output(snap_path)?;
 
-                let env_command =
-                    which::which("env").unwrap_or_else(|_| PathBuf::from("/usr/bin/env"));
-
-                let spawned = ExecProcess::new(env_command)
-                    .arg("bash")
-                    .arg("-c")
-                    .arg(cmd)
-                    .stdout(std::process::Stdio::piped())
-                    .stderr(std::process::Stdio::piped())
-                    .spawn()?;
-
-                match spawned.stdout {
-                    Some(mut stdout) => {
-                        let mut output_buf = String::new();
-                        stdout.read_to_string(&mut output_buf)?;
-                        print_output_buf(&output_buf)
-                    }
-                    None => match spawned.stderr {
-                        Some(mut stderr) => {
-                            let mut output_buf = String::new();
-                            stderr.read_to_string(&mut output_buf)?;
-                            if !output_buf.is_empty() {
-                                eprintln!("{}", &output_buf)
+                        let output_buf = match &GLOBAL_CONFIG.opt_preview_pager {
+                            Some(pager) if !output_buf.is_empty() => {
+                                PreviewRender::page(pager, output_buf)?
                             }
-                            Ok(())
-                        }
-                        None => {
-                            let msg = format!(
-                                "Preview command output was empty for path: {:?}",
-                                snap_path
-                            );
-                            Err(HttmError::new(&msg).into())
-                        }
-                    },
+                            _ => output_buf,
+                        };
+
+                        (output_buf, error_buf)
+                    }
+                    // as if the interactive view had substituted its selection line, which quotes the path,
+                    // so the bootstrap script, not us, assigns each preview variable
+                    (None, Some(command)) => {
+                        let selection_line = format!("\"{}\"", snap_path.to_string_lossy());
+
+                        let cmd = command.replace(
+                            "raw_input={}",
+                            &format!("raw_input={}", ShellKind::Posix.quote(&selection_line)),
+                        );
+
+                        // the bootstrap script is POSIX sh compatible, so prefer bash, but fall back to sh
+                        let shell_command = which::which("bash").or_else(|_| which::which("sh")).map_err(|_err| {
+                            HttmError::new("Neither 'bash' nor 'sh' could be found in the user's PATH. A shell is necessary for executing a preview command.")
+                        })?;
+
+                        let mut exec_process = ExecProcess::new(shell_command);
+
+                        exec_process.arg("-c").arg(cmd);
+
+                        output_with_timeout(&mut exec_process, snap_path)?
+                    }
+                    (None, None) => {
+                        return Err(HttmError::new("Could not parse preview command").into());
+                    }
+                };
+
+                if !output_buf.is_empty() {
+                    return print_output_buf(&output_buf);
                 }
+
+                if !error_buf.is_empty() {
+                    eprintln!("{}", &error_buf)
+                }
+
+                Ok(())
             }
         }
     }
@@ -0,0 +1,285 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::data::paths::PathData;
+use crate::interactive::view_mode::{MultiSelect, ViewMode};
+use crate::library::audit::AuditLog;
+use crate::library::i18n::{Consent, Msg};
+use crate::library::results::{HttmError, HttmResult};
+use crate::lookup::versions::VersionsMap;
+use crate::zfs::snap_guard::{PrecautionarySnapType, SnapGuard};
+use crate::GLOBAL_CONFIG;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+// "archive then remove": before deleting any live file, we make certain a snapshot contains its
+// current version, and, if the newest snapshot does not, we take a new snapshot first, but only once
+// the user has consented, so nothing may change between the snapshot and the removal unnoticed
+pub struct SoftDelete;
+
+impl SoftDelete {
+    pub fn exec(versions_map: &VersionsMap) -> HttmResult<()> {
+        if let Some(missing) = versions_map
+            .keys()
+            .find(|live_version| live_version.opt_metadata().is_none())
+        {
+            let msg = format!(
+                "Cannot soft delete a file which does not exist: {:?}",
+                missing.path()
+            );
+            return Err(HttmError::new(&msg).into());
+        }
+
+        let uncaptured: Vec<&PathData> = versions_map
+            .iter()
+            .filter(|(live_version, snaps)| !Self::is_captured(live_version, snaps))
+            .map(|(live_version, _snaps)| live_version)
+            .collect();
+
+        if GLOBAL_CONFIG.opt_dry_run {
+            if !uncaptured.is_empty() {
+                Self::snapshot(&uncaptured)?;
+            }
+
+            let paths_string: String = versions_map
+                .keys()
+                .map(|live_version| format!("{:?}\n", live_version.path()))
+                .collect();
+
+            println!(
+                "httm would delete the following live file/s:\n\n{paths_string}\n\
+                Dry run.  No files were deleted."
+            );
+
+            return Ok(());
+        }
+
+        InteractiveSoftDelete::new(versions_map, &uncaptured)
+    }
+
+    // a snapshot version is identical, as best we can tell, when its metadata is the same.  Directories
+    // are never considered captured by an existing snapshot, as the contents of a directory may have
+    // changed, even when its own metadata has not
+    fn is_captured(live_version: &PathData, snaps: &[PathData]) -> bool {
+        let Some(newest) = snaps.last() else {
+            return false;
+        };
+
+        if live_version.path().is_dir() {
+            return false;
+        }
+
+        newest.opt_metadata() == live_version.opt_metadata()
+    }
+
+    // via SnapGuard, so btrfs subvolumes are guarded as well as ZFS datasets
+    fn snapshot(uncaptured: &[&PathData]) -> HttmResult<()> {
+        SnapGuard::from_paths(
+            uncaptured.iter().map(|pathdata| pathdata.path()),
+            PrecautionarySnapType::PreSoftDelete,
+        )
+        .map(|_guards| ())
+    }
+
+    // called only once the user has consented, see InteractiveSoftDelete
+    fn delete_all(versions_map: &VersionsMap, uncaptured: &[&PathData]) -> HttmResult<()> {
+        if !uncaptured.is_empty() {
+            Self::snapshot(uncaptured)?;
+        }
+
+        versions_map.iter().try_for_each(|(live_version, snaps)| {
+            let opt_preserved = if uncaptured.contains(&live_version) {
+                SnapGuard::preserved_version(live_version.path())
+                    .map(|(_snap_name, preserved)| preserved)
+            } else {
+                snaps.last().map(|newest| newest.path().to_path_buf())
+            };
+
+            // never delete a file we cannot prove is preserved
+            let Some(preserved) = opt_preserved else {
+                let msg = format!(
+                    "No snapshot contains the current version of the following file, and the file has not been deleted: {:?}",
+                    live_version.path()
+                );
+                return Err(HttmError::new(&msg).into());
+            };
+
+            let res = Self::delete(live_version, &preserved);
+
+            AuditLog::soft_delete(&preserved, live_version.path(), &res);
+
+            res
+        })
+    }
+
+    // the live file must be just as it was when we proved it was captured, or we refuse to delete it
+    fn delete(live_version: &PathData, preserved: &Path) -> HttmResult<()> {
+        let live_path = live_version.path();
+
+        if PathData::from(live_path).opt_metadata() != live_version.opt_metadata() {
+            return Err(Self::modified_err(live_path));
+        }
+
+        let md = live_path.symlink_metadata()?;
+
+        if md.is_dir() {
+            Self::verify_one_filesystem(live_path, md.dev())?;
+            Self::remove_dir_preserved(live_path, preserved, md.dev())?;
+        } else {
+            Self::verify_preserved(live_path, preserved)?;
+            std::fs::remove_file(live_path)?;
+        }
+
+        Ok(())
+    }
+
+    // each file is compared to its preserved copy just before it is removed, so a file
+    // modified since the snapshot was taken is never lost
+    fn verify_preserved(live_path: &Path, preserved: &Path) -> HttmResult<()> {
+        let live_metadata = PathData::from(live_path).opt_metadata();
+
+        if live_metadata.is_none() || PathData::from(preserved).opt_metadata() != live_metadata {
+            return Err(Self::modified_err(live_path));
+        }
+
+        Ok(())
+    }
+
+    fn modified_err(live_path: &Path) -> Box<dyn std::error::Error + Send + Sync> {
+        let msg = format!(
+            "httm will not delete {:?}, as the live file was modified, created, or removed since httm found it preserved by a snapshot.  \
+            Please review the live file, and try the soft delete again.  Quitting.",
+            live_path
+        );
+
+        HttmError::new(&msg).into()
+    }
+
+    // a snapshot of one dataset never preserves another filesystem mounted beneath it,
+    // so check the whole tree before we remove anything
+    fn verify_one_filesystem(dir: &Path, dev: u64) -> HttmResult<()> {
+        std::fs::read_dir(dir)?.try_for_each(|entry| {
+            let entry = entry?;
+            let md = entry.path().symlink_metadata()?;
+
+            if md.dev() != dev {
+                return Err(Self::other_filesystem_err(&entry.path()));
+            }
+
+            if md.is_dir() {
+                return Self::verify_one_filesystem(&entry.path(), dev);
+            }
+
+            Ok(())
+        })
+    }
+
+    fn other_filesystem_err(path: &Path) -> Box<dyn std::error::Error + Send + Sync> {
+        let msg = format!(
+            "httm will not delete {:?}, as another filesystem is mounted there, which no snapshot of this dataset preserves.  Quitting.",
+            path
+        );
+
+        HttmError::new(&msg).into()
+    }
+
+    fn remove_dir_preserved(dir: &Path, preserved_dir: &Path, dev: u64) -> HttmResult<()> {
+        std::fs::read_dir(dir)?.try_for_each(|entry| {
+            let entry = entry?;
+            let md = entry.path().symlink_metadata()?;
+            let preserved: PathBuf = preserved_dir.join(entry.file_name());
+
+            if md.dev() != dev {
+                return Err(Self::other_filesystem_err(&entry.path()));
+            }
+
+            if md.is_dir() {
+                Self::remove_dir_preserved(&entry.path(), &preserved, dev)
+            } else {
+                Self::verify_preserved(&entry.path(), &preserved)?;
+                std::fs::remove_file(entry.path()).map_err(|err| err.into())
+            }
+        })?;
+
+        std::fs::remove_dir(dir)?;
+
+        Ok(())
+    }
+}
+
+struct InteractiveSoftDelete;
+
+impl InteractiveSoftDelete {
+    fn new(versions_map: &VersionsMap, uncaptured: &[&PathData]) -> HttmResult<()> {
+        let pairs_string: String = versions_map
+            .iter()
+            .map(|(live_version, snaps)| match snaps.last() {
+                Some(newest) if !uncaptured.contains(&live_version) => format!(
+                    "{:?}\n\tpreserved at: {:?}\n",
+                    live_version.path(),
+                    newest.path()
+                ),
+                _ => format!(
+                    "{:?}\n\tto be preserved by a new snapshot\n",
+                    live_version.path()
+                ),
+            })
+            .collect();
+
+        let soft_delete_buffer = format!(
+            "User has requested the following live file/s be deleted.  Each is, or will be, preserved by a snapshot version:\n\n{}\n\
+            {}\n\
+            ─────────────────────────────────────────────────────────────────────────────\n\
+            {}",
+            pairs_string,
+            Msg::SoftDeleteConsent.text(),
+            Consent::choices()
+        );
+
+        // loop until user consents or doesn't
+        loop {
+            let view_mode = ViewMode::SoftDelete;
+
+            let selection = view_mode.view_buffer(&soft_delete_buffer, MultiSelect::Off)?;
+
+            let user_consent = selection
+                .get(0)
+                .ok_or_else(|| HttmError::new("Could not obtain the first match selected"))?;
+
+            match Consent::from(user_consent.as_str()) {
+                Consent::Yes => {
+                    SoftDelete::delete_all(versions_map, uncaptured)?;
+
+                    let result_buffer = format!(
+                        "httm deleted the following live file/s:\n\n{}\n\
+                        Soft delete completed successfully.",
+                        pairs_string
+                    );
+
+                    break eprintln!("{result_buffer}");
+                }
+                Consent::No => {
+                    break eprintln!("User declined soft delete.  No files were deleted.")
+                }
+                // if not yes or no, then noop and continue to the next iter of loop
+                Consent::Undecided => {}
+            }
+        }
+
+        Ok(())
+    }
+}
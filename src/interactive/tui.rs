@@ -0,0 +1,625 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::data::paths::PathData;
+use crate::display::preview_render::PreviewRender;
+use crate::display::unified_diff::UnifiedDiff;
+use crate::interactive::restore::{InteractiveRestore, LiveFileGuard};
+use crate::interactive::view_mode::ViewMode;
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{date_string, display_human_size, split_snap_path, DateFormat};
+use crate::lookup::versions::VersionsMap;
+use crate::GLOBAL_CONFIG;
+use hashbrown::HashMap;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{
+    disable_raw_mode,
+    enable_raw_mode,
+    EnterAlternateScreen,
+    LeaveAlternateScreen,
+};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{Frame, Terminal};
+use std::fs::read_dir;
+use std::io::{Stdout, Write};
+use std::path::{Path, PathBuf};
+
+const TUI_KEY_HELP: &str =
+    "tab: switch pane | enter/→: open | ←: parent | d: diff | r: restore | y: copy path | q: quit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Files,
+    Versions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum PreviewKind {
+    Contents,
+    Diff,
+}
+
+// a preview is rendered once for each kind, file, and version, not once for each frame drawn
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PreviewKey {
+    kind: PreviewKind,
+    opt_live: Option<PathBuf>,
+    opt_version: Option<PathBuf>,
+}
+
+enum TuiExit {
+    Quit,
+    Restore(PathData, PathData),
+}
+
+// an alternative to the skim browse and select views: a file pane, a timeline of the selected
+// file's versions, oldest first, and a preview of the selected version, all at once
+pub struct Tui {
+    current_dir: PathBuf,
+    files: Vec<PathData>,
+    file_state: ListState,
+    // key: live path, val: snapshot versions, as ordered by VersionsMap, oldest first
+    versions_cache: HashMap<PathBuf, Vec<PathData>>,
+    preview_cache: HashMap<PreviewKey, (String, Text<'static>)>,
+    version_state: ListState,
+    focus: Pane,
+    preview_kind: PreviewKind,
+    preview_scroll: u16,
+    status: String,
+}
+
+impl Tui {
+    pub fn exec() -> HttmResult<()> {
+        let start = GLOBAL_CONFIG
+            .paths
+            .get(0)
+            .map(|pathdata| pathdata.path().to_path_buf())
+            .ok_or_else(|| HttmError::new("httm could not determine a directory to browse."))?;
+
+        let mut tui = Self::new(&start)?;
+
+        loop {
+            let exit = tui.run()?;
+
+            match exit {
+                TuiExit::Quit => return Ok(()),
+                TuiExit::Restore(live, snap) => {
+                    let res = Self::restore(&live, &snap);
+
+                    // only return to the TUI when the user has so requested, see CONTINUE
+                    if !GLOBAL_CONFIG.opt_continue {
+                        return res;
+                    }
+
+                    if let Err(err) = res {
+                        eprintln!("WARN: {err}");
+                    }
+
+                    // the restore may have added or modified files
+                    tui.versions_cache.clear();
+                    tui.preview_cache.clear();
+                    tui.read_current_dir();
+                }
+            }
+        }
+    }
+
+    fn new(start: &Path) -> HttmResult<Self> {
+        // when the user has requested a file, browse its directory, with the file selected
+        let (current_dir, opt_selected) = if start.is_dir() {
+            (start.to_path_buf(), None)
+        } else {
+            match start.parent() {
+                Some(parent) => (parent.to_path_buf(), Some(start)),
+                None => {
+                    let msg = format!("httm could not determine a directory to browse: {start:?}");
+                    return Err(HttmError::new(&msg).into());
+                }
+            }
+        };
+
+        let mut tui = Self {
+            current_dir,
+            files: Vec::new(),
+            file_state: ListState::default(),
+            versions_cache: HashMap::new(),
+            preview_cache: HashMap::new(),
+            version_state: ListState::default(),
+            focus: Pane::Files,
+            preview_kind: PreviewKind::Contents,
+            preview_scroll: 0,
+            status: TUI_KEY_HELP.to_string(),
+        };
+
+        tui.read_current_dir();
+
+        if let Some(selected) = opt_selected {
+            let opt_idx = tui
+                .files
+                .iter()
+                .position(|pathdata| pathdata.path() == selected);
+
+            tui.file_state.select(opt_idx.or(Some(0)));
+            tui.select_latest_version();
+        }
+
+        Ok(tui)
+    }
+
+    fn run(&mut self) -> HttmResult<TuiExit> {
+        enable_raw_mode()?;
+
+        let mut stdout = std::io::stdout();
+        execute!(stdout, EnterAlternateScreen)?;
+
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+        let res = self.event_loop(&mut terminal);
+
+        // always return the terminal to the user as we found it, even on error
+        disable_raw_mode()?;
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        terminal.show_cursor()?;
+
+        res
+    }
+
+    fn event_loop(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    ) -> HttmResult<TuiExit> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if let Some(exit) = self.handle_key(key) {
+                return Ok(exit);
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Option<TuiExit> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Some(TuiExit::Quit),
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Pane::Files => Pane::Versions,
+                    Pane::Versions => Pane::Files,
+                };
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::PageDown => self.preview_scroll = self.preview_scroll.saturating_add(10),
+            KeyCode::PageUp => self.preview_scroll = self.preview_scroll.saturating_sub(10),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => self.open(),
+            KeyCode::Left | KeyCode::Backspace | KeyCode::Char('h') => self.parent(),
+            KeyCode::Char('d') => {
+                self.preview_kind = match self.preview_kind {
+                    PreviewKind::Contents => PreviewKind::Diff,
+                    PreviewKind::Diff => PreviewKind::Contents,
+                };
+                self.preview_scroll = 0;
+            }
+            KeyCode::Char('y') => self.copy_path(),
+            KeyCode::Char('r') => match (self.selected_file(), self.selected_version()) {
+                (Some(live), Some(snap)) => return Some(TuiExit::Restore(live, snap)),
+                _ => self.status = "Select a snapshot version to restore.".to_string(),
+            },
+            _ => {}
+        }
+
+        None
+    }
+
+    fn read_current_dir(&mut self) {
+        let mut files: Vec<PathData> = match read_dir(&self.current_dir) {
+            Ok(read_dir) => read_dir
+                .flatten()
+                .map(|entry| PathData::from(entry.path().as_path()))
+                .collect(),
+            Err(err) => {
+                self.status = format!("Could not read {:?}: {err}", self.current_dir);
+                Vec::new()
+            }
+        };
+
+        // directories first, then by name
+        files.sort_by(|a, b| {
+            b.path()
+                .is_dir()
+                .cmp(&a.path().is_dir())
+                .then_with(|| a.path().cmp(b.path()))
+        });
+
+        self.files = files;
+        self.file_state
+            .select(if self.files.is_empty() { None } else { Some(0) });
+        self.select_latest_version();
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let (state, len) = match self.focus {
+            Pane::Files => (&mut self.file_state, self.files.len()),
+            Pane::Versions => {
+                let len = self.selected_versions().len();
+                (&mut self.version_state, len)
+            }
+        };
+
+        if len == 0 {
+            return;
+        }
+
+        let idx = state.selected().unwrap_or(0) as isize + delta;
+        state.select(Some(idx.clamp(0, len as isize - 1) as usize));
+
+        if self.focus == Pane::Files {
+            self.select_latest_version();
+        }
+
+        self.preview_scroll = 0;
+    }
+
+    fn open(&mut self) {
+        match self.focus {
+            Pane::Files => match self.selected_file() {
+                Some(pathdata) if pathdata.path().is_dir() => {
+                    self.current_dir = pathdata.path().to_path_buf();
+                    self.read_current_dir();
+                }
+                Some(_) => self.focus = Pane::Versions,
+                None => {}
+            },
+            Pane::Versions => {
+                self.preview_kind = PreviewKind::Contents;
+                self.preview_scroll = 0;
+            }
+        }
+    }
+
+    fn parent(&mut self) {
+        if self.focus == Pane::Versions {
+            self.focus = Pane::Files;
+            return;
+        }
+
+        let Some(parent) = self.current_dir.parent().map(Path::to_path_buf) else {
+            return;
+        };
+
+        let previous = std::mem::replace(&mut self.current_dir, parent);
+
+        self.read_current_dir();
+
+        let opt_idx = self
+            .files
+            .iter()
+            .position(|pathdata| pathdata.path() == previous);
+
+        if opt_idx.is_some() {
+            self.file_state.select(opt_idx);
+            self.select_latest_version();
+        }
+    }
+
+    fn selected_file(&self) -> Option<PathData> {
+        self.file_state
+            .selected()
+            .and_then(|idx| self.files.get(idx))
+            .cloned()
+    }
+
+    // versions are only ever looked up here, as the selected file changes, and never while drawing
+    fn load_selected_versions(&mut self) {
+        let Some(live) = self.selected_file() else {
+            return;
+        };
+
+        self.versions_cache
+            .entry(live.path().to_path_buf())
+            .or_insert_with(|| {
+                VersionsMap::new(&GLOBAL_CONFIG, &[live.clone()])
+                    .ok()
+                    .and_then(|versions_map| versions_map.values().next().cloned())
+                    .unwrap_or_default()
+            });
+    }
+
+    fn selected_versions(&self) -> &[PathData] {
+        self.selected_file()
+            .and_then(|live| self.versions_cache.get(live.path()))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn selected_version(&self) -> Option<PathData> {
+        let idx = self.version_state.selected()?;
+
+        self.selected_versions().get(idx).cloned()
+    }
+
+    fn select_latest_version(&mut self) {
+        self.load_selected_versions();
+
+        let len = self.selected_versions().len();
+
+        self.version_state.select(len.checked_sub(1));
+    }
+
+    // copy by way of the OSC 52 escape sequence, which most terminal emulators, and tmux,
+    // support, and which works over ssh, where no local clipboard may be available
+    fn copy_path(&mut self) {
+        let opt_pathdata = match self.focus {
+            Pane::Files => self.selected_file(),
+            Pane::Versions => self.selected_version(),
+        };
+
+        let Some(pathdata) = opt_pathdata else {
+            return;
+        };
+
+        let path_string = pathdata.path().to_string_lossy();
+
+        let mut stdout = std::io::stdout();
+        let res = write!(stdout, "\x1b]52;c;{}\x07", base64(path_string.as_bytes()))
+            .and_then(|_| stdout.flush());
+
+        self.status = match res {
+            Ok(_) => format!("Copied: {path_string}"),
+            Err(err) => format!("Could not copy path: {err}"),
+        };
+    }
+
+    fn restore(live: &PathData, snap: &PathData) -> HttmResult<()> {
+        let versions_map = VersionsMap::new(&GLOBAL_CONFIG, &[live.clone()])?;
+
+        // remember the live file as it was, before any overwrite restore, see LiveFileGuard
+        LiveFileGuard::record(&versions_map);
+
+        let interactive_restore = InteractiveRestore {
+            _view_mode: ViewMode::Restore,
+            snap_path_strings: vec![snap.path().to_string_lossy().into_owned()],
+            opt_live_version: Some(live.path().to_string_lossy().into_owned()),
+        };
+
+        interactive_restore.restore()
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main_area, status_area] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(1)])
+            .areas(frame.area());
+
+        let [files_area, versions_area, preview_area] = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(30),
+                Constraint::Percentage(30),
+                Constraint::Percentage(40),
+            ])
+            .areas(main_area);
+
+        self.draw_files(frame, files_area);
+        self.draw_versions(frame, versions_area);
+        self.draw_preview(frame, preview_area);
+
+        frame.render_widget(
+            Paragraph::new(self.status.as_str()).style(Style::default().fg(Color::DarkGray)),
+            status_area,
+        );
+    }
+
+    fn pane_block(&self, title: String, pane: Pane) -> Block<'static> {
+        let border_style = if self.focus == pane {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(border_style)
+            .title(title)
+    }
+
+    fn draw_files(&mut self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .files
+            .iter()
+            .map(|pathdata| {
+                let name = pathdata
+                    .path()
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+
+                if pathdata.path().is_dir() {
+                    ListItem::new(format!("{name}/")).style(Style::default().fg(Color::Blue))
+                } else {
+                    ListItem::new(name)
+                }
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(self.pane_block(self.current_dir.to_string_lossy().into_owned(), Pane::Files))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(list, area, &mut self.file_state);
+    }
+
+    fn draw_versions(&mut self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .selected_versions()
+            .iter()
+            .map(|snap| {
+                let date = snap
+                    .opt_metadata()
+                    .map(|md| {
+                        format!(
+                            "{}  {:>10}",
+                            date_string(
+                                GLOBAL_CONFIG.requested_utc_offset,
+                                &md.mtime(),
+                                DateFormat::Display
+                            ),
+                            display_human_size(md.size())
+                        )
+                    })
+                    .unwrap_or_default();
+
+                let snap_mount = split_snap_path(snap.path())
+                    .and_then(|(snap_mount, _dataset, _relative)| snap_mount.file_name())
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+
+                ListItem::new(Text::from(vec![
+                    Line::from(date),
+                    Line::styled(
+                        format!("  {snap_mount}"),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]))
+            })
+            .collect();
+
+        let title = format!("Versions ({})", items.len());
+
+        let list = List::new(items)
+            .block(self.pane_block(title, Pane::Versions))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(list, area, &mut self.version_state);
+    }
+
+    fn draw_preview(&mut self, frame: &mut Frame, area: Rect) {
+        let opt_live = self.selected_file();
+        let opt_version = self.selected_version();
+
+        let preview_kind = self.preview_kind;
+
+        let key = PreviewKey {
+            kind: preview_kind,
+            opt_live: opt_live.as_ref().map(|live| live.path().to_path_buf()),
+            opt_version: opt_version.as_ref().map(|snap| snap.path().to_path_buf()),
+        };
+
+        let (title, text) = self
+            .preview_cache
+            .entry(key)
+            .or_insert_with(|| Self::render_preview(preview_kind, opt_live, opt_version))
+            .clone();
+
+        let paragraph = Paragraph::new(text)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .wrap(Wrap { trim: false })
+            .scroll((self.preview_scroll, 0));
+
+        frame.render_widget(paragraph, area);
+    }
+
+    fn render_preview(
+        preview_kind: PreviewKind,
+        opt_live: Option<PathData>,
+        opt_version: Option<PathData>,
+    ) -> (String, Text<'static>) {
+        match (preview_kind, opt_live, opt_version) {
+            (PreviewKind::Diff, Some(live), Some(snap)) => (
+                "Diff: snapshot -> live".to_string(),
+                Self::diff_text(snap.path(), live.path()),
+            ),
+            (PreviewKind::Diff, _, None) => (
+                "Diff".to_string(),
+                Text::raw("Select a snapshot version to diff against the live version."),
+            ),
+            (_, opt_live, opt_version) => match opt_version.or(opt_live) {
+                Some(pathdata) => (
+                    "Preview".to_string(),
+                    Text::raw(
+                        PreviewRender::render(pathdata.path())
+                            .unwrap_or_else(|err| format!("Could not preview: {err}")),
+                    ),
+                ),
+                None => ("Preview".to_string(), Text::default()),
+            },
+        }
+    }
+
+    fn diff_text(snap_path: &Path, live_path: &Path) -> Text<'static> {
+        let buffer = match UnifiedDiff::pair_buffer(snap_path, live_path, false) {
+            Ok(buffer) if buffer.is_empty() => {
+                return Text::raw("The snapshot version and the live version are identical.")
+            }
+            Ok(buffer) => buffer,
+            Err(err) => return Text::raw(format!("Could not diff: {err}")),
+        };
+
+        let lines: Vec<Line> = buffer
+            .lines()
+            .map(|line| {
+                let color = match line.as_bytes().first() {
+                    _ if line.starts_with("---") || line.starts_with("+++") => Color::Reset,
+                    Some(b'@') => Color::Cyan,
+                    Some(b'-') => Color::Red,
+                    Some(b'+') => Color::Green,
+                    _ => Color::Reset,
+                };
+
+                Line::styled(line.to_string(), Style::default().fg(color))
+            })
+            .collect();
+
+        Text::from(lines)
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    bytes
+        .chunks(3)
+        .flat_map(|chunk| {
+            let buf = [
+                chunk[0],
+                chunk.get(1).copied().unwrap_or(0),
+                chunk.get(2).copied().unwrap_or(0),
+            ];
+            let triple = u32::from_be_bytes([0, buf[0], buf[1], buf[2]]);
+
+            (0..4).map(move |idx| {
+                if idx > chunk.len() {
+                    return '=';
+                }
+
+                ALPHABET[((triple >> (18 - 6 * idx)) & 0x3f) as usize] as char
+            })
+        })
+        .collect()
+}
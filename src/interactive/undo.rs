@@ -0,0 +1,285 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::data::paths::PathData;
+use crate::interactive::view_mode::{MultiSelect, ViewMode};
+use crate::library::audit::AuditLog;
+use crate::library::file_ops::{Copy, HashFileContents, Remove};
+use crate::library::i18n::{Consent, Msg};
+use crate::library::results::{HttmError, HttmResult};
+use crate::lookup::versions::VersionsMap;
+use crate::GLOBAL_CONFIG;
+use hashbrown::HashSet;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+// the restore, as recorded in the audit log, which we would undo
+struct RestoreRecord<'a> {
+    id: &'a str,
+    timestamp: &'a str,
+    source: &'a str,
+    destination: PathBuf,
+    mode: &'a str,
+    opt_hash: Option<&'a str>,
+    opt_prior_type: Option<&'a str>,
+    opt_prior_hash: Option<&'a str>,
+    opt_guard_version: Option<&'a str>,
+}
+
+// the state of the destination before the restore
+enum PreviousState {
+    // the live file overwritten is preserved at this snapshot path
+    Version(PathBuf, &'static str),
+    // the restore created the destination
+    Absent,
+}
+
+pub struct UndoRestore;
+
+impl UndoRestore {
+    pub fn exec() -> HttmResult<()> {
+        let records = AuditLog::read()?;
+
+        let restore = Self::last_undoable(&records).ok_or_else(|| {
+            HttmError::new(
+                "httm could not find a successful restore, not already undone, in its audit log.",
+            )
+        })?;
+
+        let previous_state = Self::previous_state(&restore)?;
+
+        let dst = restore.destination.as_path();
+
+        let action = match &previous_state {
+            PreviousState::Version(snap_path, reason) => {
+                format!("httm will restore {dst:?}\n\tfrom: {snap_path:?}\n\t({reason})\n")
+            }
+            PreviousState::Absent => {
+                format!("httm will remove {dst:?}\n\t(the restore created it)\n")
+            }
+        };
+
+        // the user may have since modified the restored file, and we would not lose such changes silently
+        let opt_warning = restore
+            .opt_hash
+            .filter(|_hash| dst.is_file())
+            .filter(|hash| {
+                HashFileContents::path_to_hash(dst).map_or(true, |current| current != *hash)
+            })
+            .map(|_hash| {
+                format!("WARN: {dst:?} has been modified since it was restored.  Such modifications will be lost.\n")
+            });
+
+        let undo_buffer = format!(
+            "User has requested the most recent restore be undone:\n\n\
+            {}: {:?} -> {:?}\n\n\
+            {}{}\n",
+            restore.timestamp,
+            restore.source,
+            dst,
+            action,
+            opt_warning.unwrap_or_default()
+        );
+
+        if GLOBAL_CONFIG.opt_dry_run {
+            eprintln!("{undo_buffer}Dry run.  No files were modified.");
+            return Ok(());
+        }
+
+        let consent_buffer = format!(
+            "{}\
+            {}\n\
+            ─────────────────────────────────────────────────────────────────────────────\n\
+            {}",
+            undo_buffer,
+            Msg::UndoConsent.text(),
+            Consent::choices()
+        );
+
+        // loop until user consents or doesn't
+        loop {
+            let view_mode = ViewMode::Restore;
+
+            let selection = view_mode.view_buffer(&consent_buffer, MultiSelect::Off)?;
+
+            let user_consent = selection
+                .get(0)
+                .ok_or_else(|| HttmError::new("Could not obtain the first match selected"))?;
+
+            match Consent::from(user_consent.as_str()) {
+                Consent::Yes => {
+                    let res = match &previous_state {
+                        PreviousState::Version(snap_path, _reason) => {
+                            Copy::recursive_quiet(snap_path, dst, true)
+                                .and_then(|_| Self::remove_added(snap_path, dst))
+                        }
+                        PreviousState::Absent => Remove::recursive_quiet(dst),
+                    };
+
+                    AuditLog::undo(restore.id, dst, &res);
+
+                    res?;
+
+                    break eprintln!("{undo_buffer}Undo completed successfully.");
+                }
+                Consent::No => break eprintln!("User declined undo.  No files were modified."),
+                // if not yes or no, then noop and continue to the next iter of loop
+                Consent::Undecided => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    // the most recent successful restore, skipping any already undone, so that repeated
+    // undos step back through restores, one by one
+    // a restore is identified by its record's id, as many restores may share a timestamp.  A restore
+    // recorded without an id, or without the state of its destination beforehand, cannot be undone
+    fn last_undoable(records: &[Value]) -> Option<RestoreRecord> {
+        let mut undone: HashSet<&str> = HashSet::new();
+
+        records.iter().rev().find_map(|record| {
+            let event = record["event"].as_str()?;
+            let source = record["source"].as_str()?;
+            let destination = record["destination"].as_str()?;
+
+            if record["result"].as_str() != Some("success") {
+                return None;
+            }
+
+            match event {
+                "undo" => {
+                    undone.insert(source);
+                    None
+                }
+                "restore" => {
+                    let id = record["id"].as_str()?;
+
+                    if undone.contains(id) {
+                        return None;
+                    }
+
+                    Some(RestoreRecord {
+                        id,
+                        timestamp: record["timestamp"].as_str().unwrap_or_default(),
+                        source,
+                        destination: PathBuf::from(destination),
+                        mode: record["mode"].as_str().unwrap_or_default(),
+                        opt_hash: record["hash"].as_str(),
+                        opt_prior_type: record["prior_type"].as_str(),
+                        opt_prior_hash: record["prior_hash"].as_str(),
+                        opt_guard_version: record["guard_version"].as_str(),
+                    })
+                }
+                _ => None,
+            }
+        })
+    }
+
+    fn previous_state(restore: &RestoreRecord) -> HttmResult<PreviousState> {
+        if matches!(restore.mode, "copy" | "copy-and-preserve") {
+            return Ok(PreviousState::Absent);
+        }
+
+        match restore.opt_prior_type {
+            Some("absent") => return Ok(PreviousState::Absent),
+            Some(_) => {}
+            None => {
+                let msg = format!(
+                    "httm did not record the state of {:?} before the restore, and so cannot undo it.",
+                    restore.destination
+                );
+                return Err(HttmError::new(&msg).into());
+            }
+        }
+
+        // only the exact precautionary snapshot taken before this restore
+        if let Some(guard_version) = restore.opt_guard_version {
+            let snap_path = PathBuf::from(guard_version);
+
+            if snap_path.symlink_metadata().is_err() {
+                let msg = format!(
+                    "The precautionary snapshot taken before the restore, which preserved {:?}, no longer exists: {:?}",
+                    restore.destination, snap_path
+                );
+                return Err(HttmError::new(&msg).into());
+            }
+
+            return Ok(PreviousState::Version(
+                snap_path,
+                "preserved by the precautionary snapshot taken before the restore",
+            ));
+        }
+
+        Self::from_prior_hash(restore)
+            .map(|snap_path| {
+                PreviousState::Version(
+                    snap_path,
+                    "the most recent snapshot version identical to the file overwritten",
+                )
+            })
+            .ok_or_else(|| {
+                let msg = format!(
+                    "httm could not find the previous state of {:?} upon any snapshot.",
+                    restore.destination
+                );
+                HttmError::new(&msg).into()
+            })
+    }
+
+    // without a precautionary snapshot, only a snapshot version whose contents are exactly those
+    // of the file overwritten will do
+    fn from_prior_hash(restore: &RestoreRecord) -> Option<PathBuf> {
+        let prior_hash = restore.opt_prior_hash?;
+
+        let dst_pathdata = PathData::from(restore.destination.as_path());
+
+        let versions_map = VersionsMap::new(&GLOBAL_CONFIG, &[dst_pathdata]).ok()?;
+
+        versions_map
+            .values()
+            .flatten()
+            .rev()
+            .map(|snap_pd| snap_pd.path())
+            .filter(|snap_path| snap_path.is_file())
+            .find(|snap_path| {
+                HashFileContents::path_to_hash(snap_path).is_ok_and(|hash| hash == prior_hash)
+            })
+            .map(Path::to_path_buf)
+    }
+
+    // copying a directory back from a snapshot leaves in place whatever the restore added, so
+    // remove each entry of the live directory which the snapshot version does not have
+    fn remove_added(snap_dir: &Path, live_dir: &Path) -> HttmResult<()> {
+        if !snap_dir.is_dir() || snap_dir.is_symlink() {
+            return Ok(());
+        }
+
+        std::fs::read_dir(live_dir)?.try_for_each(|entry| {
+            let entry = entry?;
+            let snap_version = snap_dir.join(entry.file_name());
+
+            match snap_version.symlink_metadata() {
+                Err(_) => Remove::recursive_quiet(&entry.path()),
+                Ok(md) if md.is_dir() && entry.file_type()?.is_dir() => {
+                    Self::remove_added(&snap_version, &entry.path())
+                }
+                Ok(_) => Ok(()),
+            }
+        })
+    }
+}
@@ -15,8 +15,10 @@
 // For the full copyright and license information, please view the LICENSE file
 // that was distributed with this source code.
 
+use crate::interactive::browse::AvailabilityFilter;
 use crate::interactive::preview::PreviewSelection;
-use crate::library::results::HttmError;
+use crate::library::i18n::Msg;
+use crate::library::results::{HttmAbort, HttmError};
 use crate::{HttmResult, GLOBAL_CONFIG};
 use skim::prelude::*;
 use std::io::Cursor;
@@ -26,6 +28,7 @@ pub enum ViewMode {
     Select(Option<String>),
     Restore,
     Prune,
+    SoftDelete,
 }
 
 pub enum MultiSelect {
@@ -35,25 +38,75 @@ pub enum MultiSelect {
 
 impl ViewMode {
     pub fn print_header(&self) -> String {
-        format!(
-            "PREVIEW UP: shift+up | PREVIEW DOWN: shift+down | {}\n\
-        PAGE UP:    page up  | PAGE DOWN:    page down \n\
-        EXIT:       esc      | SELECT:       enter      | SELECT, MULTIPLE: shift+tab\n\
-        ──────────────────────────────────────────────────────────────────────────────",
-            self.print_mode()
-        )
-    }
+        let mut header = format!(
+            "{:<12}{:<9}| {:<14}{:<11}| {}\n\
+        {:<12}{:<9}| {:<14}{} \n\
+        {:<12}{:<9}| {:<14}{:<11}| {} {}\n",
+            Msg::PreviewUp.text(),
+            "shift+up",
+            Msg::PreviewDown.text(),
+            "shift+down",
+            self.print_mode(),
+            Msg::PageUp.text(),
+            "page up",
+            Msg::PageDown.text(),
+            "page down",
+            Msg::Exit.text(),
+            "esc",
+            Msg::Select.text(),
+            "enter",
+            Msg::SelectMultiple.text(),
+            "shift+tab",
+        );
 
-    fn print_mode(&self) -> &str {
+        // only the browse view may filter its entries by availability, see AvailabilityFilter,
+        // or snapshot the highlighted entries, and only the select view may open an editor
         match self {
-            ViewMode::Browse => "====> [ Browse Mode ] <====",
-            ViewMode::Select(_) => "====> [ Select Mode ] <====",
-            ViewMode::Restore => "====> [ Restore Mode ] <====",
-            ViewMode::Prune => "====> [ Prune Mode ] <====",
+            ViewMode::Browse => {
+                header += &format!(
+                    "{:<12}{:<9}| {:<14}{:<11}| {}\n",
+                    Msg::Snapshot.text(),
+                    "ctrl+s",
+                    Msg::Filter.text(),
+                    "ctrl+v",
+                    AvailabilityFilter::current().text()
+                );
+            }
+            ViewMode::Select(_) => {
+                header += &format!("{:<12}{:<9}|\n", Msg::Edit.text(), "ctrl+o");
+            }
+            _ => {}
         }
+
+        header += "──────────────────────────────────────────────────────────────────────────────";
+
+        header
+    }
+
+    fn print_mode(&self) -> String {
+        let mode = match self {
+            ViewMode::Browse => Msg::BrowseMode,
+            ViewMode::Select(_) => Msg::SelectMode,
+            ViewMode::Restore => Msg::RestoreMode,
+            ViewMode::Prune => Msg::PruneMode,
+            ViewMode::SoftDelete => Msg::SoftDeleteMode,
+        };
+
+        format!("====> [ {} ] <====", mode.text())
     }
 
     pub fn view_buffer(&self, buffer: &str, opt_multi: MultiSelect) -> HttmResult<Vec<String>> {
+        self.view_buffer_with_key(buffer, opt_multi)
+            .map(|(selections, _final_key)| selections)
+    }
+
+    // also returns the key which ended the session, so a caller may distinguish an ordinary
+    // selection from one made via an alternate binding, like ctrl+o in the select view
+    pub fn view_buffer_with_key(
+        &self,
+        buffer: &str,
+        opt_multi: MultiSelect,
+    ) -> HttmResult<(Vec<String>, Key)> {
         let preview_selection = PreviewSelection::new(&self)?;
 
         let header = self.print_header();
@@ -63,10 +116,22 @@ impl ViewMode {
             MultiSelect::Off => false,
         };
 
+        // wrapping may always be toggled, whether or not initially enabled via PREVIEW_WRAP
+        let mut bind = if preview_selection.opt_preview_command.is_some() {
+            vec!["ctrl-w:toggle-preview-wrap"]
+        } else {
+            Vec::new()
+        };
+
+        if let ViewMode::Select(_) = self {
+            bind.push("ctrl-o:accept");
+        }
+
         // build our browse view - less to do than before - no previews, looking through one 'lil buffer
         let skim_opts = SkimOptionsBuilder::default()
             .preview_window(preview_selection.opt_preview_window.as_deref())
             .preview(preview_selection.opt_preview_command.as_deref())
+            .bind(bind)
             .disabled(true)
             .tac(true)
             .nosort(true)
@@ -88,14 +153,24 @@ impl ViewMode {
         // run_with() reads and shows items from the thread stream created above
         let res = match skim::Skim::run_with(&skim_opts, Some(items)) {
             Some(output) if output.is_abort => {
-                eprintln!("httm select/restore/prune session was aborted.  Quitting.");
-                std::process::exit(0);
+                // return to the browse view, instead of quitting, see CONTINUE
+                if GLOBAL_CONFIG.opt_continue && !matches!(self, ViewMode::Browse) {
+                    return Err(HttmError::new("httm select/restore session was aborted.").into());
+                }
+
+                return Err(HttmAbort::new(
+                    "httm select/restore/prune session was aborted.  Quitting.",
+                )
+                .into());
             }
-            Some(output) => output
-                .selected_items
-                .iter()
-                .map(|i| i.output().into_owned())
-                .collect(),
+            Some(output) => (
+                output
+                    .selected_items
+                    .iter()
+                    .map(|i| i.output().into_owned())
+                    .collect(),
+                output.final_key,
+            ),
             None => {
                 return Err(HttmError::new("httm select/restore/prune session failed.").into());
             }
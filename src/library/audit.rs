@@ -0,0 +1,357 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::{ExecMode, InteractiveMode, RestoreMode, RestoreSnapGuard};
+use crate::library::content_hash::{ContentHasher, SelectedHasher};
+use crate::library::file_ops::HashFileContents;
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{date_string, DateFormat};
+use crate::zfs::snap_guard::SnapGuard;
+use crate::GLOBAL_CONFIG;
+use hashbrown::HashMap;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const AUDIT_LOG_FILE_NAME: &str = "restore.log";
+
+// our audit log is shared among all restore workers
+static AUDIT_LOG_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+// the state of each destination just before it was restored to, see AuditLog::before_restore
+static PRIOR_STATES: LazyLock<Mutex<HashMap<PathBuf, PriorState>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static RECORD_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Copy)]
+pub enum AuditEvent {
+    Prune,
+    Restore,
+    Rollback,
+    Snapshot,
+    SoftDelete,
+    Undo,
+}
+
+impl AuditEvent {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AuditEvent::Prune => "prune",
+            AuditEvent::Restore => "restore",
+            AuditEvent::Rollback => "rollback",
+            AuditEvent::Snapshot => "snapshot",
+            AuditEvent::SoftDelete => "soft-delete",
+            AuditEvent::Undo => "undo",
+        }
+    }
+}
+
+// what was at a destination before a restore, so that an undo may put back exactly that
+#[derive(Debug, Clone)]
+struct PriorState {
+    file_type: &'static str,
+    opt_hash: Option<String>,
+}
+
+impl PriorState {
+    fn new(path: &Path) -> Self {
+        let file_type = match path.symlink_metadata() {
+            Ok(md) if md.is_file() => "file",
+            Ok(md) if md.is_dir() => "directory",
+            Ok(_) => "other",
+            Err(_) => "absent",
+        };
+
+        let opt_hash = (file_type == "file")
+            .then(|| HashFileContents::path_to_hash(path).ok())
+            .flatten();
+
+        Self {
+            file_type,
+            opt_hash,
+        }
+    }
+}
+
+// one JSON line per event, appended to the audit log, so the user may later prove
+// what was restored, overwritten, rolled back, or snapshotted, and when
+pub struct AuditRecord<'a> {
+    event: AuditEvent,
+    source: Option<&'a str>,
+    destination: Option<&'a Path>,
+    result: Result<(), String>,
+    opt_prior: Option<PriorState>,
+    // the precautionary snapshot which preserves the destination, and the destination's path within it
+    opt_guard: Option<(String, PathBuf)>,
+}
+
+impl<'a> Serialize for AuditRecord<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let timestamp = date_string(
+            GLOBAL_CONFIG.requested_utc_offset,
+            &SystemTime::now(),
+            DateFormat::Timestamp,
+        );
+
+        // only a successfully restored regular file has contents worth hashing
+        let opt_hash = match (&self.result, self.destination) {
+            (Ok(_), Some(destination)) if destination.is_file() => {
+                HashFileContents::path_to_hash(destination).ok()
+            }
+            _ => None,
+        };
+
+        let result = match &self.result {
+            Ok(_) => "success",
+            Err(_) => "failure",
+        };
+
+        // unique, unlike the timestamp, which has a resolution of only one second
+        let id = format!(
+            "{}-{}-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_nanos())
+                .unwrap_or_default(),
+            std::process::id(),
+            RECORD_COUNT.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let mut state = serializer.serialize_struct("AuditRecord", 14)?;
+
+        state.serialize_field("timestamp", &timestamp)?;
+        state.serialize_field("id", &id)?;
+        state.serialize_field("event", self.event.as_str())?;
+        state.serialize_field("source", &self.source)?;
+        state.serialize_field("destination", &self.destination)?;
+        state.serialize_field("mode", AuditLog::mode())?;
+        state.serialize_field("result", result)?;
+        state.serialize_field("error", &self.result.as_ref().err())?;
+        state.serialize_field("hash", &opt_hash)?;
+        state.serialize_field("hash_algorithm", SelectedHasher::ALGORITHM)?;
+        state.serialize_field(
+            "prior_type",
+            &self.opt_prior.as_ref().map(|prior| prior.file_type),
+        )?;
+        state.serialize_field(
+            "prior_hash",
+            &self
+                .opt_prior
+                .as_ref()
+                .and_then(|prior| prior.opt_hash.as_ref()),
+        )?;
+        state.serialize_field(
+            "guard_snapshot",
+            &self
+                .opt_guard
+                .as_ref()
+                .map(|(snap_name, _version)| snap_name),
+        )?;
+        state.serialize_field(
+            "guard_version",
+            &self.opt_guard.as_ref().map(|(_snap_name, version)| version),
+        )?;
+        state.end()
+    }
+}
+
+pub struct AuditLog;
+
+impl AuditLog {
+    // called just before a destination is written, so the restore record may describe what it replaced
+    pub fn before_restore(dst: &Path) {
+        let prior = PriorState::new(dst);
+
+        if let Ok(mut prior_states) = PRIOR_STATES.lock() {
+            prior_states.insert(dst.to_path_buf(), prior);
+        }
+    }
+
+    pub fn restore<T>(src: &Path, dst: &Path, res: &HttmResult<T>) {
+        let source = src.to_string_lossy();
+
+        let opt_prior = PRIOR_STATES
+            .lock()
+            .ok()
+            .and_then(|prior_states| prior_states.get(dst).cloned());
+
+        Self::append(&AuditRecord {
+            event: AuditEvent::Restore,
+            source: Some(&source),
+            destination: Some(dst),
+            result: Self::result(res),
+            opt_prior,
+            opt_guard: SnapGuard::preserved_version(dst),
+        })
+    }
+
+    pub fn rollback<T>(snap_name: &str, res: &HttmResult<T>) {
+        Self::append(&AuditRecord {
+            event: AuditEvent::Rollback,
+            source: Some(snap_name),
+            destination: None,
+            result: Self::result(res),
+            opt_prior: None,
+            opt_guard: None,
+        })
+    }
+
+    pub fn prune<T>(snap_name: &str, res: &HttmResult<T>) {
+        Self::append(&AuditRecord {
+            event: AuditEvent::Prune,
+            source: Some(snap_name),
+            destination: None,
+            result: Self::result(res),
+            opt_prior: None,
+            opt_guard: None,
+        })
+    }
+
+    pub fn snapshot<T>(snap_name: &str, res: &HttmResult<T>) {
+        Self::append(&AuditRecord {
+            event: AuditEvent::Snapshot,
+            source: Some(snap_name),
+            destination: None,
+            result: Self::result(res),
+            opt_prior: None,
+            opt_guard: None,
+        })
+    }
+
+    // the source is the snapshot version which preserves the deleted live file
+    pub fn soft_delete<T>(snap_path: &Path, live_path: &Path, res: &HttmResult<T>) {
+        let source = snap_path.to_string_lossy();
+
+        Self::append(&AuditRecord {
+            event: AuditEvent::SoftDelete,
+            source: Some(&source),
+            destination: Some(live_path),
+            result: Self::result(res),
+            opt_prior: None,
+            opt_guard: None,
+        })
+    }
+
+    // the source is the id of the restore record undone, so that it is never undone twice
+    pub fn undo<T>(restore_id: &str, dst: &Path, res: &HttmResult<T>) {
+        Self::append(&AuditRecord {
+            event: AuditEvent::Undo,
+            source: Some(restore_id),
+            destination: Some(dst),
+            result: Self::result(res),
+            opt_prior: None,
+            opt_guard: None,
+        })
+    }
+
+    // every record of the audit log, oldest first.  A line which cannot be parsed, perhaps
+    // because it was only partially written, is skipped
+    pub fn read() -> HttmResult<Vec<serde_json::Value>> {
+        let path = Self::path()
+            .ok_or_else(|| HttmError::new("Could not determine a location for the audit log."))?;
+
+        let contents = std::fs::read_to_string(&path).map_err(|err| {
+            let msg = format!("Could not read the audit log at {path:?}: {err}");
+            HttmError::new(&msg)
+        })?;
+
+        let records = contents
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+
+        Ok(records)
+    }
+
+    fn result<T>(res: &HttmResult<T>) -> Result<(), String> {
+        match res {
+            Ok(_) => Ok(()),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    fn mode() -> &'static str {
+        match &GLOBAL_CONFIG.exec_mode {
+            ExecMode::Interactive(InteractiveMode::Restore(restore_mode))
+            | ExecMode::NonInteractiveRestore(restore_mode) => match restore_mode {
+                RestoreMode::CopyOnly => "copy",
+                RestoreMode::CopyAndPreserve => "copy-and-preserve",
+                RestoreMode::Overwrite(RestoreSnapGuard::NotGuarded) => "overwrite",
+                RestoreMode::Overwrite(RestoreSnapGuard::Guarded) => "guard",
+                RestoreMode::Merge(RestoreSnapGuard::NotGuarded) => "merge",
+                RestoreMode::Merge(RestoreSnapGuard::Guarded) => "merge-guard",
+            },
+            ExecMode::RollForward(_) => "roll-forward",
+            ExecMode::SoftDelete => "soft-delete",
+            ExecMode::UndoRestore => "undo",
+            _ => "other",
+        }
+    }
+
+    // the user may specify an alternate location via HTTM_AUDIT_LOG, else we follow the XDG spec
+    fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("HTTM_AUDIT_LOG") {
+            return Some(PathBuf::from(path));
+        }
+
+        let data_dir = match std::env::var_os("XDG_DATA_HOME") {
+            Some(xdg_data_home) => PathBuf::from(xdg_data_home),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".local/share"),
+        };
+
+        Some(data_dir.join("httm").join(AUDIT_LOG_FILE_NAME))
+    }
+
+    // a failure to write to the audit log should never cause a restore to fail, so we only warn
+    fn append(record: &AuditRecord) {
+        if let Err(err) = Self::append_impl(record) {
+            eprintln!("WARN: httm could not write to its audit log: {err}");
+        }
+    }
+
+    fn append_impl(record: &AuditRecord) -> HttmResult<()> {
+        let path = Self::path()
+            .ok_or_else(|| HttmError::new("Could not determine a location for the audit log."))?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let _lock = AUDIT_LOG_LOCK
+            .lock()
+            .map_err(|_err| HttmError::new("Could not obtain a lock on the audit log."))?;
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::filesystem::borg::BorgRepos;
+use crate::filesystem::restic::ResticRepos;
+use crate::filesystem::ssh_aliases::SshAliases;
+use crate::filesystem::time_machine::TimeMachineLocal;
+use crate::lookup::cache::LookupCache;
+use crate::lookup::versions::SnapAutoMount;
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+
+static CLEANUP: Once = Once::new();
+static IS_CLEANUP_STARTED: AtomicBool = AtomicBool::new(false);
+
+// undo whatever httm has done to the system for the life of the program: unmount any snapshots,
+// borg and restic repos, remote dirs, and Time Machine snapshots we mounted, and write back any lookups we have cached.
+// cleanup runs exactly once, whether the guard held by main() drops, httm receives a terminating
+// signal, or httm must exit early, so nothing mounted is ever left behind
+pub struct CleanupGuard;
+
+impl CleanupGuard {
+    pub fn new() -> Self {
+        Self::handle_signals();
+
+        Self
+    }
+
+    fn handle_signals() {
+        let mut signals = match Signals::new([SIGHUP, SIGINT, SIGTERM]) {
+            Ok(signals) => signals,
+            Err(err) => {
+                eprintln!("WARN: httm could not install its signal handler: {err}");
+                return;
+            }
+        };
+
+        // a signal handler proper may only do async-signal-safe work, so we wait upon our own thread
+        std::thread::spawn(move || {
+            if let Some(signal) = signals.forever().next() {
+                Self::cleanup();
+
+                // the exit status of a process terminated by a signal
+                std::process::exit(128 + signal)
+            }
+        });
+    }
+
+    // mounts must not be made once cleanup has begun, else they would never be unmounted
+    pub fn is_started() -> bool {
+        IS_CLEANUP_STARTED.load(Ordering::SeqCst)
+    }
+
+    pub fn cleanup() {
+        CLEANUP.call_once(|| {
+            IS_CLEANUP_STARTED.store(true, Ordering::SeqCst);
+
+            // write back any lookups we have cached, see LOOKUP_CACHE
+            LookupCache::persist();
+            // unmount any snapshots we mounted, see AUTO_MOUNT_SNAPS
+            SnapAutoMount::cleanup();
+            // likewise, unmount any borg repos we mounted, see BORG_REPO
+            BorgRepos::cleanup();
+            // likewise, unmount any restic repos we mounted, see RESTIC_REPO
+            ResticRepos::cleanup();
+            // likewise, unmount any remote dirs we mounted, see MAP_ALIASES
+            SshAliases::cleanup();
+            // likewise, unmount any local Time Machine snapshots we mounted
+            TimeMachineLocal::cleanup();
+        });
+    }
+
+    // only for those few places which cannot return an error, like detached threads
+    pub fn exit(code: i32) -> ! {
+        Self::cleanup();
+
+        std::process::exit(code)
+    }
+}
+
+impl Drop for CleanupGuard {
+    fn drop(&mut self) {
+        Self::cleanup();
+    }
+}
@@ -0,0 +1,136 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+// the algorithm used to hash file contents is selected at compile time, by cargo feature, where the
+// strongest algorithm enabled wins: "hash-sha256", when cryptographic assurance is needed, as for an audit,
+// then "hash-blake3", then "hash-xxh3", the default.  With none enabled, we fall back to SipHash.
+pub trait ContentHasher: Default {
+    const ALGORITHM: &'static str;
+
+    fn update(&mut self, bytes: &[u8]);
+    fn hex_digest(self) -> String;
+}
+
+#[cfg(feature = "hash-sha256")]
+pub type SelectedHasher = Sha256Hasher;
+#[cfg(all(feature = "hash-blake3", not(feature = "hash-sha256")))]
+pub type SelectedHasher = Blake3Hasher;
+#[cfg(all(
+    feature = "hash-xxh3",
+    not(any(feature = "hash-blake3", feature = "hash-sha256"))
+))]
+pub type SelectedHasher = Xxh3Hasher;
+#[cfg(not(any(
+    feature = "hash-xxh3",
+    feature = "hash-blake3",
+    feature = "hash-sha256"
+)))]
+pub type SelectedHasher = SipHasher;
+
+#[cfg(feature = "hash-xxh3")]
+#[derive(Default)]
+pub struct Xxh3Hasher {
+    inner: xxhash_rust::xxh3::Xxh3,
+}
+
+#[cfg(feature = "hash-xxh3")]
+impl ContentHasher for Xxh3Hasher {
+    const ALGORITHM: &'static str = "xxh3-128";
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.inner.update(bytes)
+    }
+
+    fn hex_digest(self) -> String {
+        format!("{:032x}", self.inner.digest128())
+    }
+}
+
+#[cfg(feature = "hash-blake3")]
+#[derive(Default)]
+pub struct Blake3Hasher {
+    inner: blake3::Hasher,
+}
+
+#[cfg(feature = "hash-blake3")]
+impl ContentHasher for Blake3Hasher {
+    const ALGORITHM: &'static str = "blake3";
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.inner.update(bytes);
+    }
+
+    fn hex_digest(self) -> String {
+        self.inner.finalize().to_hex().to_string()
+    }
+}
+
+#[cfg(feature = "hash-sha256")]
+#[derive(Default)]
+pub struct Sha256Hasher {
+    inner: sha2::Sha256,
+}
+
+#[cfg(feature = "hash-sha256")]
+impl ContentHasher for Sha256Hasher {
+    const ALGORITHM: &'static str = "sha256";
+
+    fn update(&mut self, bytes: &[u8]) {
+        sha2::Digest::update(&mut self.inner, bytes)
+    }
+
+    fn hex_digest(self) -> String {
+        sha2::Digest::finalize(self.inner)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+#[cfg(not(any(
+    feature = "hash-xxh3",
+    feature = "hash-blake3",
+    feature = "hash-sha256"
+)))]
+#[derive(Default)]
+pub struct SipHasher {
+    inner: std::hash::DefaultHasher,
+}
+
+#[cfg(not(any(
+    feature = "hash-xxh3",
+    feature = "hash-blake3",
+    feature = "hash-sha256"
+)))]
+impl ContentHasher for SipHasher {
+    const ALGORITHM: &'static str = "siphash-1-3";
+
+    fn update(&mut self, bytes: &[u8]) {
+        std::hash::Hasher::write(&mut self.inner, bytes)
+    }
+
+    fn hex_digest(self) -> String {
+        format!("{:016x}", std::hash::Hasher::finish(&self.inner))
+    }
+}
+
+// hash a buffer already in memory with the selected algorithm
+pub fn bytes_to_digest(bytes: &[u8]) -> String {
+    let mut hasher = SelectedHasher::default();
+    hasher.update(bytes);
+    hasher.hex_digest()
+}
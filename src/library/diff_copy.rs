@@ -50,11 +50,16 @@ use crate::{ExecMode, GLOBAL_CONFIG, IN_BUFFER_SIZE};
 use std::fs::{File, OpenOptions};
 use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Seek, SeekFrom, Write};
 use std::os::fd::{AsFd, BorrowedFd};
+use std::os::unix::fs::FileExt;
 use std::path::Path;
 use std::process::Command as ExecProcess;
 use std::sync::atomic::AtomicBool;
 use std::sync::LazyLock;
 
+// FICLONE, as defined in linux/fs.h, is _IOW(0x94, 9, int)
+#[cfg(target_os = "linux")]
+nix::ioctl_write_int!(ficlone, 0x94, 9);
+
 static IS_CLONE_COMPATIBLE: LazyLock<AtomicBool> = LazyLock::new(|| {
     if let Ok(run_zfs) = RunZFSCommand::new() {
         let Ok(process_output) = ExecProcess::new(&run_zfs.zfs_command).arg("-V").output() else {
@@ -134,33 +139,72 @@ impl DiffCopy {
     fn new(src_file: &File, dst_file: &mut File) -> HttmResult<()> {
         let src_len = src_file.metadata()?.len();
 
+        let is_sparse = Self::is_sparse(src_file, src_len);
+
         if !GLOBAL_CONFIG.opt_no_clones
             && IS_CLONE_COMPATIBLE.load(std::sync::atomic::Ordering::Relaxed)
         {
             let src_fd = src_file.as_fd();
             let dst_fd = dst_file.as_fd();
 
-            match Self::copy_file_range(src_fd, dst_fd, src_len as usize) {
+            // a reflink clones the whole file, or nothing, and is always zero copy, whereas
+            // copy_file_range may fall back to an in-kernel copy, so a reflink is tried first
+            match Self::reflink(src_fd, dst_fd) {
                 Ok(_) => {
                     if GLOBAL_CONFIG.opt_debug {
-                        eprintln!("DEBUG: copy_file_range call successful.");
+                        eprintln!("DEBUG: FICLONE call successful.");
                     }
                     return Ok(());
                 }
                 Err(err) => {
-                    IS_CLONE_COMPATIBLE.store(false, std::sync::atomic::Ordering::Relaxed);
                     if GLOBAL_CONFIG.opt_debug {
                         eprintln!(
-                            "DEBUG: copy_file_range call unsuccessful for the following reason: \"{:?}\".\n
-                            DEBUG: Retrying a conventional diff copy.",
+                            "DEBUG: FICLONE call unsuccessful for the following reason: \"{:?}\".\n
+                            DEBUG: Retrying with copy_file_range.",
                             err
                         );
                     }
                 }
             }
+
+            // copy_file_range may fill the holes of a sparse file, when it falls back to an in-kernel copy
+            if !is_sparse {
+                match Self::copy_file_range(src_fd, dst_fd, src_len as usize) {
+                    Ok(_) => {
+                        if GLOBAL_CONFIG.opt_debug {
+                            eprintln!("DEBUG: copy_file_range call successful.");
+                        }
+                        return Ok(());
+                    }
+                    Err(err) => {
+                        IS_CLONE_COMPATIBLE.store(false, std::sync::atomic::Ordering::Relaxed);
+                        if GLOBAL_CONFIG.opt_debug {
+                            eprintln!(
+                                "DEBUG: copy_file_range call unsuccessful for the following reason: \"{:?}\".\n
+                                DEBUG: Retrying a conventional diff copy.",
+                                err
+                            );
+                        }
+                    }
+                }
+            }
         }
 
-        Self::write_no_cow(&src_file, &dst_file)?;
+        if is_sparse {
+            if let Err(err) = Self::write_sparse(&src_file, &dst_file, src_len) {
+                if GLOBAL_CONFIG.opt_debug {
+                    eprintln!(
+                        "DEBUG: Sparse copy unsuccessful for the following reason: \"{:?}\".\n
+                        DEBUG: Retrying a conventional diff copy.",
+                        err
+                    );
+                }
+
+                Self::write_no_cow(&src_file, &dst_file)?;
+            }
+        } else {
+            Self::write_no_cow(&src_file, &dst_file)?;
+        }
 
         // re docs, both a flush and a sync seem to be required re consistency
         dst_file.flush()?;
@@ -236,6 +280,133 @@ impl DiffCopy {
         Ok(())
     }
 
+    // copies only the data segments of a sparse file, as reported by SEEK_DATA and SEEK_HOLE, and
+    // punches holes in the destination where the source has holes, so a sparse file remains sparse
+    #[allow(unreachable_code, unused_variables)]
+    // block counts mislead upon compressed filesystems, so a file is sparse only where it has a hole
+    // before EOF.  Every file has an implicit hole at EOF
+    fn is_sparse(src_file: &File, src_len: u64) -> bool {
+        #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+        {
+            use nix::unistd::{lseek, Whence};
+            use std::os::fd::AsRawFd;
+
+            let src_fd = src_file.as_raw_fd();
+
+            let is_sparse = lseek(src_fd, 0, Whence::SeekHole)
+                .is_ok_and(|hole_start| (hole_start as u64) < src_len);
+
+            // restore the file offset, as any later read may be sequential
+            let _ = lseek(src_fd, 0, Whence::SeekSet);
+
+            return is_sparse;
+        }
+        #[allow(unreachable_code)]
+        false
+    }
+
+    fn write_sparse(src_file: &File, dst_file: &File, src_len: u64) -> HttmResult<()> {
+        #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+        {
+            use nix::unistd::{lseek, Whence};
+            use std::os::fd::AsRawFd;
+
+            let src_fd = src_file.as_raw_fd();
+            let len = src_len as i64;
+            let mut pos = 0i64;
+
+            while pos < len {
+                // ENXIO indicates no data remains beyond pos, so the remainder of the file is a hole
+                let data_start = match lseek(src_fd, pos, Whence::SeekData) {
+                    Ok(offset) => offset.min(len),
+                    Err(nix::errno::Errno::ENXIO) => len,
+                    Err(err) => return Err(err.into()),
+                };
+
+                if data_start > pos {
+                    Self::punch_hole(dst_file, pos as u64, data_start as u64)?;
+                }
+
+                if data_start >= len {
+                    break;
+                }
+
+                let data_end = lseek(src_fd, data_start, Whence::SeekHole)?.min(len);
+
+                Self::write_range(src_file, dst_file, data_start as u64, data_end as u64)?;
+
+                pos = data_end;
+            }
+
+            return Ok(());
+        }
+        Err(HttmError::new("Operating system does not support SEEK_DATA and SEEK_HOLE.").into())
+    }
+
+    fn write_range(src_file: &File, dst_file: &File, start: u64, end: u64) -> HttmResult<()> {
+        let mut src_buf = vec![0u8; IN_BUFFER_SIZE];
+        let mut dst_buf = vec![0u8; IN_BUFFER_SIZE];
+        let mut offset = start;
+
+        while offset < end {
+            let amt = ((end - offset) as usize).min(IN_BUFFER_SIZE);
+
+            src_file.read_exact_at(&mut src_buf[..amt], offset)?;
+
+            // as with a diff copy, only those bytes which differ are written
+            let is_same = dst_file.read_exact_at(&mut dst_buf[..amt], offset).is_ok()
+                && Self::is_same_bytes(&src_buf[..amt], &dst_buf[..amt]);
+
+            if !is_same {
+                dst_file.write_all_at(&src_buf[..amt], offset)?;
+            }
+
+            offset += amt as u64;
+        }
+
+        Ok(())
+    }
+
+    // the destination may not be a new file, and so may have data where the source has a hole
+    fn punch_hole(dst_file: &File, start: u64, end: u64) -> HttmResult<()> {
+        #[cfg(target_os = "linux")]
+        {
+            use nix::fcntl::{fallocate, FallocateFlags};
+            use std::os::fd::AsRawFd;
+
+            if fallocate(
+                dst_file.as_raw_fd(),
+                FallocateFlags::FALLOC_FL_PUNCH_HOLE | FallocateFlags::FALLOC_FL_KEEP_SIZE,
+                start as i64,
+                (end - start) as i64,
+            )
+            .is_ok()
+            {
+                return Ok(());
+            }
+        }
+
+        // else, zero only those ranges which are not already zeroes, as a hole reads as zeroes
+        let zeroes = vec![0u8; IN_BUFFER_SIZE];
+        let mut dst_buf = vec![0u8; IN_BUFFER_SIZE];
+        let mut offset = start;
+
+        while offset < end {
+            let amt = ((end - offset) as usize).min(IN_BUFFER_SIZE);
+
+            let is_zeroes = dst_file.read_exact_at(&mut dst_buf[..amt], offset).is_ok()
+                && dst_buf[..amt].iter().all(|byte| *byte == 0);
+
+            if !is_zeroes {
+                dst_file.write_all_at(&zeroes[..amt], offset)?;
+            }
+
+            offset += amt as u64;
+        }
+
+        Ok(())
+    }
+
     #[inline]
     fn is_same_bytes(a_bytes: &[u8], b_bytes: &[u8]) -> bool {
         let (a_hash, b_hash): (u64, u64) =
@@ -266,6 +437,25 @@ impl DiffCopy {
         Ok(())
     }
 
+    #[allow(unreachable_code, unused_variables)]
+    fn reflink(src_file_fd: BorrowedFd, dst_file_fd: BorrowedFd) -> HttmResult<()> {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::fd::AsRawFd;
+
+            // SAFETY: both fds are borrowed from open files, which outlive this call
+            unsafe {
+                ficlone(
+                    dst_file_fd.as_raw_fd(),
+                    src_file_fd.as_raw_fd() as nix::sys::ioctl::ioctl_param_type,
+                )
+            }?;
+
+            return Ok(());
+        }
+        Err(HttmError::new("Operating system does not support FICLONE.").into())
+    }
+
     #[allow(unreachable_code, unused_variables)]
     fn copy_file_range(
         src_file_fd: BorrowedFd,
@@ -274,52 +464,46 @@ impl DiffCopy {
     ) -> HttmResult<()> {
         #[cfg(any(target_os = "linux", target_os = "freebsd"))]
         {
-            let mut amt_written = 0u64;
-
-            // copy_file_range needs to be run in a loop as it is interruptible
-            match nix::fcntl::copy_file_range(
-                src_file_fd,
-                Some(&mut (amt_written as i64)),
-                dst_file_fd,
-                Some(&mut (amt_written as i64)),
-                len,
-            ) {
-                // However,	a return of zero  for  a  non-zero  len  argument
-                // indicates that the offset for infd is at or beyond EOF.
-                Ok(bytes_written) if bytes_written == 0usize && len != 0usize => {
-                    return Err(HttmError::new("Amount written == 0 for a file len > 0.  This may indicate that the offset for source file is at or beyond EOF.").into());
-                }
-                Ok(bytes_written) => {
-                    amt_written += bytes_written as u64;
-
-                    if amt_written == len as u64 {
-                        return Ok(());
+            let mut src_offset = 0i64;
+            let mut dst_offset = 0i64;
+            let mut amt_written = 0usize;
+
+            // copy_file_range needs to be run in a loop as it is interruptible, and,
+            // upon success, may copy fewer bytes than the length originally requested
+            while amt_written < len {
+                match nix::fcntl::copy_file_range(
+                    src_file_fd,
+                    Some(&mut src_offset),
+                    dst_file_fd,
+                    Some(&mut dst_offset),
+                    len - amt_written,
+                ) {
+                    // However,	a return of zero  for  a  non-zero  len  argument
+                    // indicates that the offset for infd is at or beyond EOF.
+                    Ok(0) => {
+                        return Err(HttmError::new("Amount written == 0 for a file len > 0.  This may indicate that the offset for source file is at or beyond EOF.").into());
                     }
-
-                    if amt_written < len as u64 {
-                        //  Upon successful completion, copy_file_range() will  return  the  number  of  bytes  copied
-                        //  between files.  This could be less than the length originally requested.
-                        return Ok(());
-                    }
-
-                    if amt_written > len as u64 {
-                        return Err(HttmError::new("Amount written larger than file len.").into());
+                    Ok(bytes_written) => {
+                        amt_written += bytes_written;
                     }
-                }
-                Err(err) => match err {
-                    nix::errno::Errno::ENOSYS => {
+                    Err(nix::errno::Errno::EINTR) => continue,
+                    Err(nix::errno::Errno::ENOSYS) => {
                         return Err(HttmError::new(
                             "Operating system does not support copy_file_ranges.",
                         )
                         .into())
                     }
-                    _ => {
+                    Err(err) => {
                         if GLOBAL_CONFIG.opt_debug {
                             eprintln!("DEBUG: copy_file_range call failed for the following reason: {}\nDEBUG: Falling back to default diff copy behavior.", err);
                         }
+
+                        return Err(err.into());
                     }
-                },
+                }
             }
+
+            return Ok(());
         }
         Err(HttmError::new("Operating system does not support copy_file_ranges.").into())
     }
@@ -0,0 +1,130 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::shell::UserShell;
+use crate::lookup::versions::VersionsMap;
+use rayon::prelude::*;
+use std::path::Path;
+use std::process::Command as ExecProcess;
+
+const EXEC_PLACEHOLDER: &str = "{}";
+
+// like find -exec, runs a user command once per version path, with "{}" in the
+// command replaced by the path, or, if no "{}" is present, the path appended.
+//
+// the path is never interpolated into the command string itself, rather the command
+// is run by the user's shell as '$SHELL -c <command> httm <path>' and "{}" is replaced by a quoted "$1"
+// (or, for fish, '$SHELL -c <command> <path>' and a quoted "$argv[1]"),
+// so paths containing whitespace or shell metacharacters are always safe
+pub struct ExecCommand<'a> {
+    template: &'a str,
+    num_threads: usize,
+}
+
+impl<'a> ExecCommand<'a> {
+    pub fn new(template: &'a str, num_threads: usize) -> Self {
+        Self {
+            template,
+            num_threads,
+        }
+    }
+
+    // only snapshot versions, never the live version, are passed to the command
+    pub fn exec_versions_map(&self, versions_map: &VersionsMap) -> HttmResult<()> {
+        let snap_paths: Vec<&Path> = versions_map
+            .values()
+            .flatten()
+            .map(|pathdata| pathdata.path())
+            .collect();
+
+        self.exec(&snap_paths)
+    }
+
+    pub fn exec<P>(&self, paths: &[P]) -> HttmResult<()>
+    where
+        P: AsRef<Path> + Sync,
+    {
+        if paths.is_empty() {
+            eprintln!("NOTICE: httm found no version paths upon which to execute the command.");
+            return Ok(());
+        }
+
+        let shell = UserShell::new()?;
+
+        let first_arg = shell.kind.first_positional();
+
+        let script = if self.template.contains(EXEC_PLACEHOLDER) {
+            self.template.replace(EXEC_PLACEHOLDER, first_arg)
+        } else {
+            format!("{} {first_arg}", self.template)
+        };
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.num_threads)
+            .build()?;
+
+        let failures: Vec<(&Path, String)> = pool.install(|| {
+            paths
+                .par_iter()
+                .map(AsRef::as_ref)
+                .filter_map(|path| {
+                    Self::exec_one(&shell, &script, path)
+                        .err()
+                        .map(|err| (path, err.to_string()))
+                })
+                .collect()
+        });
+
+        if failures.is_empty() {
+            return Ok(());
+        }
+
+        let failures_buffer: String = failures
+            .iter()
+            .map(|(path, err)| format!("\t{:?}: {}\n", path, err))
+            .collect();
+
+        let msg = format!(
+            "The command failed for {} of {} version path/s:\n{}",
+            failures.len(),
+            paths.len(),
+            failures_buffer.trim_end()
+        );
+
+        Err(HttmError::new(&msg).into())
+    }
+
+    fn exec_one(shell: &UserShell, script: &str, path: &Path) -> HttmResult<()> {
+        let status = ExecProcess::new(&shell.path)
+            .arg("-c")
+            .arg(script)
+            .args(shell.kind.positional_args(&[path]))
+            .status()?;
+
+        if !status.success() {
+            let msg = match status.code() {
+                Some(code) => format!("command exited with status {code}"),
+                None => "command was terminated by a signal".to_owned(),
+            };
+
+            return Err(HttmError::new(&msg).into());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::data::paths::PathData;
+use crate::library::content_hash::{bytes_to_digest, ContentHasher, SelectedHasher};
+use crate::library::file_ops::HashFileContents;
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{date_string, DateFormat};
+use crate::lookup::versions::VersionsMap;
+use crate::GLOBAL_CONFIG;
+use rayon::prelude::*;
+use serde_json::{Map, Value};
+use std::fs::OpenOptions;
+use std::io::{BufReader, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const EXPORT_FORMAT_VERSION: u64 = 1;
+// the checksum covers the serialized "entries" value, which the preserve_order feature of serde_json
+// guarantees will serialize identically, when read back, so long as it is unmodified.  Note: unless httm
+// was built with the "hash-sha256" feature, this hash detects any accidental change, but is not
+// cryptographic, and therefore cannot prove an export was not forged
+fn checksum(entries: &Value) -> HttmResult<String> {
+    let serialized = serde_json::to_string(entries)?;
+
+    Ok(bytes_to_digest(serialized.as_bytes()))
+}
+
+fn file_hash(path: &Path) -> Option<String> {
+    if !path.is_file() {
+        return None;
+    }
+
+    HashFileContents::path_to_hash(path).ok()
+}
+
+pub struct ExportVersions;
+
+impl ExportVersions {
+    pub fn exec(versions_map: &VersionsMap, export_file: &Path) -> HttmResult<()> {
+        let entries: Vec<Value> = versions_map
+            .iter()
+            .map(|(live_version, snaps)| {
+                let versions: Vec<Value> = snaps.par_iter().map(Self::version).collect();
+
+                let mut entry = Map::new();
+                entry.insert(
+                    "live".to_owned(),
+                    Value::from(live_version.path().to_string_lossy()),
+                );
+                entry.insert("versions".to_owned(), Value::from(versions));
+
+                Value::from(entry)
+            })
+            .collect();
+
+        let entries = Value::from(entries);
+
+        let mut export = Map::new();
+        export.insert(
+            "format_version".to_owned(),
+            Value::from(EXPORT_FORMAT_VERSION),
+        );
+        export.insert(
+            "httm_version".to_owned(),
+            Value::from(env!("CARGO_PKG_VERSION")),
+        );
+        export.insert(
+            "created".to_owned(),
+            Value::from(date_string(
+                GLOBAL_CONFIG.requested_utc_offset,
+                &SystemTime::now(),
+                DateFormat::Timestamp,
+            )),
+        );
+        export.insert(
+            "hash_algorithm".to_owned(),
+            Value::from(SelectedHasher::ALGORITHM),
+        );
+        export.insert("checksum".to_owned(), Value::from(checksum(&entries)?));
+        export.insert("entries".to_owned(), entries);
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(export_file)?;
+
+        let mut buffer = serde_json::to_string_pretty(&Value::from(export))?;
+        buffer.push('\n');
+
+        file.write_all(buffer.as_bytes())?;
+        file.sync_data()?;
+
+        let num_versions: usize = versions_map.values().map(|snaps| snaps.len()).sum();
+
+        eprintln!(
+            "httm exported {} versions of {} files to: {:?}",
+            num_versions,
+            versions_map.len(),
+            export_file
+        );
+
+        Ok(())
+    }
+
+    fn version(pathdata: &PathData) -> Value {
+        let mut version = Map::new();
+
+        version.insert(
+            "path".to_owned(),
+            Value::from(pathdata.path().to_string_lossy()),
+        );
+
+        if let Some(metadata) = pathdata.opt_metadata() {
+            let modify_time = metadata
+                .mtime()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+
+            version.insert("size".to_owned(), Value::from(metadata.size()));
+            version.insert(
+                "modify_time_secs".to_owned(),
+                Value::from(modify_time.as_secs()),
+            );
+            version.insert(
+                "modify_time_nanos".to_owned(),
+                Value::from(modify_time.subsec_nanos()),
+            );
+        }
+
+        version.insert(
+            "hash".to_owned(),
+            file_hash(pathdata.path()).map_or(Value::Null, Value::from),
+        );
+
+        Value::from(version)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VerifyStatus {
+    Unchanged,
+    Modified,
+    Missing,
+}
+
+impl VerifyStatus {
+    fn new(version: &Value) -> (Self, String) {
+        let path = version
+            .get("path")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_owned();
+
+        let Ok(md) = Path::new(&path).symlink_metadata() else {
+            return (VerifyStatus::Missing, path);
+        };
+
+        let is_size_same = version
+            .get("size")
+            .and_then(Value::as_u64)
+            .map_or(true, |size| size == md.len());
+
+        let is_hash_same = match version.get("hash").and_then(Value::as_str) {
+            Some(exported_hash) => {
+                file_hash(Path::new(&path)).is_some_and(|hash| hash == exported_hash)
+            }
+            None => true,
+        };
+
+        if is_size_same && is_hash_same {
+            (VerifyStatus::Unchanged, path)
+        } else {
+            (VerifyStatus::Modified, path)
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            VerifyStatus::Unchanged => "unchanged",
+            VerifyStatus::Modified => "modified",
+            VerifyStatus::Missing => "missing",
+        }
+    }
+}
+
+pub struct VerifyExport;
+
+impl VerifyExport {
+    pub fn exec(export_file: &Path) -> HttmResult<()> {
+        let file = OpenOptions::new().read(true).open(export_file)?;
+        let export: Value = serde_json::from_reader(BufReader::new(file))?;
+
+        let Some(entries) = export.get("entries") else {
+            let msg = format!(
+                "Export file does not contain any entries: {:?}",
+                export_file
+            );
+            return Err(HttmError::new(&msg).into());
+        };
+
+        // neither the checksum nor any file hash can be compared if computed by another algorithm
+        match export.get("hash_algorithm").and_then(Value::as_str) {
+            Some(algorithm) if algorithm == SelectedHasher::ALGORITHM => {}
+            opt_algorithm => {
+                let msg = format!(
+                    "Export file was hashed with {}, but this httm was built to hash with {}.  Rebuild httm with the matching \"hash-*\" feature to verify: {:?}",
+                    opt_algorithm.unwrap_or("an unknown algorithm"),
+                    SelectedHasher::ALGORITHM,
+                    export_file
+                );
+                return Err(HttmError::new(&msg).into());
+            }
+        }
+
+        let opt_checksum = export.get("checksum").and_then(Value::as_str);
+
+        if opt_checksum != Some(checksum(entries)?.as_str()) {
+            let msg = format!(
+                "Export file checksum does not match its contents, the export file may have been modified: {:?}",
+                export_file
+            );
+            return Err(HttmError::new(&msg).into());
+        }
+
+        if let Some(httm_version) = export.get("httm_version").and_then(Value::as_str) {
+            if httm_version != env!("CARGO_PKG_VERSION") {
+                eprintln!(
+                    "WARN: Export file was created by a different version of httm ({}).  Hashes may differ between versions.",
+                    httm_version
+                );
+            }
+        }
+
+        let versions: Vec<&Value> = entries
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.get("versions").and_then(Value::as_array))
+            .flatten()
+            .collect();
+
+        let results: Vec<(VerifyStatus, String)> = versions
+            .par_iter()
+            .map(|version| VerifyStatus::new(version))
+            .collect();
+
+        let mut num_changed = 0usize;
+
+        results.iter().for_each(|(status, path)| {
+            if !matches!(status, VerifyStatus::Unchanged) {
+                num_changed += 1;
+            }
+
+            println!("{}\t{}", status.as_str(), path);
+        });
+
+        if num_changed > 0 {
+            let msg = format!(
+                "{} of {} exported versions no longer match the export file.",
+                num_changed,
+                results.len()
+            );
+            return Err(HttmError::new(&msg).into());
+        }
+
+        eprintln!(
+            "All {} exported versions match the export file.",
+            results.len()
+        );
+
+        Ok(())
+    }
+}
@@ -18,17 +18,29 @@
 use crate::data::paths::{PathData, PathDeconstruction};
 use crate::library::diff_copy::HttmCopy;
 use crate::library::results::{HttmError, HttmResult};
+use crate::library::terminal::Terminal;
+use crate::library::utility::display_human_size;
 use crate::{GLOBAL_CONFIG, IN_BUFFER_SIZE};
-use nix::sys::stat::SFlag;
-use nu_ansi_term::Color::{Blue, Red};
-use std::fs::{create_dir_all, read_dir, set_permissions};
+use hashbrown::hash_map::Entry;
+use hashbrown::{HashMap, HashSet};
+use indicatif::{ProgressBar, ProgressStyle};
+use nix::sys::stat::{utimensat, SFlag, UtimensatFlags};
+use nix::sys::time::TimeSpec;
+use nu_ansi_term::Color::{Blue, Green, Red};
+use rayon::prelude::*;
+use std::fs::{create_dir_all, read_dir, set_permissions, Metadata};
 use std::iter::Iterator;
-use std::os::unix::fs::{chown, FileTypeExt, MetadataExt};
-use std::path::Path;
+use std::os::unix::fs::{chown, lchown, FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 const CHAR_KIND: SFlag = nix::sys::stat::SFlag::S_IFCHR;
 const BLK_KIND: SFlag = nix::sys::stat::SFlag::S_IFBLK;
 
+// (dev, inode) of a source file with several hard links, to the first destination restored from it
+type HardLinkMap = HashMap<(u64, u64), PathBuf>;
+
 pub struct Copy;
 
 impl Copy {
@@ -44,28 +56,45 @@ impl Copy {
 
     pub fn direct(src: &Path, dst: &Path, should_preserve: bool) -> HttmResult<()> {
         Self::direct_quiet(src, dst, should_preserve)?;
-        eprintln!("{}: {:?} -> {:?}", Blue.paint("Restored "), src, dst);
+        eprintln!(
+            "{}: {:?} -> {:?}",
+            Terminal::paint_stderr(Blue, "Restored "),
+            src,
+            dst
+        );
 
         Ok(())
     }
 
     pub fn direct_quiet(src: &Path, dst: &Path, should_preserve: bool) -> HttmResult<()> {
-        if src.is_dir() {
+        // symlinks are recreated, never followed, so the file type is that of the path itself
+        let src_file_type = src.symlink_metadata()?.file_type();
+
+        if src_file_type.is_dir() {
             create_dir_all(&dst)?;
         } else {
             Self::generate_dst_parent(&dst)?;
 
-            if src.is_file() {
+            let opt_dst_file_type = dst.symlink_metadata().ok().map(|md| md.file_type());
+
+            if src_file_type.is_file() {
+                // a copy to a symlink would write through to the symlink's target
+                if opt_dst_file_type.is_some_and(|file_type| file_type.is_symlink()) {
+                    Remove::recursive_quiet(dst)?;
+                }
+
                 HttmCopy::new(&src, &dst)?;
             } else {
-                if dst.exists() {
+                if opt_dst_file_type.is_some() {
                     Remove::recursive_quiet(dst)?;
                 }
-                if src.is_symlink() {
+
+                if src_file_type.is_symlink() {
                     let link_target = std::fs::read_link(&src)?;
                     std::os::unix::fs::symlink(&link_target, &dst)?;
-                } else {
-                    Self::special_file(src, dst)?;
+                } else if !Self::special_file(src, dst)? {
+                    // nothing was created, and so there is nothing to preserve
+                    return Ok(());
                 }
             }
         }
@@ -77,8 +106,9 @@ impl Copy {
         Ok(())
     }
 
-    fn special_file(src: &Path, dst: &Path) -> HttmResult<()> {
-        let src_metadata = src.metadata()?;
+    // returns whether the special file was created, as some special files cannot be, or should not be, recreated
+    fn special_file(src: &Path, dst: &Path) -> HttmResult<bool> {
+        let src_metadata = src.symlink_metadata()?;
         let src_file_type = src_metadata.file_type();
         let src_mode_bits = src_metadata.mode();
         #[cfg(target_os = "linux")]
@@ -92,14 +122,27 @@ impl Copy {
         let is_socket = src_file_type.is_socket();
 
         if is_blk || is_char {
-            let dev = src_metadata.dev();
+            // the device the node represents, not the device upon which the node resides
+            let dev = src_metadata.rdev();
             let kind = if is_blk { BLK_KIND } else { CHAR_KIND };
             #[cfg(target_os = "linux")]
-            nix::sys::stat::mknod(dst, kind, dst_mode, dev)?;
+            let res = nix::sys::stat::mknod(dst, kind, dst_mode, dev);
             #[cfg(target_os = "macos")]
-            nix::sys::stat::mknod(dst, kind, dst_mode, dev as i32)?;
+            let res = nix::sys::stat::mknod(dst, kind, dst_mode, dev as i32);
             #[cfg(target_os = "freebsd")]
-            nix::sys::stat::mknod(dst, kind, dst_mode, dev as u32)?;
+            let res = nix::sys::stat::mknod(dst, kind, dst_mode, dev as u32);
+
+            match res {
+                Ok(_) => {}
+                Err(nix::errno::Errno::EPERM) => {
+                    eprintln!(
+                        "WARN: Source path could not be copied.  Source path is a device node, and only the superuser may create device nodes: \"{}\"",
+                        src.display()
+                    );
+                    return Ok(false);
+                }
+                Err(err) => return Err(err.into()),
+            }
         } else if is_fifo {
             // create new fifo
             nix::unistd::mkfifo(dst, dst_mode)?;
@@ -109,7 +152,8 @@ impl Copy {
             Traditionally, sockets could not be copied, and they should always be recreated by the generating daemon, when deleted: \"{}\"",
             src.display()
         );
-            eprintln!("{}", msg)
+            eprintln!("{}", msg);
+            return Ok(false);
         } else {
             let msg = format!(
             "httm could not determine the source path's file type, and therefore it could not be copied.  \
@@ -120,44 +164,318 @@ impl Copy {
             return Err(HttmError::new(&msg).into());
         }
 
-        Ok(())
+        Ok(true)
     }
 
     pub fn recursive(src: &Path, dst: &Path, should_preserve: bool) -> HttmResult<()> {
-        if src.is_dir() {
-            Self::direct(src, dst, should_preserve)?;
+        Self::recursive_impl(
+            src,
+            dst,
+            should_preserve,
+            false,
+            &HashSet::new(),
+            &mut HardLinkMap::new(),
+        )
+    }
+
+    pub fn recursive_quiet(src: &Path, dst: &Path, should_preserve: bool) -> HttmResult<()> {
+        Self::recursive_impl(
+            src,
+            dst,
+            should_preserve,
+            true,
+            &HashSet::new(),
+            &mut HardLinkMap::new(),
+        )
+    }
+
+    // leaves any destination file in the excluded set untouched, see recursive_parallel_excluding
+    pub fn recursive_quiet_excluding(
+        src: &Path,
+        dst: &Path,
+        should_preserve: bool,
+        excluded: &HashSet<PathBuf>,
+    ) -> HttmResult<()> {
+        Self::recursive_impl(
+            src,
+            dst,
+            should_preserve,
+            true,
+            excluded,
+            &mut HardLinkMap::new(),
+        )
+    }
+
+    fn recursive_impl(
+        src: &Path,
+        dst: &Path,
+        should_preserve: bool,
+        is_quiet: bool,
+        excluded: &HashSet<PathBuf>,
+        hard_links: &mut HardLinkMap,
+    ) -> HttmResult<()> {
+        let direct = if is_quiet {
+            Self::direct_quiet
+        } else {
+            Self::direct
+        };
+
+        if src.symlink_metadata()?.is_dir() {
+            direct(src, dst, should_preserve)?;
 
             for entry in read_dir(&src)?.flatten() {
                 let file_type = entry.file_type()?;
                 let entry_src = entry.path();
                 let entry_dst = dst.join(entry.file_name());
 
-                if entry_src.exists() {
+                // a dangling symlink is restored like any other symlink
+                if entry_src.symlink_metadata().is_ok() {
                     if file_type.is_dir() {
-                        Self::recursive(&entry_src, &entry_dst, should_preserve)?;
+                        Self::recursive_impl(
+                            &entry_src,
+                            &entry_dst,
+                            should_preserve,
+                            is_quiet,
+                            excluded,
+                            hard_links,
+                        )?;
+                    } else if excluded.contains(&entry_dst) {
+                        continue;
+                    } else if let Some(first_dst) =
+                        Self::first_hard_link(&entry_src, &entry_dst, hard_links)
+                    {
+                        Self::hard_link(&first_dst, &entry_src, &entry_dst, should_preserve)?;
+
+                        if !is_quiet {
+                            eprintln!(
+                                "{}: {:?} -> {:?}",
+                                Terminal::paint_stderr(Blue, "Linked "),
+                                first_dst,
+                                entry_dst
+                            );
+                        }
                     } else {
-                        Self::direct(&entry_src, &entry_dst, should_preserve)?;
+                        direct(&entry_src, &entry_dst, should_preserve)?;
                     }
                 }
             }
         } else {
-            Self::direct(&src, dst, should_preserve)?;
+            direct(&src, dst, should_preserve)?;
         }
 
         if should_preserve {
-            // macos likes to fail on the metadata copy
-            match Preserve::recursive(src, dst) {
-                Ok(_) => {}
-                Err(err) => {
-                    if is_metadata_same(src, dst).is_ok() {
-                        if GLOBAL_CONFIG.opt_debug {
-                            eprintln!("WARN: The OS reports an error that it was unable to copy file metadata for the following reason: {}", err.to_string().trim_end());
-                            eprintln!("NOTICE: This is most likely because such feature is unsupported by this OS.  httm confirms basic file metadata (size and mtime) are the same for transfer: {:?} -> {:?}.", src, dst)
-                        }
-                    } else {
-                        return Err(err);
+            Self::preserve_ancestors(src, dst)?;
+        }
+
+        Ok(())
+    }
+
+    // returns the destination of a file already restored from the same inode, if any,
+    // otherwise records this destination as the one later hard links should point to
+    fn first_hard_link(src: &Path, dst: &Path, hard_links: &mut HardLinkMap) -> Option<PathBuf> {
+        if GLOBAL_CONFIG.opt_no_hard_links {
+            return None;
+        }
+
+        let src_metadata = src.symlink_metadata().ok()?;
+
+        if !src_metadata.is_file() || src_metadata.nlink() < 2 {
+            return None;
+        }
+
+        match hard_links.entry((src_metadata.dev(), src_metadata.ino())) {
+            Entry::Occupied(first_dst) => Some(first_dst.get().clone()),
+            Entry::Vacant(vacant) => {
+                vacant.insert(dst.to_path_buf());
+                None
+            }
+        }
+    }
+
+    fn hard_link(
+        first_dst: &Path,
+        src: &Path,
+        dst: &Path,
+        should_preserve: bool,
+    ) -> HttmResult<()> {
+        if let Ok(dst_metadata) = dst.symlink_metadata() {
+            let is_linked = first_dst.symlink_metadata().is_ok_and(|first_metadata| {
+                first_metadata.dev() == dst_metadata.dev()
+                    && first_metadata.ino() == dst_metadata.ino()
+            });
+
+            if is_linked {
+                return Ok(());
+            }
+
+            Remove::recursive_quiet(dst)?;
+        }
+
+        match std::fs::hard_link(first_dst, dst) {
+            Ok(_) => Ok(()),
+            // hard links are impossible across filesystems, so fall back to an independent copy
+            Err(_) => Self::direct_quiet(src, dst, should_preserve),
+        }
+    }
+
+    fn preserve_ancestors(src: &Path, dst: &Path) -> HttmResult<()> {
+        // macos likes to fail on the metadata copy
+        match Preserve::recursive(src, dst) {
+            Ok(_) => {}
+            Err(err) => {
+                if is_metadata_same(src, dst).is_ok() {
+                    if GLOBAL_CONFIG.opt_debug {
+                        eprintln!("WARN: The OS reports an error that it was unable to copy file metadata for the following reason: {}", err.to_string().trim_end());
+                        eprintln!("NOTICE: This is most likely because such feature is unsupported by this OS.  httm confirms basic file metadata (size and mtime) are the same for transfer: {:?} -> {:?}.", src, dst)
                     }
+                } else {
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // copy a directory tree with a pool of workers, and display progress as we go.  Directories are
+    // created first, serially, so every worker finds its destination's parent already in place.
+    pub fn recursive_parallel(
+        src: &Path,
+        dst: &Path,
+        should_preserve: bool,
+        num_threads: usize,
+    ) -> HttmResult<CopySummary> {
+        Self::recursive_parallel_excluding(src, dst, should_preserve, num_threads, &HashSet::new())
+    }
+
+    // as above, but leaves any destination file in the excluded set untouched, for instance,
+    // where the user chose to keep a newer live file over its snapshot version
+    pub fn recursive_parallel_excluding(
+        src: &Path,
+        dst: &Path,
+        should_preserve: bool,
+        num_threads: usize,
+        excluded: &HashSet<PathBuf>,
+    ) -> HttmResult<CopySummary> {
+        let start = Instant::now();
+
+        if !src.symlink_metadata()?.is_dir() {
+            Self::recursive(src, dst, should_preserve)?;
+
+            return Ok(CopySummary {
+                num_files: 1,
+                num_dirs: 0,
+                num_bytes: src.symlink_metadata().map(|md| md.len()).unwrap_or(0),
+                elapsed: start.elapsed(),
+            });
+        }
+
+        let mut dirs: Vec<(PathBuf, PathBuf)> = vec![(src.to_path_buf(), dst.to_path_buf())];
+        let mut files: Vec<(PathBuf, PathBuf, u64)> = Vec::new();
+
+        Self::collect_tree(src, dst, &mut dirs, &mut files)?;
+
+        files.retain(|(_file_src, file_dst, _size)| !excluded.contains(file_dst));
+
+        // only the first of several hard links to the same inode is copied, the rest are linked
+        // to it, once all copies are complete
+        let mut hard_links = HardLinkMap::new();
+        let mut links: Vec<(PathBuf, PathBuf, PathBuf)> = Vec::new();
+
+        files.retain(|(file_src, file_dst, _size)| {
+            match Self::first_hard_link(file_src, file_dst, &mut hard_links) {
+                Some(first_dst) => {
+                    links.push((first_dst, file_src.clone(), file_dst.clone()));
+                    false
                 }
+                None => true,
+            }
+        });
+
+        dirs.iter()
+            .try_for_each(|(_dir_src, dir_dst)| create_dir_all(dir_dst))?;
+
+        let num_copies = files.len();
+        let num_bytes: u64 = files.iter().map(|(_src, _dst, size)| size).sum();
+
+        let progress_bar = ProgressBar::new(num_bytes);
+        progress_bar.set_style(
+            ProgressStyle::with_template(
+                "{spinner} Restoring: {bytes}/{total_bytes} [{elapsed_precise}] {msg}",
+            )
+            .unwrap_or_else(|_| ProgressStyle::default_bar()),
+        );
+
+        let files_copied = AtomicUsize::new(0);
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()?;
+
+        let res = pool.install(|| {
+            files
+                .par_iter()
+                .try_for_each(|(file_src, file_dst, size)| -> HttmResult<()> {
+                    Self::direct_quiet(file_src, file_dst, should_preserve)?;
+
+                    let num_copied = files_copied.fetch_add(1, Ordering::Relaxed) + 1;
+                    progress_bar.inc(*size);
+                    progress_bar.set_message(format!("{num_copied}/{num_copies} files"));
+
+                    Ok(())
+                })
+        });
+
+        progress_bar.finish_and_clear();
+
+        res?;
+
+        links
+            .iter()
+            .try_for_each(|(first_dst, link_src, link_dst)| {
+                Self::hard_link(first_dst, link_src, link_dst, should_preserve)
+            })?;
+
+        // writing a directory's contents updates its timestamps, so we preserve directory
+        // metadata only after all files are copied, and the most deeply nested dirs first
+        if should_preserve {
+            dirs.iter()
+                .skip(1)
+                .rev()
+                .try_for_each(|(dir_src, dir_dst)| Preserve::direct(dir_src, dir_dst))?;
+
+            Self::preserve_ancestors(src, dst)?;
+        }
+
+        Ok(CopySummary {
+            num_files: num_copies + links.len(),
+            num_dirs: dirs.len(),
+            num_bytes,
+            elapsed: start.elapsed(),
+        })
+    }
+
+    pub fn collect_tree(
+        src: &Path,
+        dst: &Path,
+        dirs: &mut Vec<(PathBuf, PathBuf)>,
+        files: &mut Vec<(PathBuf, PathBuf, u64)>,
+    ) -> HttmResult<()> {
+        for entry in read_dir(src)?.flatten() {
+            let file_type = entry.file_type()?;
+            let entry_src = entry.path();
+            let entry_dst = dst.join(entry.file_name());
+
+            if entry_src.symlink_metadata().is_err() {
+                continue;
+            }
+
+            if file_type.is_dir() {
+                dirs.push((entry_src.clone(), entry_dst.clone()));
+                Self::collect_tree(&entry_src, &entry_dst, dirs, files)?;
+            } else {
+                let size = entry.metadata().map(|md| md.len()).unwrap_or(0);
+                files.push((entry_src, entry_dst, size));
             }
         }
 
@@ -165,11 +483,145 @@ impl Copy {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct CopySummary {
+    pub num_files: usize,
+    pub num_dirs: usize,
+    pub num_bytes: u64,
+    pub elapsed: Duration,
+}
+
+impl std::fmt::Display for CopySummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} file/s, {} dir/s, {} in {:.2?}",
+            self.num_files,
+            self.num_dirs,
+            display_human_size(self.num_bytes),
+            self.elapsed
+        )
+    }
+}
+
+pub struct Verify;
+
+impl Verify {
+    pub fn recursive(src: &Path, dst: &Path) -> HttmResult<()> {
+        Self::recursive_impl(src, dst, false, &HashSet::new())
+    }
+
+    pub fn recursive_quiet_excluding(
+        src: &Path,
+        dst: &Path,
+        excluded: &HashSet<PathBuf>,
+    ) -> HttmResult<()> {
+        Self::recursive_impl(src, dst, true, excluded)
+    }
+
+    // files the user chose not to restore are expected to differ, so skip them
+    pub fn recursive_excluding(
+        src: &Path,
+        dst: &Path,
+        excluded: &HashSet<PathBuf>,
+    ) -> HttmResult<()> {
+        Self::recursive_impl(src, dst, false, excluded)
+    }
+
+    // compare the contents of every regular file beneath the source with its restored counterpart
+    fn recursive_impl(
+        src: &Path,
+        dst: &Path,
+        is_quiet: bool,
+        excluded: &HashSet<PathBuf>,
+    ) -> HttmResult<()> {
+        let pairs: Vec<(PathBuf, PathBuf)> = if src.is_dir() {
+            let mut dirs = Vec::new();
+            let mut files = Vec::new();
+
+            Copy::collect_tree(src, dst, &mut dirs, &mut files)?;
+
+            files
+                .into_iter()
+                .filter(|(_file_src, file_dst, _size)| !excluded.contains(file_dst))
+                .map(|(file_src, file_dst, _size)| (file_src, file_dst))
+                .collect()
+        } else {
+            vec![(src.to_path_buf(), dst.to_path_buf())]
+        };
+
+        // symlinks and special files have no contents to hash
+        // a file we cannot read is never verified
+        let results: Vec<(&Path, bool)> = pairs
+            .par_iter()
+            .filter(|(file_src, _file_dst)| file_src.is_file() && !file_src.is_symlink())
+            .map(|(file_src, file_dst)| {
+                if !file_dst.is_file() {
+                    return Ok((file_dst.as_path(), false));
+                }
+
+                let is_verified = HashFileContents::path_to_hash(file_src)?
+                    == HashFileContents::path_to_hash(file_dst)?;
+
+                Ok((file_dst.as_path(), is_verified))
+            })
+            .collect::<HttmResult<Vec<(&Path, bool)>>>()
+            .map_err(|err| {
+                let msg = format!("httm could not verify the restored file/s: {err}");
+                HttmError::new(&msg)
+            })?;
+
+        if !is_quiet {
+            results.iter().for_each(|(file_dst, is_verified)| {
+                if *is_verified {
+                    eprintln!(
+                        "{}: {:?}",
+                        Terminal::paint_stderr(Green, "Verified "),
+                        file_dst
+                    );
+                } else {
+                    eprintln!(
+                        "{}: {:?}",
+                        Terminal::paint_stderr(Red, "Mismatch "),
+                        file_dst
+                    );
+                }
+            });
+        }
+
+        let mismatches: Vec<&Path> = results
+            .iter()
+            .filter(|(_file_dst, is_verified)| !is_verified)
+            .map(|(file_dst, _is_verified)| *file_dst)
+            .collect();
+
+        if mismatches.is_empty() {
+            return Ok(());
+        }
+
+        let msg = format!(
+            "httm could not verify {} of {} restored file/s, as their contents differ from the snapshot version: {:?}",
+            mismatches.len(),
+            results.len(),
+            mismatches
+        );
+
+        Err(HttmError::new(&msg).into())
+    }
+}
+
 pub struct Preserve;
 
 impl Preserve {
     pub fn direct(src: &Path, dst: &Path) -> HttmResult<()> {
         let src_metadata = src.symlink_metadata()?;
+        let src_file_type = src_metadata.file_type();
+
+        // a symlink must not be followed, and a fifo must not be opened, lest we block, waiting on a writer
+        if !src_file_type.is_file() && !src_file_type.is_dir() {
+            return Self::special_file(dst, &src_metadata);
+        }
+
         let dst_file = std::fs::File::options()
             .create(false)
             .read(true)
@@ -224,6 +676,28 @@ impl Preserve {
         Ok(())
     }
 
+    fn special_file(dst: &Path, src_metadata: &Metadata) -> HttmResult<()> {
+        // Mode - a symlink's mode is meaningless, and chmod would follow the symlink
+        if !src_metadata.file_type().is_symlink() {
+            set_permissions(dst, src_metadata.permissions())?
+        }
+
+        // Ownership
+        {
+            lchown(dst, Some(src_metadata.uid()), Some(src_metadata.gid()))?
+        }
+
+        // Timestamps
+        {
+            let atime = TimeSpec::new(src_metadata.atime() as _, src_metadata.atime_nsec() as _);
+            let mtime = TimeSpec::new(src_metadata.mtime() as _, src_metadata.mtime_nsec() as _);
+
+            utimensat(None, dst, &atime, &mtime, UtimensatFlags::NoFollowSymlink)?;
+        }
+
+        Ok(())
+    }
+
     pub fn recursive(src: &Path, dst: &Path) -> HttmResult<()> {
         let dst_pathdata: PathData = dst.into();
 
@@ -254,18 +728,27 @@ impl Remove {
     pub fn recursive(src: &Path) -> HttmResult<()> {
         Self::recursive_quiet(src)?;
 
-        eprintln!("{}: {:?} -> 🗑️", Red.paint("Removed  "), src);
+        eprintln!(
+            "{}: {:?} -> 🗑️",
+            Terminal::paint_stderr(Red, "Removed  "),
+            src
+        );
 
         Ok(())
     }
 
     pub fn recursive_quiet(src: &Path) -> HttmResult<()> {
-        if src.is_dir() {
+        // a symlink to a directory is removed, never followed
+        let Ok(src_metadata) = src.symlink_metadata() else {
+            return Ok(());
+        };
+
+        if src_metadata.is_dir() {
             for entry in read_dir(&src)?.flatten() {
                 let file_type = entry.file_type()?;
                 let path = entry.path();
 
-                if path.exists() {
+                if path.symlink_metadata().is_ok() {
                     if file_type.is_dir() {
                         Self::recursive(&path)?;
                     } else {
@@ -274,10 +757,10 @@ impl Remove {
                 }
             }
 
-            if src.exists() {
+            if src.symlink_metadata().is_ok() {
                 std::fs::remove_dir_all(&src)?
             }
-        } else if src.exists() {
+        } else {
             std::fs::remove_file(&src)?
         }
 
@@ -285,21 +768,40 @@ impl Remove {
     }
 }
 
+use super::content_hash::{ContentHasher, SelectedHasher};
+use super::hash_pool::HashPool;
 use super::utility::is_metadata_same;
-use std::hash::{Hash, Hasher};
 use std::io::{BufRead, BufReader, ErrorKind};
+use std::os::unix::fs::FileExt;
+
+pub const HASH_BLOCK_SIZE: u64 = 4_194_304;
 
 pub struct HashFileContents<'a> {
     inner: &'a Path,
 }
 
 impl<'a> HashFileContents<'a> {
-    pub fn path_to_hash(path: &Path) -> u64 {
-        let mut ahasher = ahash::AHasher::default();
+    // a hex digest of the file's contents, by the algorithm selected at compile time, see content_hash
+    pub fn path_to_hash(path: &Path) -> HttmResult<String> {
+        HashPool::install(|| {
+            let mut hasher = SelectedHasher::default();
+
+            HashFileContents::from(path).hash(&mut hasher)?;
+
+            Ok(hasher.hex_digest())
+        })
+    }
 
-        HashFileContents::from(path).hash(&mut ahasher);
+    // a hex digest of only one fixed size block of the file's contents, so that large files may be
+    // compared block by block, see CompareContentsContainer
+    pub fn block_to_hash(path: &Path, block_idx: u64) -> HttmResult<String> {
+        HashPool::install(|| {
+            let mut hasher = SelectedHasher::default();
 
-        ahasher.finish()
+            HashFileContents::from(path).hash_block(&mut hasher, block_idx)?;
+
+            Ok(hasher.hex_digest())
+        })
     }
 }
 
@@ -309,15 +811,11 @@ impl<'a> From<&'a Path> for HashFileContents<'a> {
     }
 }
 
-impl<'a> Hash for HashFileContents<'a> {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        let Some(self_file) = std::fs::OpenOptions::new()
-            .read(true)
-            .open(&self.inner)
-            .ok()
-        else {
-            return;
-        };
+impl<'a> HashFileContents<'a> {
+    // an error, rather than the digest of whatever we read before it, so a file we cannot read
+    // is never mistaken for one whose contents match
+    fn hash<H: ContentHasher>(&self, state: &mut H) -> HttmResult<()> {
+        let self_file = std::fs::OpenOptions::new().read(true).open(self.inner)?;
 
         let mut reader = BufReader::with_capacity(IN_BUFFER_SIZE, self_file);
 
@@ -325,22 +823,45 @@ impl<'a> Hash for HashFileContents<'a> {
             let consumed = match reader.fill_buf() {
                 Ok(buf) => {
                     if buf.is_empty() {
-                        return;
+                        return Ok(());
                     }
 
-                    state.write(buf);
+                    state.update(buf);
+                    HashPool::throttle(buf.len());
                     buf.len()
                 }
                 Err(err) => match err.kind() {
                     ErrorKind::Interrupted => continue,
-                    ErrorKind::UnexpectedEof => {
-                        return;
-                    }
-                    _ => return,
+                    _ => return Err(err.into()),
                 },
             };
 
             reader.consume(consumed);
         }
     }
+
+    fn hash_block<H: ContentHasher>(&self, state: &mut H, block_idx: u64) -> HttmResult<()> {
+        let self_file = std::fs::OpenOptions::new().read(true).open(self.inner)?;
+
+        let block_start = block_idx * HASH_BLOCK_SIZE;
+        let mut buf = vec![0u8; IN_BUFFER_SIZE];
+        let mut offset = 0u64;
+
+        while offset < HASH_BLOCK_SIZE {
+            let want = (HASH_BLOCK_SIZE - offset).min(IN_BUFFER_SIZE as u64) as usize;
+
+            match self_file.read_at(&mut buf[..want], block_start + offset) {
+                Ok(0) => return Ok(()),
+                Ok(read) => {
+                    state.update(&buf[..read]);
+                    HashPool::throttle(read);
+                    offset += read as u64;
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,118 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::GLOBAL_CONFIG;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+static HASH_POOL: LazyLock<HashPool> = LazyLock::new(HashPool::new);
+
+// hashing file contents is IO bound, so we hash upon a pool of our own, see HASH_JOBS, and leave
+// rayon's global pool free for the CPU bound work of searching directories
+pub struct HashPool {
+    opt_pool: Option<rayon::ThreadPool>,
+    opt_throttle: Option<HashThrottle>,
+}
+
+impl HashPool {
+    fn new() -> Self {
+        // should we fail to build a pool, we simply hash upon the calling thread
+        let opt_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(GLOBAL_CONFIG.hash_jobs)
+            .thread_name(|idx| format!("httm-hash-{idx}"))
+            .build()
+            .ok();
+
+        let opt_throttle = GLOBAL_CONFIG.opt_hash_rate.map(HashThrottle::new);
+
+        Self {
+            opt_pool,
+            opt_throttle,
+        }
+    }
+
+    // a call from within the pool runs in place, so nested calls never wait upon the pool itself
+    pub fn install<F, R>(op: F) -> R
+    where
+        F: FnOnce() -> R + Send,
+        R: Send,
+    {
+        match &HASH_POOL.opt_pool {
+            Some(pool) => pool.install(op),
+            None => op(),
+        }
+    }
+
+    pub fn throttle(bytes: usize) {
+        if let Some(throttle) = &HASH_POOL.opt_throttle {
+            throttle.consume(bytes as u64);
+        }
+    }
+}
+
+struct ThrottleState {
+    window_start: Instant,
+    consumed: u64,
+}
+
+// shared by all hashing threads, such that the rate specified is the rate across all of them
+struct HashThrottle {
+    bytes_per_sec: u64,
+    state: Mutex<ThrottleState>,
+}
+
+impl HashThrottle {
+    fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec,
+            state: Mutex::new(ThrottleState {
+                window_start: Instant::now(),
+                consumed: 0,
+            }),
+        }
+    }
+
+    fn consume(&self, bytes: u64) {
+        let sleep_for = {
+            let Ok(mut state) = self.state.lock() else {
+                return;
+            };
+
+            let elapsed = state.window_start.elapsed();
+            let allowed =
+                Duration::from_secs_f64(state.consumed as f64 / self.bytes_per_sec as f64);
+
+            // after an idle period, start a new window, else we would permit a burst to make up
+            // for the time we were idle
+            if elapsed > allowed + Duration::from_secs(1) {
+                state.window_start = Instant::now();
+                state.consumed = 0;
+            }
+
+            state.consumed += bytes;
+
+            let allowed =
+                Duration::from_secs_f64(state.consumed as f64 / self.bytes_per_sec as f64);
+
+            allowed.saturating_sub(state.window_start.elapsed())
+        };
+
+        if !sleep_for.is_zero() {
+            std::thread::sleep(sleep_for);
+        }
+    }
+}
@@ -0,0 +1,370 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use std::sync::LazyLock;
+
+// a lightweight message catalog for user facing interactive strings.  locale is selected,
+// in order of precedence, via HTTM_LANG, LC_ALL, LC_MESSAGES, or LANG, and defaults to English
+static LOCALE: LazyLock<Locale> = LazyLock::new(Locale::from_env);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    En,
+    De,
+    Es,
+    Fr,
+}
+
+impl Locale {
+    fn from_env() -> Self {
+        ["HTTM_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .map(|value| Self::from_tag(&value))
+            .unwrap_or(Locale::En)
+    }
+
+    // accepts tags like "de", "de_DE.UTF-8", or "fr-CA"
+    fn from_tag(tag: &str) -> Self {
+        let language: String = tag
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        match language.as_str() {
+            "de" => Locale::De,
+            "es" => Locale::Es,
+            "fr" => Locale::Fr,
+            _ => Locale::En,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    Yes,
+    No,
+    Each,
+    PreviewUp,
+    PreviewDown,
+    PageUp,
+    PageDown,
+    Exit,
+    Select,
+    SelectMultiple,
+    BrowseMode,
+    SelectMode,
+    RestoreMode,
+    PruneMode,
+    SoftDeleteMode,
+    RestoreConsent,
+    PruneConsent,
+    PruneTypedConsent,
+    SoftDeleteConsent,
+    BisectPrompt,
+    UndoConsent,
+    Filter,
+    FilterAll,
+    FilterWithSnaps,
+    FilterDiffersFromLast,
+    Snapshot,
+    Edit,
+    HunkLive,
+    HunkSnapshot,
+    HunkQuit,
+    KeepLive,
+    TakeSnapshot,
+    ChoosePerFile,
+    Skip,
+    Overwrite,
+    RenameNumbered,
+}
+
+impl Msg {
+    pub fn text(&self) -> &'static str {
+        self.localized(*LOCALE)
+    }
+
+    fn localized(&self, locale: Locale) -> &'static str {
+        match (self, locale) {
+            (Msg::Yes, Locale::En) => "YES",
+            (Msg::Yes, Locale::De) => "JA",
+            (Msg::Yes, Locale::Es) => "SÍ",
+            (Msg::Yes, Locale::Fr) => "OUI",
+            (Msg::No, Locale::En | Locale::Es) => "NO",
+            (Msg::No, Locale::De) => "NEIN",
+            (Msg::No, Locale::Fr) => "NON",
+            (Msg::Each, Locale::En) => "EACH",
+            (Msg::Each, Locale::De) => "EINZELN",
+            (Msg::Each, Locale::Es) => "CADA UNO",
+            (Msg::Each, Locale::Fr) => "UN PAR UN",
+            (Msg::PreviewUp, Locale::En) => "PREVIEW UP:",
+            (Msg::PreviewUp, Locale::De) => "VORSCHAU HOCH:",
+            (Msg::PreviewUp, Locale::Es) => "VISTA PREVIA ARRIBA:",
+            (Msg::PreviewUp, Locale::Fr) => "APERÇU HAUT :",
+            (Msg::PreviewDown, Locale::En) => "PREVIEW DOWN:",
+            (Msg::PreviewDown, Locale::De) => "VORSCHAU RUNTER:",
+            (Msg::PreviewDown, Locale::Es) => "VISTA PREVIA ABAJO:",
+            (Msg::PreviewDown, Locale::Fr) => "APERÇU BAS :",
+            (Msg::PageUp, Locale::En) => "PAGE UP:",
+            (Msg::PageUp, Locale::De) => "SEITE HOCH:",
+            (Msg::PageUp, Locale::Es) => "RE PÁG:",
+            (Msg::PageUp, Locale::Fr) => "PAGE PRÉC. :",
+            (Msg::PageDown, Locale::En) => "PAGE DOWN:",
+            (Msg::PageDown, Locale::De) => "SEITE RUNTER:",
+            (Msg::PageDown, Locale::Es) => "AV PÁG:",
+            (Msg::PageDown, Locale::Fr) => "PAGE SUIV. :",
+            (Msg::Exit, Locale::En) => "EXIT:",
+            (Msg::Exit, Locale::De) => "BEENDEN:",
+            (Msg::Exit, Locale::Es) => "SALIR:",
+            (Msg::Exit, Locale::Fr) => "QUITTER :",
+            (Msg::Select, Locale::En) => "SELECT:",
+            (Msg::Select, Locale::De) => "AUSWÄHLEN:",
+            (Msg::Select, Locale::Es) => "SELECCIONAR:",
+            (Msg::Select, Locale::Fr) => "SÉLECTIONNER :",
+            (Msg::SelectMultiple, Locale::En) => "SELECT, MULTIPLE:",
+            (Msg::SelectMultiple, Locale::De) => "MEHRFACHAUSWAHL:",
+            (Msg::SelectMultiple, Locale::Es) => "SELECCIÓN MÚLTIPLE:",
+            (Msg::SelectMultiple, Locale::Fr) => "SÉLECTION MULTIPLE :",
+            (Msg::BrowseMode, Locale::En) => "Browse Mode",
+            (Msg::BrowseMode, Locale::De) => "Durchsuchen",
+            (Msg::BrowseMode, Locale::Es) => "Modo Explorar",
+            (Msg::BrowseMode, Locale::Fr) => "Mode Parcourir",
+            (Msg::SelectMode, Locale::En) => "Select Mode",
+            (Msg::SelectMode, Locale::De) => "Auswahl",
+            (Msg::SelectMode, Locale::Es) => "Modo Selección",
+            (Msg::SelectMode, Locale::Fr) => "Mode Sélection",
+            (Msg::RestoreMode, Locale::En) => "Restore Mode",
+            (Msg::RestoreMode, Locale::De) => "Wiederherstellen",
+            (Msg::RestoreMode, Locale::Es) => "Modo Restaurar",
+            (Msg::RestoreMode, Locale::Fr) => "Mode Restauration",
+            (Msg::PruneMode, Locale::En) => "Prune Mode",
+            (Msg::PruneMode, Locale::De) => "Bereinigen",
+            (Msg::PruneMode, Locale::Es) => "Modo Podar",
+            (Msg::PruneMode, Locale::Fr) => "Mode Élagage",
+            (Msg::SoftDeleteMode, Locale::En) => "Soft Delete Mode",
+            (Msg::SoftDeleteMode, Locale::De) => "Sicher Löschen",
+            (Msg::SoftDeleteMode, Locale::Es) => "Modo Borrado Seguro",
+            (Msg::SoftDeleteMode, Locale::Fr) => "Mode Suppression Sûre",
+            (Msg::RestoreConsent, Locale::En) => {
+                "Before httm performs a restore, it would like your consent. Continue? (YES/NO)"
+            }
+            (Msg::RestoreConsent, Locale::De) => {
+                "Bevor httm eine Wiederherstellung durchführt, bittet es um Ihre Zustimmung. Fortfahren? (JA/NEIN)"
+            }
+            (Msg::RestoreConsent, Locale::Es) => {
+                "Antes de realizar una restauración, httm solicita su consentimiento. ¿Continuar? (SÍ/NO)"
+            }
+            (Msg::RestoreConsent, Locale::Fr) => {
+                "Avant d'effectuer une restauration, httm demande votre consentement. Continuer ? (OUI/NON)"
+            }
+            (Msg::PruneConsent, Locale::En) => {
+                "Before httm destroys these snapshot/s, it would like your consent. Continue? (YES/NO)"
+            }
+            (Msg::PruneConsent, Locale::De) => {
+                "Bevor httm diese Snapshots löscht, bittet es um Ihre Zustimmung. Fortfahren? (JA/NEIN)"
+            }
+            (Msg::PruneConsent, Locale::Es) => {
+                "Antes de destruir estas instantáneas, httm solicita su consentimiento. ¿Continuar? (SÍ/NO)"
+            }
+            (Msg::PruneConsent, Locale::Fr) => {
+                "Avant de détruire ces instantanés, httm demande votre consentement. Continuer ? (OUI/NON)"
+            }
+            (Msg::PruneTypedConsent, Locale::En) => {
+                "To confirm, type the number of snapshot/s httm will destroy:"
+            }
+            (Msg::PruneTypedConsent, Locale::De) => {
+                "Zur Bestätigung geben Sie die Anzahl der Snapshots ein, die httm löschen wird:"
+            }
+            (Msg::PruneTypedConsent, Locale::Es) => {
+                "Para confirmar, escriba el número de instantáneas que httm destruirá:"
+            }
+            (Msg::PruneTypedConsent, Locale::Fr) => {
+                "Pour confirmer, saisissez le nombre d'instantanés que httm va détruire :"
+            }
+            (Msg::SoftDeleteConsent, Locale::En) => {
+                "Before httm deletes these live file/s, it would like your consent. Continue? (YES/NO)"
+            }
+            (Msg::SoftDeleteConsent, Locale::De) => {
+                "Bevor httm diese Live-Dateien löscht, bittet es um Ihre Zustimmung. Fortfahren? (JA/NEIN)"
+            }
+            (Msg::SoftDeleteConsent, Locale::Es) => {
+                "Antes de eliminar estos archivos, httm solicita su consentimiento. ¿Continuar? (SÍ/NO)"
+            }
+            (Msg::SoftDeleteConsent, Locale::Fr) => {
+                "Avant de supprimer ces fichiers, httm demande votre consentement. Continuer ? (OUI/NON)"
+            }
+            (Msg::BisectPrompt, Locale::En) => {
+                "Is this version good or bad? (GOOD/BAD/SKIP)"
+            }
+            (Msg::BisectPrompt, Locale::De) => {
+                "Ist diese Version gut oder schlecht? (GOOD/BAD/SKIP)"
+            }
+            (Msg::BisectPrompt, Locale::Es) => {
+                "¿Esta versión es buena o mala? (GOOD/BAD/SKIP)"
+            }
+            (Msg::BisectPrompt, Locale::Fr) => {
+                "Cette version est-elle bonne ou mauvaise ? (GOOD/BAD/SKIP)"
+            }
+            (Msg::UndoConsent, Locale::En) => {
+                "Before httm undoes this restore, it would like your consent. Continue? (YES/NO)"
+            }
+            (Msg::UndoConsent, Locale::De) => {
+                "Bevor httm diese Wiederherstellung rückgängig macht, bittet es um Ihre Zustimmung. Fortfahren? (JA/NEIN)"
+            }
+            (Msg::UndoConsent, Locale::Es) => {
+                "Antes de deshacer esta restauración, httm solicita su consentimiento. ¿Continuar? (SÍ/NO)"
+            }
+            (Msg::UndoConsent, Locale::Fr) => {
+                "Avant d'annuler cette restauration, httm demande votre consentement. Continuer ? (OUI/NON)"
+            }
+            (Msg::Edit, Locale::En) => "EDIT:",
+            (Msg::Edit, Locale::De) => "BEARBEITEN:",
+            (Msg::Edit, Locale::Es) => "EDITAR:",
+            (Msg::Edit, Locale::Fr) => "ÉDITER :",
+            (Msg::Snapshot, Locale::En) => "SNAPSHOT:",
+            (Msg::Snapshot, Locale::De) => "SNAPSHOT:",
+            (Msg::Snapshot, Locale::Es) => "INSTANTÁNEA:",
+            (Msg::Snapshot, Locale::Fr) => "INSTANTANÉ :",
+            (Msg::HunkLive, Locale::En) => "LIVE",
+            (Msg::HunkLive, Locale::De) => "LIVE-VERSION",
+            (Msg::HunkLive, Locale::Es) => "VERSIÓN ACTUAL",
+            (Msg::HunkLive, Locale::Fr) => "VERSION ACTUELLE",
+            (Msg::HunkSnapshot, Locale::En | Locale::De) => "SNAPSHOT",
+            (Msg::HunkSnapshot, Locale::Es) => "INSTANTÁNEA",
+            (Msg::HunkSnapshot, Locale::Fr) => "INSTANTANÉ",
+            (Msg::HunkQuit, Locale::En) => "QUIT",
+            (Msg::HunkQuit, Locale::De) => "BEENDEN",
+            (Msg::HunkQuit, Locale::Es) => "SALIR",
+            (Msg::HunkQuit, Locale::Fr) => "QUITTER",
+            (Msg::KeepLive, Locale::En) => "KEEP LIVE",
+            (Msg::KeepLive, Locale::De) => "LIVE-VERSION BEHALTEN",
+            (Msg::KeepLive, Locale::Es) => "CONSERVAR VERSIÓN ACTUAL",
+            (Msg::KeepLive, Locale::Fr) => "GARDER VERSION ACTUELLE",
+            (Msg::TakeSnapshot, Locale::En) => "TAKE SNAPSHOT",
+            (Msg::TakeSnapshot, Locale::De) => "SNAPSHOT ÜBERNEHMEN",
+            (Msg::TakeSnapshot, Locale::Es) => "TOMAR INSTANTÁNEA",
+            (Msg::TakeSnapshot, Locale::Fr) => "PRENDRE INSTANTANÉ",
+            (Msg::ChoosePerFile, Locale::En) => "CHOOSE PER FILE",
+            (Msg::ChoosePerFile, Locale::De) => "PRO DATEI WÄHLEN",
+            (Msg::ChoosePerFile, Locale::Es) => "ELEGIR POR ARCHIVO",
+            (Msg::ChoosePerFile, Locale::Fr) => "CHOISIR PAR FICHIER",
+            (Msg::Skip, Locale::En) => "SKIP",
+            (Msg::Skip, Locale::De) => "ÜBERSPRINGEN",
+            (Msg::Skip, Locale::Es) => "OMITIR",
+            (Msg::Skip, Locale::Fr) => "IGNORER",
+            (Msg::Overwrite, Locale::En) => "OVERWRITE",
+            (Msg::Overwrite, Locale::De) => "ÜBERSCHREIBEN",
+            (Msg::Overwrite, Locale::Es) => "SOBRESCRIBIR",
+            (Msg::Overwrite, Locale::Fr) => "ÉCRASER",
+            (Msg::RenameNumbered, Locale::En) => "RENAME NUMBERED",
+            (Msg::RenameNumbered, Locale::De) => "NUMMERIERT UMBENENNEN",
+            (Msg::RenameNumbered, Locale::Es) => "RENOMBRAR NUMERADO",
+            (Msg::RenameNumbered, Locale::Fr) => "RENOMMER AVEC NUMÉRO",
+            (Msg::Filter, Locale::En) => "FILTER:",
+            (Msg::Filter, Locale::De) => "FILTER:",
+            (Msg::Filter, Locale::Es) => "FILTRAR:",
+            (Msg::Filter, Locale::Fr) => "FILTRER :",
+            (Msg::FilterAll, Locale::En) => "all entries",
+            (Msg::FilterAll, Locale::De) => "alle Einträge",
+            (Msg::FilterAll, Locale::Es) => "todas las entradas",
+            (Msg::FilterAll, Locale::Fr) => "toutes les entrées",
+            (Msg::FilterWithSnaps, Locale::En) => "only entries with snapshot versions",
+            (Msg::FilterWithSnaps, Locale::De) => "nur Einträge mit Snapshot-Versionen",
+            (Msg::FilterWithSnaps, Locale::Es) => "solo entradas con versiones en instantáneas",
+            (Msg::FilterWithSnaps, Locale::Fr) => "seulement les entrées avec des versions d'instantanés",
+            (Msg::FilterDiffersFromLast, Locale::En) => "only entries which differ from the last snapshot",
+            (Msg::FilterDiffersFromLast, Locale::De) => "nur Einträge, die vom letzten Snapshot abweichen",
+            (Msg::FilterDiffersFromLast, Locale::Es) => "solo entradas que difieren de la última instantánea",
+            (Msg::FilterDiffersFromLast, Locale::Fr) => "seulement les entrées qui diffèrent du dernier instantané",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Consent {
+    Yes,
+    No,
+    Undecided,
+}
+
+impl From<&str> for Consent {
+    // only whole words are matched, never prefixes, and the English words are always accepted,
+    // so a script or muscle memory written for one locale cannot be misread in another
+    fn from(selection: &str) -> Self {
+        let selection = selection.trim().to_uppercase();
+
+        match selection.as_str() {
+            "YES" | "Y" => return Consent::Yes,
+            "NO" | "N" => return Consent::No,
+            _ => {}
+        }
+
+        let yes = Msg::Yes.text();
+        let no = Msg::No.text();
+
+        // also accept a localized confirmation typed without diacritics, like "SI" for "SÍ"
+        if selection == yes || selection == Self::strip_diacritics(yes) {
+            return Consent::Yes;
+        }
+
+        if selection == no || selection == Self::strip_diacritics(no) {
+            return Consent::No;
+        }
+
+        Consent::Undecided
+    }
+}
+
+impl Consent {
+    fn strip_diacritics(word: &str) -> String {
+        word.chars()
+            .map(|c| match c {
+                'Á' | 'À' | 'Â' | 'Ä' => 'A',
+                'É' | 'È' | 'Ê' | 'Ë' => 'E',
+                'Í' | 'Ì' | 'Î' | 'Ï' => 'I',
+                'Ó' | 'Ò' | 'Ô' | 'Ö' => 'O',
+                'Ú' | 'Ù' | 'Û' | 'Ü' => 'U',
+                _ => c,
+            })
+            .collect()
+    }
+
+    // the choices presented to the user for selection
+    pub fn choices() -> String {
+        format!("{}\n{}", Msg::Yes.text(), Msg::No.text())
+    }
+
+    // a batch may also be consented to item by item
+    pub fn batch_choices() -> String {
+        format!(
+            "{}\n{}\n{}",
+            Msg::Yes.text(),
+            Msg::Each.text(),
+            Msg::No.text()
+        )
+    }
+
+    pub fn is_each(selection: &str) -> bool {
+        let selection = selection.trim().to_uppercase();
+        let each = Msg::Each.text();
+
+        selection == "EACH" || selection == each || selection == Self::strip_diacritics(each)
+    }
+}
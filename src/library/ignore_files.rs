@@ -0,0 +1,125 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use hashbrown::HashMap;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, RwLock};
+
+const VCS_DIRECTORY_NAMES: [&str; 3] = [".git", ".hg", ".svn"];
+
+// the ignore files of each directory, read once per directory
+static IGNORE_CACHE: LazyLock<RwLock<HashMap<PathBuf, Arc<DirIgnores>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+struct DirIgnores {
+    // like ripgrep, a ".gitignore" only applies within a repository, whereas an ".ignore" always applies
+    gitignore: Gitignore,
+    dot_ignore: Gitignore,
+    is_repo_root: bool,
+}
+
+impl DirIgnores {
+    fn new(dir: &Path) -> Self {
+        Self {
+            gitignore: Self::build(dir, ".gitignore"),
+            dot_ignore: Self::build(dir, ".ignore"),
+            is_repo_root: VCS_DIRECTORY_NAMES
+                .iter()
+                .any(|vcs| dir.join(vcs).symlink_metadata().is_ok()),
+        }
+    }
+
+    // an unreadable or malformed ignore file ignores nothing, as with any other ignore file tool
+    fn build(dir: &Path, file_name: &str) -> Gitignore {
+        let mut builder = GitignoreBuilder::new(dir);
+
+        let _ = builder.add(dir.join(file_name));
+
+        builder.build().unwrap_or_else(|_| Gitignore::empty())
+    }
+}
+
+pub struct IgnoreFiles;
+
+impl IgnoreFiles {
+    // like ripgrep, rules of an ignore file nearer the path override those further away, and ".ignore"
+    // rules override ".gitignore" rules.  No ignore file above the repository root is ever consulted.
+    pub fn is_ignored(path: &Path, is_dir: bool) -> bool {
+        if is_dir
+            && path.file_name().map_or(false, |file_name| {
+                VCS_DIRECTORY_NAMES.iter().any(|vcs| file_name == *vcs)
+            })
+        {
+            return true;
+        }
+
+        let dirs: Vec<(&Path, Arc<DirIgnores>)> = path
+            .ancestors()
+            .skip(1)
+            .map(|dir| (dir, Self::dir_ignores(dir)))
+            .collect();
+
+        // where the path is not within a repository, only ".ignore" files apply, from any directory above
+        let opt_repo_root_idx = dirs
+            .iter()
+            .position(|(_dir, dir_ignores)| dir_ignores.is_repo_root);
+
+        let within_repo = match opt_repo_root_idx {
+            Some(idx) => &dirs[..=idx],
+            None => &dirs[..],
+        };
+
+        within_repo
+            .iter()
+            .find_map(|(_dir, dir_ignores)| {
+                let is_dot_ignored = dir_ignores.dot_ignore.matched(path, is_dir);
+
+                let matched = if is_dot_ignored.is_none() && opt_repo_root_idx.is_some() {
+                    dir_ignores.gitignore.matched(path, is_dir)
+                } else {
+                    is_dot_ignored
+                };
+
+                match matched {
+                    Match::None => None,
+                    Match::Ignore(_) => Some(true),
+                    Match::Whitelist(_) => Some(false),
+                }
+            })
+            .unwrap_or(false)
+    }
+
+    fn dir_ignores(dir: &Path) -> Arc<DirIgnores> {
+        if let Some(cached) = IGNORE_CACHE
+            .read()
+            .ok()
+            .and_then(|cache| cache.get(dir).cloned())
+        {
+            return cached;
+        }
+
+        let dir_ignores = Arc::new(DirIgnores::new(dir));
+
+        if let Ok(mut cache) = IGNORE_CACHE.write() {
+            cache.insert(dir.to_path_buf(), dir_ignores.clone());
+        }
+
+        dir_ignores
+    }
+}
@@ -0,0 +1,181 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::data::paths::PathData;
+use crate::display::snap_info::SnapInfo;
+use crate::library::file_ops::Copy;
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{date_string, DateFormat};
+use crate::lookup::versions::VersionsMap;
+use crate::GLOBAL_CONFIG;
+use hashbrown::HashSet;
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+
+// the subdirectory of the target dir in which live versions are placed
+const LIVE_SUBDIR: &str = "live";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LinkMethod {
+    HardLink,
+    Copy,
+    Exists,
+}
+
+// every unique version of the requested files, materialized beneath a target directory, one subdirectory
+// per snapshot, for the benefit of tools which know nothing of snapshots, like an external diff tool
+pub struct LinkFarm;
+
+impl LinkFarm {
+    pub fn exec(versions_map: &VersionsMap, target_dir: &Path) -> HttmResult<()> {
+        let jobs: Vec<(&PathData, PathBuf, bool)> = versions_map
+            .iter()
+            .flat_map(|(live_version, snaps)| {
+                let snap_jobs = snaps
+                    .iter()
+                    .map(move |snap| (snap, target_dir.join(Self::subdir(snap)), false));
+
+                let live_job = live_version
+                    .opt_metadata()
+                    .is_some()
+                    .then(|| (live_version, target_dir.join(LIVE_SUBDIR), true));
+
+                snap_jobs
+                    .chain(live_job)
+                    .map(move |(version, subdir, is_live)| {
+                        (version, Self::dst(&subdir, live_version.path()), is_live)
+                    })
+            })
+            .collect();
+
+        // two versions of the same file, whose snapshots share a timestamp, would share a destination,
+        // so only the first is exported, and the rest are reported
+        let mut seen: HashSet<&Path> = HashSet::new();
+        let is_collision: Vec<bool> = jobs
+            .iter()
+            .map(|(_version, dst, _is_live)| !seen.insert(dst.as_path()))
+            .collect();
+
+        if GLOBAL_CONFIG.opt_dry_run {
+            let jobs_string: String = jobs
+                .iter()
+                .map(|(version, dst, _is_live)| format!("{:?} -> {:?}\n", version.path(), dst))
+                .collect();
+
+            println!(
+                "httm would export the following version/s:\n\n{jobs_string}\n\
+                Dry run.  No versions were exported."
+            );
+
+            return Ok(());
+        }
+
+        let results: Vec<HttmResult<LinkMethod>> = jobs
+            .par_iter()
+            .zip(is_collision.par_iter())
+            .map(|((version, dst, is_live), is_collision)| {
+                if *is_collision {
+                    let msg = format!(
+                        "another version was exported to the same destination, as their snapshots share a timestamp: {:?}",
+                        dst
+                    );
+                    return Err(HttmError::new(&msg).into());
+                }
+
+                Self::link_or_copy(version.path(), dst, *is_live)
+            })
+            .collect();
+
+        let count = |method: LinkMethod| {
+            results
+                .iter()
+                .filter(|res| matches!(res, Ok(res_method) if *res_method == method))
+                .count()
+        };
+
+        let failures: Vec<String> = jobs
+            .iter()
+            .zip(results.iter())
+            .filter_map(|((version, _dst, _is_live), res)| {
+                res.as_ref()
+                    .err()
+                    .map(|err| format!("\t{:?}: {}\n", version.path(), err))
+            })
+            .collect();
+
+        eprintln!(
+            "httm exported {} version/s of {} file/s to {:?}: {} hard linked, {} copied, {} already existed.",
+            jobs.len() - failures.len(),
+            versions_map.len(),
+            target_dir,
+            count(LinkMethod::HardLink),
+            count(LinkMethod::Copy),
+            count(LinkMethod::Exists)
+        );
+
+        if failures.is_empty() {
+            return Ok(());
+        }
+
+        let msg = format!(
+            "httm could not export {} version/s:\n{}",
+            failures.len(),
+            failures.concat().trim_end()
+        );
+
+        Err(HttmError::new(&msg).into())
+    }
+
+    // a snapshot version is placed beneath a subdirectory named for the creation time of its snapshot,
+    // else its modify time, such that the same snapshot's versions of different files share a subdirectory
+    fn subdir(snap: &PathData) -> String {
+        let time = SnapInfo::new(snap)
+            .and_then(|snap_info| snap_info.opt_creation)
+            .unwrap_or_else(|| snap.metadata_infallible().mtime());
+
+        date_string(
+            GLOBAL_CONFIG.requested_utc_offset,
+            &time,
+            DateFormat::Timestamp,
+        )
+    }
+
+    fn dst(subdir: &Path, live_path: &Path) -> PathBuf {
+        let relative = live_path.strip_prefix("/").unwrap_or(live_path);
+
+        subdir.join(relative)
+    }
+
+    // a hard link is cheapest, but a snapshot is, most often, a filesystem of its own, so,
+    // failing a hard link, we copy, which, where the filesystem supports it, is a reflink.  The live
+    // version is always copied, as a tool which writes to a hard link would write to the live file
+    fn link_or_copy(src: &Path, dst: &Path, is_live: bool) -> HttmResult<LinkMethod> {
+        if dst.symlink_metadata().is_ok() {
+            return Ok(LinkMethod::Exists);
+        }
+
+        Copy::generate_dst_parent(dst)?;
+
+        if !is_live && src.is_file() && std::fs::hard_link(src, dst).is_ok() {
+            return Ok(LinkMethod::HardLink);
+        }
+
+        Copy::direct_quiet(src, dst, true)?;
+
+        Ok(LinkMethod::Copy)
+    }
+}
@@ -76,3 +76,26 @@ impl From<IoError> for HttmError {
         HttmError { details: context }
     }
 }
+
+// the user has quit, which is not an error, so fn main() prints the message and exits successfully,
+// after cleanup, rather than quitting from deep within an interactive view
+#[derive(Debug)]
+pub struct HttmAbort {
+    pub details: String,
+}
+
+impl HttmAbort {
+    pub fn new(msg: &str) -> Self {
+        HttmAbort {
+            details: msg.to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for HttmAbort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.details)
+    }
+}
+
+impl Error for HttmAbort {}
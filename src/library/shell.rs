@@ -0,0 +1,191 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::library::results::{HttmError, HttmResult};
+use std::path::{Path, PathBuf};
+use which::which;
+
+// the families of shells whose quoting rules we know, zsh, dash, ksh, and bash each quote as a POSIX sh does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellKind {
+    Posix,
+    Fish,
+}
+
+impl ShellKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.file_name()?.to_str()? {
+            "sh" | "bash" | "dash" | "zsh" | "ksh" | "mksh" | "ash" | "yash" => {
+                Some(ShellKind::Posix)
+            }
+            "fish" => Some(ShellKind::Fish),
+            _ => None,
+        }
+    }
+
+    // single quote a value, so the shell treats it as a single word, and never expands it
+    pub fn quote(&self, value: &str) -> String {
+        match self {
+            // within single quotes, nothing, not even a single quote, may be escaped, so close the quote, escape, and reopen
+            ShellKind::Posix => format!("'{}'", value.replace('\'', "'\\''")),
+            // within single quotes, fish escapes only a single quote and a backslash
+            ShellKind::Fish => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'")),
+        }
+    }
+
+    // a reference to a variable, within double quotes, which may be immediately followed by other characters
+    pub fn variable(&self, name: &str) -> String {
+        match self {
+            ShellKind::Posix => format!("${{{name}}}"),
+            // fish has no braced reference within double quotes, so we close the quotes around a bare
+            // reference, which fish, unlike a POSIX sh, never splits
+            ShellKind::Fish => format!("\"${name}\""),
+        }
+    }
+
+    // a reference to a variable, outside of any quotes, which the shell treats as a single word
+    pub fn quoted_variable(&self, name: &str) -> String {
+        match self {
+            ShellKind::Posix => format!("\"${{{name}}}\""),
+            ShellKind::Fish => format!("\"${name}\""),
+        }
+    }
+
+    pub fn assignment(&self, name: &str, value: &str) -> String {
+        match self {
+            ShellKind::Posix => format!("{name}={};", self.quote(value)),
+            ShellKind::Fish => format!("set {name} {};", self.quote(value)),
+        }
+    }
+
+    // fish, unlike a POSIX sh, also recognizes a backslash escape within single quotes
+    pub fn is_escaped_within_single_quotes(&self) -> bool {
+        matches!(self, ShellKind::Fish)
+    }
+
+    // the arguments which follow a script, "-c <script>", such that the args given are the script's positional parameters
+    pub fn positional_args<'a>(&self, args: &[&'a Path]) -> Vec<&'a Path> {
+        match self {
+            // a POSIX sh assigns the first argument after the script to "$0"
+            ShellKind::Posix => std::iter::once(Path::new("httm"))
+                .chain(args.iter().copied())
+                .collect(),
+            ShellKind::Fish => args.to_vec(),
+        }
+    }
+
+    // a quoted reference to the script's first positional parameter, see positional_args
+    pub fn first_positional(&self) -> &'static str {
+        match self {
+            ShellKind::Posix => "\"$1\"",
+            ShellKind::Fish => "\"$argv[1]\"",
+        }
+    }
+
+    // a reference to every positional parameter, each a single word, see positional_args
+    pub fn all_positionals(&self) -> &'static str {
+        match self {
+            ShellKind::Posix => "\"$@\"",
+            // fish never splits a list variable's elements
+            ShellKind::Fish => "$argv",
+        }
+    }
+}
+
+// where a shell is genuinely needed, for instance, for a user's pipeline, the user's $SHELL, so long as
+// we know how that shell quotes, otherwise, like for csh or nushell, 'sh'
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserShell {
+    pub path: PathBuf,
+    pub kind: ShellKind,
+}
+
+impl UserShell {
+    pub fn new() -> HttmResult<Self> {
+        let opt_user_shell = std::env::var_os("SHELL")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute() && path.exists())
+            .and_then(|path| ShellKind::from_path(&path).map(|kind| Self { path, kind }));
+
+        match opt_user_shell {
+            Some(user_shell) => Ok(user_shell),
+            None => Self::posix(),
+        }
+    }
+
+    pub fn posix() -> HttmResult<Self> {
+        let sh = which("sh").map_err(|_err| {
+            HttmError::new("'sh' command not found. Make sure the command 'sh' is in your path.")
+        })?;
+
+        Ok(Self {
+            path: sh,
+            kind: ShellKind::Posix,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn posix_quote() {
+        assert_eq!(ShellKind::Posix.quote("my file"), "'my file'");
+        assert_eq!(ShellKind::Posix.quote("it's"), "'it'\\''s'");
+        assert_eq!(ShellKind::Posix.quote("\"$HOME\""), "'\"$HOME\"'");
+        // a backslash is never an escape within single quotes
+        assert_eq!(ShellKind::Posix.quote("a\\b"), "'a\\b'");
+    }
+
+    #[test]
+    fn fish_quote() {
+        assert_eq!(ShellKind::Fish.quote("my file"), "'my file'");
+        assert_eq!(ShellKind::Fish.quote("it's"), "'it\\'s'");
+        assert_eq!(ShellKind::Fish.quote("a\\b"), "'a\\\\b'");
+        assert_eq!(ShellKind::Fish.quote("\"$HOME\""), "'\"$HOME\"'");
+    }
+
+    #[test]
+    fn variable() {
+        assert_eq!(ShellKind::Posix.variable("snap_file"), "${snap_file}");
+        assert_eq!(ShellKind::Fish.variable("snap_file"), "\"$snap_file\"");
+
+        assert_eq!(
+            ShellKind::Posix.quoted_variable("snap_file"),
+            "\"${snap_file}\""
+        );
+        assert_eq!(
+            ShellKind::Fish.quoted_variable("snap_file"),
+            "\"$snap_file\""
+        );
+    }
+
+    #[test]
+    fn positional_args() {
+        let paths = [Path::new("/a b"), Path::new("/c")];
+
+        assert_eq!(
+            ShellKind::Posix.positional_args(&paths),
+            vec![Path::new("httm"), Path::new("/a b"), Path::new("/c")]
+        );
+        assert_eq!(
+            ShellKind::Fish.positional_args(&paths),
+            vec![Path::new("/a b"), Path::new("/c")]
+        );
+    }
+}
@@ -0,0 +1,58 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use nu_ansi_term::Color;
+use std::borrow::Cow;
+use std::io::IsTerminal;
+use std::sync::LazyLock;
+
+static IS_PLAIN: LazyLock<bool> = LazyLock::new(|| Terminal::is_dumb() || Terminal::is_watch());
+static IS_STDERR_COLORED: LazyLock<bool> = LazyLock::new(|| {
+    !*IS_PLAIN && std::env::var_os("NO_COLOR").is_none() && std::io::stderr().is_terminal()
+});
+
+// one place to ask what the terminal on the other end can display
+pub struct Terminal;
+
+impl Terminal {
+    // no TERM at all (as from cron), or a terminal which declares it cannot interpret escape sequences
+    fn is_dumb() -> bool {
+        match std::env::var_os("TERM") {
+            Some(term) => term.is_empty() || term == "dumb",
+            None => true,
+        }
+    }
+
+    // procps watch sets WATCH_INTERVAL for its child, and, by default, prints escape sequences verbatim
+    fn is_watch() -> bool {
+        std::env::var_os("WATCH_INTERVAL").is_some()
+    }
+
+    // should httm prefer output without border lines and colors, see FORCE_PRETTY
+    pub fn is_plain() -> bool {
+        *IS_PLAIN
+    }
+
+    // paint status messages, like "Restored", only where someone will see the color
+    pub fn paint_stderr(color: Color, msg: &str) -> Cow<str> {
+        if *IS_STDERR_COLORED {
+            return Cow::Owned(color.paint(msg).to_string());
+        }
+
+        Cow::Borrowed(msg)
+    }
+}
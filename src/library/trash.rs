@@ -0,0 +1,193 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::library::file_ops::{Copy, Remove};
+use crate::library::results::{HttmError, HttmResult};
+use crate::GLOBAL_CONFIG;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use time::{format_description, OffsetDateTime};
+
+const HTTM_TRASH_DIR: &str = ".httm_trash";
+const TRASH_FILES_DIR: &str = "files";
+const TRASH_INFO_DIR: &str = "info";
+const TRASH_INFO_EXT: &str = "trashinfo";
+const TRASH_INFO_DATE_FORMAT: &str = "[year]-[month]-[day]T[hour]:[minute]:[second]";
+
+// before an overwrite restore, stage the live file in a trash directory, laid out per the
+// freedesktop.org trash spec, so the live file is recoverable even where no snapshot can be taken
+pub struct Trash;
+
+impl Trash {
+    // returns the staged location, or None where there is no live file to stage
+    pub fn stage(live_path: &Path) -> HttmResult<Option<PathBuf>> {
+        let Ok(live_metadata) = live_path.symlink_metadata() else {
+            return Ok(None);
+        };
+
+        let trash_dir = Self::trash_dir(live_path, live_metadata.dev())?;
+
+        if GLOBAL_CONFIG.opt_dry_run {
+            println!(
+                "httm would move the live file {:?} to the trash directory {:?}.",
+                live_path, trash_dir
+            );
+            return Ok(None);
+        }
+
+        let files_dir = trash_dir.join(TRASH_FILES_DIR);
+        let info_dir = trash_dir.join(TRASH_INFO_DIR);
+
+        std::fs::create_dir_all(&files_dir)?;
+        std::fs::create_dir_all(&info_dir)?;
+
+        let trashed = files_dir.join(Self::claim_name(live_path, &info_dir)?);
+
+        if live_metadata.is_dir() {
+            // a directory is copied, not moved, because an overwrite restore may exclude some of
+            // its live files, and those must remain in place, see RestoreConflicts
+            Copy::recursive_quiet(live_path, &trashed, true)?;
+        } else {
+            Self::move_file(live_path, &trashed)?;
+        }
+
+        Ok(Some(trashed))
+    }
+
+    // the XDG trash is only used where it resides on the same filesystem as the live file,
+    // else we use a trash directory at the root of the live file's filesystem
+    fn trash_dir(live_path: &Path, live_dev: u64) -> HttmResult<PathBuf> {
+        if let Some(xdg_trash) = Self::xdg_trash() {
+            let same_dev = xdg_trash
+                .ancestors()
+                .find_map(|ancestor| ancestor.metadata().ok())
+                .is_some_and(|md| md.dev() == live_dev);
+
+            if same_dev {
+                return Ok(xdg_trash);
+            }
+        }
+
+        let fs_root = live_path
+            .ancestors()
+            .skip(1)
+            .take_while(|ancestor| ancestor.metadata().is_ok_and(|md| md.dev() == live_dev))
+            .last()
+            .ok_or_else(|| {
+                let msg = format!(
+                    "Could not determine a trash directory for the live file: {:?}",
+                    live_path
+                );
+                HttmError::new(&msg)
+            })?;
+
+        Ok(fs_root.join(HTTM_TRASH_DIR))
+    }
+
+    fn xdg_trash() -> Option<PathBuf> {
+        let data_dir = match std::env::var_os("XDG_DATA_HOME") {
+            Some(xdg_data_home) => PathBuf::from(xdg_data_home),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".local/share"),
+        };
+
+        Some(data_dir.join("Trash"))
+    }
+
+    // a name is claimed by creating its trashinfo file exclusively, so that concurrent
+    // restores never stage two live files under the same name
+    fn claim_name(live_path: &Path, info_dir: &Path) -> HttmResult<String> {
+        let file_name = live_path
+            .file_name()
+            .ok_or_else(|| HttmError::new("Could not obtain a file name for the live file."))?
+            .to_string_lossy();
+
+        let trash_info = Self::trash_info(live_path)?;
+
+        for idx in 0usize.. {
+            let name = match idx {
+                0 => file_name.to_string(),
+                _ => format!("{file_name}.{idx}"),
+            };
+
+            let info_path = info_dir.join(format!("{name}.{TRASH_INFO_EXT}"));
+
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_path)
+            {
+                Ok(mut info_file) => {
+                    info_file.write_all(trash_info.as_bytes())?;
+                    info_file.sync_data()?;
+                    return Ok(name);
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        unreachable!()
+    }
+
+    // records the original location and the time of deletion, from which a trash utility may
+    // restore the live file
+    fn trash_info(live_path: &Path) -> HttmResult<String> {
+        let parsed_format = format_description::parse(TRASH_INFO_DATE_FORMAT)
+            .expect("trash info date format is invalid");
+
+        let deletion_date = OffsetDateTime::from(SystemTime::now())
+            .to_offset(GLOBAL_CONFIG.requested_utc_offset)
+            .format(&parsed_format)
+            .map_err(|err| HttmError::new(&err.to_string()))?;
+
+        let original_path = std::path::absolute(live_path)?;
+
+        Ok(format!(
+            "[Trash Info]\nPath={}\nDeletionDate={deletion_date}\n",
+            Self::percent_encode(&original_path)
+        ))
+    }
+
+    fn percent_encode(path: &Path) -> String {
+        path.as_os_str()
+            .as_bytes()
+            .iter()
+            .map(|byte| match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                    (*byte as char).to_string()
+                }
+                _ => format!("%{byte:02X}"),
+            })
+            .collect()
+    }
+
+    // rename where we can, but a bind mount may make the same filesystem appear as two
+    fn move_file(src: &Path, dst: &Path) -> HttmResult<()> {
+        match std::fs::rename(src, dst) {
+            Ok(_) => Ok(()),
+            Err(err) if err.raw_os_error() == Some(nix::errno::Errno::EXDEV as i32) => {
+                Copy::recursive_quiet(src, dst, true)?;
+                Remove::recursive_quiet(src)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
@@ -15,22 +15,29 @@
 // For the full copyright and license information, please view the LICENSE file
 // that was distributed with this source code.
 
-use crate::config::generate::{PrintMode, RawMode};
+use crate::config::generate::RelativeTo;
 use crate::data::paths::{BasicDirEntryInfo, PathData, PathMetadata};
 use crate::data::selection::SelectionCandidate;
+use crate::filesystem::borg::BorgRepos;
+use crate::filesystem::restic::ResticRepos;
+use crate::filesystem::ssh_aliases::SshAliases;
 use crate::library::results::{HttmError, HttmResult};
+use crate::library::shell::UserShell;
 use crate::GLOBAL_CONFIG;
+use hashbrown::{HashMap, HashSet};
 use lscolors::{Colorable, LsColors, Style};
-use nu_ansi_term::Style as AnsiTermStyle;
+use nu_ansi_term::{Color, Style as AnsiTermStyle};
 use number_prefix::NumberPrefix;
 use std::borrow::Cow;
-use std::fs::FileType;
+use std::ffi::OsStr;
+use std::fs::{File, FileType, OpenOptions};
 use std::io::Write;
 use std::iter::Iterator;
+use std::os::unix::fs::OpenOptionsExt;
 use std::path::{Path, PathBuf};
-use std::sync::LazyLock;
+use std::sync::{LazyLock, Mutex};
 use std::time::SystemTime;
-use time::{format_description, OffsetDateTime, UtcOffset};
+use time::{format_description, Date, OffsetDateTime, PrimitiveDateTime, UtcOffset};
 use which::which;
 
 pub fn get_mount_command() -> HttmResult<PathBuf> {
@@ -49,6 +56,46 @@ pub fn get_btrfs_command() -> HttmResult<PathBuf> {
     })
 }
 
+// unmount a FUSE filesystem, like a restic repo or sshfs mount, as an unprivileged user may
+pub fn fuse_unmount(mount_point: &Path) -> bool {
+    let (opt_command, args): (Option<PathBuf>, &[&str]) =
+        match which("fusermount3").or_else(|_err| which("fusermount")) {
+            Ok(fusermount) => (Some(fusermount), &["-u"]),
+            Err(_) => (which("umount").ok(), &[]),
+        };
+
+    let Some(command) = opt_command else {
+        return false;
+    };
+
+    std::process::Command::new(command)
+        .args(args)
+        .arg(mount_point)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+// a new dir, accessible only to us, at a name no other user could have predicted, so neither a dir,
+// nor a symlink, planted by another user is ever mounted upon, or written through
+pub fn private_temp_dir(prefix: &str) -> HttmResult<PathBuf> {
+    let template = std::env::temp_dir().join(format!("{prefix}-XXXXXX"));
+
+    Ok(nix::unistd::mkdtemp(&template)?)
+}
+
+// a new file, which must not already exist, and which is never opened through a symlink
+pub fn create_new_file(path: &Path, mode: u32) -> HttmResult<File> {
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(mode)
+        .custom_flags(nix::fcntl::OFlag::O_NOFOLLOW.bits())
+        .open(path)?;
+
+    Ok(file)
+}
+
 pub fn user_has_effective_root(msg: &str) -> HttmResult<()> {
     if !nix::unistd::geteuid().is_root() {
         let err = format!("Superuser privileges are required to execute: {}.", msg);
@@ -58,14 +105,41 @@ pub fn user_has_effective_root(msg: &str) -> HttmResult<()> {
     Ok(())
 }
 
+// every line of output, raw or formatted, is terminated by a NULL when ZEROS is specified
 pub fn delimiter() -> char {
-    if let PrintMode::Raw(RawMode::Zero) = GLOBAL_CONFIG.print_mode {
+    if GLOBAL_CONFIG.opt_zero {
         return '\0';
     }
 
     '\n'
 }
 
+// RFC 4180: each field is quoted, and any quotation mark within is doubled, so
+// paths which contain commas, quotes, or newlines survive a round trip
+pub fn csv_field(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+// the headers already printed, as each kind of table is known by its columns
+static CSV_HEADERS_PRINTED: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+// a CSV header is printed once per run for each kind of table, although rows may be printed in many batches
+pub fn csv_header(columns: &[&str]) -> String {
+    let header = columns.join(",");
+
+    let is_printed = !CSV_HEADERS_PRINTED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(header.clone());
+
+    if is_printed {
+        return String::new();
+    }
+
+    format!("{}{}", header, delimiter())
+}
+
 // pub enum Never {}
 
 // pub fn is_channel_closed(chan: &Receiver<Never>) -> bool {
@@ -84,6 +158,70 @@ pub fn make_tmp_path(path: &Path) -> PathBuf {
     PathBuf::from(res)
 }
 
+// a copy of some file contents, for an external program, like an editor, to open, in its own
+// temporary directory, which is removed when dropped.  the copy keeps the original file name,
+// so the program may still detect the file type
+pub struct TempCopy {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl TempCopy {
+    pub fn new(file_name: &OsStr, contents: &[u8], is_read_only: bool) -> HttmResult<Self> {
+        let dir = private_temp_dir("httm-copy")?;
+
+        // from here, the directory is removed on any error, when temp_copy is dropped
+        let temp_copy = Self {
+            path: dir.join(file_name),
+            dir,
+        };
+
+        let mode = if is_read_only { 0o400 } else { 0o600 };
+
+        create_new_file(&temp_copy.path, mode)?.write_all(contents)?;
+
+        Ok(temp_copy)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempCopy {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+// run a user specified program, like an $EDITOR, which may include its own arguments,
+// like "code --wait", upon the paths given, with the terminal attached.  As git does for its editor,
+// the user's shell parses the program, with its quotes and escapes, while each path is passed as
+// a positional parameter, and never reparsed
+pub fn exec_user_program(program: &str, paths: &[&Path]) -> HttmResult<()> {
+    if program.trim().is_empty() {
+        let msg = format!("Could not parse the user's program: {:?}", program);
+        return Err(HttmError::new(&msg).into());
+    }
+
+    let shell = UserShell::new()?;
+
+    let script = format!("{program} {}", shell.kind.all_positionals());
+
+    let status = std::process::Command::new(&shell.path)
+        .arg("-c")
+        .arg(script)
+        .args(shell.kind.positional_args(paths))
+        .status()?;
+
+    if !status.success() {
+        let msg = format!("Program {:?} exited with an error: {status}", program);
+        return Err(HttmError::new(&msg).into());
+    }
+
+    Ok(())
+}
+
 pub fn find_common_path<I, P>(paths: I) -> Option<Box<Path>>
 where
     I: IntoIterator<Item = P>,
@@ -115,6 +253,37 @@ fn cmp_path<A: AsRef<Path>, B: AsRef<Path>>(a: A, b: B) -> Option<PathBuf> {
     }
 }
 
+pub const HEX_DUMP_LINE_WIDTH: usize = 16;
+pub const HEX_DUMP_MAX_BYTES: usize = 512;
+
+// a bounded, canonical hex dump, offsets, then hex, then printable ascii, of at most HEX_DUMP_MAX_BYTES
+pub fn hex_dump(contents: &[u8]) -> String {
+    contents
+        .chunks(HEX_DUMP_LINE_WIDTH)
+        .take(HEX_DUMP_MAX_BYTES / HEX_DUMP_LINE_WIDTH)
+        .enumerate()
+        .map(|(idx, chunk)| {
+            let hex: String = chunk.iter().map(|byte| format!("{byte:02x} ")).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|byte| {
+                    if byte.is_ascii_graphic() || *byte == b' ' {
+                        *byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+
+            format!(
+                "{:08x}  {hex:<width$} |{ascii}|\n",
+                idx * HEX_DUMP_LINE_WIDTH,
+                width = HEX_DUMP_LINE_WIDTH * 3
+            )
+        })
+        .collect()
+}
+
 pub fn print_output_buf(output_buf: &str) -> HttmResult<()> {
     // mutex keeps threads from writing over each other
     let out = std::io::stdout();
@@ -221,6 +390,127 @@ where
     Cow::Borrowed(display_name)
 }
 
+// key: snap mount, val: the dataset mount (or the local dir) to which the snap mount belongs
+static SNAP_MOUNT_TO_DATASET: LazyLock<HashMap<&'static Path, &'static Path>> =
+    LazyLock::new(|| {
+        let native = GLOBAL_CONFIG
+            .dataset_collection
+            .map_of_snaps
+            .iter()
+            .flat_map(|(mount, snap_mounts)| {
+                snap_mounts
+                    .iter()
+                    .map(move |snap_mount| (snap_mount.as_ref(), mount.as_ref()))
+            });
+
+        let explicit = GLOBAL_CONFIG
+            .dataset_collection
+            .opt_map_of_explicit_snaps
+            .iter()
+            .flat_map(|map| map.iter())
+            .flat_map(|(local_dir, snap_mounts)| {
+                snap_mounts
+                    .iter()
+                    .map(move |snap_mount| (snap_mount.as_ref(), local_dir.as_ref()))
+            });
+
+        native
+            .chain(explicit)
+            .chain(BorgRepos::mounted())
+            .chain(ResticRepos::mounted())
+            .chain(SshAliases::mounted())
+            .collect()
+    });
+static DATASET_STYLE: LazyLock<AnsiTermStyle> = LazyLock::new(|| Color::Blue.normal());
+static SNAPSHOT_STYLE: LazyLock<AnsiTermStyle> = LazyLock::new(|| Color::Yellow.normal());
+
+// split a snapshot version's path into its snap mount, the dataset mount (or local dir) to which
+// that snap mount belongs, and the path relative to the snap mount
+pub fn split_snap_path(path: &Path) -> Option<(&Path, &'static Path, &Path)> {
+    let (snap_mount, dataset) = path.ancestors().skip(1).find_map(|ancestor| {
+        SNAP_MOUNT_TO_DATASET
+            .get(ancestor)
+            .map(|dataset| (ancestor, *dataset))
+    })?;
+
+    let relative = path.strip_prefix(snap_mount).ok()?;
+
+    Some((snap_mount, dataset, relative))
+}
+
+// paint a snapshot version's path in three parts, so long rows are scannable: the dataset, the snapshot
+// (for instance, "/.zfs/snapshot/<snap name>"), and the path relative to the snapshot, in ls colors
+pub fn paint_snap_path(pathdata: &PathData) -> Cow<str> {
+    let path = pathdata.path();
+
+    let Some((snap_mount, dataset, relative)) = split_snap_path(path) else {
+        return path.to_string_lossy();
+    };
+
+    // a snap mount may not be located beneath its dataset, as with some btrfs and Time Machine snapshots
+    let (dataset_str, snapshot_str) = match snap_mount.strip_prefix(dataset) {
+        Ok(snapshot) if dataset != Path::new("/") => (
+            dataset.to_string_lossy(),
+            Cow::Owned(format!("/{}", snapshot.to_string_lossy())),
+        ),
+        _ => (Cow::Borrowed(""), snap_mount.to_string_lossy()),
+    };
+
+    let relative_str = format!("/{}", relative.to_string_lossy());
+
+    Cow::Owned(format!(
+        "{}{}{}",
+        DATASET_STYLE.paint(dataset_str),
+        SNAPSHOT_STYLE.paint(snapshot_str),
+        paint_string(pathdata, &relative_str)
+    ))
+}
+
+// the path as displayed, which, if the user requested, may be relative to a base dir, see RELATIVE
+pub fn display_path(pathdata: &PathData) -> Cow<str> {
+    let path = pathdata.path();
+
+    let opt_base = match &GLOBAL_CONFIG.opt_relative {
+        None => None,
+        Some(RelativeTo::Dir(base)) => Some(base.as_ref()),
+        Some(RelativeTo::DatasetMount) => dataset_mount_for_display(path),
+    };
+
+    // paths not beneath their base are displayed as they are
+    match opt_base.and_then(|base| path.strip_prefix(base).ok()) {
+        Some(relative) if relative.as_os_str().is_empty() => Cow::Borrowed("."),
+        Some(relative) => relative.to_string_lossy(),
+        None => path.to_string_lossy(),
+    }
+}
+
+// for a snapshot version, the dataset mount (or local dir) to which its snap mount belongs,
+// and for a live path, the local dir of any alias or snapshot mount before its proximate dataset,
+// so paths display relative to the same dir the user sees, and not to any underlying remote dir
+fn dataset_mount_for_display(path: &Path) -> Option<&Path> {
+    if let Some(dataset) = path
+        .ancestors()
+        .skip(1)
+        .find_map(|ancestor| SNAP_MOUNT_TO_DATASET.get(ancestor))
+    {
+        return Some(*dataset);
+    }
+
+    let dataset_collection = &GLOBAL_CONFIG.dataset_collection;
+
+    path.ancestors().find(|ancestor| {
+        dataset_collection
+            .opt_map_of_aliases
+            .as_ref()
+            .is_some_and(|map| map.contains_key(*ancestor))
+            || dataset_collection
+                .opt_map_of_explicit_snaps
+                .as_ref()
+                .is_some_and(|map| map.contains_key(*ancestor))
+            || dataset_collection.map_of_datasets.contains_key(*ancestor)
+    })
+}
+
 pub trait PaintString {
     fn ls_style(&self) -> Option<&'_ lscolors::style::Style>;
     fn is_phantom(&self) -> bool;
@@ -253,6 +543,14 @@ pub enum DateFormat {
 static DATE_FORMAT_DISPLAY: &str =
     "[weekday repr:short] [month repr:short] [day] [hour]:[minute]:[second] [year]";
 static DATE_FORMAT_TIMESTAMP: &str = "[year]-[month]-[day]-[hour]:[minute]:[second]";
+static TIMESTAMP_INPUT_FORMATS: [&str; 5] = [
+    DATE_FORMAT_TIMESTAMP,
+    "[year]-[month]-[day] [hour]:[minute]:[second]",
+    "[year]-[month]-[day]T[hour]:[minute]:[second]",
+    "[year]-[month]-[day] [hour]:[minute]",
+    "[year]-[month]-[day]T[hour]:[minute]",
+];
+static DATE_INPUT_FORMAT: &str = "[year]-[month]-[day]";
 
 pub fn date_string(
     utc_offset: UtcOffset,
@@ -286,6 +584,99 @@ fn date_string_format<'a>(format: &DateFormat) -> &'a str {
     }
 }
 
+// accepts a date, with an optional time, in the local time zone, unless UTC is specified, or the
+// timestamp carries a "_UTC" suffix, as does httm's own timestamp format, or seconds since the epoch
+pub fn parse_timestamp(input: &str, utc_offset: UtcOffset) -> HttmResult<SystemTime> {
+    let input = input.trim();
+
+    if let Some(epoch_secs) = input.strip_prefix('@') {
+        let secs: u64 = epoch_secs.parse().map_err(|_err| {
+            let msg = format!("Could not parse seconds since the epoch: {input:?}");
+            HttmError::new(&msg)
+        })?;
+
+        return Ok(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs));
+    }
+
+    if let Some(system_time) = parse_relative_timestamp(input, utc_offset) {
+        return Ok(system_time);
+    }
+
+    let (input, utc_offset) = match input.strip_suffix("_UTC") {
+        Some(stripped) => (stripped, UtcOffset::UTC),
+        None => (input, utc_offset),
+    };
+
+    let opt_date_time = TIMESTAMP_INPUT_FORMATS
+        .iter()
+        .filter_map(|format| format_description::parse(format).ok())
+        .find_map(|format| PrimitiveDateTime::parse(input, &format).ok())
+        .or_else(|| {
+            format_description::parse(DATE_INPUT_FORMAT)
+                .ok()
+                .and_then(|format| Date::parse(input, &format).ok())
+                .map(|date| date.midnight())
+        });
+
+    match opt_date_time {
+        Some(date_time) => Ok(date_time.assume_offset(utc_offset).into()),
+        None => {
+            let msg = format!(
+                "Could not parse timestamp: {input:?}.  Please specify a timestamp in the form \"YYYY-MM-DD\", \"YYYY-MM-DD HH:MM:SS\", \
+                or \"YYYY-MM-DD-HH:MM:SS\", as seconds since the epoch, in the form \"@SECONDS\", \
+                or relative to the present, as \"now\", \"today\", \"yesterday\", or \"<NUMBER> <UNIT> ago\"."
+            );
+            Err(HttmError::new(&msg).into())
+        }
+    }
+}
+
+// "now", "today", "yesterday", and "<NUMBER> <UNIT> ago", where "today" and "yesterday" are midnight, local time
+fn parse_relative_timestamp(input: &str, utc_offset: UtcOffset) -> Option<SystemTime> {
+    let now = SystemTime::now();
+
+    let midnight = |days_ago: i64| -> Option<SystemTime> {
+        let today = OffsetDateTime::now_utc().to_offset(utc_offset).date();
+        let date = today.checked_sub(time::Duration::days(days_ago))?;
+
+        Some(date.midnight().assume_offset(utc_offset).into())
+    };
+
+    match input.to_ascii_lowercase().as_str() {
+        "now" => return Some(now),
+        "today" => return midnight(0),
+        "yesterday" => return midnight(1),
+        _ => {}
+    }
+
+    let age = parse_age(input.strip_suffix("ago")?)?;
+
+    now.checked_sub(age)
+}
+
+// an age, like "30d", "12h", or "3 days", as a duration
+pub fn parse_age(input: &str) -> Option<std::time::Duration> {
+    let input = input.trim();
+
+    let (amount, unit) = input.split_at(input.find(|c: char| !c.is_ascii_digit())?);
+
+    let amount: u64 = amount.parse().ok()?;
+
+    let unit_secs: u64 = match unit.trim().trim_end_matches('s') {
+        // "s" is trimmed to nothing
+        "" | "sec" | "second" => 1,
+        "m" | "min" | "minute" => 60,
+        "h" | "hr" | "hour" => 60 * 60,
+        "d" | "day" => 24 * 60 * 60,
+        "w" | "week" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+
+    amount
+        .checked_mul(unit_secs)
+        .map(std::time::Duration::from_secs)
+}
+
 pub fn display_human_size(size: u64) -> String {
     let size = size as f64;
 
@@ -346,3 +737,44 @@ pub fn pwd() -> HttmResult<PathBuf> {
 
     Ok(pwd)
 }
+
+pub fn is_writable(path: &Path) -> bool {
+    // access(2) with W_OK reports EROFS for read-only mounts, as well as plain permission errors
+    nix::unistd::access(path, nix::unistd::AccessFlags::W_OK).is_ok()
+}
+
+// a simple shell style glob match, where '*' matches any run of bytes, including none,
+// and '?' matches exactly one byte.  Everything else must match exactly.
+pub fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut pattern_idx, mut text_idx) = (0usize, 0usize);
+    let mut opt_backtrack: Option<(usize, usize)> = None;
+
+    while text_idx < text.len() {
+        match pattern.get(pattern_idx) {
+            Some(b'*') => {
+                // remember where we may resume, should the rest of the pattern fail to match
+                opt_backtrack = Some((pattern_idx, text_idx));
+                pattern_idx += 1;
+                continue;
+            }
+            Some(byte) if *byte == b'?' || *byte == text[text_idx] => {
+                pattern_idx += 1;
+                text_idx += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        match opt_backtrack {
+            // let the last '*' consume one more byte, and try again
+            Some((star_idx, star_text_idx)) => {
+                pattern_idx = star_idx + 1;
+                text_idx = star_text_idx + 1;
+                opt_backtrack = Some((star_idx, text_idx));
+            }
+            None => return false,
+        }
+    }
+
+    pattern[pattern_idx..].iter().all(|byte| *byte == b'*')
+}
@@ -0,0 +1,368 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::DedupBy;
+use crate::data::paths::{PathData, PathMetadata};
+use crate::lookup::versions::RelativePathAndSnapMounts;
+use crate::GLOBAL_CONFIG;
+use hashbrown::HashMap;
+use serde_json::{Map, Value};
+use std::fs::OpenOptions;
+use std::hash::{DefaultHasher, Hasher};
+use std::io::{BufReader, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const LOOKUP_CACHE_FORMAT_VERSION: u64 = 1;
+
+static LOOKUP_CACHE: LazyLock<Mutex<HashMap<Box<Path>, DatasetCache>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// the versions found for each relative path, for one dataset of interest.  Snapshots are immutable,
+// so a lookup result may only change when the set of snapshots changes, or when a mutable source,
+// like a dated backup dir or a borg repo, is modified, and, when either happens, the fingerprint
+// of the dataset's snap mounts, their names and modify times, will no longer match, and we
+// discard every entry
+#[derive(Debug, Default)]
+struct DatasetCache {
+    // names only, cheap enough to compare upon every lookup
+    snap_mounts_key: String,
+    fingerprint: String,
+    entries: Map<String, Value>,
+    is_dirty: bool,
+}
+
+impl DatasetCache {
+    fn load(dataset_of_interest: &Path, snap_mounts_key: &str, fingerprint: &str) -> Self {
+        let empty = Self {
+            snap_mounts_key: snap_mounts_key.to_owned(),
+            fingerprint: fingerprint.to_owned(),
+            entries: Map::new(),
+            is_dirty: false,
+        };
+
+        let Some(cache_file) = LookupCache::cache_file(dataset_of_interest) else {
+            return empty;
+        };
+
+        let Ok(file) = OpenOptions::new().read(true).open(cache_file) else {
+            return empty;
+        };
+
+        let Ok(Value::Object(mut cached)) = serde_json::from_reader(BufReader::new(file)) else {
+            return empty;
+        };
+
+        let is_valid = cached.get("format_version").and_then(Value::as_u64)
+            == Some(LOOKUP_CACHE_FORMAT_VERSION)
+            && cached.get("fingerprint").and_then(Value::as_str) == Some(fingerprint);
+
+        match cached.remove("entries") {
+            Some(Value::Object(entries)) if is_valid => Self {
+                snap_mounts_key: snap_mounts_key.to_owned(),
+                fingerprint: fingerprint.to_owned(),
+                entries,
+                is_dirty: false,
+            },
+            _ => empty,
+        }
+    }
+}
+
+pub struct LookupCache;
+
+impl LookupCache {
+    // $XDG_CACHE_HOME/httm/lookup, else $HOME/.cache/httm/lookup
+    fn cache_dir() -> Option<PathBuf> {
+        let cache_home = match std::env::var_os("XDG_CACHE_HOME") {
+            Some(xdg_cache_home) => PathBuf::from(xdg_cache_home),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+        };
+
+        Some(cache_home.join("httm").join("lookup"))
+    }
+
+    fn hash_hex(bytes: impl Iterator<Item = impl AsRef<[u8]>>) -> String {
+        // ahash's keys are seeded per process, and these names must be stable between runs
+        let mut hasher = DefaultHasher::new();
+
+        bytes.for_each(|bytes| {
+            hasher.write(bytes.as_ref());
+            // delimit each item, so "ab", "c" and "a", "bc" differ
+            hasher.write_u8(0);
+        });
+
+        format!("{:016x}", hasher.finish())
+    }
+
+    fn cache_file(dataset_of_interest: &Path) -> Option<PathBuf> {
+        let name = Self::hash_hex(std::iter::once(dataset_of_interest.as_os_str().as_bytes()));
+
+        Self::cache_dir().map(|cache_dir| cache_dir.join(format!("{name}.json")))
+    }
+
+    fn snap_mounts_key(snap_mounts: &[Box<Path>]) -> String {
+        let mut names: Vec<&[u8]> = snap_mounts
+            .iter()
+            .map(|snap_mount| snap_mount.as_os_str().as_bytes())
+            .collect();
+
+        names.sort_unstable();
+
+        Self::hash_hex(names.into_iter())
+    }
+
+    // snapshots are never modified, but dated backup dirs and borg repos may be, so we also
+    // fingerprint the modify time of each snap mount
+    fn fingerprint(snap_mounts: &[Box<Path>]) -> String {
+        let mut sources: Vec<Vec<u8>> = snap_mounts
+            .iter()
+            .map(|snap_mount| {
+                let modify_time = snap_mount
+                    .symlink_metadata()
+                    .and_then(|md| md.modified())
+                    .ok()
+                    .and_then(|modify_time| modify_time.duration_since(UNIX_EPOCH).ok())
+                    .unwrap_or_default();
+
+                let mut source = snap_mount.as_os_str().as_bytes().to_vec();
+                source.extend_from_slice(
+                    format!(":{}.{}", modify_time.as_secs(), modify_time.subsec_nanos()).as_bytes(),
+                );
+                source
+            })
+            .collect();
+
+        sources.sort_unstable();
+
+        Self::hash_hex(sources.into_iter())
+    }
+
+    fn key(bundle: &RelativePathAndSnapMounts, dedup_by: &DedupBy) -> String {
+        // versions deduplicated under one CHANGE_DETECTION policy may not be valid under another,
+        // and versions filtered by one SINCE/UNTIL time window are certainly not valid under another
+        format!(
+            "{:?}:{:?}:{}:{}",
+            dedup_by,
+            GLOBAL_CONFIG.change_detection,
+            Self::time_window_key(),
+            bundle.relative_path.to_string_lossy()
+        )
+    }
+
+    fn time_window_key() -> String {
+        let secs = |opt_time: Option<SystemTime>| {
+            opt_time
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| format!("{}.{}", duration.as_secs(), duration.subsec_nanos()))
+                .unwrap_or_default()
+        };
+
+        GLOBAL_CONFIG
+            .opt_time_window
+            .as_ref()
+            .map(|window| format!("{}-{}", secs(window.opt_since), secs(window.opt_until)))
+            .unwrap_or_default()
+    }
+
+    // call with the lock held, loads the cache for the dataset on first use
+    fn with_dataset<T>(
+        bundle: &RelativePathAndSnapMounts,
+        f: impl FnOnce(&mut DatasetCache) -> T,
+    ) -> Option<T> {
+        if !GLOBAL_CONFIG.opt_lookup_cache || bundle.snap_mounts.is_empty() {
+            return None;
+        }
+
+        let snap_mounts_key = Self::snap_mounts_key(bundle.snap_mounts);
+
+        let mut cache = LOOKUP_CACHE.lock().ok()?;
+
+        // stat each snap mount only once per dataset, not upon every lookup
+        let dataset_cache = cache
+            .entry(Box::from(bundle.dataset_of_interest))
+            .or_insert_with(|| {
+                let fingerprint = Self::fingerprint(bundle.snap_mounts);
+
+                DatasetCache::load(bundle.dataset_of_interest, &snap_mounts_key, &fingerprint)
+            });
+
+        // snapshots were taken or destroyed during this run
+        if dataset_cache.snap_mounts_key != snap_mounts_key {
+            *dataset_cache = DatasetCache {
+                fingerprint: Self::fingerprint(bundle.snap_mounts),
+                snap_mounts_key,
+                entries: Map::new(),
+                is_dirty: true,
+            };
+        }
+
+        Some(f(dataset_cache))
+    }
+
+    pub fn get(bundle: &RelativePathAndSnapMounts, dedup_by: &DedupBy) -> Option<Vec<PathData>> {
+        let key = Self::key(bundle, dedup_by);
+
+        let cached = Self::with_dataset(bundle, |dataset_cache| {
+            dataset_cache.entries.get(&key).cloned()
+        })??;
+
+        cached
+            .as_array()?
+            .iter()
+            .map(Self::from_value)
+            .collect::<Option<Vec<PathData>>>()
+    }
+
+    pub fn insert(bundle: &RelativePathAndSnapMounts, dedup_by: &DedupBy, versions: &[PathData]) {
+        let key = Self::key(bundle, dedup_by);
+        let value = Value::from(versions.iter().map(Self::to_value).collect::<Vec<Value>>());
+
+        Self::with_dataset(bundle, |dataset_cache| {
+            dataset_cache.entries.insert(key, value);
+            dataset_cache.is_dirty = true;
+        });
+    }
+
+    fn to_value(pathdata: &PathData) -> Value {
+        let mut version = Map::new();
+
+        version.insert(
+            "path".to_owned(),
+            Value::from(pathdata.path().to_string_lossy()),
+        );
+
+        if let Some(metadata) = pathdata.opt_metadata() {
+            let modify_time = metadata
+                .mtime()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+
+            version.insert("size".to_owned(), Value::from(metadata.size()));
+            version.insert("secs".to_owned(), Value::from(modify_time.as_secs()));
+            version.insert("nanos".to_owned(), Value::from(modify_time.subsec_nanos()));
+
+            let change_time = metadata
+                .ctime()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+
+            version.insert("csecs".to_owned(), Value::from(change_time.as_secs()));
+            version.insert("cnanos".to_owned(), Value::from(change_time.subsec_nanos()));
+        }
+
+        Value::from(version)
+    }
+
+    fn from_value(value: &Value) -> Option<PathData> {
+        let path = PathBuf::from(value.get("path")?.as_str()?);
+
+        let opt_metadata = match (
+            value.get("size").and_then(Value::as_u64),
+            value.get("secs").and_then(Value::as_u64),
+            value.get("nanos").and_then(Value::as_u64),
+        ) {
+            (Some(size), Some(secs), Some(nanos)) => {
+                let modify_time = UNIX_EPOCH + Duration::new(secs, nanos as u32);
+
+                // caches written before ctime was recorded fall back to the modify time
+                let change_time = match (
+                    value.get("csecs").and_then(Value::as_u64),
+                    value.get("cnanos").and_then(Value::as_u64),
+                ) {
+                    (Some(csecs), Some(cnanos)) => UNIX_EPOCH + Duration::new(csecs, cnanos as u32),
+                    _ => modify_time,
+                };
+
+                Some(PathMetadata::from_parts(size, modify_time, change_time))
+            }
+            _ => None,
+        };
+
+        Some(PathData::from_parts(path, opt_metadata))
+    }
+
+    // write any changed dataset caches back to disk, a failure here is never fatal
+    pub fn persist() {
+        let Ok(cache) = LOOKUP_CACHE.lock() else {
+            return;
+        };
+
+        let Some(cache_dir) = Self::cache_dir() else {
+            return;
+        };
+
+        if cache.values().any(|dataset_cache| dataset_cache.is_dirty) {
+            if let Err(err) = std::fs::create_dir_all(&cache_dir) {
+                eprintln!("WARN: httm could not create its lookup cache directory: {err}");
+                return;
+            }
+        }
+
+        cache
+            .iter()
+            .filter(|(_dataset, dataset_cache)| dataset_cache.is_dirty)
+            .for_each(|(dataset, dataset_cache)| {
+                let Some(cache_file) = Self::cache_file(dataset) else {
+                    return;
+                };
+
+                if let Err(err) = Self::write(&cache_file, dataset, dataset_cache) {
+                    eprintln!("WARN: httm could not write its lookup cache: {err}");
+                }
+            });
+    }
+
+    fn write(
+        cache_file: &Path,
+        dataset: &Path,
+        dataset_cache: &DatasetCache,
+    ) -> std::io::Result<()> {
+        let mut cached = Map::new();
+        cached.insert(
+            "format_version".to_owned(),
+            Value::from(LOOKUP_CACHE_FORMAT_VERSION),
+        );
+        cached.insert("dataset".to_owned(), Value::from(dataset.to_string_lossy()));
+        cached.insert(
+            "fingerprint".to_owned(),
+            Value::from(dataset_cache.fingerprint.as_str()),
+        );
+        cached.insert(
+            "entries".to_owned(),
+            Value::from(dataset_cache.entries.clone()),
+        );
+
+        let buffer = serde_json::to_string(&Value::from(cached))?;
+
+        // write and rename, so a concurrent httm never reads a partially written cache
+        let tmp_file = cache_file.with_extension(format!("tmp.{}", std::process::id()));
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&tmp_file)?;
+
+        file.write_all(buffer.as_bytes())?;
+        file.sync_data()?;
+
+        std::fs::rename(&tmp_file, cache_file)
+    }
+}
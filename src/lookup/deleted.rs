@@ -15,19 +15,32 @@
 // For the full copyright and license information, please view the LICENSE file
 // that was distributed with this source code.
 
+use crate::config::generate::DeletedOrder;
 use crate::data::paths::{BasicDirEntryInfo, PathData};
+use crate::filesystem::btrfs_nested::BtrfsNestedSubvol;
 use crate::library::results::HttmResult;
 use crate::lookup::versions::{ProximateDatasetAndOptAlts, RelativePathAndSnapMounts};
+use crate::GLOBAL_CONFIG;
+use hashbrown::hash_map::Entry;
 use hashbrown::{HashMap, HashSet};
+use std::cmp::Reverse;
 use std::ffi::OsString;
 use std::fs::read_dir;
 use std::path::Path;
+use std::time::SystemTime;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct DeletedFiles {
     inner: Vec<BasicDirEntryInfo>,
 }
 
+// a deleted entry found on some snapshot, and, if the user requested an ordering, the time by which
+// we rank it among the other snapshot entries of the same name, and among the other deleted entries
+struct DeletedCandidate {
+    entry: BasicDirEntryInfo,
+    opt_rank_time: Option<SystemTime>,
+}
+
 // deleted lookup is a dumb impl. if we want to rank outputs, get last in time, etc.
 // we do that elsewhere.  deleted is simply about finding at least one version of a deleted file
 // this, believe it or not, will be faster
@@ -61,34 +74,119 @@ impl DeletedFiles {
         // requesting dir to those of their relative dirs on snapshots
         let path_data = PathData::from(requested_dir);
 
+        let prox_opt_alts = ProximateDatasetAndOptAlts::new(&path_data)?;
+
+        // a nested btrfs subvolume is only an empty directory on the snapshots of its parent, so every
+        // file within would appear to be a new file, instead, search the nested subvolume's own snapshots
+        if let Some(nested) = GLOBAL_CONFIG
+            .opt_traverse_nested
+            .then(|| BtrfsNestedSubvol::new(requested_dir, prox_opt_alts.proximate_dataset))
+            .flatten()
+        {
+            if let Some(search_bundle) = nested.search_bundle() {
+                return Ok(Self::unique_and_ordered(Self::deleted_files_for_dataset(
+                    search_bundle,
+                    &local_filenames_set,
+                )));
+            }
+        }
+
         // create vec of all local and replicated backups at once
         //
         // we need to make certain that what we return from possibly multiple datasets are unique
-        let unique_deleted_for_dir: HashMap<OsString, BasicDirEntryInfo> =
-            ProximateDatasetAndOptAlts::new(&path_data)?
+        Ok(Self::unique_and_ordered(
+            prox_opt_alts
                 .into_search_bundles()
                 .flat_map(|search_bundle| {
                     Self::deleted_files_for_dataset(search_bundle, &local_filenames_set)
-                })
-                .collect();
+                }),
+        ))
+    }
+
+    // of the entries of the same name, we keep the one ranked latest, see DELETED_ORDER,
+    // and, when no order is requested, whichever we happened to find first
+    fn unique_and_ordered(
+        candidates: impl Iterator<Item = (OsString, DeletedCandidate)>,
+    ) -> Vec<BasicDirEntryInfo> {
+        let mut unique_deleted_for_dir: HashMap<OsString, DeletedCandidate> = HashMap::new();
+
+        candidates.for_each(|(file_name, candidate)| {
+            match unique_deleted_for_dir.entry(file_name) {
+                Entry::Occupied(mut occupied)
+                    if candidate.opt_rank_time > occupied.get().opt_rank_time =>
+                {
+                    occupied.insert(candidate);
+                }
+                Entry::Occupied(_) => {}
+                Entry::Vacant(vacant) => {
+                    vacant.insert(candidate);
+                }
+            }
+        });
 
-        Ok(unique_deleted_for_dir.into_values().collect())
+        let mut unique: Vec<DeletedCandidate> = unique_deleted_for_dir.into_values().collect();
+
+        match GLOBAL_CONFIG.opt_deleted_order {
+            Some(DeletedOrder::Deleted | DeletedOrder::Mtime) => {
+                unique.sort_by_key(|candidate| Reverse(candidate.opt_rank_time))
+            }
+            Some(DeletedOrder::Name) => {
+                unique.sort_by(|a, b| a.entry.filename().cmp(b.entry.filename()))
+            }
+            None => {}
+        }
+
+        unique
+            .into_iter()
+            .map(|candidate| candidate.entry)
+            .collect()
     }
 
     #[inline(always)]
     fn deleted_files_for_dataset<'a>(
         search_bundle: RelativePathAndSnapMounts<'a>,
         local_filenames_set: &'a HashSet<OsString>,
-    ) -> impl Iterator<Item = (OsString, BasicDirEntryInfo)> + 'a {
+    ) -> impl Iterator<Item = (OsString, DeletedCandidate)> + 'a {
         // compare local filenames to all unique snap filenames - none values are unique, here
         search_bundle
             .snap_mounts
             .iter()
             .map(|path| path.join(search_bundle.relative_path.as_os_str()))
-            .flat_map(std::fs::read_dir)
-            .flatten()
-            .flatten()
-            .filter(|dir_entry| !local_filenames_set.contains(&dir_entry.file_name()))
-            .map(|dir_entry| (dir_entry.file_name(), BasicDirEntryInfo::from(&dir_entry)))
+            .flat_map(|snap_dir| {
+                // any deletion modifies the parent directory, so an entry was deleted sometime after
+                // the modify time of its parent, on the newest snapshot which still contains the entry
+                let opt_dir_mtime = match GLOBAL_CONFIG.opt_deleted_order {
+                    Some(DeletedOrder::Deleted) => snap_dir
+                        .symlink_metadata()
+                        .and_then(|md| md.modified())
+                        .ok(),
+                    _ => None,
+                };
+
+                read_dir(&snap_dir)
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .map(move |dir_entry| (dir_entry, opt_dir_mtime))
+            })
+            .filter(|(dir_entry, _opt_dir_mtime)| {
+                !local_filenames_set.contains(&dir_entry.file_name())
+            })
+            .map(|(dir_entry, opt_dir_mtime)| {
+                let opt_rank_time = match GLOBAL_CONFIG.opt_deleted_order {
+                    Some(DeletedOrder::Deleted) => opt_dir_mtime,
+                    Some(DeletedOrder::Mtime) => {
+                        dir_entry.metadata().and_then(|md| md.modified()).ok()
+                    }
+                    _ => None,
+                };
+
+                let candidate = DeletedCandidate {
+                    entry: BasicDirEntryInfo::from(&dir_entry),
+                    opt_rank_time,
+                };
+
+                (dir_entry.file_name(), candidate)
+            })
     }
 }
@@ -0,0 +1,347 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::{FormattedMode, GrepSpec, PrintMode, RawMode};
+use crate::data::paths::PathData;
+use crate::library::results::HttmResult;
+use crate::library::utility::{csv_field, date_string, delimiter, print_output_buf, DateFormat};
+use crate::lookup::versions::VersionsMap;
+use crate::GLOBAL_CONFIG;
+use rayon::prelude::*;
+use regex::bytes::Regex;
+use std::fmt;
+use std::fs::{read_dir, File};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+// a file without newlines, like many a binary, is read in chunks of at most this length
+const GREP_MAX_LINE_LEN: u64 = 1_048_576;
+// the tail of each such chunk is matched again with the next, so a match may span the two
+const GREP_CHUNK_OVERLAP: usize = 4_096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GrepStatus {
+    Contains,
+    Lacks,
+    Unreadable,
+}
+
+impl fmt::Display for GrepStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            GrepStatus::Contains => "contains",
+            GrepStatus::Lacks => "lacks",
+            GrepStatus::Unreadable => "unreadable",
+        };
+
+        write!(f, "{status}")
+    }
+}
+
+// one version of a requested path, and, for a file, whether it contains the pattern,
+// or, for a directory, the files beneath it which contain the pattern
+struct GrepVersion<'a> {
+    version: &'a PathData,
+    matching: Vec<PathBuf>,
+    // whether any file could not be read, such that it may contain the pattern unbeknownst to us
+    is_unreadable: bool,
+}
+
+impl<'a> GrepVersion<'a> {
+    fn new(version: &'a PathData, regex: &Regex) -> Self {
+        let (matching, is_unreadable) = SnapshotGrep::matching_files(version.path(), regex);
+
+        Self {
+            version,
+            matching,
+            is_unreadable,
+        }
+    }
+
+    fn status(&self) -> GrepStatus {
+        if !self.matching.is_empty() {
+            return GrepStatus::Contains;
+        }
+
+        if self.is_unreadable {
+            return GrepStatus::Unreadable;
+        }
+
+        GrepStatus::Lacks
+    }
+
+    fn date(&self, format: DateFormat) -> String {
+        date_string(
+            GLOBAL_CONFIG.requested_utc_offset,
+            &self.version.metadata_infallible().mtime(),
+            format,
+        )
+    }
+
+    // for a file, the only matching path is the version itself
+    fn matching_paths(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.matching.iter().map(|relative| {
+            if relative.as_os_str().is_empty() {
+                return self.version.path().to_path_buf();
+            }
+
+            self.version.path().join(relative)
+        })
+    }
+
+    fn format(&self, label: &str) -> String {
+        let delimiter = delimiter();
+
+        match &GLOBAL_CONFIG.print_mode {
+            PrintMode::Formatted(FormattedMode::Default) => {
+                let mut buffer = format!(
+                    "  {:<17} {}  {:?}\n",
+                    label,
+                    self.date(DateFormat::Display),
+                    self.version.path()
+                );
+
+                self.matching
+                    .iter()
+                    .filter(|relative| !relative.as_os_str().is_empty())
+                    .for_each(|relative| buffer += &format!("{:<21}{:?}\n", "", relative));
+
+                buffer
+            }
+            PrintMode::Raw(RawMode::Csv) => self
+                .raw_paths()
+                .map(|path| {
+                    format!(
+                        "{},{},{}{}",
+                        label,
+                        self.date(DateFormat::Timestamp),
+                        csv_field(&path.to_string_lossy()),
+                        delimiter
+                    )
+                })
+                .collect(),
+            PrintMode::Formatted(FormattedMode::NotPretty) | PrintMode::Raw(_) => self
+                .raw_paths()
+                .map(|path| {
+                    format!(
+                        "{}\t{}\t{}{}",
+                        label,
+                        self.date(DateFormat::Timestamp),
+                        path.display(),
+                        delimiter
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    // in the raw modes, every matching file is a line of its own
+    fn raw_paths(&self) -> Box<dyn Iterator<Item = PathBuf> + '_> {
+        if self.matching.is_empty() {
+            return Box::new(std::iter::once(self.version.path().to_path_buf()));
+        }
+
+        Box::new(self.matching_paths())
+    }
+}
+
+pub struct SnapshotGrep;
+
+impl SnapshotGrep {
+    pub fn exec(versions_map: &VersionsMap, grep_spec: &GrepSpec) -> HttmResult<()> {
+        let output_buf: String = versions_map
+            .iter()
+            .map(|(live_version, snaps)| Self::report(live_version, snaps, grep_spec))
+            .collect();
+
+        print_output_buf(&output_buf)
+    }
+
+    fn report(live_version: &PathData, snaps: &[PathData], grep_spec: &GrepSpec) -> String {
+        // oldest first, and the live version last, if it still exists
+        let all_versions: Vec<&PathData> = snaps
+            .iter()
+            .chain(std::iter::once(live_version))
+            .filter(|version| version.opt_metadata().is_some())
+            .collect();
+
+        let grep_versions: Vec<GrepVersion> = all_versions
+            .par_iter()
+            .map(|version| GrepVersion::new(version, &grep_spec.regex))
+            .collect();
+
+        let mut buffer = String::new();
+
+        if let PrintMode::Formatted(FormattedMode::Default) = GLOBAL_CONFIG.print_mode {
+            buffer += &format!("{:?}\n", live_version.path());
+        }
+
+        grep_versions.iter().for_each(|grep_version| {
+            buffer += &grep_version.format(&grep_version.status().to_string())
+        });
+
+        if grep_spec.opt_appearance {
+            buffer += &Self::appearance(&grep_versions);
+        }
+
+        if let PrintMode::Formatted(FormattedMode::Default) = GLOBAL_CONFIG.print_mode {
+            if grep_versions.is_empty() {
+                buffer += "  No versions found.\n";
+            }
+
+            buffer += "\n";
+        }
+
+        buffer
+    }
+
+    fn appearance(grep_versions: &[GrepVersion]) -> String {
+        let is_contains =
+            |grep_version: &GrepVersion| grep_version.status() == GrepStatus::Contains;
+
+        let (Some(first), Some(last)) = (
+            grep_versions.iter().position(is_contains),
+            grep_versions.iter().rposition(is_contains),
+        ) else {
+            if let PrintMode::Formatted(FormattedMode::Default) = GLOBAL_CONFIG.print_mode {
+                return "  The pattern appears in no version.\n".to_string();
+            }
+
+            return String::new();
+        };
+
+        let mut buffer = grep_versions[first].format("first appearance");
+
+        buffer += &grep_versions[last].format("last appearance");
+
+        // the first version after the last appearance is the version from which the pattern was removed,
+        // unless that version is unreadable, and we cannot know
+        if let Some(removed) = grep_versions
+            .get(last + 1)
+            .filter(|grep_version| grep_version.status() == GrepStatus::Lacks)
+        {
+            buffer += &removed.format("removed in");
+        }
+
+        buffer
+    }
+
+    // paths, relative to the requested path, of the files which contain the pattern,
+    // where an empty relative path is the requested path itself, and whether any file was unreadable
+    fn matching_files(path: &Path, regex: &Regex) -> (Vec<PathBuf>, bool) {
+        match path.symlink_metadata() {
+            Ok(md) if md.is_dir() => {
+                let mut files = Vec::new();
+
+                Self::walk(path, Path::new(""), &mut files);
+
+                let num_files = files.len();
+
+                let results: Vec<(PathBuf, bool)> = files
+                    .into_par_iter()
+                    .filter_map(|relative| {
+                        Self::contains_or_warn(&path.join(&relative), regex)
+                            .map(|is_match| (relative, is_match))
+                    })
+                    .collect();
+
+                let is_unreadable = results.len() < num_files;
+
+                let mut matching: Vec<PathBuf> = results
+                    .into_iter()
+                    .filter(|(_relative, is_match)| *is_match)
+                    .map(|(relative, _is_match)| relative)
+                    .collect();
+
+                matching.sort();
+
+                (matching, is_unreadable)
+            }
+            Ok(md) if md.is_file() => match Self::contains_or_warn(path, regex) {
+                Some(true) => (vec![PathBuf::new()], false),
+                Some(false) => (Vec::new(), false),
+                None => (Vec::new(), true),
+            },
+            _ => (Vec::new(), false),
+        }
+    }
+
+    fn walk(dir: &Path, relative_dir: &Path, files: &mut Vec<PathBuf>) {
+        let Ok(read_dir) = read_dir(dir) else {
+            return;
+        };
+
+        read_dir.flatten().for_each(|entry| {
+            let Ok(file_type) = entry.file_type() else {
+                return;
+            };
+
+            let relative = relative_dir.join(entry.file_name());
+
+            if file_type.is_dir() {
+                Self::walk(&entry.path(), &relative, files);
+                return;
+            }
+
+            if file_type.is_file() {
+                files.push(relative);
+            }
+        })
+    }
+
+    // None, where the file could not be read
+    fn contains_or_warn(path: &Path, regex: &Regex) -> Option<bool> {
+        match Self::contains(path, regex) {
+            Ok(is_match) => Some(is_match),
+            Err(err) => {
+                eprintln!("WARN: httm could not search {:?}: {err}", path);
+                None
+            }
+        }
+    }
+
+    // the pattern is matched line by line, so a file need never be read into memory whole,
+    // and an overlong line is matched in chunks
+    fn contains(path: &Path, regex: &Regex) -> HttmResult<bool> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut line = Vec::new();
+
+        loop {
+            let amt = reader
+                .by_ref()
+                .take(GREP_MAX_LINE_LEN)
+                .read_until(b'\n', &mut line)?;
+
+            if amt == 0 {
+                return Ok(false);
+            }
+
+            if regex.is_match(&line) {
+                return Ok(true);
+            }
+
+            if line.ends_with(b"\n") {
+                line.clear();
+                continue;
+            }
+
+            // the line was cut short, so carry its tail over to the next chunk
+            let tail_start = line.len().saturating_sub(GREP_CHUNK_OVERLAP);
+            line.drain(..tail_start);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::ExecMode;
+use crate::GLOBAL_CONFIG;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+
+// we refresh the message every so many snapshots scanned, as a single file may have thousands
+const REFRESH_INTERVAL: usize = 256;
+
+static ACTIVE_PROGRESS: LazyLock<RwLock<Option<ProgressBar>>> = LazyLock::new(|| RwLock::new(None));
+static SNAPS_SCANNED: AtomicUsize = AtomicUsize::new(0);
+static VERSIONS_FOUND: AtomicUsize = AtomicUsize::new(0);
+
+// reports the progress of a version lookup, which the user awaits, until dropped, see QUIET.
+// Lookups deeper within httm, such as those of a recursive search, report only to an active
+// progress, and never begin one of their own
+pub struct LookupProgress {
+    is_active: bool,
+}
+
+impl LookupProgress {
+    pub fn new(num_paths: usize) -> Self {
+        if GLOBAL_CONFIG.opt_quiet {
+            return Self { is_active: false };
+        }
+
+        // before the select view, the terminal is ours, but we know only that we are waiting
+        let progress_bar = match &GLOBAL_CONFIG.exec_mode {
+            ExecMode::Interactive(_) => {
+                let progress_bar = ProgressBar::new_spinner();
+                progress_bar.set_style(
+                    ProgressStyle::with_template("{spinner} Searching for versions: {msg}")
+                        .unwrap_or_else(|_| ProgressStyle::default_spinner()),
+                );
+                progress_bar
+            }
+            _ => {
+                let progress_bar = ProgressBar::new(num_paths as u64);
+                progress_bar.set_style(
+                    ProgressStyle::with_template(
+                        "{spinner} Searching for versions: {pos}/{len} files [{elapsed_precise}] {msg}",
+                    )
+                    .unwrap_or_else(|_| ProgressStyle::default_bar()),
+                );
+                progress_bar
+            }
+        };
+
+        progress_bar.enable_steady_tick(Duration::from_millis(100));
+
+        let Ok(mut active) = ACTIVE_PROGRESS.write() else {
+            return Self { is_active: false };
+        };
+
+        if active.is_some() {
+            progress_bar.finish_and_clear();
+            return Self { is_active: false };
+        }
+
+        SNAPS_SCANNED.store(0, Ordering::Relaxed);
+        VERSIONS_FOUND.store(0, Ordering::Relaxed);
+
+        *active = Some(progress_bar);
+
+        Self { is_active: true }
+    }
+
+    #[inline(always)]
+    pub fn snap_scanned() {
+        let num_scanned = SNAPS_SCANNED.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if num_scanned % REFRESH_INTERVAL == 0 {
+            Self::refresh(false);
+        }
+    }
+
+    #[inline(always)]
+    pub fn file_done(num_versions: usize) {
+        VERSIONS_FOUND.fetch_add(num_versions, Ordering::Relaxed);
+
+        Self::refresh(true);
+    }
+
+    fn refresh(is_file_done: bool) {
+        let Ok(active) = ACTIVE_PROGRESS.try_read() else {
+            return;
+        };
+
+        let Some(progress_bar) = active.as_ref() else {
+            return;
+        };
+
+        if is_file_done {
+            progress_bar.inc(1);
+        }
+
+        progress_bar.set_message(format!(
+            "{} snapshots scanned, {} versions found",
+            SNAPS_SCANNED.load(Ordering::Relaxed),
+            VERSIONS_FOUND.load(Ordering::Relaxed)
+        ));
+    }
+}
+
+impl Drop for LookupProgress {
+    fn drop(&mut self) {
+        if !self.is_active {
+            return;
+        }
+
+        if let Ok(mut active) = ACTIVE_PROGRESS.write() {
+            if let Some(progress_bar) = active.take() {
+                progress_bar.finish_and_clear();
+            }
+        }
+    }
+}
@@ -0,0 +1,383 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::{FormattedMode, PrintMode, RawMode, SnapDiffPair};
+use crate::data::paths::{PathData, PathDeconstruction, PathMetadata};
+use crate::library::file_ops::HashFileContents;
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{
+    csv_field,
+    date_string,
+    delimiter,
+    display_human_size,
+    print_output_buf,
+    DateFormat,
+};
+use crate::lookup::versions::RelativePathAndSnapMounts;
+use crate::GLOBAL_CONFIG;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapDiffStatus {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl fmt::Display for SnapDiffStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            SnapDiffStatus::Added => "added",
+            SnapDiffStatus::Removed => "removed",
+            SnapDiffStatus::Modified => "modified",
+        };
+
+        write!(f, "{status}")
+    }
+}
+
+// a non-directory entry beneath a snapshot of the requested directory
+#[derive(Debug, Clone)]
+struct SnapDiffEntry {
+    path: PathBuf,
+    opt_metadata: Option<PathMetadata>,
+    is_regular_file: bool,
+}
+
+struct SnapDiffLine {
+    status: SnapDiffStatus,
+    live_path: PathBuf,
+    opt_first: Option<PathMetadata>,
+    opt_second: Option<PathMetadata>,
+}
+
+impl SnapDiffLine {
+    fn format(&self, padding: usize) -> String {
+        let delimiter = delimiter();
+        let live_path = self.live_path.display();
+
+        match &GLOBAL_CONFIG.print_mode {
+            PrintMode::Formatted(FormattedMode::Default) => format!(
+                "{:<9} {:<width$} : {} -> {}{}",
+                self.status.to_string(),
+                live_path,
+                Self::describe(self.opt_first.as_ref()),
+                Self::describe(self.opt_second.as_ref()),
+                delimiter,
+                width = padding
+            ),
+            PrintMode::Raw(RawMode::Csv) => format!(
+                "{},{},{},{},{},{}{}",
+                self.status,
+                csv_field(&live_path.to_string()),
+                Self::size(self.opt_first.as_ref()),
+                Self::timestamp(self.opt_first.as_ref()),
+                Self::size(self.opt_second.as_ref()),
+                Self::timestamp(self.opt_second.as_ref()),
+                delimiter
+            ),
+            PrintMode::Formatted(FormattedMode::NotPretty) | PrintMode::Raw(_) => format!(
+                "{}\t{}\t{}\t{}\t{}\t{}{}",
+                self.status,
+                live_path,
+                Self::size(self.opt_first.as_ref()),
+                Self::timestamp(self.opt_first.as_ref()),
+                Self::size(self.opt_second.as_ref()),
+                Self::timestamp(self.opt_second.as_ref()),
+                delimiter
+            ),
+        }
+    }
+
+    fn describe(opt_md: Option<&PathMetadata>) -> String {
+        match opt_md {
+            Some(md) => format!(
+                "{} ({})",
+                date_string(
+                    GLOBAL_CONFIG.requested_utc_offset,
+                    &md.mtime(),
+                    DateFormat::Display
+                ),
+                display_human_size(md.size())
+            ),
+            None => "-".to_string(),
+        }
+    }
+
+    fn size(opt_md: Option<&PathMetadata>) -> String {
+        opt_md.map_or_else(|| "-".to_string(), |md| md.size().to_string())
+    }
+
+    fn timestamp(opt_md: Option<&PathMetadata>) -> String {
+        opt_md.map_or_else(
+            || "-".to_string(),
+            |md| {
+                date_string(
+                    GLOBAL_CONFIG.requested_utc_offset,
+                    &md.mtime(),
+                    DateFormat::Timestamp,
+                )
+            },
+        )
+    }
+}
+
+pub struct SnapshotDiffReport;
+
+impl SnapshotDiffReport {
+    pub fn exec(snap_diff_pair: &SnapDiffPair) -> HttmResult<()> {
+        let requested = match GLOBAL_CONFIG.paths.as_slice() {
+            [requested] => requested,
+            _ => {
+                return Err(HttmError::new(
+                    "SNAP_DIFF requires exactly one directory, or none, in which case the present working directory is used.",
+                )
+                .into())
+            }
+        };
+
+        let dataset_of_interest = requested.proximate_dataset()?;
+        let relative_path = requested.relative_path(dataset_of_interest)?;
+
+        let Some(snap_mounts) = RelativePathAndSnapMounts::new(relative_path, dataset_of_interest)
+            .map(|prox| prox.snap_mounts)
+        else {
+            let msg = format!(
+                "httm could not find any snapshots of the dataset which contains: {:?}",
+                requested.path()
+            );
+            return Err(HttmError::new(&msg).into());
+        };
+
+        let first_mount = Self::snap_mount(&snap_diff_pair.first, snap_mounts, requested)?;
+        let second_mount = Self::snap_mount(&snap_diff_pair.second, snap_mounts, requested)?;
+
+        let first_entries = Self::entries(&first_mount.join(relative_path))?;
+        let second_entries = Self::entries(&second_mount.join(relative_path))?;
+
+        let lines = Self::compare(requested.path(), &first_entries, &second_entries);
+
+        let padding = lines
+            .iter()
+            .map(|line| line.live_path.as_os_str().len())
+            .max()
+            .unwrap_or_default();
+
+        let mut output_buf: String = lines.iter().map(|line| line.format(padding)).collect();
+
+        if let PrintMode::Formatted(FormattedMode::Default) = GLOBAL_CONFIG.print_mode {
+            let count =
+                |status: SnapDiffStatus| lines.iter().filter(|line| line.status == status).count();
+
+            output_buf += &format!(
+                "\n{} added, {} removed, {} modified, from {:?} to {:?}\n",
+                count(SnapDiffStatus::Added),
+                count(SnapDiffStatus::Removed),
+                count(SnapDiffStatus::Modified),
+                first_mount,
+                second_mount
+            );
+        }
+
+        print_output_buf(&output_buf)
+    }
+
+    // a snapshot may be specified by its name, by its full ZFS name, by any trailing portion
+    // of its snapshot mount's path, or by the path of its snapshot mount
+    fn snap_mount<'a>(
+        snap_spec: &str,
+        snap_mounts: &'a [Box<Path>],
+        requested: &PathData,
+    ) -> HttmResult<&'a Path> {
+        let snap_name = match snap_spec.split_once('@') {
+            Some((fs_name, snap_name)) => {
+                if requested
+                    .source(None)
+                    .is_some_and(|source| source != Path::new(fs_name))
+                {
+                    let msg = format!(
+                        "Snapshot {snap_spec:?} is not a snapshot of the dataset which contains: {:?}",
+                        requested.path()
+                    );
+                    return Err(HttmError::new(&msg).into());
+                }
+
+                snap_name
+            }
+            None => snap_spec,
+        };
+
+        let matches: Vec<&Path> = snap_mounts
+            .iter()
+            .map(|snap_mount| snap_mount.as_ref())
+            .filter(|snap_mount| {
+                *snap_mount == Path::new(snap_spec) || snap_mount.ends_with(snap_name)
+            })
+            .collect();
+
+        match matches.as_slice() {
+            [snap_mount] => Ok(snap_mount),
+            [] => {
+                let msg = format!(
+                    "httm could not find a snapshot named {snap_spec:?} of the dataset which contains: {:?}",
+                    requested.path()
+                );
+                Err(HttmError::new(&msg).into())
+            }
+            _ => {
+                let msg = format!(
+                    "The snapshot name {snap_spec:?} is ambiguous, and matches each of: {:?}",
+                    matches
+                );
+                Err(HttmError::new(&msg).into())
+            }
+        }
+    }
+
+    // key: path relative to the snapshot of the requested directory
+    fn entries(root: &Path) -> HttmResult<BTreeMap<PathBuf, SnapDiffEntry>> {
+        let mut entries = BTreeMap::new();
+
+        match root.symlink_metadata() {
+            Ok(md) if md.is_dir() => Self::walk(root, Path::new(""), &mut entries)?,
+            Ok(md) => {
+                entries.insert(
+                    PathBuf::new(),
+                    SnapDiffEntry {
+                        path: root.to_path_buf(),
+                        opt_metadata: PathMetadata::new(&md),
+                        is_regular_file: md.is_file(),
+                    },
+                );
+            }
+            // the requested directory need not exist within either snapshot
+            Err(_) => {}
+        }
+
+        Ok(entries)
+    }
+
+    fn walk(
+        dir: &Path,
+        relative_dir: &Path,
+        entries: &mut BTreeMap<PathBuf, SnapDiffEntry>,
+    ) -> HttmResult<()> {
+        for entry in read_dir(dir)?.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+
+            let relative = relative_dir.join(entry.file_name());
+
+            if file_type.is_dir() {
+                Self::walk(&entry.path(), &relative, entries)?;
+                continue;
+            }
+
+            let opt_metadata = entry.metadata().ok().and_then(|md| PathMetadata::new(&md));
+
+            entries.insert(
+                relative,
+                SnapDiffEntry {
+                    path: entry.path(),
+                    opt_metadata,
+                    is_regular_file: file_type.is_file(),
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    fn compare(
+        requested_dir: &Path,
+        first_entries: &BTreeMap<PathBuf, SnapDiffEntry>,
+        second_entries: &BTreeMap<PathBuf, SnapDiffEntry>,
+    ) -> Vec<SnapDiffLine> {
+        let live_path = |relative: &Path| {
+            if relative.as_os_str().is_empty() {
+                return requested_dir.to_path_buf();
+            }
+
+            requested_dir.join(relative)
+        };
+
+        let removed = first_entries
+            .iter()
+            .filter(|(relative, _entry)| !second_entries.contains_key(*relative))
+            .map(|(relative, entry)| SnapDiffLine {
+                status: SnapDiffStatus::Removed,
+                live_path: live_path(relative),
+                opt_first: entry.opt_metadata,
+                opt_second: None,
+            });
+
+        let added = second_entries
+            .iter()
+            .filter(|(relative, _entry)| !first_entries.contains_key(*relative))
+            .map(|(relative, entry)| SnapDiffLine {
+                status: SnapDiffStatus::Added,
+                live_path: live_path(relative),
+                opt_first: None,
+                opt_second: entry.opt_metadata,
+            });
+
+        // versions are compared, as elsewhere, on the basis of size and modify time, but,
+        // where these differ, only a difference in contents makes a file modified
+        let in_both: Vec<(&PathBuf, &SnapDiffEntry, &SnapDiffEntry)> = first_entries
+            .iter()
+            .filter_map(|(relative, first)| {
+                second_entries
+                    .get(relative)
+                    .map(|second| (relative, first, second))
+            })
+            .filter(|(_relative, first, second)| first.opt_metadata != second.opt_metadata)
+            .collect();
+
+        let modified: Vec<SnapDiffLine> = in_both
+            .par_iter()
+            .filter(|(_relative, first, second)| {
+                !first.is_regular_file
+                    || !second.is_regular_file
+                    // a version we cannot read is reported as modified, rather than silently omitted
+                    || match (
+                        HashFileContents::path_to_hash(&first.path),
+                        HashFileContents::path_to_hash(&second.path),
+                    ) {
+                        (Ok(first_hash), Ok(second_hash)) => first_hash != second_hash,
+                        _ => true,
+                    }
+            })
+            .map(|(relative, first, second)| SnapDiffLine {
+                status: SnapDiffStatus::Modified,
+                live_path: live_path(relative),
+                opt_first: first.opt_metadata,
+                opt_second: second.opt_metadata,
+            })
+            .collect();
+
+        let mut lines: Vec<SnapDiffLine> = removed.chain(added).chain(modified).collect();
+
+        lines.sort_by(|a, b| a.live_path.cmp(&b.live_path));
+
+        lines
+    }
+}
@@ -15,7 +15,7 @@
 // For the full copyright and license information, please view the LICENSE file
 // that was distributed with this source code.
 
-use crate::config::generate::ListSnapsFilters;
+use crate::config::generate::{ListSnapsFilters, VersionSpec};
 use crate::data::paths::{PathData, PathDeconstruction, ZfsSnapPathGuard};
 use crate::filesystem::mounts::FilesystemType;
 use crate::library::results::{HttmError, HttmResult};
@@ -23,7 +23,7 @@ use crate::lookup::versions::VersionsMap;
 use rayon::prelude::*;
 use std::collections::BTreeMap;
 use std::ops::Deref;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SnapNameMap {
@@ -141,4 +141,81 @@ impl SnapNameMap {
 
         Ok(inner.into())
     }
+
+    // the inverse of a versions lookup: exactly which snapshots contain a given version of a file,
+    // where a version matches by metadata (size and mtime), or, if requested, by contents
+    pub fn for_version(versions_map: VersionsMap, version_spec: &VersionSpec) -> HttmResult<Self> {
+        let reference = PathData::from(version_spec.reference.as_path());
+
+        let inner: BTreeMap<PathData, Vec<String>> = versions_map
+            .iter()
+            .map(|(pathdata, snaps)| {
+                let opt_proximate_dataset = pathdata.proximate_dataset().ok();
+                let fs_type = pathdata.fs_type(opt_proximate_dataset);
+
+                let snap_names: Vec<String> = snaps
+                    .par_iter()
+                    .filter(|snap_pd| {
+                        if version_spec.by_contents {
+                            snap_pd.is_same_file_contents(&reference)
+                        } else {
+                            snap_pd.opt_metadata() == reference.opt_metadata()
+                        }
+                    })
+                    .map(|snap_pd| {
+                        Self::snap_name(snap_pd, &fs_type, opt_proximate_dataset)
+                            .unwrap_or_else(|| snap_pd.path().to_path_buf())
+                            .to_string_lossy()
+                            .to_string()
+                    })
+                    .collect();
+
+                (pathdata.to_owned(), snap_names)
+            })
+            .filter(|(pathdata, snap_names)| {
+                if snap_names.is_empty() {
+                    eprintln!(
+                        "WARN: No snapshot contains the version of {:?} specified.",
+                        pathdata.path()
+                    );
+                    return false;
+                }
+
+                true
+            })
+            .collect();
+
+        if inner.is_empty() {
+            return Err(HttmError::new(
+                "No snapshot contains the version specified of any of the paths given. Quitting.",
+            )
+            .into());
+        }
+
+        Ok(inner.into())
+    }
+
+    // for those filesystems where a snapshot has a name apart from its path, the name
+    // of the snapshot containing a snap version
+    pub fn snap_name(
+        snap_pd: &PathData,
+        fs_type: &Option<FilesystemType>,
+        opt_proximate_dataset: Option<&Path>,
+    ) -> Option<PathBuf> {
+        match fs_type {
+            Some(FilesystemType::Zfs) => {
+                ZfsSnapPathGuard::new(snap_pd).and_then(|spd| spd.source(opt_proximate_dataset))
+            }
+            Some(FilesystemType::Btrfs(Some(additional_btrfs_data))) => {
+                let snap_names = additional_btrfs_data.snap_names.get()?;
+
+                snap_pd
+                    .path()
+                    .ancestors()
+                    .find_map(|ancestor| snap_names.get(ancestor))
+                    .map(|snap_name| snap_name.to_path_buf())
+            }
+            _ => None,
+        }
+    }
 }
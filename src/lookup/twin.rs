@@ -0,0 +1,206 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use crate::config::generate::{FormattedMode, PrintMode, RawMode, TwinDirs};
+use crate::data::paths::{PathData, PathMetadata};
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{csv_field, delimiter, print_output_buf};
+use crate::lookup::versions::VersionsMap;
+use crate::GLOBAL_CONFIG;
+use hashbrown::HashSet;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TwinStatus {
+    InSync,
+    // the local live version is newer than the twin, the twin's live version is in local history
+    Ahead,
+    // the twin live version is newer than the local, the local live version is in the twin's history
+    Behind,
+    // neither live version appears in the other's history
+    Conflict,
+    MissingLocal,
+    MissingTwin,
+}
+
+impl fmt::Display for TwinStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            TwinStatus::InSync => "in-sync",
+            TwinStatus::Ahead => "ahead",
+            TwinStatus::Behind => "behind",
+            TwinStatus::Conflict => "conflict",
+            TwinStatus::MissingLocal => "missing-local",
+            TwinStatus::MissingTwin => "missing-twin",
+        };
+
+        write!(f, "{status}")
+    }
+}
+
+pub struct TwinComparison {
+    local: PathData,
+    twin: PathData,
+    status: TwinStatus,
+    num_local_only: usize,
+    num_twin_only: usize,
+}
+
+impl TwinComparison {
+    fn new(
+        local: PathData,
+        local_snaps: &[PathData],
+        twin: PathData,
+        twin_snaps: &[PathData],
+    ) -> Self {
+        // versions are compared, as elsewhere, on the basis of size and modify time,
+        // which replication by send/receive preserves
+        let local_history: HashSet<PathMetadata> = local_snaps
+            .iter()
+            .filter_map(|pd| *pd.opt_metadata())
+            .collect();
+        let twin_history: HashSet<PathMetadata> = twin_snaps
+            .iter()
+            .filter_map(|pd| *pd.opt_metadata())
+            .collect();
+
+        let status = match (local.opt_metadata(), twin.opt_metadata()) {
+            (None, _) => TwinStatus::MissingLocal,
+            (_, None) => TwinStatus::MissingTwin,
+            (Some(local_md), Some(twin_md)) if local_md == twin_md => TwinStatus::InSync,
+            (_, Some(twin_md)) if local_history.contains(twin_md) => TwinStatus::Ahead,
+            (Some(local_md), _) if twin_history.contains(local_md) => TwinStatus::Behind,
+            _ => TwinStatus::Conflict,
+        };
+
+        let num_local_only = local_history.difference(&twin_history).count();
+        let num_twin_only = twin_history.difference(&local_history).count();
+
+        Self {
+            local,
+            twin,
+            status,
+            num_local_only,
+            num_twin_only,
+        }
+    }
+
+    fn format(&self, padding: usize) -> String {
+        let delimiter = delimiter();
+        let local = self.local.path().display();
+        let twin = self.twin.path().display();
+
+        match &GLOBAL_CONFIG.print_mode {
+            PrintMode::Formatted(FormattedMode::Default) => format!(
+                "{:<13} {:<width$} : {} (snapshot versions only local: {}, only twin: {}){}",
+                self.status.to_string(),
+                local,
+                twin,
+                self.num_local_only,
+                self.num_twin_only,
+                delimiter,
+                width = padding
+            ),
+            PrintMode::Raw(RawMode::Csv) => format!(
+                "{},{},{},{},{}{}",
+                self.status,
+                csv_field(&local.to_string()),
+                csv_field(&twin.to_string()),
+                self.num_local_only,
+                self.num_twin_only,
+                delimiter
+            ),
+            PrintMode::Formatted(FormattedMode::NotPretty) | PrintMode::Raw(_) => format!(
+                "{}\t{}\t{}\t{}\t{}{}",
+                self.status, local, twin, self.num_local_only, self.num_twin_only, delimiter
+            ),
+        }
+    }
+}
+
+pub struct CompareTwin;
+
+impl CompareTwin {
+    pub fn exec(twin_dirs: &TwinDirs) -> HttmResult<()> {
+        let pairs: Vec<(PathData, PathData)> = GLOBAL_CONFIG
+            .paths
+            .iter()
+            .filter_map(|pathdata| match Self::twin_path(pathdata.path(), twin_dirs) {
+                Some(twin) => Some((pathdata.clone(), twin)),
+                None => {
+                    eprintln!(
+                        "WARN: Path is not a descendant of the local directory of the twin pair, skipping: {:?}",
+                        pathdata.path()
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        if pairs.is_empty() {
+            return Err(HttmError::new(
+                "httm could not find any paths within the local directory to compare against a twin.",
+            )
+            .into());
+        }
+
+        let path_set: Vec<PathData> = pairs
+            .iter()
+            .flat_map(|(local, twin)| [local.clone(), twin.clone()])
+            .collect();
+
+        let versions_map = VersionsMap::new(&GLOBAL_CONFIG, &path_set)?;
+
+        let comparisons: Vec<TwinComparison> = pairs
+            .into_iter()
+            .map(|(local, twin)| {
+                let local_snaps = Self::snaps_for(&versions_map, &local);
+                let twin_snaps = Self::snaps_for(&versions_map, &twin);
+
+                TwinComparison::new(local, local_snaps, twin, twin_snaps)
+            })
+            .collect();
+
+        let padding = comparisons
+            .iter()
+            .map(|comparison| comparison.local.path().as_os_str().len())
+            .max()
+            .unwrap_or_default();
+
+        let output_buf: String = comparisons
+            .iter()
+            .map(|comparison| comparison.format(padding))
+            .collect();
+
+        print_output_buf(&output_buf)
+    }
+
+    fn twin_path(local_path: &Path, twin_dirs: &TwinDirs) -> Option<PathData> {
+        local_path
+            .strip_prefix(&twin_dirs.local_dir)
+            .ok()
+            .map(|relative| PathData::from(twin_dirs.twin_dir.join(relative)))
+    }
+
+    fn snaps_for<'a>(versions_map: &'a VersionsMap, pathdata: &PathData) -> &'a [PathData] {
+        versions_map
+            .get(pathdata)
+            .map(|snaps| snaps.as_slice())
+            .unwrap_or_default()
+    }
+}
@@ -17,16 +17,31 @@
 
 use crate::config::generate::{Config, DedupBy, ExecMode, LastSnapMode};
 use crate::data::paths::{CompareContentsContainer, PathData, PathDeconstruction};
-use crate::filesystem::mounts::LinkType;
+use crate::filesystem::borg::BorgRepos;
+use crate::filesystem::btrfs_nested::BtrfsNestedSubvol;
+use crate::filesystem::mounts::{FilesystemType, LinkType, NILFS2_FSTYPE};
+use crate::filesystem::nilfs2::Nilfs2Snapshots;
+use crate::filesystem::provider::provider_for_dataset;
+use crate::filesystem::restic::ResticRepos;
+use crate::filesystem::shadow_copy::ShadowCopy;
+use crate::filesystem::ssh_aliases::SshAliases;
+use crate::library::cleanup::CleanupGuard;
 use crate::library::results::{HttmError, HttmResult};
-use crate::GLOBAL_CONFIG;
+use crate::library::utility::{get_mount_command, private_temp_dir, user_has_effective_root};
+use crate::lookup::cache::LookupCache;
+use crate::lookup::progress::LookupProgress;
+use crate::zfs::run_command::RunZFSCommand;
+use crate::{GLOBAL_CONFIG, NILFS2_SNAPSHOT_ID_KEY};
 use hashbrown::HashSet;
 use rayon::prelude::*;
 use std::collections::BTreeMap;
 use std::io::ErrorKind;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
-use std::sync::{LazyLock, RwLock};
+use std::process::Command as ExecProcess;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, OnceLock, PoisonError, RwLock};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VersionsMap {
@@ -61,22 +76,91 @@ impl DerefMut for VersionsMap {
     }
 }
 
+pub struct LookupBudget {
+    opt_deadline: Option<Instant>,
+}
+
+impl LookupBudget {
+    const UNBOUNDED: LookupBudget = LookupBudget { opt_deadline: None };
+
+    pub fn new(opt_timeout: Option<Duration>) -> Self {
+        Self {
+            opt_deadline: opt_timeout.map(|timeout| Instant::now() + timeout),
+        }
+    }
+
+    // checked before each path, and before each snapshot of each path, as a single path
+    // on a slow dataset may have thousands of snapshots
+    #[inline(always)]
+    fn is_exhausted(&self) -> bool {
+        self.opt_deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+pub struct BoundedVersionsMap {
+    pub versions_map: VersionsMap,
+    // true if the budget was exhausted before all paths were looked up
+    pub is_truncated: bool,
+}
+
 impl VersionsMap {
     pub fn new(config: &Config, path_set: &[PathData]) -> HttmResult<VersionsMap> {
-        let is_interactive_mode = matches!(GLOBAL_CONFIG.exec_mode, ExecMode::Interactive(_));
+        Self::new_bounded(config, path_set, &LookupBudget::UNBOUNDED)
+            .map(|bounded| bounded.versions_map)
+    }
+
+    // like new(), but reports progress to the user, for lookups which the user awaits, see QUIET
+    pub fn with_progress(config: &Config, path_set: &[PathData]) -> HttmResult<VersionsMap> {
+        let _progress = LookupProgress::new(path_set.len());
+
+        Self::new(config, path_set)
+    }
+
+    // like new(), but returns whatever partial results are available once the budget is exhausted,
+    // instead of blocking indefinitely on slow datasets
+    pub fn new_bounded(
+        config: &Config,
+        path_set: &[PathData],
+        budget: &LookupBudget,
+    ) -> HttmResult<BoundedVersionsMap> {
+        // warnings printed to stderr would garble the TUI, see TUI
+        let is_interactive_mode = matches!(
+            GLOBAL_CONFIG.exec_mode,
+            ExecMode::Interactive(_) | ExecMode::Tui
+        );
+        let is_truncated = AtomicBool::new(false);
 
         let all_snap_versions: BTreeMap<PathData, Vec<PathData>> = path_set
             .par_iter()
-            .filter_map(|pathdata| match Versions::new(pathdata, config) {
-                Ok(versions) => Some(versions),
-                Err(err) => {
-                    if !is_interactive_mode {
-                        eprintln!("WARN: {}", err.to_string())
-                    }
-                    None
+            .filter(|_pathdata| {
+                if budget.is_exhausted() {
+                    is_truncated.store(true, Ordering::Relaxed);
+                    return false;
                 }
+
+                true
             })
+            .filter_map(
+                |pathdata| match Versions::new_bounded(pathdata, config, budget) {
+                    Ok(versions) => {
+                        if versions.is_truncated {
+                            is_truncated.store(true, Ordering::Relaxed);
+                        }
+
+                        Some(versions)
+                    }
+                    Err(err) => {
+                        if !is_interactive_mode {
+                            eprintln!("WARN: {}", err.to_string())
+                        }
+                        None
+                    }
+                },
+            )
             .map(|versions| {
+                LookupProgress::file_done(versions.snap_versions.len());
+
                 if !is_interactive_mode
                     && versions.live_path.opt_metadata().is_none()
                     && versions.snap_versions.is_empty()
@@ -91,11 +175,14 @@ impl VersionsMap {
             })
             .collect();
 
+        let is_truncated = is_truncated.into_inner();
+
         let mut versions_map: VersionsMap = all_snap_versions.into();
 
         // check if all files (snap and live) do not exist, if this is true, then user probably messed up
         // and entered a file that never existed (that is, perhaps a wrong file name)?
-        if versions_map.values().all(std::vec::Vec::is_empty)
+        if !is_truncated
+            && versions_map.values().all(std::vec::Vec::is_empty)
             && versions_map
                 .keys()
                 .all(|pathdata| pathdata.opt_metadata().is_none())
@@ -115,12 +202,19 @@ impl VersionsMap {
             versions_map.last_snap(last_snap_mode)
         }
 
-        Ok(versions_map)
+        if let Some(index) = config.opt_select_by {
+            versions_map.select_by(index)
+        }
+
+        Ok(BoundedVersionsMap {
+            versions_map,
+            is_truncated,
+        })
     }
 
     pub fn is_live_version_redundant(live_pathdata: &PathData, snaps: &[PathData]) -> bool {
         if let Some(last_snap) = snaps.last() {
-            return last_snap.opt_metadata() == live_pathdata.opt_metadata();
+            return last_snap.is_same_version(live_pathdata);
         }
 
         false
@@ -135,17 +229,29 @@ impl VersionsMap {
         });
     }
 
+    // the newest snapshot version is numbered 1, see SELECT_BY
+    fn select_by(&mut self, index: usize) {
+        self.values_mut().for_each(|snaps| {
+            *snaps = snaps
+                .iter()
+                .rev()
+                .nth(index - 1)
+                .map(|selected| vec![selected.to_owned()])
+                .unwrap_or_default();
+        });
+    }
+
     fn last_snap(&mut self, last_snap_mode: &LastSnapMode) {
         self.iter_mut().for_each(|(pathdata, snaps)| {
             *snaps = match snaps.last() {
                 // if last() is some, then should be able to unwrap pop()
                 Some(last) => match last_snap_mode {
                     LastSnapMode::Any => vec![last.to_owned()],
-                    LastSnapMode::DittoOnly if pathdata.opt_metadata() == last.opt_metadata() => {
+                    LastSnapMode::DittoOnly if pathdata.is_same_version(last) => {
                         vec![last.to_owned()]
                     }
                     LastSnapMode::NoDittoExclusive | LastSnapMode::NoDittoInclusive
-                        if pathdata.opt_metadata() != last.opt_metadata() =>
+                        if !pathdata.is_same_version(last) =>
                     {
                         vec![last.to_owned()]
                     }
@@ -165,30 +271,165 @@ impl VersionsMap {
 pub struct Versions {
     live_path: PathData,
     snap_versions: Vec<PathData>,
+    // true if the budget was exhausted before all snapshots were searched
+    is_truncated: bool,
 }
 
 impl Versions {
     #[inline(always)]
     pub fn new(pathdata: &PathData, config: &Config) -> HttmResult<Self> {
+        Self::new_bounded(pathdata, config, &LookupBudget::UNBOUNDED)
+    }
+
+    #[inline(always)]
+    fn new_bounded(
+        pathdata: &PathData,
+        config: &Config,
+        budget: &LookupBudget,
+    ) -> HttmResult<Self> {
+        let opt_target = if config.opt_follow {
+            Self::follow(pathdata)
+        } else {
+            None
+        };
+
+        let pathdata = opt_target.as_ref().unwrap_or(pathdata);
+
         let prox_opt_alts = ProximateDatasetAndOptAlts::new(pathdata)?;
         let live_path = prox_opt_alts.pathdata.clone();
+
+        if let Some(nested) =
+            BtrfsNestedSubvol::new(pathdata.path(), prox_opt_alts.proximate_dataset)
+        {
+            if let Some(search_bundle) = nested
+                .search_bundle()
+                .filter(|_| config.opt_traverse_nested)
+            {
+                let snap_versions = search_bundle.versions_processed(&config.dedup_by, budget);
+
+                return Ok(Self {
+                    live_path,
+                    snap_versions,
+                    is_truncated: budget.is_exhausted(),
+                });
+            }
+
+            if !matches!(
+                GLOBAL_CONFIG.exec_mode,
+                ExecMode::Interactive(_) | ExecMode::Tui
+            ) {
+                nested.annotate(prox_opt_alts.proximate_dataset);
+            }
+        }
+
         let snap_versions: Vec<PathData> = prox_opt_alts
             .into_search_bundles()
             .flat_map(|relative_path_snap_mounts| {
-                relative_path_snap_mounts.versions_processed(&config.dedup_by)
+                relative_path_snap_mounts.versions_processed(&config.dedup_by, budget)
             })
             .collect();
 
         Ok(Self {
             live_path,
             snap_versions,
+            is_truncated: budget.is_exhausted(),
         })
     }
 
+    // PathData uses symlink_metadata, so, by default, we version the link itself, here, when
+    // the user requests, we resolve the link, and version its target instead
+    fn follow(pathdata: &PathData) -> Option<PathData> {
+        let is_symlink = pathdata
+            .path()
+            .symlink_metadata()
+            .is_ok_and(|md| md.file_type().is_symlink());
+
+        if !is_symlink {
+            return None;
+        }
+
+        let is_interactive_mode = matches!(
+            GLOBAL_CONFIG.exec_mode,
+            ExecMode::Interactive(_) | ExecMode::Tui
+        );
+
+        match pathdata.path().canonicalize() {
+            Ok(target) => {
+                if !is_interactive_mode {
+                    eprintln!(
+                        "NOTE: {:?} is a symlink, httm has displayed the versions of its target: {:?}",
+                        pathdata.path(),
+                        target
+                    );
+                }
+
+                Some(PathData::from(target.as_path()))
+            }
+            Err(_) => {
+                if !is_interactive_mode {
+                    eprintln!(
+                        "WARN: Could not resolve the target of symlink, so httm has displayed the versions of the link itself: {:?}",
+                        pathdata.path()
+                    );
+                }
+
+                None
+            }
+        }
+    }
+
     #[inline(always)]
     pub fn into_inner(self) -> (PathData, Vec<PathData>) {
         (self.live_path, self.snap_versions)
     }
+
+    // cheaper than a full lookup, as versions are neither deduplicated nor cached, and the search
+    // stops at the first version found
+    pub fn any_exist(pathdata: &PathData) -> bool {
+        Self::with_search_bundles(pathdata, |mut search_bundles| {
+            search_bundles.any(|search_bundle| {
+                search_bundle
+                    .all_versions_unprocessed(&LookupBudget::UNBOUNDED)
+                    .any(|version| RelativePathAndSnapMounts::is_within_time_window(&version))
+            })
+        })
+        .unwrap_or(false)
+    }
+
+    // the newest version by modify time, again without deduplication or caching
+    pub fn newest(pathdata: &PathData) -> Option<PathData> {
+        Self::with_search_bundles(pathdata, |search_bundles| {
+            search_bundles
+                .flat_map(|search_bundle| {
+                    search_bundle
+                        .all_versions_unprocessed(&LookupBudget::UNBOUNDED)
+                        .filter(RelativePathAndSnapMounts::is_within_time_window)
+                        .collect::<Vec<PathData>>()
+                })
+                .max_by_key(|version| version.metadata_infallible().mtime())
+        })
+        .ok()
+        .flatten()
+    }
+
+    fn with_search_bundles<T>(
+        pathdata: &PathData,
+        f: impl FnOnce(Box<dyn Iterator<Item = RelativePathAndSnapMounts<'_>> + '_>) -> T,
+    ) -> HttmResult<T> {
+        let prox_opt_alts = ProximateDatasetAndOptAlts::new(pathdata)?;
+
+        let opt_nested = BtrfsNestedSubvol::new(pathdata.path(), prox_opt_alts.proximate_dataset)
+            .filter(|_| GLOBAL_CONFIG.opt_traverse_nested);
+
+        if let Some(search_bundle) = opt_nested
+            .as_ref()
+            .and_then(|nested| nested.search_bundle())
+        {
+            return Ok(f(Box::new(std::iter::once(search_bundle))));
+        }
+
+        Ok(f(Box::new(prox_opt_alts.into_search_bundles())))
+    }
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -229,6 +470,25 @@ impl<'a> ProximateDatasetAndOptAlts<'a> {
         // will compare the most proximate dataset to our our canonical path and the difference
         // between ZFS mount point and the canonical path is the path we will use to search the
         // hidden snapshot dirs
+        //
+        // user specified snapshot mounts are the only version source for paths under their
+        // local dirs, so there are no alts to consider
+        if let Some((proximate_dataset, relative_path)) = GLOBAL_CONFIG
+            .dataset_collection
+            .opt_map_of_explicit_snaps
+            .as_ref()
+            .and_then(|map_of_explicit_snaps| {
+                map_of_explicit_snaps.local_dir_and_relative_path(pathdata.path())
+            })
+        {
+            return Ok(Self {
+                pathdata,
+                proximate_dataset,
+                relative_path,
+                opt_alts: None,
+            });
+        }
+
         let (proximate_dataset, relative_path) = pathdata
             .alias()
             .map(|alias| (alias.proximate_dataset, alias.relative_path))
@@ -291,8 +551,41 @@ impl<'a> RelativePathAndSnapMounts<'a> {
         // for user specified dirs/aliases these are specified by the user
         GLOBAL_CONFIG
             .dataset_collection
-            .map_of_snaps
-            .get(dataset_of_interest)
+            .opt_map_of_explicit_snaps
+            .as_ref()
+            .and_then(|map_of_explicit_snaps| map_of_explicit_snaps.get(dataset_of_interest))
+            .or_else(|| {
+                GLOBAL_CONFIG
+                    .dataset_collection
+                    .map_of_snaps
+                    .get(dataset_of_interest)
+            })
+            .map(|snap_mounts| match snap_mounts.as_slice() {
+                // the archives of a borg or restic repo, and the snapshots of an SSH alias, are only known
+                // once mounted, and the previous versions of an SMB share are only enumerated once a path
+                // on the share is looked up
+                [] => {
+                    let mounted: [fn(&Path) -> &'static [Box<Path>]; 4] = [
+                        BorgRepos::snap_mounts,
+                        ResticRepos::snap_mounts,
+                        SshAliases::snap_mounts,
+                        ShadowCopy::snap_mounts,
+                    ];
+
+                    match mounted
+                        .into_iter()
+                        .map(|snap_mounts| snap_mounts(dataset_of_interest))
+                        .find(|snap_mounts| !snap_mounts.is_empty())
+                    {
+                        Some(snap_mounts) => snap_mounts,
+                        None if GLOBAL_CONFIG.opt_auto_mount_snaps => {
+                            SnapAutoMount::snap_mounts(dataset_of_interest)
+                        }
+                        None => &[],
+                    }
+                }
+                _ => snap_mounts.as_slice(),
+            })
             .map(|snap_mounts| Self {
                 relative_path,
                 snap_mounts,
@@ -301,13 +594,37 @@ impl<'a> RelativePathAndSnapMounts<'a> {
     }
 
     #[inline(always)]
-    pub fn versions_processed(&'a self, dedup_by: &DedupBy) -> Vec<PathData> {
+    pub fn versions_processed(
+        &'a self,
+        dedup_by: &DedupBy,
+        budget: &LookupBudget,
+    ) -> Vec<PathData> {
+        if let Some(cached) = LookupCache::get(self, dedup_by) {
+            return cached;
+        }
+
+        let res = self.versions_processed_uncached(dedup_by, budget);
+
+        // partial results are never cached
+        if !budget.is_exhausted() {
+            LookupCache::insert(self, dedup_by, &res);
+        }
+
+        res
+    }
+
+    #[inline(always)]
+    fn versions_processed_uncached(
+        &'a self,
+        dedup_by: &DedupBy,
+        budget: &LookupBudget,
+    ) -> Vec<PathData> {
         loop {
-            let all_versions = self.all_versions_unprocessed();
+            let all_versions = self.all_versions_unprocessed(budget);
 
             let res = Self::sort_dedup_versions(all_versions, dedup_by);
 
-            if res.is_empty() {
+            if res.is_empty() && !budget.is_exhausted() {
                 // opendir and readdir iter on the snap path are necessary to mount snapshots over SMB
                 match NetworkAutoMount::new(&self) {
                     NetworkAutoMount::Break => break res,
@@ -320,14 +637,21 @@ impl<'a> RelativePathAndSnapMounts<'a> {
     }
 
     #[inline(always)]
-    fn all_versions_unprocessed(&'a self) -> impl Iterator<Item = PathData> + 'a {
+    fn all_versions_unprocessed(
+        &'a self,
+        budget: &'a LookupBudget,
+    ) -> impl Iterator<Item = PathData> + 'a {
+        let provider = provider_for_dataset(self.dataset_of_interest);
+
         // get the DirEntry for our snapshot path which will have all our possible
         // snapshots, like so: .zfs/snapshots/<some snap name>/
         self
             .snap_mounts
             .iter()
-            .map(|snap_path| {
-                snap_path.join(self.relative_path)
+            .take_while(move |_snap_path| !budget.is_exhausted())
+            .map(move |snap_path| {
+                LookupProgress::snap_scanned();
+                provider.versioned_path(snap_path, self.relative_path)
             })
             .filter_map(|joined_path| {
                 match joined_path.symlink_metadata() {
@@ -344,7 +668,7 @@ impl<'a> RelativePathAndSnapMounts<'a> {
                                 eprintln!("Error: When httm tried to find a file contained within a snapshot directory, permission was denied.  \
                                 Perhaps you need to use sudo or equivalent to view the contents of this snapshot (for instance, btrfs by default creates privileged snapshots).  \
                                 \nDetails: {err}");
-                                std::process::exit(1)
+                                CleanupGuard::exit(1)
                             },
                             // if file metadata is not found, or is otherwise not available, 
                             // continue, it simply means we do not have a snapshot of this file
@@ -355,12 +679,25 @@ impl<'a> RelativePathAndSnapMounts<'a> {
             })
     }
 
+    #[inline(always)]
+    fn is_within_time_window(pathdata: &PathData) -> bool {
+        GLOBAL_CONFIG
+            .opt_time_window
+            .as_ref()
+            .map_or(true, |window| {
+                window.contains(&pathdata.metadata_infallible().mtime())
+            })
+    }
+
     // remove duplicates with the same system modify time and size/file len (or contents! See --DEDUP_BY)
+    // and any versions outside of the requested time window (see SINCE and UNTIL)
     #[inline(always)]
     fn sort_dedup_versions(
         iter: impl Iterator<Item = PathData>,
         dedup_by: &DedupBy,
     ) -> Vec<PathData> {
+        let iter = iter.filter(Self::is_within_time_window);
+
         match dedup_by {
             DedupBy::Disable => {
                 let mut vec: Vec<PathData> = iter.collect();
@@ -370,8 +707,13 @@ impl<'a> RelativePathAndSnapMounts<'a> {
             DedupBy::Metadata => {
                 let mut vec: Vec<PathData> = iter.collect();
 
-                vec.sort_unstable_by_key(|pathdata| pathdata.metadata_infallible());
-                vec.dedup_by_key(|a| a.metadata_infallible());
+                vec.sort_unstable_by(|a, b| {
+                    a.metadata_infallible().cmp_change(&b.metadata_infallible())
+                });
+                vec.dedup_by(|a, b| {
+                    a.metadata_infallible()
+                        .is_same_change(&b.metadata_infallible())
+                });
 
                 vec
             }
@@ -383,6 +725,15 @@ impl<'a> RelativePathAndSnapMounts<'a> {
                 vec.sort_unstable();
                 vec.dedup();
 
+                vec.into_iter().map(|container| container.into()).collect()
+            }
+            DedupBy::ContentsByBlocks => {
+                let mut vec: Vec<CompareContentsContainer> =
+                    iter.map(CompareContentsContainer::by_blocks).collect();
+
+                vec.sort_unstable();
+                vec.dedup();
+
                 vec.into_iter().map(|container| container.into()).collect()
             }
         }
@@ -436,3 +787,212 @@ impl NetworkAutoMount {
         NetworkAutoMount::Break
     }
 }
+
+// key: dataset mount, val: snapshot mounts we mounted ourselves, leaked as these must
+// live as long as GLOBAL_CONFIG's snap mounts, that is, for the life of the program
+static AUTO_MOUNTED_SNAPS: LazyLock<RwLock<BTreeMap<PathBuf, &'static [Box<Path>]>>> =
+    LazyLock::new(|| RwLock::new(BTreeMap::new()));
+
+// the private dir beneath which we mount each dataset's snapshots
+static AUTO_MOUNT_BASE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+// when a ZFS dataset has snapdir=disabled, or its snapshots are otherwise unavailable
+// at .zfs/snapshot, or when none of a NILFS2 device's snapshots are mounted, mount each
+// snapshot read-only beneath a temporary dir, on demand
+pub struct SnapAutoMount;
+
+impl SnapAutoMount {
+    fn snap_mounts(dataset_of_interest: &Path) -> &'static [Box<Path>] {
+        if let Some(cached) = AUTO_MOUNTED_SNAPS
+            .read()
+            .ok()
+            .and_then(|cached| cached.get(dataset_of_interest).copied())
+        {
+            return cached;
+        }
+
+        let Ok(mut cached) = AUTO_MOUNTED_SNAPS.write() else {
+            return &[];
+        };
+
+        // another thread may have mounted this dataset's snapshots while we waited
+        if let Some(snap_mounts) = cached.get(dataset_of_interest) {
+            return snap_mounts;
+        }
+
+        // anything mounted once cleanup has begun would never be unmounted
+        if CleanupGuard::is_started() {
+            return &[];
+        }
+
+        let snap_mounts = Self::mount_all(dataset_of_interest).unwrap_or_else(|err| {
+            eprintln!(
+                "WARN: httm could not auto-mount snapshots for {dataset_of_interest:?}: {err}"
+            );
+            Vec::new()
+        });
+
+        let snap_mounts: &'static [Box<Path>] = Box::leak(snap_mounts.into_boxed_slice());
+
+        cached.insert(dataset_of_interest.to_path_buf(), snap_mounts);
+
+        snap_mounts
+    }
+
+    fn mount_all(dataset_of_interest: &Path) -> HttmResult<Vec<Box<Path>>> {
+        let Some(dataset_metadata) = GLOBAL_CONFIG
+            .dataset_collection
+            .map_of_datasets
+            .get(dataset_of_interest)
+        else {
+            return Ok(Vec::new());
+        };
+
+        match dataset_metadata.fs_type {
+            FilesystemType::Zfs => Self::mount_all_zfs(&dataset_metadata.source),
+            FilesystemType::Nilfs2 => Self::mount_all_nilfs2(&dataset_metadata.source),
+            // only ZFS and NILFS2 snapshots may be auto-mounted
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn mount_all_zfs(source: &Path) -> HttmResult<Vec<Box<Path>>> {
+        user_has_effective_root("auto-mounting ZFS snapshots")?;
+
+        let fs_name = source.to_string_lossy();
+
+        let run_zfs = RunZFSCommand::new()?;
+        let mount_command = get_mount_command()?;
+
+        let dataset_dir = Self::base_dir()?.join(fs_name.replace('/', "_"));
+
+        let snap_mounts = run_zfs
+            .list_snapshots(&fs_name)?
+            .iter()
+            .filter_map(|full_snap_name| {
+                let (_fs_name, snap_name) = full_snap_name.split_once('@')?;
+                let snap_dir = dataset_dir.join(snap_name);
+
+                std::fs::create_dir_all(&snap_dir).ok()?;
+
+                let is_mounted = ExecProcess::new(&mount_command)
+                    .args(["-t", "zfs", "-o", "ro", full_snap_name])
+                    .arg(&snap_dir)
+                    .output()
+                    .map(|output| output.status.success())
+                    .unwrap_or(false);
+
+                if !is_mounted {
+                    eprintln!("WARN: httm could not mount snapshot: {full_snap_name}");
+                    let _ = std::fs::remove_dir(&snap_dir);
+                    return None;
+                }
+
+                Some(snap_dir.into_boxed_path())
+            })
+            .collect();
+
+        Ok(snap_mounts)
+    }
+
+    // NILFS2 snapshots are checkpoints, identified by number, of the same block device
+    fn mount_all_nilfs2(device: &Path) -> HttmResult<Vec<Box<Path>>> {
+        user_has_effective_root("auto-mounting NILFS2 snapshots")?;
+
+        let mount_command = get_mount_command()?;
+
+        let device_dir = Self::base_dir()?.join(
+            device
+                .to_string_lossy()
+                .trim_start_matches('/')
+                .replace('/', "_"),
+        );
+
+        let snap_mounts = Nilfs2Snapshots::checkpoint_numbers(device)?
+            .into_iter()
+            .filter_map(|cno| {
+                let snap_dir = device_dir.join(format!("cp-{cno}"));
+
+                std::fs::create_dir_all(&snap_dir).ok()?;
+
+                let is_mounted = ExecProcess::new(&mount_command)
+                    .arg("-t")
+                    .arg(NILFS2_FSTYPE)
+                    .arg("-o")
+                    .arg(format!("ro,{NILFS2_SNAPSHOT_ID_KEY}{cno}"))
+                    .arg(device)
+                    .arg(&snap_dir)
+                    .output()
+                    .map(|output| output.status.success())
+                    .unwrap_or(false);
+
+                if !is_mounted {
+                    eprintln!("WARN: httm could not mount NILFS2 checkpoint {cno} of: {device:?}");
+                    let _ = std::fs::remove_dir(&snap_dir);
+                    return None;
+                }
+
+                Some(snap_dir.into_boxed_path())
+            })
+            .collect();
+
+        Ok(snap_mounts)
+    }
+
+    // a private dir, made once, and recorded before anything is mounted beneath it, so cleanup may remove it
+    fn base_dir() -> HttmResult<&'static Path> {
+        if let Some(base_dir) = AUTO_MOUNT_BASE_DIR.get() {
+            return Ok(base_dir);
+        }
+
+        let base_dir = private_temp_dir("httm-snaps")?;
+
+        Ok(AUTO_MOUNT_BASE_DIR.get_or_init(|| base_dir))
+    }
+
+    // unmount and remove only those dirs we created, and never recursively
+    pub fn cleanup() {
+        // a thread which panicked while mounting may have poisoned the lock, but its mounts remain
+        let cached = AUTO_MOUNTED_SNAPS
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let Some(base_dir) = AUTO_MOUNT_BASE_DIR.get() else {
+            return;
+        };
+
+        if cached.values().all(|snap_mounts| snap_mounts.is_empty()) {
+            let _ = std::fs::remove_dir(base_dir);
+            return;
+        }
+
+        let Ok(umount_command) = which::which("umount") else {
+            eprintln!("WARN: 'umount' command not found.  Snapshots auto-mounted by httm remain mounted beneath: {base_dir:?}");
+            return;
+        };
+
+        cached
+            .values()
+            .flat_map(|snap_mounts| snap_mounts.iter())
+            .for_each(|snap_dir| {
+                let is_unmounted = ExecProcess::new(&umount_command)
+                    .arg(snap_dir.as_ref())
+                    .output()
+                    .map(|output| output.status.success())
+                    .unwrap_or(false);
+
+                if !is_unmounted {
+                    eprintln!("WARN: httm could not unmount auto-mounted snapshot: {snap_dir:?}");
+                    return;
+                }
+
+                let _ = std::fs::remove_dir(snap_dir);
+
+                if let Some(dataset_dir) = snap_dir.parent() {
+                    let _ = std::fs::remove_dir(dataset_dir);
+                }
+            });
+
+        let _ = std::fs::remove_dir(base_dir);
+    }
+}
@@ -21,8 +21,17 @@ mod data {
     pub mod selection;
 }
 mod display {
+    pub mod deleted_report;
+    pub mod deleted_summary;
+    pub mod diff_summary;
+    pub mod format_template;
+    pub mod latest_per_dir;
     pub mod maps;
     pub mod num_versions;
+    pub mod preview_render;
+    pub mod snap_info;
+    pub mod stats;
+    pub mod unified_diff;
     pub mod versions;
     pub mod wrapper;
 }
@@ -31,11 +40,20 @@ mod background {
     pub mod recursive;
 }
 mod interactive {
+    pub mod bisect;
     pub mod browse;
+    pub mod conflicts;
+    pub mod merge;
     pub mod preview;
     pub mod prune;
     pub mod restore;
+    pub mod restore_queue;
+    pub mod restore_tree;
     pub mod select;
+    pub mod soft_delete;
+    #[cfg(feature = "tui")]
+    pub mod tui;
+    pub mod undo;
     pub mod view_mode;
 }
 mod roll_forward {
@@ -44,55 +62,105 @@ mod roll_forward {
     pub mod preserve_hard_links;
 }
 mod config {
+    pub mod completions;
     pub mod generate;
     pub mod install_hot_keys;
 }
 mod library {
+    pub mod audit;
+    pub mod cleanup;
+    pub mod content_hash;
     pub mod diff_copy;
+    pub mod exec_command;
+    pub mod export;
     pub mod file_ops;
+    pub mod hash_pool;
+    pub mod i18n;
+    pub mod ignore_files;
     pub mod iter_extensions;
+    pub mod link_farm;
     pub mod results;
+    pub mod shell;
+    pub mod terminal;
+    pub mod trash;
     pub mod utility;
 }
 mod lookup {
+    pub mod cache;
     pub mod deleted;
     pub mod file_mounts;
+    pub mod grep;
+    pub mod progress;
+    pub mod snap_diff;
     pub mod snap_names;
+    pub mod twin;
     pub mod versions;
 }
 mod filesystem {
     pub mod aliases;
     pub mod alts;
+    pub mod borg;
+    pub mod btrfs_nested;
+    pub mod dated_backups;
+    pub mod explicit_snaps;
     pub mod mounts;
+    pub mod nilfs2;
+    pub mod provider;
+    pub mod restic;
+    pub mod shadow_copy;
     pub mod snaps;
+    pub mod ssh_aliases;
+    pub mod time_machine;
 }
 mod zfs {
     pub mod run_command;
     pub mod snap_guard;
     pub mod snap_mounts;
+    pub mod snap_schedule;
 }
 
 use crate::config::generate::InteractiveMode;
+use crate::interactive::bisect::BisectVersions;
 use crate::interactive::browse::InteractiveBrowse;
 use crate::interactive::select::InteractiveSelect;
 use background::recursive::NonInteractiveRecursiveWrapper;
 use config::generate::{Config, ExecMode};
+use display::latest_per_dir::LatestPerDir;
 use display::maps::PrintAsMap;
+use display::preview_render::PreviewRender;
+use display::stats::VersionStats;
+use display::unified_diff::UnifiedDiff;
 use display::wrapper::DisplayWrapper;
+use interactive::preview::{PreviewExec, PreviewVariables};
 use interactive::prune::PruneSnaps;
-use interactive::restore::InteractiveRestore;
-use library::results::HttmResult;
+use interactive::restore::{InteractiveRestore, NonInteractiveRestore};
+use interactive::restore_tree::RestoreTree;
+use interactive::soft_delete::SoftDelete;
+use interactive::undo::UndoRestore;
+use library::cleanup::CleanupGuard;
+use library::exec_command::ExecCommand;
+use library::export::{ExportVersions, VerifyExport};
+use library::link_farm::LinkFarm;
+use library::results::{HttmAbort, HttmResult};
 use library::utility::print_output_buf;
 use lookup::file_mounts::MountsForFiles;
+use lookup::grep::SnapshotGrep;
+use lookup::progress::LookupProgress;
+use lookup::snap_diff::SnapshotDiffReport;
 use lookup::snap_names::SnapNameMap;
-use lookup::versions::VersionsMap;
+use lookup::twin::CompareTwin;
+use lookup::versions::{LookupBudget, VersionsMap};
 use roll_forward::exec::RollForward;
 use std::sync::LazyLock;
 use zfs::snap_mounts::SnapshotMounts;
+use zfs::snap_schedule::SnapSchedule;
 
 pub const ZFS_HIDDEN_DIRECTORY: &str = ".zfs";
 pub const ZFS_SNAPSHOT_DIRECTORY: &str = ".zfs/snapshot";
 pub const BTRFS_SNAPPER_HIDDEN_DIRECTORY: &str = ".snapshots";
+// btrfs has no equivalent of ZFS's hidden snapshot directory, so we place our precautionary
+// snapshots in a directory of our own, at the root of the snapshotted subvolume
+pub const BTRFS_SNAP_GUARD_DIRECTORY: &str = ".httm_snapshots";
 pub const TM_DIR_REMOTE: &str = "/Volumes/.timemachine";
 pub const TM_DIR_LOCAL: &str = "/Volumes/com.apple.TimeMachine.localsnapshots/Backups.backupdb";
 pub const BTRFS_SNAPPER_SUFFIX: &str = "snapshot";
@@ -102,8 +170,19 @@ pub const RESTIC_LATEST_SNAPSHOT_DIRECTORY: &str = "snapshots/latest";
 pub const IN_BUFFER_SIZE: usize = 131_072;
 
 fn main() {
-    match exec() {
+    let res = {
+        // unmounts whatever we mounted, and writes back the lookup cache, upon drop or signal
+        let _cleanup_guard = CleanupGuard::new();
+
+        exec()
+    };
+
+    match res {
         Ok(_) => std::process::exit(0),
+        Err(error) if error.is::<HttmAbort>() => {
+            eprintln!("{error}");
+            std::process::exit(0)
+        }
         Err(error) => {
             eprintln!("ERROR: {error}");
             std::process::exit(1)
@@ -112,17 +191,35 @@ fn main() {
 }
 
 // get our program args and generate a config for use
-// everywhere else
+// everywhere else.  Where a function may need a config other than this one, as for a test,
+// it takes a &Config, like VersionsMap::new(), but most code reads this static directly,
+// as the config never changes once built
 static GLOBAL_CONFIG: LazyLock<Config> = LazyLock::new(|| {
     Config::new()
         .map_err(|error| {
             eprintln!("Error: {error}");
-            std::process::exit(1)
+            // building the config may have mounted local Time Machine snapshots
+            CleanupGuard::exit(1)
         })
         .unwrap()
 });
 
 fn exec() -> HttmResult<()> {
+    // mount any borg repos only now, so the cleanup guard is in place to unmount them, see BORG_REPO
+    if let Some(borg_repos) = &GLOBAL_CONFIG.dataset_collection.opt_borg_repos {
+        borg_repos.mount()?;
+    }
+
+    // likewise, any restic repos, see RESTIC_REPO
+    if let Some(restic_repos) = &GLOBAL_CONFIG.dataset_collection.opt_restic_repos {
+        restic_repos.mount()?;
+    }
+
+    // likewise, the remote dirs of any SSH aliases, see MAP_ALIASES
+    if let Some(ssh_aliases) = &GLOBAL_CONFIG.dataset_collection.opt_ssh_aliases {
+        ssh_aliases.mount()?;
+    }
+
     // fn exec() handles the basic display cases, and sends other cases to be processed elsewhere
     match &GLOBAL_CONFIG.exec_mode {
         // ExecMode::Interactive *may* return back to this function to be printed
@@ -130,16 +227,34 @@ fn exec() -> HttmResult<()> {
             let mut browse_result = InteractiveBrowse::new()?;
 
             match interactive_mode {
-                InteractiveMode::Restore(_) => {
-                    let interactive_select = InteractiveSelect::try_from(&mut browse_result)?;
+                InteractiveMode::Restore(_) => loop {
+                    let res = InteractiveSelect::try_from(&mut browse_result).and_then(
+                        |interactive_select| InteractiveRestore::from(interactive_select).restore(),
+                    );
 
-                    let interactive_restore = InteractiveRestore::from(interactive_select);
+                    // only return to the browse view when the user has requested a directory to browse,
+                    // else there is nothing to return to, see CONTINUE
+                    if !GLOBAL_CONFIG.opt_continue
+                        || GLOBAL_CONFIG.opt_requested_dir.is_none()
+                        || res.as_ref().is_err_and(|err| err.is::<HttmAbort>())
+                    {
+                        break res;
+                    }
 
-                    interactive_restore.restore()
-                }
+                    if let Err(err) = res {
+                        eprintln!("WARN: {err}  Returning to browse.");
+                    }
+
+                    browse_result = InteractiveBrowse::new()?;
+                },
                 InteractiveMode::Select(select_mode) => {
                     let interactive_select = InteractiveSelect::try_from(&mut browse_result)?;
 
+                    if let Some(template) = &GLOBAL_CONFIG.opt_exec {
+                        return ExecCommand::new(template, GLOBAL_CONFIG.exec_jobs)
+                            .exec(&interactive_select.snap_path_strings);
+                    }
+
                     interactive_select.print_selections(&select_mode)
                 }
                 // InteractiveMode::Browse executes back through fn exec() in main.rs
@@ -147,6 +262,11 @@ fn exec() -> HttmResult<()> {
                     let versions_map =
                         VersionsMap::new(&GLOBAL_CONFIG, &browse_result.selected_pathdata)?;
 
+                    if let Some(template) = &GLOBAL_CONFIG.opt_exec {
+                        return ExecCommand::new(template, GLOBAL_CONFIG.exec_jobs)
+                            .exec_versions_map(&versions_map);
+                    }
+
                     let output_buf = DisplayWrapper::from(&GLOBAL_CONFIG, versions_map).to_string();
 
                     print_output_buf(&output_buf)
@@ -155,7 +275,22 @@ fn exec() -> HttmResult<()> {
         }
         // ExecMode::BasicDisplay will be just printed, we already know the paths
         ExecMode::BasicDisplay | ExecMode::NumVersions(_) => {
-            let versions_map = VersionsMap::new(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths)?;
+            let budget = LookupBudget::new(GLOBAL_CONFIG.opt_lookup_timeout);
+            let bounded = {
+                let _progress = LookupProgress::new(GLOBAL_CONFIG.paths.len());
+                VersionsMap::new_bounded(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths, &budget)?
+            };
+
+            if bounded.is_truncated {
+                eprintln!("WARN: The lookup timeout elapsed before all paths could be searched.  Results have been truncated.");
+            }
+
+            let versions_map = bounded.versions_map;
+
+            if let Some(template) = &GLOBAL_CONFIG.opt_exec {
+                return ExecCommand::new(template, GLOBAL_CONFIG.exec_jobs)
+                    .exec_versions_map(&versions_map);
+            }
             let output_buf = DisplayWrapper::from(&GLOBAL_CONFIG, versions_map).to_string();
 
             print_output_buf(&output_buf)
@@ -164,16 +299,25 @@ fn exec() -> HttmResult<()> {
         // output elsewhere
         ExecMode::NonInteractiveRecursive(_) => NonInteractiveRecursiveWrapper::exec(),
         ExecMode::SnapFileMount(snapshot_suffix) => SnapshotMounts::exec(snapshot_suffix),
+        ExecMode::SnapSchedule(schedule_spec) => SnapSchedule::exec(schedule_spec),
         ExecMode::SnapsForFiles(opt_filters) => {
-            let versions_map = VersionsMap::new(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths)?;
+            let versions_map = VersionsMap::with_progress(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths)?;
             let snap_name_map = SnapNameMap::new(versions_map, opt_filters)?;
             let printable_map = PrintAsMap::from(&snap_name_map);
             let output_buf = printable_map.to_string();
 
             print_output_buf(&output_buf)
         }
+        ExecMode::SnapsForVersion(version_spec) => {
+            let versions_map = VersionsMap::with_progress(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths)?;
+            let snap_name_map = SnapNameMap::for_version(versions_map, version_spec)?;
+            let printable_map = PrintAsMap::from(&snap_name_map);
+            let output_buf = printable_map.to_string();
+
+            print_output_buf(&output_buf)
+        }
         ExecMode::Prune(opt_filters) => {
-            let versions_map = VersionsMap::new(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths)?;
+            let versions_map = VersionsMap::with_progress(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths)?;
             PruneSnaps::exec(versions_map, opt_filters)
         }
         ExecMode::MountsForFiles(mount_display) => {
@@ -184,5 +328,65 @@ fn exec() -> HttmResult<()> {
             print_output_buf(&output_buf)
         }
         ExecMode::RollForward(full_snap_name) => RollForward::new(full_snap_name)?.exec(),
+        ExecMode::CompareTwin(twin_dirs) => CompareTwin::exec(twin_dirs),
+        ExecMode::SnapshotDiffReport(snap_diff_pair) => SnapshotDiffReport::exec(snap_diff_pair),
+        ExecMode::RestoreTree(as_of) => RestoreTree::exec(as_of),
+        #[cfg(feature = "tui")]
+        ExecMode::Tui => interactive::tui::Tui::exec(),
+        // Config::from will not construct this mode, when built without the "tui" feature
+        #[cfg(not(feature = "tui"))]
+        ExecMode::Tui => unreachable!(),
+        ExecMode::Diff(Some(pair)) => UnifiedDiff::exec_pair(&pair.snap_file, &pair.live_file),
+        ExecMode::PreviewRender(raw_input) => PreviewRender::exec(raw_input),
+        ExecMode::PreviewExec(command, live_file, raw_input) => {
+            PreviewExec::exec(command, live_file, raw_input)
+        }
+        ExecMode::PreviewVariables(snap_file) => PreviewVariables::exec(snap_file),
+        ExecMode::Diff(None) => {
+            let versions_map = VersionsMap::with_progress(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths)?;
+            UnifiedDiff::exec_last_snap(&versions_map)
+        }
+        ExecMode::Export(export_file) => {
+            let versions_map = VersionsMap::with_progress(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths)?;
+
+            ExportVersions::exec(&versions_map, export_file)
+        }
+        ExecMode::VerifyExport(export_file) => VerifyExport::exec(export_file),
+        ExecMode::LinkFarm(target_dir) => {
+            let versions_map = VersionsMap::with_progress(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths)?;
+
+            LinkFarm::exec(&versions_map, target_dir)
+        }
+        ExecMode::SoftDelete => {
+            let versions_map = VersionsMap::with_progress(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths)?;
+
+            SoftDelete::exec(&versions_map)
+        }
+        ExecMode::UndoRestore => UndoRestore::exec(),
+        ExecMode::Stats => {
+            let versions_map = VersionsMap::with_progress(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths)?;
+            let output_buf = VersionStats::from(&versions_map).to_string();
+
+            print_output_buf(&output_buf)
+        }
+        ExecMode::LatestPerDir => {
+            let entries = LatestPerDir::entries(&GLOBAL_CONFIG.paths)?;
+            let versions_map = VersionsMap::with_progress(&GLOBAL_CONFIG, &entries)?;
+            let output_buf = DisplayWrapper::from(&GLOBAL_CONFIG, versions_map).to_string();
+
+            print_output_buf(&output_buf)
+        }
+        ExecMode::Grep(grep_spec) => {
+            let versions_map = VersionsMap::with_progress(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths)?;
+            SnapshotGrep::exec(&versions_map, grep_spec)
+        }
+        ExecMode::Bisect(opt_command) => {
+            let versions_map = VersionsMap::with_progress(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths)?;
+            BisectVersions::exec(&versions_map, opt_command.as_deref())
+        }
+        ExecMode::NonInteractiveRestore(_) => {
+            let versions_map = VersionsMap::with_progress(&GLOBAL_CONFIG, &GLOBAL_CONFIG.paths)?;
+            NonInteractiveRestore::exec(&versions_map)
+        }
     }
 }
@@ -19,12 +19,14 @@ use crate::data::paths::{PathData, PathDeconstruction};
 use crate::library::file_ops::{Copy, Preserve, Remove};
 use crate::library::iter_extensions::HttmIter;
 use crate::library::results::{HttmError, HttmResult};
+use crate::library::terminal::Terminal;
 use crate::library::utility::{is_metadata_same, user_has_effective_root};
 use crate::roll_forward::diff_events::{DiffEvent, DiffType};
 use crate::roll_forward::preserve_hard_links::{PreserveHardLinks, SpawnPreserveLinks};
-use crate::zfs::run_command::RunZFSCommand;
+use crate::zfs::run_command::{RunZFSCommand, RunZpoolCommand};
 use crate::zfs::snap_guard::{PrecautionarySnapType, SnapGuard};
 use crate::{GLOBAL_CONFIG, ZFS_SNAPSHOT_DIRECTORY};
+use hashbrown::HashMap;
 use indicatif::ProgressBar;
 use nu_ansi_term::Color::{Blue, Red};
 use rayon::prelude::*;
@@ -79,6 +81,16 @@ impl RollForward {
         // we need root, so we do a raw SnapGuard after checking that we have root
         user_has_effective_root("Roll forward to a snapshot.")?;
 
+        if let Some(warning) =
+            RunZpoolCommand::checkpoint_warning(std::iter::once(self.dataset.as_str()))
+        {
+            eprintln!("{warning}");
+        }
+
+        if GLOBAL_CONFIG.opt_dry_run {
+            return self.dry_run();
+        }
+
         let snap_guard: SnapGuard =
             SnapGuard::new(&self.dataset, PrecautionarySnapType::PreRollForward)?;
 
@@ -98,7 +110,9 @@ impl RollForward {
                     .rollback()
                     .map(|_| println!("Rollback succeeded."))?;
 
-                std::process::exit(1)
+                return Err(
+                    HttmError::new("httm roll forward failed, and was rolled back.").into(),
+                );
             }
         };
 
@@ -125,6 +139,38 @@ impl RollForward {
 
         let (snap_handle, live_handle) = (spawn_res.snap_handle, spawn_res.live_handle);
 
+        let group_map = self.diff_group_map()?;
+
+        // need to wait for these to finish before executing any diff_action
+        let snap_map = snap_handle
+            .join()
+            .map_err(|_err| HttmError::new("Thread panicked!"))??;
+
+        let live_map = live_handle
+            .join()
+            .map_err(|_err| HttmError::new("Thread panicked!"))??;
+
+        let preserve_hard_links = PreserveHardLinks::new(&live_map, &snap_map, self.to_owned())?;
+        let exclusions = preserve_hard_links.exec()?;
+
+        // into iter and reverse because we want to go largest first
+        eprintln!("Reversing 'zfs diff' actions.");
+        group_map
+            .par_iter()
+            .filter(|(key, _values)| !exclusions.contains(key.as_path()))
+            .flat_map(|(_key, values)| values.iter().max_by_key(|event| event.time))
+            .for_each(|event| match &event.diff_type {
+                DiffType::Renamed(new_file) if exclusions.contains(new_file) => (),
+                _ => {
+                    let _ = self.diff_action(event);
+                }
+            });
+
+        self.verify()
+    }
+
+    // zfs-diff can return multiple file actions for a single inode, so events are grouped by path
+    fn diff_group_map(&self) -> HttmResult<HashMap<PathBuf, Vec<DiffEvent>>> {
         let run_zfs = RunZFSCommand::new()?;
 
         let mut process_handle = run_zfs.diff(&self)?;
@@ -172,32 +218,71 @@ impl RollForward {
             return Err(HttmError::new(&msg).into());
         }
 
-        // need to wait for these to finish before executing any diff_action
-        let snap_map = snap_handle
-            .join()
-            .map_err(|_err| HttmError::new("Thread panicked!"))??;
+        Ok(group_map)
+    }
 
-        let live_map = live_handle
-            .join()
-            .map_err(|_err| HttmError::new("Thread panicked!"))??;
+    // the actions a roll forward would take, without taking them, see DRY_RUN
+    fn dry_run(&self) -> HttmResult<()> {
+        SnapGuard::new(&self.dataset, PrecautionarySnapType::PreRollForward)?;
+
+        let group_map = self.diff_group_map()?;
+
+        let mut actions: Vec<String> = group_map
+            .values()
+            .flat_map(|values| values.iter().max_by_key(|event| event.time))
+            .filter_map(|event| {
+                let snap_file_path = self.snap_path(&event.path_buf)?;
+
+                let action = match &event.diff_type {
+                    DiffType::Removed | DiffType::Modified => {
+                        format!("Restore: {:?} -> {:?}", snap_file_path, event.path_buf)
+                    }
+                    DiffType::Created => {
+                        Self::dry_run_overwrite_or_remove(&snap_file_path, &event.path_buf)
+                    }
+                    DiffType::Renamed(new_file_name) => {
+                        let snap_new_file_name = self.snap_path(new_file_name)?;
+
+                        let mut action =
+                            Self::dry_run_overwrite_or_remove(&snap_new_file_name, new_file_name);
+
+                        if snap_file_path.exists() {
+                            action +=
+                                &format!("\nRestore: {:?} -> {:?}", snap_file_path, event.path_buf);
+                        }
+
+                        action
+                    }
+                };
+
+                Some(action)
+            })
+            .collect();
 
-        let preserve_hard_links = PreserveHardLinks::new(&live_map, &snap_map, self.to_owned())?;
-        let exclusions = preserve_hard_links.exec()?;
+        actions.sort();
 
-        // into iter and reverse because we want to go largest first
-        eprintln!("Reversing 'zfs diff' actions.");
-        group_map
-            .par_iter()
-            .filter(|(key, _values)| !exclusions.contains(key.as_path()))
-            .flat_map(|(_key, values)| values.iter().max_by_key(|event| event.time))
-            .for_each(|event| match &event.diff_type {
-                DiffType::Renamed(new_file) if exclusions.contains(new_file) => (),
-                _ => {
-                    let _ = self.diff_action(event);
-                }
-            });
+        println!(
+            "httm would reverse the following 'zfs diff' actions since snapshot {}:\n\n{}\n",
+            self.full_name(),
+            actions.join("\n")
+        );
 
-        self.verify()
+        SnapGuard::new(
+            &self.dataset,
+            PrecautionarySnapType::PostRollForward(self.snap.to_owned()),
+        )?;
+
+        println!("Dry run.  No files were rolled forward.");
+
+        Ok(())
+    }
+
+    fn dry_run_overwrite_or_remove(src: &Path, dst: &Path) -> String {
+        if src.exists() {
+            return format!("Restore: {:?} -> {:?}", src, dst);
+        }
+
+        format!("Remove:  {:?}", dst)
     }
 
     fn verify(&self) -> HttmResult<()> {
@@ -398,7 +483,12 @@ impl RollForward {
 
         Preserve::direct(src, dst)?;
 
-        eprintln!("{}: {:?} -> {:?}", Blue.paint("Restored "), src, dst);
+        eprintln!(
+            "{}: {:?} -> {:?}",
+            Terminal::paint_stderr(Blue, "Restored "),
+            src,
+            dst
+        );
         Ok(())
     }
 
@@ -442,7 +532,11 @@ impl RollForward {
             }
         }
 
-        eprintln!("{}: {:?} -> 🗑️", Red.paint("Removed  "), dst);
+        eprintln!(
+            "{}: {:?} -> 🗑️",
+            Terminal::paint_stderr(Red, "Removed  "),
+            dst
+        );
 
         Ok(())
     }
@@ -18,6 +18,7 @@
 use crate::data::paths::BasicDirEntryInfo;
 use crate::library::file_ops::{Copy, Preserve, Remove};
 use crate::library::results::{HttmError, HttmResult};
+use crate::library::terminal::Terminal;
 use crate::RollForward;
 use hashbrown::{HashMap, HashSet};
 use nu_ansi_term::Color::{Green, Yellow};
@@ -364,7 +365,12 @@ impl<'a> PreserveHardLinks<'a> {
             return Err(HttmError::new("Could not obtain snap path").into());
         }
 
-        eprintln!("{}: {:?} -> {:?}", Yellow.paint("Linked  "), original, link);
+        eprintln!(
+            "{}: {:?} -> {:?}",
+            Terminal::paint_stderr(Yellow, "Linked  "),
+            original,
+            link
+        );
 
         Ok(())
     }
@@ -386,7 +392,11 @@ impl<'a> PreserveHardLinks<'a> {
             }
         }
 
-        eprintln!("{}: {:?} -> 🗑️", Green.paint("Unlinked  "), link);
+        eprintln!(
+            "{}: {:?} -> 🗑️",
+            Terminal::paint_stderr(Green, "Unlinked  "),
+            link
+        );
 
         Ok(())
     }
@@ -20,8 +20,10 @@ use crate::filesystem::mounts::FilesystemType;
 use crate::library::results::{HttmError, HttmResult};
 use crate::library::utility::user_has_effective_root;
 use crate::roll_forward::exec::RollForward;
+use hashbrown::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::{Child, Command as ExecProcess, Stdio};
+use std::time::{Duration, SystemTime};
 use which::which;
 
 pub struct RunZFSCommand {
@@ -38,8 +40,22 @@ impl RunZFSCommand {
     }
 
     pub fn snapshot(&self, snapshot_names: &[String]) -> HttmResult<()> {
+        self.snapshot_with_property(snapshot_names, None)
+    }
+
+    // the property is a user property, like "httm:schedule=hourly"
+    pub fn snapshot_with_property(
+        &self,
+        snapshot_names: &[String],
+        opt_property: Option<&str>,
+    ) -> HttmResult<()> {
         let mut process_args = vec!["snapshot".to_owned()];
 
+        if let Some(property) = opt_property {
+            process_args.push("-o".to_owned());
+            process_args.push(property.to_owned());
+        }
+
         process_args.extend_from_slice(snapshot_names);
 
         let process_output = ExecProcess::new(&self.zfs_command)
@@ -146,6 +162,182 @@ impl RunZFSCommand {
         Ok(())
     }
 
+    pub fn list_snapshots(&self, fs_name: &str) -> HttmResult<Vec<String>> {
+        // -H: no header, tab separated, -d 1: only this dataset's snapshots
+        let process_args = vec![
+            "list", "-H", "-o", "name", "-t", "snapshot", "-d", "1", fs_name,
+        ];
+
+        let process_output = ExecProcess::new(&self.zfs_command)
+            .args(&process_args)
+            .output()?;
+        let stderr_string = std::str::from_utf8(&process_output.stderr)?.trim();
+        let stdout_string: &str = std::str::from_utf8(&process_output.stdout)?.trim();
+
+        // stderr_string is a string not an error, so here we build an err or output
+        if !stderr_string.is_empty() {
+            let msg = "httm was unable to list snapshots for the dataset given. The 'zfs' command issued the following error: ".to_owned() + stderr_string;
+
+            return Err(HttmError::new(&msg).into());
+        }
+
+        let snap_names = stdout_string
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| line.to_owned())
+            .collect();
+
+        Ok(snap_names)
+    }
+
+    // snapshot names of every dataset, keyed by dataset name, ordered by creation time
+    pub fn list_all_snapshots(&self) -> HttmResult<HashMap<String, Vec<String>>> {
+        let mut snap_names: HashMap<String, Vec<String>> = HashMap::new();
+
+        self.all_snapshots_by_creation()?
+            .iter()
+            .filter_map(|(full_name, _creation)| full_name.split_once('@'))
+            .for_each(|(fs_name, snap_name)| {
+                snap_names
+                    .entry(fs_name.to_owned())
+                    .or_default()
+                    .push(snap_name.to_owned())
+            });
+
+        Ok(snap_names)
+    }
+
+    // every snapshot's full name, "pool/dataset@snap", and its creation time, oldest first
+    pub fn all_snapshots_by_creation(&self) -> HttmResult<Vec<(String, SystemTime)>> {
+        // -H: no header, tab separated, -p: creation as seconds since the epoch
+        let process_args = vec![
+            "list",
+            "-H",
+            "-p",
+            "-o",
+            "name,creation",
+            "-t",
+            "snapshot",
+            "-s",
+            "creation",
+        ];
+
+        let process_output = ExecProcess::new(&self.zfs_command)
+            .args(&process_args)
+            .output()?;
+        let stderr_string = std::str::from_utf8(&process_output.stderr)?.trim();
+        let stdout_string: &str = std::str::from_utf8(&process_output.stdout)?.trim();
+
+        // stderr_string is a string not an error, so here we build an err or output
+        if !stderr_string.is_empty() {
+            let msg =
+                "httm was unable to list snapshots. The 'zfs' command issued the following error: "
+                    .to_owned()
+                    + stderr_string;
+
+            return Err(HttmError::new(&msg).into());
+        }
+
+        let snapshots = stdout_string
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .filter_map(|(full_name, creation)| {
+                creation.trim().parse::<u64>().ok().map(|secs| {
+                    (
+                        full_name.to_owned(),
+                        SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+                    )
+                })
+            })
+            .collect();
+
+        Ok(snapshots)
+    }
+
+    // every snapshot's full name, and its value of the user property named, oldest first.  A snapshot
+    // without the property has the value "-"
+    pub fn snapshots_with_property(
+        &self,
+        property_name: &str,
+    ) -> HttmResult<Vec<(String, String)>> {
+        let columns = format!("name,{property_name}");
+
+        let process_args = vec![
+            "list", "-H", "-o", &columns, "-t", "snapshot", "-s", "creation",
+        ];
+
+        let process_output = ExecProcess::new(&self.zfs_command)
+            .args(&process_args)
+            .output()?;
+        let stderr_string = std::str::from_utf8(&process_output.stderr)?.trim();
+        let stdout_string: &str = std::str::from_utf8(&process_output.stdout)?.trim();
+
+        // stderr_string is a string not an error, so here we build an err or output
+        if !stderr_string.is_empty() {
+            let msg =
+                "httm was unable to list snapshots. The 'zfs' command issued the following error: "
+                    .to_owned()
+                    + stderr_string;
+
+            return Err(HttmError::new(&msg).into());
+        }
+
+        let snapshots = stdout_string
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(full_name, value)| (full_name.to_owned(), value.to_owned()))
+            .collect();
+
+        Ok(snapshots)
+    }
+
+    // the creation time of each snapshot named
+    pub fn creation_times(
+        &self,
+        snapshot_names: &[String],
+    ) -> HttmResult<HashMap<String, SystemTime>> {
+        // -H: no header, tab separated, -p: creation as seconds since the epoch
+        let mut process_args = vec![
+            "get".to_owned(),
+            "-H".to_owned(),
+            "-p".to_owned(),
+            "-o".to_owned(),
+            "name,value".to_owned(),
+            "creation".to_owned(),
+        ];
+
+        process_args.extend_from_slice(snapshot_names);
+
+        let process_output = ExecProcess::new(&self.zfs_command)
+            .args(&process_args)
+            .output()?;
+        let stderr_string = std::str::from_utf8(&process_output.stderr)?.trim();
+        let stdout_string: &str = std::str::from_utf8(&process_output.stdout)?.trim();
+
+        // stderr_string is a string not an error, so here we build an err or output
+        if !stderr_string.is_empty() {
+            let msg = "httm was unable to determine the creation time of snapshots. The 'zfs' command issued the following error: ".to_owned() + stderr_string;
+
+            return Err(HttmError::new(&msg).into());
+        }
+
+        let creation_times = stdout_string
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .filter_map(|(name, creation)| {
+                creation.trim().parse::<u64>().ok().map(|secs| {
+                    (
+                        name.to_owned(),
+                        SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+                    )
+                })
+            })
+            .collect();
+
+        Ok(creation_times)
+    }
+
     pub fn diff(&self, roll_forward: &RollForward) -> HttmResult<Child> {
         // -H: tab separated, -t: Specify time, -h: Normalize paths (don't use escape codes)
         let full_name = roll_forward.full_name();
@@ -161,6 +353,155 @@ impl RunZFSCommand {
     }
 }
 
+pub struct RunZpoolCommand {
+    pub zpool_command: PathBuf,
+}
+
+impl RunZpoolCommand {
+    pub fn new() -> HttmResult<Self> {
+        let zpool_command = which("zpool").map_err(|_err| {
+            HttmError::new(
+                "'zpool' command not found. Make sure the command 'zpool' is in your path.",
+            )
+        })?;
+
+        Ok(Self { zpool_command })
+    }
+
+    // returns a warning for display, if any pool containing the datasets or snapshots
+    // named has an active checkpoint.  we don't error here, as a missing 'zpool' command
+    // or an unparseable status is no reason to stop a prune or rollback
+    pub fn checkpoint_warning<'a, I>(names: I) -> Option<String>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        let run_zpool = Self::new().ok()?;
+
+        let mut pool_names: Vec<&str> = names
+            .filter_map(|name| name.split(['/', '@']).next())
+            .filter(|pool_name| !pool_name.is_empty())
+            .collect();
+
+        pool_names.sort_unstable();
+        pool_names.dedup();
+
+        let checkpoints: Vec<String> = pool_names
+            .into_iter()
+            .filter_map(|pool_name| {
+                run_zpool
+                    .checkpoint(pool_name)
+                    .ok()
+                    .flatten()
+                    .map(|checkpoint| format!("\t{pool_name}:\t{checkpoint}\n"))
+            })
+            .collect();
+
+        if checkpoints.is_empty() {
+            return None;
+        }
+
+        let warning = format!(
+            "WARN: The following pool/s have an active checkpoint:\n\n{}\n\
+            Snapshots destroyed or rolled back on a checkpointed pool continue to consume space until the checkpoint is discarded, \
+            and rewinding to the checkpoint will undo this action.\n",
+            checkpoints.concat()
+        );
+
+        Some(warning)
+    }
+
+    pub fn checkpoint(&self, pool_name: &str) -> HttmResult<Option<String>> {
+        let process_args = vec!["status", pool_name];
+
+        let process_output = ExecProcess::new(&self.zpool_command)
+            .args(&process_args)
+            .output()?;
+        let stderr_string = std::str::from_utf8(&process_output.stderr)?.trim();
+        let stdout_string: &str = std::str::from_utf8(&process_output.stdout)?;
+
+        // stderr_string is a string not an error, so here we build an err or output
+        if !stderr_string.is_empty() {
+            let msg = "httm was unable to determine the status of the pool given. The 'zpool' command issued the following error: ".to_owned() + stderr_string;
+
+            return Err(HttmError::new(&msg).into());
+        }
+
+        // an active checkpoint appears in 'zpool status' as a line like:
+        // "checkpoint: created Mon Jun  5 10:00:00 2023, consumes 1.2M"
+        let opt_checkpoint = stdout_string
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("checkpoint:"))
+            .map(|checkpoint| checkpoint.trim().to_owned())
+            .next();
+
+        Ok(opt_checkpoint)
+    }
+}
+
+pub struct RunBtrfsCommand {
+    pub btrfs_command: PathBuf,
+}
+
+impl RunBtrfsCommand {
+    pub fn new() -> HttmResult<Self> {
+        let btrfs_command = which("btrfs").map_err(|_err| {
+            HttmError::new(
+                "'btrfs' command not found. Make sure the command 'btrfs' is in your path.",
+            )
+        })?;
+
+        Ok(Self { btrfs_command })
+    }
+
+    // unlike 'zfs', 'btrfs' reports progress on stdout, so we must check the exit status
+    pub fn snapshot(&self, subvol_root: &Path, snap_path: &Path) -> HttmResult<()> {
+        let process_output = ExecProcess::new(&self.btrfs_command)
+            .arg("subvolume")
+            .arg("snapshot")
+            .arg("-r")
+            .arg(subvol_root)
+            .arg(snap_path)
+            .output()?;
+
+        if !process_output.status.success() {
+            let stderr_string = std::str::from_utf8(&process_output.stderr)?.trim();
+
+            let msg = if stderr_string.contains("Permission denied")
+                || stderr_string.contains("Operation not permitted")
+            {
+                "httm must have root privileges to snapshot a btrfs subvolume".to_owned()
+            } else {
+                "httm was unable to take snapshots. The 'btrfs' command issued the following error: "
+                    .to_owned()
+                    + stderr_string
+            };
+
+            return Err(HttmError::new(&msg).into());
+        }
+
+        Ok(())
+    }
+
+    pub fn delete(&self, snap_path: &Path) -> HttmResult<()> {
+        let process_output = ExecProcess::new(&self.btrfs_command)
+            .arg("subvolume")
+            .arg("delete")
+            .arg(snap_path)
+            .output()?;
+
+        if !process_output.status.success() {
+            let stderr_string = std::str::from_utf8(&process_output.stderr)?.trim();
+            let msg = "httm was unable to delete a snapshot. The 'btrfs' command issued the following error: "
+                .to_owned()
+                + stderr_string;
+
+            return Err(HttmError::new(&msg).into());
+        }
+
+        Ok(())
+    }
+}
+
 pub enum ZfsAllowPriv {
     Snapshot,
     Rollback,
@@ -15,24 +15,48 @@
 // For the full copyright and license information, please view the LICENSE file
 // that was distributed with this source code.
 
-use super::run_command::RunZFSCommand;
-use crate::library::results::HttmResult;
+use super::run_command::{RunBtrfsCommand, RunZFSCommand, RunZpoolCommand};
+use crate::data::paths::{PathData, PathDeconstruction};
+use crate::filesystem::btrfs_nested::BTRFS_SUBVOL_ROOT_INODE;
+use crate::filesystem::mounts::FilesystemType;
+use crate::library::audit::AuditLog;
+use crate::library::file_ops::{Copy, Remove};
+use crate::library::results::{HttmError, HttmResult};
 use crate::library::utility::{date_string, DateFormat};
 use crate::zfs::run_command::ZfsAllowPriv;
-use crate::{print_output_buf, GLOBAL_CONFIG};
-use std::path::Path;
+use crate::{print_output_buf, BTRFS_SNAP_GUARD_DIRECTORY, GLOBAL_CONFIG, ZFS_SNAPSHOT_DIRECTORY};
+use hashbrown::HashMap;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
 use std::time::SystemTime;
 
+// only the most recent precautionary snapshots of a btrfs subvolume are kept, as, unlike ZFS's, ours
+// are visible within the live tree, and would otherwise accumulate without limit
+const BTRFS_SNAP_GUARD_RETENTION: usize = 8;
+const BTRFS_SNAP_GUARD_SUFFIX: &str = "_httmSnapRestore";
+
+// each precautionary snapshot taken before a restore, as the live root it preserves, the root of the
+// snapshot itself, and its name, so the audit log may record exactly which snapshot preserves a destination
+static PRE_RESTORE_SNAPS: LazyLock<Mutex<Vec<(PathBuf, PathBuf, String)>>> =
+    LazyLock::new(|| Mutex::new(Vec::new()));
+
+#[derive(Clone)]
 pub enum PrecautionarySnapType {
     PreRollForward,
     PostRollForward(String),
     PreRestore,
+    PreSoftDelete,
 }
 
 impl TryFrom<&Path> for SnapGuard {
     type Error = Box<dyn std::error::Error + Send + Sync>;
 
     fn try_from(path: &Path) -> HttmResult<Self> {
+        if let Some(subvol_root) = SnapGuard::btrfs_subvol_root(path) {
+            return SnapGuard::new_btrfs(&subvol_root, vec![path.to_path_buf()]);
+        }
+
         // guards the ZFS action, returns source dataset
         let allowed_source = ZfsAllowPriv::Snapshot.from_path(&path)?;
 
@@ -43,12 +67,137 @@ impl TryFrom<&Path> for SnapGuard {
     }
 }
 
+enum GuardedFilesystem {
+    Zfs,
+    // btrfs cannot roll back a mounted subvolume, so, instead, we copy back each guarded path
+    Btrfs {
+        subvol_root: PathBuf,
+        guarded_paths: Vec<PathBuf>,
+    },
+}
+
 pub struct SnapGuard {
     new_snap_name: String,
     dataset_name: String,
+    guarded_fs: GuardedFilesystem,
 }
 
 impl SnapGuard {
+    // one guard per dataset or subvolume, no matter how many of the paths it contains
+    pub fn from_paths<'a>(
+        paths: impl Iterator<Item = &'a Path>,
+        snap_type: PrecautionarySnapType,
+    ) -> HttmResult<Vec<Self>> {
+        let mut dataset_names: Vec<String> = Vec::new();
+        let mut subvols: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+
+        for path in paths {
+            match Self::btrfs_subvol_root(path) {
+                Some(subvol_root) => subvols
+                    .entry(subvol_root)
+                    .or_default()
+                    .push(path.to_path_buf()),
+                None => dataset_names.push(
+                    ZfsAllowPriv::Snapshot
+                        .from_path(path)?
+                        .to_string_lossy()
+                        .into_owned(),
+                ),
+            }
+        }
+
+        dataset_names.sort_unstable();
+        dataset_names.dedup();
+
+        let zfs_guards = dataset_names
+            .iter()
+            .map(|dataset_name| Self::new(dataset_name, snap_type.clone()));
+
+        let btrfs_guards = subvols
+            .into_iter()
+            .map(|(subvol_root, guarded_paths)| Self::new_btrfs(&subvol_root, guarded_paths));
+
+        zfs_guards.chain(btrfs_guards).collect()
+    }
+
+    // the nearest ancestor which is the root of a btrfs subvolume, as a nested subvolume may not
+    // be mounted, and so may not be the proximate dataset, see BtrfsNestedSubvol
+    fn btrfs_subvol_root(path: &Path) -> Option<PathBuf> {
+        let pathdata = PathData::from(path);
+        let proximate_dataset = pathdata.proximate_dataset().ok()?;
+
+        if !matches!(
+            pathdata.fs_type(Some(proximate_dataset)),
+            Some(FilesystemType::Btrfs(_))
+        ) {
+            return None;
+        }
+
+        path.ancestors()
+            .filter(|ancestor| ancestor.starts_with(proximate_dataset))
+            .find(|ancestor| {
+                ancestor
+                    .symlink_metadata()
+                    .is_ok_and(|md| md.file_type().is_dir() && md.ino() == BTRFS_SUBVOL_ROOT_INODE)
+            })
+            .map(Path::to_path_buf)
+    }
+
+    fn new_btrfs(subvol_root: &Path, guarded_paths: Vec<PathBuf>) -> HttmResult<Self> {
+        let timestamp = date_string(
+            GLOBAL_CONFIG.requested_utc_offset,
+            &SystemTime::now(),
+            DateFormat::Timestamp,
+        );
+
+        let snap_dir = subvol_root.join(BTRFS_SNAP_GUARD_DIRECTORY);
+        let snap_path = snap_dir.join(format!("snap_pre_{}{}", timestamp, BTRFS_SNAP_GUARD_SUFFIX));
+        let new_snap_name = snap_path.to_string_lossy().into_owned();
+
+        let snap_guard = SnapGuard {
+            new_snap_name,
+            dataset_name: subvol_root.to_string_lossy().into_owned(),
+            guarded_fs: GuardedFilesystem::Btrfs {
+                subvol_root: subvol_root.to_path_buf(),
+                guarded_paths,
+            },
+        };
+
+        if GLOBAL_CONFIG.opt_dry_run {
+            print_output_buf(&format!(
+                "httm would take a precautionary snapshot named: {}\n",
+                &snap_guard.new_snap_name
+            ))?;
+
+            return Ok(snap_guard);
+        }
+
+        let run_btrfs = RunBtrfsCommand::new()?;
+
+        let res_snapshot: HttmResult<()> = std::fs::create_dir_all(&snap_dir)
+            .map_err(|err| err.into())
+            .and_then(|_| run_btrfs.snapshot(subvol_root, &snap_path));
+
+        AuditLog::snapshot(&snap_guard.new_snap_name, &res_snapshot);
+
+        res_snapshot?;
+
+        Self::register(subvol_root, &snap_path, &snap_guard.new_snap_name);
+
+        if let Err(err) = Self::prune_btrfs(&run_btrfs, &snap_dir) {
+            eprintln!(
+                "WARN: httm could not prune older precautionary snapshots in {snap_dir:?}: {err}"
+            );
+        }
+
+        print_output_buf(&format!(
+            "httm took a pre-execution snapshot named: {}\n",
+            &snap_guard.new_snap_name
+        ))?;
+
+        Ok(snap_guard)
+    }
+
     pub fn new(dataset_name: &str, snap_type: PrecautionarySnapType) -> HttmResult<Self> {
         let timestamp = date_string(
             GLOBAL_CONFIG.requested_utc_offset,
@@ -81,14 +230,56 @@ impl SnapGuard {
 
                 new_snap_name
             }
+            PrecautionarySnapType::PreSoftDelete => {
+                let new_snap_name =
+                    format!("{}@snap_pre_{}_httmSnapSoftDelete", dataset_name, timestamp);
+
+                new_snap_name
+            }
         };
 
+        if GLOBAL_CONFIG.opt_dry_run {
+            print_output_buf(&format!(
+                "httm would take a precautionary snapshot named: {}\n",
+                &new_snap_name
+            ))?;
+
+            return Ok(SnapGuard {
+                new_snap_name,
+                dataset_name: dataset_name.to_string(),
+                guarded_fs: GuardedFilesystem::Zfs,
+            });
+        }
+
         let run_zfs = RunZFSCommand::new()?;
 
-        run_zfs.snapshot(&[new_snap_name.clone()])?;
+        let res_snapshot = run_zfs.snapshot(&[new_snap_name.clone()]);
+
+        AuditLog::snapshot(&new_snap_name, &res_snapshot);
+
+        res_snapshot?;
+
+        if let PrecautionarySnapType::PreRestore = snap_type {
+            let opt_mount = GLOBAL_CONFIG
+                .dataset_collection
+                .map_of_datasets
+                .iter()
+                .find(|(_mount, metadata)| metadata.source.as_ref() == Path::new(dataset_name))
+                .map(|(mount, _metadata)| mount);
+
+            if let (Some(mount), Some((_dataset, snap_name))) =
+                (opt_mount, new_snap_name.split_once('@'))
+            {
+                let snap_root = mount.join(ZFS_SNAPSHOT_DIRECTORY).join(snap_name);
+
+                Self::register(mount, &snap_root, &new_snap_name);
+            }
+        }
 
         let output_buf = match &snap_type {
-            PrecautionarySnapType::PreRollForward | PrecautionarySnapType::PreRestore => {
+            PrecautionarySnapType::PreRollForward
+            | PrecautionarySnapType::PreRestore
+            | PrecautionarySnapType::PreSoftDelete => {
                 format!(
                     "httm took a pre-execution snapshot named: {}\n",
                     &new_snap_name
@@ -107,15 +298,153 @@ impl SnapGuard {
         Ok(SnapGuard {
             new_snap_name,
             dataset_name: dataset_name.to_string(),
+            guarded_fs: GuardedFilesystem::Zfs,
+        })
+    }
+
+    // names are timestamped, so sort oldest first
+    fn prune_btrfs(run_btrfs: &RunBtrfsCommand, snap_dir: &Path) -> HttmResult<()> {
+        let mut snap_paths: Vec<PathBuf> = std::fs::read_dir(snap_dir)?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().ends_with(BTRFS_SNAP_GUARD_SUFFIX))
+            })
+            .collect();
+
+        snap_paths.sort_unstable();
+
+        let num_expired = snap_paths.len().saturating_sub(BTRFS_SNAP_GUARD_RETENTION);
+
+        snap_paths[..num_expired].iter().try_for_each(|snap_path| {
+            let res = run_btrfs.delete(snap_path);
+
+            AuditLog::prune(&snap_path.to_string_lossy(), &res);
+
+            res
         })
     }
 
+    fn register(live_root: &Path, snap_root: &Path, snap_name: &str) {
+        if let Ok(mut pre_restore_snaps) = PRE_RESTORE_SNAPS.lock() {
+            pre_restore_snaps.push((
+                live_root.to_path_buf(),
+                snap_root.to_path_buf(),
+                snap_name.to_string(),
+            ));
+        }
+    }
+
+    // the name of the most proximate precautionary snapshot taken before a restore to this path,
+    // and the path's location within that snapshot
+    pub fn preserved_version(path: &Path) -> Option<(String, PathBuf)> {
+        let pre_restore_snaps = PRE_RESTORE_SNAPS.lock().ok()?;
+
+        pre_restore_snaps
+            .iter()
+            .filter(|(live_root, _snap_root, _snap_name)| path.starts_with(live_root))
+            .max_by_key(|(live_root, _snap_root, _snap_name)| live_root.components().count())
+            .and_then(|(live_root, snap_root, snap_name)| {
+                let relative = path.strip_prefix(live_root).ok()?;
+
+                Some((snap_name.clone(), snap_root.join(relative)))
+            })
+    }
+
     pub fn rollback(&self) -> HttmResult<()> {
+        if GLOBAL_CONFIG.opt_dry_run {
+            println!(
+                "httm would roll back to the precautionary snapshot named: {}",
+                self.new_snap_name
+            );
+            return Ok(());
+        }
+
+        if let GuardedFilesystem::Btrfs {
+            subvol_root,
+            guarded_paths,
+        } = &self.guarded_fs
+        {
+            let res_rollback = self.rollback_btrfs(subvol_root, guarded_paths);
+
+            AuditLog::rollback(&self.new_snap_name, &res_rollback);
+
+            return res_rollback;
+        }
+
         ZfsAllowPriv::Rollback.from_fs_name(&self.dataset_name)?;
 
+        if let Some(warning) =
+            RunZpoolCommand::checkpoint_warning(std::iter::once(self.new_snap_name.as_str()))
+        {
+            eprintln!("{warning}");
+        }
+
         let run_zfs = RunZFSCommand::new()?;
-        run_zfs.rollback(&[self.new_snap_name.to_owned()])?;
+        let res_rollback = run_zfs.rollback(&[self.new_snap_name.to_owned()]);
+
+        AuditLog::rollback(&self.new_snap_name, &res_rollback);
+
+        res_rollback
+    }
+
+    // each snapshot version is first copied beside its live path, and only then swapped in, so a
+    // copy which fails part way never leaves the live path removed
+    fn rollback_btrfs(&self, subvol_root: &Path, guarded_paths: &[PathBuf]) -> HttmResult<()> {
+        let snap_path = Path::new(&self.new_snap_name);
+
+        guarded_paths
+            .iter()
+            .try_for_each(|live_path| -> HttmResult<()> {
+                let snap_version = snap_path.join(live_path.strip_prefix(subvol_root)?);
+
+                // if the path did not exist before the restore, removing it is all we need do
+                if snap_version.symlink_metadata().is_err() {
+                    return Remove::recursive_quiet(live_path);
+                }
+
+                let staged = Self::sibling(live_path, "rollback")?;
+
+                Remove::recursive_quiet(&staged)?;
+
+                if let Err(err) = Copy::recursive_quiet(&snap_version, &staged, true) {
+                    let _ = Remove::recursive_quiet(&staged);
+                    return Err(err);
+                }
+
+                // a rename replaces a file in one step, but cannot replace a directory, nor replace
+                // a file with a directory, so such a live path is first moved aside
+                let is_dir = |path: &Path| path.symlink_metadata().is_ok_and(|md| md.is_dir());
+
+                if is_dir(live_path) || (is_dir(&staged) && live_path.symlink_metadata().is_ok()) {
+                    let displaced = Self::sibling(live_path, "displaced")?;
+
+                    std::fs::rename(live_path, &displaced)?;
+                    std::fs::rename(&staged, live_path)?;
+
+                    return Remove::recursive_quiet(&displaced);
+                }
+
+                std::fs::rename(&staged, live_path)?;
+
+                Ok(())
+            })
+    }
+
+    fn sibling(live_path: &Path, purpose: &str) -> HttmResult<PathBuf> {
+        let (Some(parent), Some(file_name)) = (live_path.parent(), live_path.file_name()) else {
+            let msg = format!(
+                "Could not determine a parent directory for: {:?}",
+                live_path
+            );
+            return Err(HttmError::new(&msg).into());
+        };
+
+        let mut sibling_name = std::ffi::OsString::from(".");
+        sibling_name.push(file_name);
+        sibling_name.push(format!(".httm-{}-{}", purpose, std::process::id()));
 
-        Ok(())
+        Ok(parent.join(sibling_name))
     }
 }
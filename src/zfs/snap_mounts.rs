@@ -17,6 +17,7 @@
 
 use super::run_command::{RunZFSCommand, ZfsAllowPriv};
 use crate::config::generate::PrintMode;
+use crate::data::paths::PathData;
 use crate::library::iter_extensions::HttmIter;
 use crate::library::results::{HttmError, HttmResult};
 use crate::library::utility::{date_string, delimiter, print_output_buf, DateFormat};
@@ -29,7 +30,31 @@ pub struct SnapshotMounts;
 
 impl SnapshotMounts {
     pub fn exec(requested_snapshot_suffix: &str) -> HttmResult<()> {
-        let mounts_for_files: MountsForFiles = MountsForFiles::new(&MountDisplay::Target)?;
+        Self::take(requested_snapshot_suffix, None)?;
+
+        Ok(())
+    }
+
+    // returns the names of the snapshots taken (or, in a dry run, which would have been taken)
+    // a user property, "name=value", set upon each snapshot taken, may later identify those snapshots exactly
+    pub fn take(
+        requested_snapshot_suffix: &str,
+        opt_property: Option<&str>,
+    ) -> HttmResult<Vec<String>> {
+        Self::take_for_paths(
+            &GLOBAL_CONFIG.paths,
+            requested_snapshot_suffix,
+            opt_property,
+        )
+    }
+
+    pub fn take_for_paths(
+        paths: &[PathData],
+        requested_snapshot_suffix: &str,
+        opt_property: Option<&str>,
+    ) -> HttmResult<Vec<String>> {
+        let mounts_for_files: MountsForFiles =
+            MountsForFiles::from_paths(paths, &MountDisplay::Target)?;
 
         let map_snapshot_names =
             Self::snapshot_names(&mounts_for_files, requested_snapshot_suffix)?;
@@ -37,7 +62,9 @@ impl SnapshotMounts {
         let run_zfs = RunZFSCommand::new()?;
 
         map_snapshot_names.values().try_for_each(|snapshot_names| {
-            run_zfs.snapshot(snapshot_names)?;
+            if !GLOBAL_CONFIG.opt_dry_run {
+                run_zfs.snapshot_with_property(snapshot_names, opt_property)?;
+            }
 
             let output_buf: String = snapshot_names
                 .iter()
@@ -45,6 +72,8 @@ impl SnapshotMounts {
                     if let PrintMode::Raw(_) = GLOBAL_CONFIG.print_mode {
                         let delimiter = delimiter();
                         format!("{}{delimiter}", &snap_name)
+                    } else if GLOBAL_CONFIG.opt_dry_run {
+                        format!("httm would take a snapshot named: {}\n", &snap_name)
                     } else {
                         format!("httm took a snapshot named: {}\n", &snap_name)
                     }
@@ -54,7 +83,7 @@ impl SnapshotMounts {
             print_output_buf(&output_buf)
         })?;
 
-        Ok(())
+        Ok(map_snapshot_names.into_values().flatten().collect())
     }
 
     pub fn pool_from_snap_name(snapshot_name: &str) -> HttmResult<String> {
@@ -0,0 +1,222 @@
+//       ___           ___           ___           ___
+//      /\__\         /\  \         /\  \         /\__\
+//     /:/  /         \:\  \        \:\  \       /::|  |
+//    /:/__/           \:\  \        \:\  \     /:|:|  |
+//   /::\  \ ___       /::\  \       /::\  \   /:/|:|__|__
+//  /:/\:\  /\__\     /:/\:\__\     /:/\:\__\ /:/ |::::\__\
+//  \/__\:\/:/  /    /:/  \/__/    /:/  \/__/ \/__/~~/:/  /
+//       \::/  /    /:/  /        /:/  /            /:/  /
+//       /:/  /     \/__/         \/__/            /:/  /
+//      /:/  /                                    /:/  /
+//      \/__/                                     \/__/
+//
+// Copyright (c) 2023, Robert Swinford <robert.swinford<...at...>gmail.com>
+//
+// For the full copyright and license information, please view the LICENSE file
+// that was distributed with this source code.
+
+use super::run_command::RunZFSCommand;
+use super::snap_mounts::SnapshotMounts;
+use crate::config::generate::{PrintMode, ScheduleSpec};
+use crate::library::results::{HttmError, HttmResult};
+use crate::library::utility::{delimiter, print_output_buf};
+use crate::GLOBAL_CONFIG;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+// each scheduled snapshot is marked with this user property, whose value is the snapshot suffix, so
+// retention only ever destroys those snapshots the schedule itself took
+const SCHEDULE_PROPERTY: &str = "httm:schedule";
+
+pub struct SnapSchedule;
+
+impl SnapSchedule {
+    pub fn exec(schedule_spec: &ScheduleSpec) -> HttmResult<()> {
+        if let Some(systemd_dir) = &schedule_spec.opt_systemd_dir {
+            return Self::systemd_units(schedule_spec, systemd_dir);
+        }
+
+        // an error upon the first snapshot is likely an error of configuration or of privilege,
+        // and so we quit, but, once scheduled, we would rather miss a snapshot than all the rest
+        Self::snapshot_and_retain(schedule_spec)?;
+
+        let Some(every) = schedule_spec.opt_every else {
+            return Ok(());
+        };
+
+        if GLOBAL_CONFIG.opt_dry_run {
+            eprintln!(
+                "httm would take a snapshot every {:?}, until interrupted.  Dry run.  No snapshots were taken or destroyed.",
+                every
+            );
+            return Ok(());
+        }
+
+        loop {
+            std::thread::sleep(every);
+
+            if let Err(err) = Self::snapshot_and_retain(schedule_spec) {
+                eprintln!("WARN: httm was unable to take a scheduled snapshot: {err}");
+            }
+        }
+    }
+
+    fn snapshot_and_retain(schedule_spec: &ScheduleSpec) -> HttmResult<()> {
+        let property = format!("{SCHEDULE_PROPERTY}={}", schedule_spec.snapshot_suffix);
+
+        let snapshot_names = SnapshotMounts::take(&schedule_spec.snapshot_suffix, Some(&property))?;
+
+        if let Some(keep) = schedule_spec.opt_keep {
+            Self::retain(&snapshot_names, &schedule_spec.snapshot_suffix, keep)?;
+        }
+
+        Ok(())
+    }
+
+    // destroy all but the most recent snapshots, marked with our suffix, of each dataset just snapshotted.
+    // Snapshots taken by other means, or by another schedule, are never destroyed, whatever their names.
+    fn retain(snapshot_names: &[String], snapshot_suffix: &str, keep: usize) -> HttmResult<()> {
+        let run_zfs = RunZFSCommand::new()?;
+
+        let fs_names: BTreeSet<&str> = snapshot_names
+            .iter()
+            .filter_map(|snapshot_name| snapshot_name.split_once('@'))
+            .map(|(fs_name, _snap_name)| fs_name)
+            .collect();
+
+        // oldest first
+        let all_snapshots = run_zfs.snapshots_with_property(SCHEDULE_PROPERTY)?;
+
+        // in a dry run, the snapshot we would have just taken is not among those listed
+        let num_untaken = usize::from(GLOBAL_CONFIG.opt_dry_run);
+
+        let expired: Vec<&String> = fs_names
+            .iter()
+            .flat_map(|fs_name| {
+                let scheduled: Vec<&String> = all_snapshots
+                    .iter()
+                    .filter(|(full_name, value)| {
+                        value == snapshot_suffix
+                            && full_name
+                                .split_once('@')
+                                .is_some_and(|(snap_fs_name, _snap_name)| snap_fs_name == *fs_name)
+                    })
+                    .map(|(full_name, _value)| full_name)
+                    .collect();
+
+                let num_expired = (scheduled.len() + num_untaken).saturating_sub(keep);
+
+                scheduled.into_iter().take(num_expired)
+            })
+            .collect();
+
+        if !GLOBAL_CONFIG.opt_dry_run {
+            expired.iter().try_for_each(|snapshot_name| {
+                run_zfs.prune(std::slice::from_ref(*snapshot_name))
+            })?;
+        }
+
+        let output_buf: String = expired
+            .iter()
+            .map(|snapshot_name| {
+                if let PrintMode::Raw(_) = GLOBAL_CONFIG.print_mode {
+                    let delimiter = delimiter();
+                    format!("{}{delimiter}", &snapshot_name)
+                } else if GLOBAL_CONFIG.opt_dry_run {
+                    format!("httm would destroy a snapshot named: {}\n", &snapshot_name)
+                } else {
+                    format!("httm destroyed a snapshot named: {}\n", &snapshot_name)
+                }
+            })
+            .collect();
+
+        print_output_buf(&output_buf)
+    }
+
+    // a oneshot service, which takes a single snapshot and applies any retention, and a timer
+    // which starts that service at the interval requested
+    fn systemd_units(schedule_spec: &ScheduleSpec, systemd_dir: &Path) -> HttmResult<()> {
+        let Some(every) = schedule_spec.opt_every else {
+            return Err(HttmError::new(
+                "SYSTEMD requires an interval, specified via EVERY, at which the timer should take snapshots.",
+            )
+            .into());
+        };
+
+        let httm_exe = std::env::current_exe()?;
+
+        let mut exec_start = vec![
+            Self::systemd_quote(&httm_exe.to_string_lossy()),
+            Self::systemd_quote(&format!("--snap={}", schedule_spec.snapshot_suffix)),
+        ];
+
+        if let Some(keep) = schedule_spec.opt_keep {
+            exec_start.push(format!("--keep={keep}"));
+        }
+
+        exec_start.extend(
+            GLOBAL_CONFIG
+                .paths
+                .iter()
+                .map(|pathdata| Self::systemd_quote(&pathdata.path().to_string_lossy())),
+        );
+
+        let unit_name = format!("httm-snap-{}", schedule_spec.snapshot_suffix);
+
+        let service = format!(
+            "[Unit]\n\
+            Description=httm snapshot of the datasets which contain the files specified, with the suffix \"{}\"\n\
+            \n\
+            [Service]\n\
+            Type=oneshot\n\
+            ExecStart={}\n",
+            schedule_spec.snapshot_suffix,
+            exec_start.join(" ")
+        );
+
+        let timer = format!(
+            "[Unit]\n\
+            Description=Take a snapshot via {unit_name}.service every {every:?}\n\
+            \n\
+            [Timer]\n\
+            OnBootSec={secs}s\n\
+            OnUnitActiveSec={secs}s\n\
+            \n\
+            [Install]\n\
+            WantedBy=timers.target\n",
+            secs = every.as_secs()
+        );
+
+        let service_path = systemd_dir.join(format!("{unit_name}.service"));
+        let timer_path = systemd_dir.join(format!("{unit_name}.timer"));
+
+        if GLOBAL_CONFIG.opt_dry_run {
+            eprintln!(
+                "httm would write the following units.  Dry run.  No files were written.\n\n{:?}:\n{}\n{:?}:\n{}",
+                service_path, service, timer_path, timer
+            );
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(systemd_dir)?;
+        std::fs::write(&service_path, service)?;
+        std::fs::write(&timer_path, timer)?;
+
+        eprintln!(
+            "httm wrote: {:?} and {:?}.  Enable the timer with: systemctl enable --now {unit_name}.timer",
+            service_path, timer_path
+        );
+
+        Ok(())
+    }
+
+    // systemd splits ExecStart on whitespace, honors double quotes, and expands '%' specifiers
+    fn systemd_quote(arg: &str) -> String {
+        let escaped = arg
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%");
+
+        format!("\"{escaped}\"")
+    }
+}